@@ -1,16 +1,28 @@
 use anyhow::{anyhow, Context, Result};
 use chrono::{SecondsFormat, Utc};
 use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use cspx_core::checkpoint::DEFAULT_CHECKPOINT_EVERY;
+use cspx_core::ir::{Module, PropertyKind};
+use cspx_core::lts_explicit::module_from_lts_file;
 use cspx_core::{
-    explore, explore_parallel, explore_parallel_profiled, explore_parallel_profiled_with_options,
-    explore_parallel_with_options, explore_profiled, CheckRequest, CheckResult, Checker,
-    DeadlockChecker, DeterminismChecker, DivergenceChecker, ExploreHotspotProfile,
-    ExploreProfileMode, Frontend, FrontendErrorKind, InMemoryStateStore, ParallelExploreOptions,
-    Reason, ReasonKind, RefinementChecker, RefinementInput, SimpleFrontend,
-    SimpleTransitionProvider, Stats, Status, VecWorkQueue,
+    explore_parallel_concurrent_with_limits, explore_with_limits, model_coverage, parse_trace_log,
+    replay_normalised_refinement, replay_property, replay_refinement_input, BitstateStore,
+    BitstateStoreOptions, CheckRequest, CheckResult, CheckpointOptions, CompressionStats,
+    ConformanceChecker, ConformanceInput, Counterexample, CoverageEntry, CspmState, CspmStateCodec,
+    DeadlockChecker, DeterminismChecker, DiskPredecessorStore, DiskStateStore,
+    DiskStateStoreOpenOptions, DiskWorkQueue, DivergenceChecker, ExplicitLtsFormat,
+    ExploreHotspotProfile, ExploreOutcome, ExploreProfileMode, Frontend, FrontendError,
+    FrontendErrorKind, HashCompactPredecessorStore, HashCompactStateStore, HybridStateStore,
+    HybridStateStoreOptions, HybridWorkQueue, HybridWorkQueueOptions, InMemoryPredecessorStore,
+    InMemoryStateStore, ModelCoverage, NormaliseError, NormalisedSpec, ParallelExploreOptions,
+    PorStats, PredecessorStore, Reason, ReasonKind, RefinementChecker, RefinementInput,
+    RefinementOptions, Replay, ReplayVerdict, RunLimits, SearchBackend, SearchCheckpoint,
+    ShardedStateStore, SimpleFrontend, SimpleTransitionProvider, SimulateOptions, Simulator,
+    SourceSpan, StateStore, Stats, Status, SymmetryStats, VecWorkQueue, WorkQueue,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::cell::{Cell, RefCell};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
@@ -58,9 +70,18 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Command {
-    Typecheck { file: PathBuf },
+    Typecheck {
+        file: PathBuf,
+
+        #[arg(long)]
+        coverage: bool,
+    },
     Check(CheckArgs),
     Refine(RefineArgs),
+    Normalise(NormaliseArgs),
+    Replay(ReplayArgs),
+    Conform(ConformArgs),
+    Simulate(SimulateArgs),
 }
 
 #[derive(Args)]
@@ -76,6 +97,37 @@ struct CheckArgs {
     #[arg(long)]
     all_assertions: bool,
 
+    #[arg(long, value_enum, default_value = "memory")]
+    store: StoreKind,
+
+    #[arg(long)]
+    store_dir: Option<PathBuf>,
+
+    #[arg(long)]
+    por: bool,
+
+    #[arg(long)]
+    symmetry: bool,
+
+    #[arg(long, default_value_t = BitstateStoreOptions::default().log2_bits)]
+    bitstate_bits: u32,
+
+    #[arg(long, default_value_t = BitstateStoreOptions::default().hashes)]
+    bitstate_hashes: u32,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_counterexamples: usize,
+
+    #[arg(long)]
+    coverage: bool,
+
+    #[command(flatten)]
+    checkpoint: CheckpointArgs,
+
     file: PathBuf,
 }
 
@@ -88,6 +140,79 @@ struct RefineArgs {
 
     #[arg(value_name = "impl")]
     impl_: PathBuf,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_counterexamples: usize,
+
+    #[command(flatten)]
+    checkpoint: CheckpointArgs,
+}
+
+#[derive(Args)]
+struct CheckpointArgs {
+    #[arg(long)]
+    checkpoint_dir: Option<PathBuf>,
+
+    #[arg(long, requires = "checkpoint_dir")]
+    resume: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_CHECKPOINT_EVERY,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    checkpoint_every: u64,
+}
+
+#[derive(Args)]
+struct NormaliseArgs {
+    spec: PathBuf,
+
+    #[arg(short = 'o', value_name = "out")]
+    out: PathBuf,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    #[arg(long)]
+    result: PathBuf,
+
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct ConformArgs {
+    #[arg(long)]
+    process: String,
+
+    model: PathBuf,
+
+    #[arg(value_name = "trace-file")]
+    trace: PathBuf,
+}
+
+#[derive(Args)]
+struct SimulateArgs {
+    #[arg(
+        long,
+        default_value_t = SimulateOptions::default().runs,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    runs: usize,
+
+    #[arg(
+        long,
+        default_value_t = SimulateOptions::default().depth,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    depth: usize,
+
+    file: PathBuf,
 }
 
 #[derive(Clone, Copy, ValueEnum)]
@@ -96,6 +221,128 @@ enum OutputFormat {
     Text,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum StoreKind {
+    Memory,
+    Disk,
+    Hybrid,
+    Bitstate,
+    HashCompact,
+}
+
+impl StoreKind {
+    fn is_approximate(self) -> bool {
+        matches!(self, Self::Bitstate | Self::HashCompact)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+            Self::Hybrid => "hybrid",
+            Self::Bitstate => "bitstate",
+            Self::HashCompact => "hash_compact",
+        }
+    }
+}
+
+struct SearchOptions<'a> {
+    store: StoreKind,
+    store_dir: Option<PathBuf>,
+    workers: usize,
+    bitstate: BitstateStoreOptions,
+    por: Option<Cell<PorStats>>,
+    symmetry: Option<Cell<SymmetryStats>>,
+    // ln of the probability that no check missed a state
+    log_no_omission: Option<Cell<f64>>,
+    compression: RefCell<Vec<CompressionStats>>,
+    limits: &'a RunLimits,
+    checkpoint: Option<CheckpointPlan>,
+    max_counterexamples: usize,
+}
+
+struct CheckpointPlan {
+    dir: PathBuf,
+    every: u64,
+    resume: bool,
+    fingerprint: String,
+    next: Cell<usize>,
+}
+
+impl CheckpointPlan {
+    // The fingerprint covers everything that changes the explored graph, so
+    // a checkpoint is never resumed against another model or configuration.
+    fn new(args: &CheckpointArgs, parts: &[&str]) -> Option<Self> {
+        let dir = args.checkpoint_dir.clone()?;
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        Some(Self {
+            dir,
+            every: args.checkpoint_every,
+            resume: args.resume,
+            fingerprint: hex::encode(hasher.finalize()),
+            next: Cell::new(0),
+        })
+    }
+
+    fn next_options(&self) -> CheckpointOptions {
+        let index = self.next.get();
+        self.next.set(index + 1);
+        CheckpointOptions {
+            dir: self.dir.join(format!("check-{index:03}")),
+            every: self.every,
+            resume: self.resume,
+            fingerprint: format!("{}-{index}", self.fingerprint),
+        }
+    }
+}
+
+impl SearchOptions<'_> {
+    fn totals(self) -> SearchTotals {
+        SearchTotals {
+            por: self.por.map(Cell::into_inner),
+            symmetry: self.symmetry.map(Cell::into_inner),
+            approximation: self
+                .log_no_omission
+                .map(|log| (self.store, -log.into_inner().exp_m1())),
+            memory: memory_totals(self.limits),
+            coverage: None,
+            compression: self.compression.into_inner(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct SearchTotals {
+    por: Option<PorStats>,
+    symmetry: Option<SymmetryStats>,
+    approximation: Option<(StoreKind, f64)>,
+    memory: Option<MemoryMetrics>,
+    coverage: Option<ModelCoverage>,
+    compression: Vec<CompressionStats>,
+}
+
+fn run_limits(cli: &Cli) -> RunLimits {
+    let mut limits = RunLimits::unlimited();
+    if let Some(mib) = cli.memory_mb {
+        limits = limits.with_memory_mib(mib);
+    }
+    if let Some(ms) = cli.timeout_ms {
+        limits = limits.with_timeout_ms(ms);
+    }
+    limits
+}
+
+fn memory_totals(limits: &RunLimits) -> Option<MemoryMetrics> {
+    limits.memory.as_ref().map(|budget| MemoryMetrics {
+        limit_bytes: budget.limit_bytes(),
+        peak_estimated_bytes: budget.peak_bytes(),
+    })
+}
+
 #[derive(Clone, Copy, ValueEnum)]
 enum RefinementModel {
     #[value(name = "T")]
@@ -124,6 +371,8 @@ struct ResultJson {
     inputs: Vec<InputInfo>,
     status: Status,
     exit_code: i32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    approximate: bool,
     started_at: String,
     finished_at: String,
     duration_ms: u64,
@@ -164,6 +413,25 @@ struct ResultMetrics {
     parallelism: ParallelismMetrics,
     #[serde(skip_serializing_if = "Option::is_none")]
     explore_hotspots: Option<ExploreHotspotsMetrics>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    compression: Vec<CompressionMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    por: Option<PorMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symmetry: Option<SymmetryMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    approximation: Option<ApproximationMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<MemoryMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage: Option<CoverageMetrics>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct MemoryMetrics {
+    limit_bytes: u64,
+    peak_estimated_bytes: u64,
 }
 
 #[derive(Serialize)]
@@ -191,6 +459,55 @@ struct ExploreHotspotsMetrics {
     hotspots: Vec<HotspotEntry>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct PorMetrics {
+    states_explored: Option<u64>,
+    ample_expansions: u64,
+    full_expansions: u64,
+    skipped_transitions: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct SymmetryMetrics {
+    canonicalised_successors: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct ApproximationMetrics {
+    store: &'static str,
+    missed_state_probability: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct CompressionMetrics {
+    function: String,
+    source_span: Option<SourceSpan>,
+    states_before: u64,
+    transitions_before: u64,
+    states_after: u64,
+    transitions_after: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct CoverageMetrics {
+    complete: bool,
+    never_enabled_events: Vec<CoverageItem>,
+    unreached_processes: Vec<CoverageItem>,
+    unreached_prefixes: Vec<CoverageItem>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+struct CoverageItem {
+    name: String,
+    source_span: SourceSpan,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "snake_case")]
 struct HotspotEntry {
@@ -228,6 +545,7 @@ type ExecuteOutput = (
     Vec<InputInfo>,
     Invocation,
     Option<ExploreHotspotProfile>,
+    SearchTotals,
 );
 
 fn main() {
@@ -246,7 +564,8 @@ fn run(cli: Cli) -> Result<i32> {
     let started_at = Utc::now();
     let timer = Instant::now();
 
-    let (status, exit_code, checks, inputs, invocation, explore_hotspot_profile) = execute(&cli)?;
+    let (status, exit_code, checks, inputs, invocation, explore_hotspot_profile, totals) =
+        execute(&cli)?;
 
     let finished_at = Utc::now();
     let duration_ms = timer.elapsed().as_millis() as u64;
@@ -255,7 +574,15 @@ fn run(cli: Cli) -> Result<i32> {
         duration_ms,
         &invocation,
         explore_hotspot_profile.as_ref(),
+        totals,
     );
+    let approximate = status == Status::Pass
+        && checks.iter().any(|check| {
+            check
+                .reason
+                .as_ref()
+                .is_some_and(|reason| reason.kind == ReasonKind::ApproximateSearch)
+        });
 
     let result = ResultJson {
         schema_version: "0.1".to_string(),
@@ -268,6 +595,7 @@ fn run(cli: Cli) -> Result<i32> {
         inputs,
         status,
         exit_code,
+        approximate,
         started_at: started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
         finished_at: finished_at.to_rfc3339_opts(SecondsFormat::Secs, true),
         duration_ms,
@@ -296,8 +624,10 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
     }
     let seed = cli.seed.unwrap_or(0);
 
+    let limits = run_limits(cli);
+    let mut totals = SearchTotals::default();
     let (command, args, inputs, checks, explore_hotspot_profile) = match &cli.command {
-        Command::Typecheck { file } => {
+        Command::Typecheck { file, coverage } => {
             let (inputs, io_error) = build_inputs(std::slice::from_ref(file));
             let (check, profile) = run_typecheck(
                 file,
@@ -306,7 +636,11 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
                 cli.deterministic,
                 seed,
                 cli.explore_profile,
+                &limits,
             );
+            if *coverage {
+                totals.coverage = build_coverage(file, &limits);
+            }
             (
                 "typecheck".to_string(),
                 vec![file.to_string_lossy().to_string()],
@@ -317,14 +651,52 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
         }
         Command::Check(args) => {
             let (inputs, io_error) = build_inputs(std::slice::from_ref(&args.file));
+            let bitstate_bits = args.bitstate_bits.to_string();
+            let bitstate_hashes = args.bitstate_hashes.to_string();
+            let max_counterexamples = args.max_counterexamples.to_string();
+            let checkpoint = CheckpointPlan::new(
+                &args.checkpoint,
+                &[
+                    "check",
+                    &inputs[0].sha256,
+                    args.assert.as_deref().unwrap_or("all-assertions"),
+                    args.store.as_str(),
+                    if args.por { "por" } else { "full" },
+                    if args.symmetry {
+                        "symmetry"
+                    } else {
+                        "concrete"
+                    },
+                    &bitstate_bits,
+                    &bitstate_hashes,
+                    &max_counterexamples,
+                ],
+            );
+            let search = SearchOptions {
+                store: args.store,
+                store_dir: args.store_dir.clone(),
+                workers: cli.parallel,
+                bitstate: BitstateStoreOptions {
+                    log2_bits: args.bitstate_bits,
+                    hashes: args.bitstate_hashes,
+                },
+                por: args.por.then(Cell::default),
+                symmetry: args.symmetry.then(Cell::default),
+                log_no_omission: args.store.is_approximate().then(Cell::default),
+                compression: RefCell::default(),
+                limits: &limits,
+                checkpoint,
+                max_counterexamples: args.max_counterexamples,
+            };
             let checks = if let Some(assertion) = &args.assert {
                 vec![run_check_by_assertion(
                     &args.file,
                     io_error.as_ref(),
                     assertion,
+                    &search,
                 )]
             } else if args.all_assertions {
-                run_all_assertions(&args.file, io_error.as_ref())
+                run_all_assertions(&args.file, io_error.as_ref(), &search)
             } else {
                 vec![build_stub_check_result(
                     "check",
@@ -334,6 +706,10 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
                     "checker not implemented yet",
                 )]
             };
+            totals = search.totals();
+            if args.coverage {
+                totals.coverage = build_coverage(&args.file, &limits);
+            }
             (
                 "check".to_string(),
                 vec![args.file.to_string_lossy().to_string()],
@@ -344,7 +720,26 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
         }
         Command::Refine(args) => {
             let (inputs, io_error) = build_inputs(&[args.spec.clone(), args.impl_.clone()]);
-            let checks = vec![run_refine_check(args, io_error.as_ref())];
+            let max_counterexamples = args.max_counterexamples.to_string();
+            let checkpoint = CheckpointPlan::new(
+                &args.checkpoint,
+                &[
+                    "refine",
+                    &inputs[0].sha256,
+                    &inputs[1].sha256,
+                    args.model.as_str(),
+                    &max_counterexamples,
+                ],
+            );
+            let checks = vec![run_refine_check(
+                args,
+                io_error.as_ref(),
+                cli.parallel,
+                &limits,
+                checkpoint.as_ref(),
+                &mut totals.compression,
+            )];
+            totals.memory = memory_totals(&limits);
             (
                 "refine".to_string(),
                 vec![
@@ -356,6 +751,61 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
                 None,
             )
         }
+        Command::Normalise(args) => {
+            let (inputs, io_error) = build_inputs(std::slice::from_ref(&args.spec));
+            let checks = vec![run_normalise(args, io_error.as_ref(), &limits)];
+            (
+                "normalise".to_string(),
+                vec![args.spec.to_string_lossy().to_string()],
+                inputs,
+                checks,
+                None,
+            )
+        }
+        Command::Conform(args) => {
+            let (inputs, io_error) = build_inputs(&[args.model.clone(), args.trace.clone()]);
+            let checks = vec![run_conform(args, io_error.as_ref(), &limits)];
+            (
+                "conform".to_string(),
+                vec![
+                    args.model.to_string_lossy().to_string(),
+                    args.trace.to_string_lossy().to_string(),
+                ],
+                inputs,
+                checks,
+                None,
+            )
+        }
+        Command::Simulate(args) => {
+            let (inputs, io_error) = build_inputs(std::slice::from_ref(&args.file));
+            let options = SimulateOptions {
+                runs: args.runs,
+                depth: args.depth,
+                seed,
+            };
+            let checks = run_simulate(&args.file, io_error.as_ref(), options, &limits);
+            (
+                "simulate".to_string(),
+                vec![args.file.to_string_lossy().to_string()],
+                inputs,
+                checks,
+                None,
+            )
+        }
+        Command::Replay(args) => {
+            let (inputs, io_error) = build_inputs(&args.files);
+            let checks = run_replay(args, io_error.as_ref(), &limits);
+            (
+                "replay".to_string(),
+                args.files
+                    .iter()
+                    .map(|file| file.to_string_lossy().to_string())
+                    .collect(),
+                inputs,
+                checks,
+                None,
+            )
+        }
     };
 
     let status = aggregate_status(&checks);
@@ -382,6 +832,7 @@ fn execute(cli: &Cli) -> Result<ExecuteOutput> {
         inputs,
         invocation,
         explore_hotspot_profile,
+        totals,
     ))
 }
 
@@ -390,6 +841,7 @@ fn build_metrics(
     duration_ms: u64,
     invocation: &Invocation,
     explore_hotspot_profile: Option<&ExploreHotspotProfile>,
+    totals: SearchTotals,
 ) -> ResultMetrics {
     let states = aggregate_stats(checks, |stats| stats.states);
     let transitions = aggregate_stats(checks, |stats| stats.transitions);
@@ -409,7 +861,70 @@ fn build_metrics(
             seed: invocation.seed,
         },
         explore_hotspots: explore_hotspot_profile.map(build_explore_hotspots),
+        compression: build_compression_metrics(totals.compression),
+        por: totals.por.map(|stats| PorMetrics {
+            states_explored: states,
+            ample_expansions: stats.ample_expansions,
+            full_expansions: stats.full_expansions,
+            skipped_transitions: stats.skipped_transitions,
+        }),
+        symmetry: totals.symmetry.map(|stats| SymmetryMetrics {
+            canonicalised_successors: stats.canonicalised_successors,
+        }),
+        approximation: totals
+            .approximation
+            .map(|(store, probability)| ApproximationMetrics {
+                store: store.as_str(),
+                missed_state_probability: probability,
+            }),
+        memory: totals.memory,
+        coverage: totals.coverage.map(|coverage| {
+            let items = |entries: Vec<CoverageEntry>| {
+                entries
+                    .into_iter()
+                    .map(|entry| CoverageItem {
+                        name: entry.name,
+                        source_span: entry.span,
+                    })
+                    .collect()
+            };
+            CoverageMetrics {
+                complete: coverage.complete,
+                never_enabled_events: items(coverage.never_enabled_events),
+                unreached_processes: items(coverage.unreached_processes),
+                unreached_prefixes: items(coverage.unreached_prefixes),
+            }
+        }),
+    }
+}
+
+fn build_coverage(file: &Path, limits: &RunLimits) -> Option<ModelCoverage> {
+    let source = fs::read_to_string(file).ok()?;
+    let output = SimpleFrontend
+        .parse_and_typecheck(&source, &file.to_string_lossy())
+        .ok()?;
+    model_coverage(&output.ir, limits).ok()
+}
+
+// The same compression shows up once per check that ran it.
+fn build_compression_metrics(stats: Vec<CompressionStats>) -> Vec<CompressionMetrics> {
+    let mut unique = Vec::new();
+    for stats in stats {
+        if !unique.contains(&stats) {
+            unique.push(stats);
+        }
     }
+    unique
+        .into_iter()
+        .map(|stats| CompressionMetrics {
+            function: stats.function.as_str().to_string(),
+            source_span: stats.span,
+            states_before: stats.states_before as u64,
+            transitions_before: stats.transitions_before as u64,
+            states_after: stats.states_after as u64,
+            transitions_after: stats.transitions_after as u64,
+        })
+        .collect()
 }
 
 fn aggregate_stats(checks: &[CheckResult], select: fn(&Stats) -> Option<u64>) -> Option<u64> {
@@ -437,6 +952,7 @@ fn build_explore_hotspots(profile: &ExploreHotspotProfile) -> ExploreHotspotsMet
         ("estimated_wait", profile.estimated_wait_ns),
     ];
     phases.retain(|(_, ns)| *ns > 0);
+    #[allow(clippy::unnecessary_sort_by)]
     phases.sort_by(|lhs, rhs| rhs.1.cmp(&lhs.1));
 
     let total_ns = phases
@@ -566,6 +1082,7 @@ fn run_typecheck(
     deterministic: bool,
     seed: u64,
     explore_profile: bool,
+    limits: &RunLimits,
 ) -> (CheckResult, Option<ExploreHotspotProfile>) {
     if let Some(message) = io_error {
         return (
@@ -597,30 +1114,34 @@ fn run_typecheck(
     };
 
     let frontend = SimpleFrontend;
-    match frontend.parse_and_typecheck(&source, &file.to_string_lossy()) {
+    match frontend.parse_and_typecheck_with_limits(&source, &file.to_string_lossy(), limits) {
         Ok(output) => {
-            let (stats, profile) =
-                build_stats(&output.ir, parallel, deterministic, seed, explore_profile);
+            let outcome = build_stats(
+                &output.ir,
+                parallel,
+                deterministic,
+                seed,
+                explore_profile,
+                limits,
+            );
             (
                 CheckResult {
                     name: "typecheck".to_string(),
                     model: None,
                     target: None,
-                    status: Status::Pass,
-                    reason: None,
+                    status: outcome.stopped.map_or(Status::Pass, |limit| limit.status()),
+                    reason: outcome.stopped.map(|limit| limit.reason()),
                     counterexample: None,
-                    stats: Some(stats),
+                    counterexamples: Vec::new(),
+                    stats: Some(outcome.stats),
+                    replay: None,
+                    simulation: None,
                 },
-                profile,
+                outcome.profile,
             )
         }
         Err(err) => {
-            let (status, reason_kind) = match err.kind {
-                FrontendErrorKind::UnsupportedSyntax => {
-                    (Status::Unsupported, ReasonKind::UnsupportedSyntax)
-                }
-                FrontendErrorKind::InvalidInput => (Status::Error, ReasonKind::InvalidInput),
-            };
+            let (status, reason_kind) = frontend_error_status(&err.kind);
             (
                 CheckResult {
                     name: "typecheck".to_string(),
@@ -632,10 +1153,14 @@ fn run_typecheck(
                         message: Some(err.to_string()),
                     }),
                     counterexample: None,
+                    counterexamples: Vec::new(),
                     stats: Some(Stats {
                         states: None,
                         transitions: None,
+                        depth: None,
                     }),
+                    replay: None,
+                    simulation: None,
                 },
                 None,
             )
@@ -649,72 +1174,41 @@ fn build_stats(
     deterministic: bool,
     seed: u64,
     explore_profile: bool,
-) -> (Stats, Option<ExploreHotspotProfile>) {
+    limits: &RunLimits,
+) -> ExploreOutcome {
+    let unknown = ExploreOutcome {
+        stats: Stats {
+            states: None,
+            transitions: None,
+            depth: None,
+        },
+        stopped: None,
+        profile: None,
+    };
     let provider = match SimpleTransitionProvider::from_module(module) {
         Ok(provider) => provider,
-        Err(_) => {
-            return (
-                Stats {
-                    states: None,
-                    transitions: None,
-                },
-                None,
-            )
-        }
+        Err(_) => return unknown,
     };
 
-    let mut store = InMemoryStateStore::new();
-    let result = if deterministic {
-        if explore_profile {
-            explore_parallel_profiled_with_options(
-                &provider,
-                &mut store,
-                ParallelExploreOptions {
-                    workers: parallel,
-                    deterministic: true,
-                    seed,
-                },
-            )
-            .map(|(stats, profile)| (stats, Some(profile)))
-        } else {
-            explore_parallel_with_options(
-                &provider,
-                &mut store,
-                ParallelExploreOptions {
-                    workers: parallel,
-                    deterministic: true,
-                    seed,
-                },
-            )
-            .map(|stats| (stats, None))
-        }
-    } else if parallel > 1 {
-        if explore_profile {
-            explore_parallel_profiled(&provider, &mut store, parallel)
-                .map(|(stats, profile)| (stats, Some(profile)))
-        } else {
-            explore_parallel(&provider, &mut store, parallel).map(|stats| (stats, None))
-        }
+    let result = if deterministic || parallel > 1 {
+        explore_parallel_concurrent_with_limits(
+            &provider,
+            &ShardedStateStore::new(),
+            ParallelExploreOptions {
+                workers: parallel,
+                deterministic,
+                seed,
+            },
+            limits,
+            explore_profile,
+        )
     } else {
+        let mut store = InMemoryStateStore::new();
         let mut queue = VecWorkQueue::new();
-        if explore_profile {
-            explore_profiled(&provider, &mut store, &mut queue)
-                .map(|(stats, profile)| (stats, Some(profile)))
-        } else {
-            explore(&provider, &mut store, &mut queue).map(|stats| (stats, None))
-        }
+        explore_with_limits(&provider, &mut store, &mut queue, limits, explore_profile)
     };
 
-    match result {
-        Ok((stats, profile)) => (stats, profile),
-        Err(_) => (
-            Stats {
-                states: None,
-                transitions: None,
-            },
-            None,
-        ),
-    }
+    result.unwrap_or(unknown)
 }
 
 fn build_stub_check_result(
@@ -744,14 +1238,23 @@ fn build_stub_check_result(
             message: Some(not_implemented_message.to_string()),
         }),
         counterexample: None,
+        counterexamples: Vec::new(),
         stats: Some(Stats {
             states: None,
             transitions: None,
+            depth: None,
         }),
+        replay: None,
+        simulation: None,
     }
 }
 
-fn run_check_by_assertion(file: &Path, io_error: Option<&String>, assertion: &str) -> CheckResult {
+fn run_check_by_assertion(
+    file: &Path,
+    io_error: Option<&String>,
+    assertion: &str,
+    search: &SearchOptions,
+) -> CheckResult {
     let supported = ["deadlock free", "divergence free", "deterministic"];
     if !supported.contains(&assertion) {
         return error_check(
@@ -766,38 +1269,35 @@ fn run_check_by_assertion(file: &Path, io_error: Option<&String>, assertion: &st
         );
     }
 
-    let module = match parse_module_for_check(file, io_error, assertion) {
+    let module = match parse_module_for_check(file, io_error, assertion, search.limits) {
         Ok(module) => module,
         Err(check) => return *check,
     };
 
     match assertion {
         "deadlock free" => {
-            let checker = DeadlockChecker;
             let request = CheckRequest {
                 command: cspx_core::check::CheckCommand::Check,
                 model: None,
                 target: Some(assertion.to_string()),
             };
-            checker.check(&request, &module)
+            run_property_check(PropertyKind::DeadlockFree, &request, &module, search)
         }
         "divergence free" => {
-            let checker = DivergenceChecker;
             let request = CheckRequest {
                 command: cspx_core::check::CheckCommand::Check,
                 model: None,
                 target: Some(assertion.to_string()),
             };
-            checker.check(&request, &module)
+            run_property_check(PropertyKind::DivergenceFree, &request, &module, search)
         }
         "deterministic" => {
-            let checker = DeterminismChecker;
             let request = CheckRequest {
                 command: cspx_core::check::CheckCommand::Check,
                 model: None,
                 target: Some(assertion.to_string()),
             };
-            checker.check(&request, &module)
+            run_property_check(PropertyKind::Deterministic, &request, &module, search)
         }
         _ => build_stub_check_result(
             "check",
@@ -809,8 +1309,12 @@ fn run_check_by_assertion(file: &Path, io_error: Option<&String>, assertion: &st
     }
 }
 
-fn run_all_assertions(file: &Path, io_error: Option<&String>) -> Vec<CheckResult> {
-    let module = match parse_module_for_check(file, io_error, "all-assertions") {
+fn run_all_assertions(
+    file: &Path,
+    io_error: Option<&String>,
+    search: &SearchOptions,
+) -> Vec<CheckResult> {
+    let module = match parse_module_for_check(file, io_error, "all-assertions", search.limits) {
         Ok(module) => module,
         Err(check) => return vec![*check],
     };
@@ -828,38 +1332,44 @@ fn run_all_assertions(file: &Path, io_error: Option<&String>) -> Vec<CheckResult
     let mut out = Vec::new();
     for assertion in &module.assertions {
         match assertion {
-            cspx_core::ir::AssertionDecl::Property {
-                target,
-                kind,
-                model,
-            } => {
-                let check_target = format!(
-                    "{} :[{} [{}]]",
-                    target.value,
-                    property_kind_str(*kind),
-                    property_model_str(*model)
-                );
+            cspx_core::ir::AssertionDecl::Property { target, kind, .. } => {
+                let check_target = assertion_target(assertion);
                 match *kind {
-                    cspx_core::ir::PropertyKind::DeadlockFree => out.push(
-                        run_deadlock_property_assertion(&module, &target.value, check_target),
-                    ),
-                    cspx_core::ir::PropertyKind::DivergenceFree => out.push(
-                        run_divergence_property_assertion(&module, &target.value, check_target),
-                    ),
-                    cspx_core::ir::PropertyKind::Deterministic => out.push(
-                        run_determinism_property_assertion(&module, &target.value, check_target),
-                    ),
+                    cspx_core::ir::PropertyKind::DeadlockFree => {
+                        out.push(run_deadlock_property_assertion(
+                            &module,
+                            &target.value,
+                            check_target,
+                            search,
+                        ))
+                    }
+                    cspx_core::ir::PropertyKind::DivergenceFree => {
+                        out.push(run_divergence_property_assertion(
+                            &module,
+                            &target.value,
+                            check_target,
+                            search,
+                        ))
+                    }
+                    cspx_core::ir::PropertyKind::Deterministic => {
+                        out.push(run_determinism_property_assertion(
+                            &module,
+                            &target.value,
+                            check_target,
+                            search,
+                        ))
+                    }
                 }
             }
             cspx_core::ir::AssertionDecl::Refinement { spec, model, impl_ } => {
-                let model_str = refinement_op_str(*model).to_string();
-                let check_target = format!("{} [{}= {}", spec.value, model_str, impl_.value);
+                let check_target = assertion_target(assertion);
                 out.push(run_refinement_assertion(
                     &module,
                     &spec.value,
                     *model,
                     &impl_.value,
                     check_target,
+                    search,
                 ));
             }
         }
@@ -867,10 +1377,32 @@ fn run_all_assertions(file: &Path, io_error: Option<&String>) -> Vec<CheckResult
     out
 }
 
+fn assertion_target(assertion: &cspx_core::ir::AssertionDecl) -> String {
+    match assertion {
+        cspx_core::ir::AssertionDecl::Property {
+            target,
+            kind,
+            model,
+        } => format!(
+            "{} :[{} [{}]]",
+            target.value,
+            property_kind_str(*kind),
+            property_model_str(*model)
+        ),
+        cspx_core::ir::AssertionDecl::Refinement { spec, model, impl_ } => format!(
+            "{} [{}= {}",
+            spec.value,
+            refinement_op_str(*model),
+            impl_.value
+        ),
+    }
+}
+
 fn run_deadlock_property_assertion(
     module: &cspx_core::ir::Module,
     target_proc: &str,
     target_desc: String,
+    search: &SearchOptions,
 ) -> CheckResult {
     let Some(expr) = module
         .declarations
@@ -890,19 +1422,19 @@ fn run_deadlock_property_assertion(
     let mut check_module = module.clone();
     check_module.entry = Some(expr);
 
-    let checker = DeadlockChecker;
     let request = CheckRequest {
         command: cspx_core::check::CheckCommand::Check,
         model: None,
         target: Some(target_desc),
     };
-    checker.check(&request, &check_module)
+    run_property_check(PropertyKind::DeadlockFree, &request, &check_module, search)
 }
 
 fn run_divergence_property_assertion(
     module: &cspx_core::ir::Module,
     target_proc: &str,
     target_desc: String,
+    search: &SearchOptions,
 ) -> CheckResult {
     let Some(expr) = module
         .declarations
@@ -922,19 +1454,24 @@ fn run_divergence_property_assertion(
     let mut check_module = module.clone();
     check_module.entry = Some(expr);
 
-    let checker = DivergenceChecker;
     let request = CheckRequest {
         command: cspx_core::check::CheckCommand::Check,
         model: None,
         target: Some(target_desc),
     };
-    checker.check(&request, &check_module)
+    run_property_check(
+        PropertyKind::DivergenceFree,
+        &request,
+        &check_module,
+        search,
+    )
 }
 
 fn run_determinism_property_assertion(
     module: &cspx_core::ir::Module,
     target_proc: &str,
     target_desc: String,
+    search: &SearchOptions,
 ) -> CheckResult {
     let Some(expr) = module
         .declarations
@@ -954,31 +1491,210 @@ fn run_determinism_property_assertion(
     let mut check_module = module.clone();
     check_module.entry = Some(expr);
 
-    let checker = DeterminismChecker;
     let request = CheckRequest {
         command: cspx_core::check::CheckCommand::Check,
         model: None,
         target: Some(target_desc),
     };
-    checker.check(&request, &check_module)
+    run_property_check(PropertyKind::Deterministic, &request, &check_module, search)
 }
 
-fn run_refinement_assertion(
-    module: &cspx_core::ir::Module,
-    spec_proc: &str,
-    model: cspx_core::ir::RefinementOp,
-    impl_proc: &str,
-    target_desc: String,
+// Index snapshots are flushed in batches; a crash only loses the unflushed tail,
+// which reopen rebuilds from the log.
+fn disk_store_options() -> DiskStateStoreOpenOptions {
+    DiskStateStoreOpenOptions {
+        index_flush_every: 64 * 1024,
+        ..DiskStateStoreOpenOptions::default()
+    }
+}
+
+fn run_property_check(
+    kind: PropertyKind,
+    request: &CheckRequest,
+    module: &Module,
+    search: &SearchOptions,
 ) -> CheckResult {
-    let Some(spec_expr) = module
-        .declarations
-        .iter()
-        .find(|decl| decl.name.value == spec_proc)
-        .map(|decl| decl.expr.clone())
-    else {
-        return error_check(
-            "refine",
-            Some(refinement_op_str(model).to_string()),
+    let scratch = match search.store {
+        StoreKind::Memory | StoreKind::Bitstate | StoreKind::HashCompact => None,
+        StoreKind::Disk | StoreKind::Hybrid => {
+            match ScratchDir::create(search.store_dir.as_deref()) {
+                Ok(dir) => Some(dir),
+                Err(err) => return store_open_error(request, err),
+            }
+        }
+    };
+    let mut store: Box<dyn StateStore<CspmState>> = match (search.store, scratch.as_ref()) {
+        (StoreKind::Disk, Some(dir)) => {
+            match DiskStateStore::open_with_options(
+                dir.path.join("state.log"),
+                CspmStateCodec,
+                disk_store_options(),
+            ) {
+                Ok(store) => Box::new(store),
+                Err(err) => return store_open_error(request, err),
+            }
+        }
+        (StoreKind::Hybrid, Some(dir)) => match HybridStateStore::open(
+            dir.path.join("spill.log"),
+            CspmStateCodec,
+            HybridStateStoreOptions {
+                disk_options: disk_store_options(),
+                ..HybridStateStoreOptions::default()
+            },
+        ) {
+            Ok(store) => Box::new(store),
+            Err(err) => return store_open_error(request, err),
+        },
+        (StoreKind::Bitstate, _) => {
+            match BitstateStore::new_with_limits(search.bitstate, search.limits) {
+                Ok(store) => Box::new(store),
+                Err(err) => return store_open_error(request, err),
+            }
+        }
+        (StoreKind::HashCompact, _) => Box::new(HashCompactStateStore::new()),
+        _ => Box::new(InMemoryStateStore::new()),
+    };
+    let mut queue: Box<dyn WorkQueue<CspmState>> = match (search.store, scratch.as_ref()) {
+        (StoreKind::Disk, Some(dir)) => {
+            match DiskWorkQueue::open(dir.path.join("queue.log"), CspmStateCodec) {
+                Ok(queue) => Box::new(queue),
+                Err(err) => return store_open_error(request, err),
+            }
+        }
+        (StoreKind::Hybrid, Some(dir)) => match HybridWorkQueue::open(
+            dir.path.join("queue.log"),
+            CspmStateCodec,
+            HybridWorkQueueOptions::default(),
+        ) {
+            Ok(queue) => Box::new(queue),
+            Err(err) => return store_open_error(request, err),
+        },
+        _ => Box::new(VecWorkQueue::new()),
+    };
+    let mut predecessors: Box<dyn PredecessorStore<CspmState>> =
+        match (search.store, scratch.as_ref()) {
+            (StoreKind::Disk | StoreKind::Hybrid, Some(dir)) => {
+                match DiskPredecessorStore::open(dir.path.join("predecessors.log"), CspmStateCodec)
+                {
+                    Ok(predecessors) => Box::new(predecessors),
+                    Err(err) => return store_open_error(request, err),
+                }
+            }
+            (StoreKind::Bitstate | StoreKind::HashCompact, _) => {
+                Box::new(HashCompactPredecessorStore::new())
+            }
+            _ => Box::new(InMemoryPredecessorStore::new()),
+        };
+    let checkpoint = search.checkpoint.as_ref().map(|plan| SearchCheckpoint {
+        options: plan.next_options(),
+        codec: &CspmStateCodec,
+    });
+    let mut por = PorStats::default();
+    let mut symmetry = SymmetryStats::default();
+    let mut compression = search.compression.borrow_mut();
+    let mut backend = SearchBackend {
+        store: store.as_mut(),
+        queue: queue.as_mut(),
+        predecessors: predecessors.as_mut(),
+        workers: search.workers,
+        por: search.por.is_some().then_some(&mut por),
+        symmetry: search.symmetry.is_some().then_some(&mut symmetry),
+        limits: Some(search.limits),
+        checkpoint: checkpoint.as_ref(),
+        compression: Some(&mut compression),
+        max_counterexamples: search.max_counterexamples,
+    };
+    let mut result = match kind {
+        PropertyKind::DeadlockFree => DeadlockChecker.check_with(request, module, &mut backend),
+        PropertyKind::DivergenceFree => DivergenceChecker.check_with(request, module, &mut backend),
+        PropertyKind::Deterministic => DeterminismChecker.check_with(request, module, &mut backend),
+    };
+    if let Some(total) = &search.por {
+        let mut sum = total.get();
+        sum.ample_expansions += por.ample_expansions;
+        sum.full_expansions += por.full_expansions;
+        sum.skipped_transitions += por.skipped_transitions;
+        total.set(sum);
+    }
+    if let Some(total) = &search.symmetry {
+        let mut sum = total.get();
+        sum.canonicalised_successors += symmetry.canonicalised_successors;
+        total.set(sum);
+    }
+    if let (Some(probability), Some(total)) =
+        (store.omission_probability(), &search.log_no_omission)
+    {
+        total.set(total.get() + (-probability).ln_1p());
+        if result.status == Status::Pass {
+            result.reason = Some(Reason {
+                kind: ReasonKind::ApproximateSearch,
+                message: Some(format!(
+                    "{} store may have missed states (estimated probability {probability:.3e})",
+                    search.store.as_str()
+                )),
+            });
+        }
+    }
+    result
+}
+
+fn store_open_error(request: &CheckRequest, err: std::io::Error) -> CheckResult {
+    let kind = match err.kind() {
+        std::io::ErrorKind::InvalidInput => ReasonKind::InvalidInput,
+        _ => ReasonKind::InternalError,
+    };
+    error_check(
+        "check",
+        None,
+        request.target.clone(),
+        kind,
+        format!("failed to open state store: {err}"),
+    )
+}
+
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    fn create(parent: Option<&Path>) -> std::io::Result<Self> {
+        let parent = parent
+            .map(Path::to_path_buf)
+            .unwrap_or_else(std::env::temp_dir);
+        fs::create_dir_all(&parent)?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        let path = parent.join(format!("cspx-store-{}-{nanos}", std::process::id()));
+        fs::create_dir(&path)?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn run_refinement_assertion(
+    module: &cspx_core::ir::Module,
+    spec_proc: &str,
+    model: cspx_core::ir::RefinementOp,
+    impl_proc: &str,
+    target_desc: String,
+    search: &SearchOptions,
+) -> CheckResult {
+    let Some(spec_expr) = module
+        .declarations
+        .iter()
+        .find(|decl| decl.name.value == spec_proc)
+        .map(|decl| decl.expr.clone())
+    else {
+        return error_check(
+            "refine",
+            Some(refinement_op_str(model).to_string()),
             Some(target_desc),
             ReasonKind::InvalidInput,
             format!("undefined process: {spec_proc}"),
@@ -1018,7 +1734,18 @@ fn run_refinement_assertion(
         spec: spec_module,
         impl_: impl_module,
     };
-    checker.check(&request, &input)
+    let checkpoint = search.checkpoint.as_ref().map(CheckpointPlan::next_options);
+    checker.check_with_options(
+        &request,
+        &input,
+        RefinementOptions {
+            workers: search.workers,
+            limits: search.limits,
+            checkpoint: checkpoint.as_ref(),
+            max_counterexamples: search.max_counterexamples,
+            compression: Some(&mut search.compression.borrow_mut()),
+        },
+    )
 }
 
 fn property_kind_str(kind: cspx_core::ir::PropertyKind) -> &'static str {
@@ -1048,6 +1775,7 @@ fn parse_module_for_check(
     file: &Path,
     io_error: Option<&String>,
     assertion: &str,
+    limits: &RunLimits,
 ) -> Result<cspx_core::ir::Module, Box<CheckResult>> {
     if let Some(message) = io_error {
         return Err(Box::new(error_check(
@@ -1073,15 +1801,10 @@ fn parse_module_for_check(
     };
 
     let frontend = SimpleFrontend;
-    match frontend.parse_and_typecheck(&source, &file.to_string_lossy()) {
+    match frontend.parse_and_typecheck_with_limits(&source, &file.to_string_lossy(), limits) {
         Ok(output) => Ok(output.ir),
         Err(err) => {
-            let (status, reason_kind) = match err.kind {
-                FrontendErrorKind::UnsupportedSyntax => {
-                    (Status::Unsupported, ReasonKind::UnsupportedSyntax)
-                }
-                FrontendErrorKind::InvalidInput => (Status::Error, ReasonKind::InvalidInput),
-            };
+            let (status, reason_kind) = frontend_error_status(&err.kind);
             Err(Box::new(CheckResult {
                 name: "check".to_string(),
                 model: None,
@@ -1092,10 +1815,14 @@ fn parse_module_for_check(
                     message: Some(err.to_string()),
                 }),
                 counterexample: None,
+                counterexamples: Vec::new(),
                 stats: Some(Stats {
                     states: None,
                     transitions: None,
+                    depth: None,
                 }),
+                replay: None,
+                simulation: None,
             }))
         }
     }
@@ -1118,14 +1845,25 @@ fn error_check(
             message: Some(message),
         }),
         counterexample: None,
+        counterexamples: Vec::new(),
         stats: Some(Stats {
             states: None,
             transitions: None,
+            depth: None,
         }),
+        replay: None,
+        simulation: None,
     }
 }
 
-fn run_refine_check(args: &RefineArgs, io_error: Option<&String>) -> CheckResult {
+fn run_refine_check(
+    args: &RefineArgs,
+    io_error: Option<&String>,
+    workers: usize,
+    limits: &RunLimits,
+    checkpoint: Option<&CheckpointPlan>,
+    compression: &mut Vec<CompressionStats>,
+) -> CheckResult {
     if let Some(message) = io_error {
         return error_check(
             "refine",
@@ -1174,15 +1912,58 @@ fn run_refine_check(args: &RefineArgs, io_error: Option<&String>) -> CheckResult
     };
 
     let frontend = SimpleFrontend;
-    let spec_ir = match frontend.parse_and_typecheck(&spec_source, &args.spec.to_string_lossy()) {
-        Ok(output) => output.ir,
+    let spec_ir = if NormalisedSpec::is_norm_path(&args.spec) {
+        match NormalisedSpec::parse(&spec_source, &args.spec.to_string_lossy()) {
+            Ok(spec) => RefineSpec::Normalised(spec),
+            Err(err) => {
+                return error_check(
+                    "refine",
+                    Some(args.model.as_str().to_string()),
+                    Some(format!(
+                        "{} {}",
+                        args.spec.to_string_lossy(),
+                        args.impl_.to_string_lossy()
+                    )),
+                    ReasonKind::InvalidInput,
+                    err.to_string(),
+                )
+            }
+        }
+    } else {
+        match parse_refine_side(&frontend, &spec_source, &args.spec, limits) {
+            Ok(ir) => RefineSpec::Module(Box::new(ir)),
+            Err(err) => {
+                let (status, reason_kind) = frontend_error_status(&err.kind);
+                return CheckResult {
+                    name: "refine".to_string(),
+                    model: Some(args.model.as_str().to_string()),
+                    target: Some(format!(
+                        "{} {}",
+                        args.spec.to_string_lossy(),
+                        args.impl_.to_string_lossy()
+                    )),
+                    status,
+                    reason: Some(Reason {
+                        kind: reason_kind,
+                        message: Some(err.to_string()),
+                    }),
+                    counterexample: None,
+                    counterexamples: Vec::new(),
+                    stats: Some(Stats {
+                        states: None,
+                        transitions: None,
+                        depth: None,
+                    }),
+                    replay: None,
+                    simulation: None,
+                };
+            }
+        }
+    };
+    let impl_ir = match parse_refine_side(&frontend, &impl_source, &args.impl_, limits) {
+        Ok(ir) => ir,
         Err(err) => {
-            let (status, reason_kind) = match err.kind {
-                FrontendErrorKind::UnsupportedSyntax => {
-                    (Status::Unsupported, ReasonKind::UnsupportedSyntax)
-                }
-                FrontendErrorKind::InvalidInput => (Status::Error, ReasonKind::InvalidInput),
-            };
+            let (status, reason_kind) = frontend_error_status(&err.kind);
             return CheckResult {
                 name: "refine".to_string(),
                 model: Some(args.model.as_str().to_string()),
@@ -1197,63 +1978,569 @@ fn run_refine_check(args: &RefineArgs, io_error: Option<&String>) -> CheckResult
                     message: Some(err.to_string()),
                 }),
                 counterexample: None,
+                counterexamples: Vec::new(),
                 stats: Some(Stats {
                     states: None,
                     transitions: None,
+                    depth: None,
                 }),
+                replay: None,
+                simulation: None,
             };
         }
     };
-    let impl_ir = match frontend.parse_and_typecheck(&impl_source, &args.impl_.to_string_lossy()) {
-        Ok(output) => output.ir,
-        Err(err) => {
-            let (status, reason_kind) = match err.kind {
-                FrontendErrorKind::UnsupportedSyntax => {
-                    (Status::Unsupported, ReasonKind::UnsupportedSyntax)
-                }
-                FrontendErrorKind::InvalidInput => (Status::Error, ReasonKind::InvalidInput),
+
+    let checker = RefinementChecker;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Refine,
+        model: Some(match args.model {
+            RefinementModel::T => cspx_core::check::RefinementModel::T,
+            RefinementModel::F => cspx_core::check::RefinementModel::F,
+            RefinementModel::FD => cspx_core::check::RefinementModel::FD,
+        }),
+        target: Some(format!(
+            "{} {}",
+            args.spec.to_string_lossy(),
+            args.impl_.to_string_lossy()
+        )),
+    };
+    let checkpoint = checkpoint.map(CheckpointPlan::next_options);
+    let options = RefinementOptions {
+        workers,
+        limits,
+        checkpoint: checkpoint.as_ref(),
+        max_counterexamples: args.max_counterexamples,
+        compression: Some(compression),
+    };
+    match spec_ir {
+        RefineSpec::Module(spec_ir) => {
+            let input = RefinementInput {
+                spec: *spec_ir,
+                impl_: impl_ir,
             };
+            checker.check_with_options(&request, &input, options)
+        }
+        RefineSpec::Normalised(spec) => {
+            checker.check_normalised_with_options(&request, &spec, &impl_ir, options)
+        }
+    }
+}
+
+enum RefineSpec {
+    Module(Box<Module>),
+    Normalised(NormalisedSpec),
+}
+
+fn run_normalise(
+    args: &NormaliseArgs,
+    io_error: Option<&String>,
+    limits: &RunLimits,
+) -> CheckResult {
+    let target = Some(args.spec.to_string_lossy().to_string());
+    if let Some(message) = io_error {
+        return error_check(
+            "normalise",
+            None,
+            target,
+            ReasonKind::InvalidInput,
+            message.clone(),
+        );
+    }
+    let source = match fs::read_to_string(&args.spec) {
+        Ok(source) => source,
+        Err(err) => {
+            return error_check(
+                "normalise",
+                None,
+                target,
+                ReasonKind::InvalidInput,
+                format!("{}: {err}", args.spec.display()),
+            )
+        }
+    };
+
+    let frontend = SimpleFrontend;
+    let module = match parse_refine_side(&frontend, &source, &args.spec, limits) {
+        Ok(ir) => ir,
+        Err(err) => {
+            let (status, reason_kind) = frontend_error_status(&err.kind);
             return CheckResult {
-                name: "refine".to_string(),
-                model: Some(args.model.as_str().to_string()),
-                target: Some(format!(
-                    "{} {}",
-                    args.spec.to_string_lossy(),
-                    args.impl_.to_string_lossy()
-                )),
+                name: "normalise".to_string(),
+                model: None,
+                target,
                 status,
                 reason: Some(Reason {
                     kind: reason_kind,
                     message: Some(err.to_string()),
                 }),
                 counterexample: None,
+                counterexamples: Vec::new(),
                 stats: Some(Stats {
                     states: None,
                     transitions: None,
+                    depth: None,
                 }),
+                replay: None,
+                simulation: None,
             };
         }
     };
+    let spec = match NormalisedSpec::from_module_with_limits(&module, limits) {
+        Ok(spec) => spec,
+        Err(NormaliseError::Model(err)) => {
+            return error_check(
+                "normalise",
+                None,
+                target,
+                ReasonKind::InvalidInput,
+                err.to_string(),
+            )
+        }
+        Err(NormaliseError::Limit(limit)) => {
+            return CheckResult {
+                name: "normalise".to_string(),
+                model: None,
+                target,
+                status: limit.status(),
+                reason: Some(limit.reason()),
+                counterexample: None,
+                counterexamples: Vec::new(),
+                stats: Some(Stats {
+                    states: None,
+                    transitions: None,
+                    depth: None,
+                }),
+                replay: None,
+                simulation: None,
+            }
+        }
+    };
+    if let Err(err) = write_atomic(&args.out, spec.to_json().as_bytes()) {
+        return error_check(
+            "normalise",
+            None,
+            target,
+            ReasonKind::InternalError,
+            format!("{err:#}"),
+        );
+    }
 
-    let checker = RefinementChecker;
-    let request = CheckRequest {
-        command: cspx_core::check::CheckCommand::Refine,
-        model: Some(match args.model {
-            RefinementModel::T => cspx_core::check::RefinementModel::T,
-            RefinementModel::F => cspx_core::check::RefinementModel::F,
-            RefinementModel::FD => cspx_core::check::RefinementModel::FD,
+    CheckResult {
+        name: "normalise".to_string(),
+        model: None,
+        target,
+        status: Status::Pass,
+        reason: None,
+        counterexample: None,
+        counterexamples: Vec::new(),
+        stats: Some(Stats {
+            states: Some(spec.node_count() as u64),
+            transitions: Some(spec.transition_count() as u64),
+            depth: None,
         }),
-        target: Some(format!(
-            "{} {}",
-            args.spec.to_string_lossy(),
-            args.impl_.to_string_lossy()
+        replay: None,
+        simulation: None,
+    }
+}
+
+fn run_conform(args: &ConformArgs, io_error: Option<&String>, limits: &RunLimits) -> CheckResult {
+    let target = Some(args.process.clone());
+    let module = match parse_module_for_check(&args.model, io_error, &args.process, limits) {
+        Ok(module) => module,
+        Err(check) => {
+            return CheckResult {
+                name: "conform".to_string(),
+                ..*check
+            }
+        }
+    };
+    let Some(expr) = module
+        .declarations
+        .iter()
+        .find(|decl| decl.name.value == args.process)
+        .map(|decl| decl.expr.clone())
+    else {
+        return error_check(
+            "conform",
+            None,
+            target,
+            ReasonKind::InvalidInput,
+            format!("undefined process: {}", args.process),
+        );
+    };
+    let log = fs::read_to_string(&args.trace)
+        .map_err(|err| format!("{}: {err}", args.trace.display()))
+        .and_then(|source| {
+            parse_trace_log(&source, &args.trace.to_string_lossy()).map_err(|err| err.to_string())
+        });
+    let log = match log {
+        Ok(log) => log,
+        Err(message) => {
+            return error_check("conform", None, target, ReasonKind::InvalidInput, message)
+        }
+    };
+
+    let mut spec = module;
+    spec.entry = Some(expr);
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Conform,
+        model: None,
+        target,
+    };
+    ConformanceChecker.check_with_limits(&request, &ConformanceInput { spec, log }, limits)
+}
+
+fn run_simulate(
+    file: &Path,
+    io_error: Option<&String>,
+    options: SimulateOptions,
+    limits: &RunLimits,
+) -> Vec<CheckResult> {
+    let module = match parse_module_for_check(file, io_error, "simulate", limits) {
+        Ok(module) => module,
+        Err(check) => {
+            return vec![CheckResult {
+                name: "simulate".to_string(),
+                ..*check
+            }]
+        }
+    };
+    let simulator = Simulator { options };
+    if module.assertions.is_empty() {
+        let request = CheckRequest {
+            command: cspx_core::check::CheckCommand::Simulate,
+            model: None,
+            target: Some(property_kind_str(PropertyKind::DeadlockFree).to_string()),
+        };
+        return vec![simulator.check_property(
+            PropertyKind::DeadlockFree,
+            &request,
+            &module,
+            limits,
+        )];
+    }
+
+    let with_entry = |name: &str| {
+        let expr = module
+            .declarations
+            .iter()
+            .find(|decl| decl.name.value == name)
+            .map(|decl| decl.expr.clone())
+            .ok_or_else(|| format!("undefined process: {name}"))?;
+        let mut entry_module = module.clone();
+        entry_module.entry = Some(expr);
+        Ok::<_, String>(entry_module)
+    };
+    let mut out = Vec::new();
+    for assertion in &module.assertions {
+        let model = match assertion {
+            cspx_core::ir::AssertionDecl::Refinement { model, .. } => Some(*model),
+            cspx_core::ir::AssertionDecl::Property { .. } => None,
+        };
+        let request = CheckRequest {
+            command: cspx_core::check::CheckCommand::Simulate,
+            model: model
+                .and_then(|model| refinement_model_from_str(Some(refinement_op_str(model)))),
+            target: Some(assertion_target(assertion)),
+        };
+        let check = match assertion {
+            cspx_core::ir::AssertionDecl::Property { target, kind, .. } => {
+                with_entry(&target.value)
+                    .map(|module| simulator.check_property(*kind, &request, &module, limits))
+            }
+            cspx_core::ir::AssertionDecl::Refinement { spec, impl_, .. } => with_entry(&spec.value)
+                .and_then(|spec| {
+                    let impl_ = with_entry(&impl_.value)?;
+                    let input = RefinementInput { spec, impl_ };
+                    Ok(simulator.check_refinement(&request, &input, limits))
+                }),
+        };
+        out.push(check.unwrap_or_else(|message| {
+            error_check(
+                "simulate",
+                model.map(|model| refinement_op_str(model).to_string()),
+                request.target.clone(),
+                ReasonKind::InvalidInput,
+                message,
+            )
+        }));
+    }
+    out
+}
+
+#[derive(Deserialize)]
+struct StoredResult {
+    invocation: StoredInvocation,
+    checks: Vec<CheckResult>,
+}
+
+#[derive(Deserialize)]
+struct StoredInvocation {
+    command: String,
+}
+
+enum ReplayModels {
+    Check(Box<Module>),
+    Refine {
+        spec: RefineSpec,
+        impl_: Box<Module>,
+    },
+}
+
+fn run_replay(
+    args: &ReplayArgs,
+    io_error: Option<&String>,
+    limits: &RunLimits,
+) -> Vec<CheckResult> {
+    let target = Some(args.result.to_string_lossy().to_string());
+    if let Some(message) = io_error {
+        return vec![error_check(
+            "replay",
+            None,
+            target,
+            ReasonKind::InvalidInput,
+            message.clone(),
+        )];
+    }
+    let stored = fs::read_to_string(&args.result)
+        .map_err(|err| err.to_string())
+        .and_then(|source| {
+            serde_json::from_str::<StoredResult>(&source).map_err(|err| err.to_string())
+        });
+    let stored = match stored {
+        Ok(stored) => stored,
+        Err(err) => {
+            return vec![error_check(
+                "replay",
+                None,
+                target,
+                ReasonKind::InvalidInput,
+                format!("{}: {err}", args.result.display()),
+            )]
+        }
+    };
+    let checks = stored
+        .checks
+        .into_iter()
+        .filter(|check| check.counterexample.is_some())
+        .collect::<Vec<_>>();
+    if checks.is_empty() {
+        return vec![error_check(
+            "replay",
+            None,
+            target,
+            ReasonKind::InvalidInput,
+            format!("{}: no counterexample to replay", args.result.display()),
+        )];
+    }
+
+    let models = match load_replay_models(&stored.invocation.command, &args.files, limits) {
+        Ok(models) => models,
+        Err((status, kind, message)) => {
+            return vec![CheckResult {
+                status,
+                ..error_check("replay", None, target, kind, message)
+            }]
+        }
+    };
+    checks
+        .into_iter()
+        .map(|check| replay_stored_check(&models, check))
+        .collect()
+}
+
+fn load_replay_models(
+    command: &str,
+    files: &[PathBuf],
+    limits: &RunLimits,
+) -> Result<ReplayModels, (Status, ReasonKind, String)> {
+    let frontend = SimpleFrontend;
+    let read = |path: &PathBuf| {
+        fs::read_to_string(path).map_err(|err| {
+            (
+                Status::Error,
+                ReasonKind::InvalidInput,
+                format!("{}: {err}", path.display()),
+            )
+        })
+    };
+    let parse = |path: &PathBuf| {
+        let source = read(path)?;
+        parse_refine_side(&frontend, &source, path, limits).map_err(|err| {
+            let (status, kind) = frontend_error_status(&err.kind);
+            (status, kind, err.to_string())
+        })
+    };
+    match (command, files) {
+        ("refine", [spec, impl_]) => {
+            let spec = if NormalisedSpec::is_norm_path(spec) {
+                let source = read(spec)?;
+                NormalisedSpec::parse(&source, &spec.to_string_lossy())
+                    .map(RefineSpec::Normalised)
+                    .map_err(|err| (Status::Error, ReasonKind::InvalidInput, err.to_string()))?
+            } else {
+                RefineSpec::Module(Box::new(parse(spec)?))
+            };
+            Ok(ReplayModels::Refine {
+                spec,
+                impl_: Box::new(parse(impl_)?),
+            })
+        }
+        ("refine", _) => Err((
+            Status::Error,
+            ReasonKind::InvalidInput,
+            "a refine result replays against <spec> <impl>".to_string(),
+        )),
+        (_, [file]) => Ok(ReplayModels::Check(Box::new(parse(file)?))),
+        (_, _) => Err((
+            Status::Error,
+            ReasonKind::InvalidInput,
+            format!("a {command} result replays against a single model file"),
         )),
+    }
+}
+
+fn replay_stored_check(models: &ReplayModels, check: CheckResult) -> CheckResult {
+    let Some(counterexample) = check.counterexample.clone() else {
+        return check;
     };
-    let input = RefinementInput {
-        spec: spec_ir,
-        impl_: impl_ir,
+    let replay = match models {
+        ReplayModels::Refine { spec, impl_ } => refinement_model_from_str(check.model.as_deref())
+            .ok_or_else(|| format!("unknown refinement model: {:?}", check.model))
+            .and_then(|model| {
+                match spec {
+                    RefineSpec::Module(spec) => replay_refinement_input(
+                        &model,
+                        &RefinementInput {
+                            spec: (**spec).clone(),
+                            impl_: (**impl_).clone(),
+                        },
+                        &counterexample,
+                    ),
+                    RefineSpec::Normalised(spec) => {
+                        replay_normalised_refinement(&model, spec, impl_, &counterexample)
+                    }
+                }
+                .map_err(|err| err.to_string())
+            }),
+        ReplayModels::Check(module) => replay_assertion(module, &check, &counterexample),
+    };
+    match replay {
+        Ok(replay) => CheckResult {
+            name: check.name,
+            model: check.model,
+            target: check.target,
+            status: if replay.verdict == ReplayVerdict::StillFails {
+                Status::Fail
+            } else {
+                Status::Pass
+            },
+            reason: None,
+            counterexample: Some(counterexample),
+            counterexamples: Vec::new(),
+            stats: Some(Stats {
+                states: None,
+                transitions: None,
+                depth: None,
+            }),
+            replay: Some(replay),
+            simulation: None,
+        },
+        Err(message) => error_check(
+            &check.name,
+            check.model,
+            check.target,
+            ReasonKind::InvalidInput,
+            message,
+        ),
+    }
+}
+
+fn replay_assertion(
+    module: &Module,
+    check: &CheckResult,
+    counterexample: &Counterexample,
+) -> Result<Replay, String> {
+    let target = check.target.as_deref().unwrap_or_default();
+    let property = match target {
+        "deadlock free" => Some(PropertyKind::DeadlockFree),
+        "divergence free" => Some(PropertyKind::DivergenceFree),
+        "deterministic" => Some(PropertyKind::Deterministic),
+        _ => None,
+    };
+    if let Some(kind) = property {
+        return replay_property(kind, module, counterexample).map_err(|err| err.to_string());
+    }
+
+    let Some(assertion) = module
+        .assertions
+        .iter()
+        .find(|assertion| assertion_target(assertion) == target)
+    else {
+        return Err(format!("assertion not found in model: {target}"));
     };
-    checker.check(&request, &input)
+    let with_entry = |name: &str| {
+        let expr = module
+            .declarations
+            .iter()
+            .find(|decl| decl.name.value == name)
+            .map(|decl| decl.expr.clone())
+            .ok_or_else(|| format!("undefined process: {name}"))?;
+        let mut entry_module = module.clone();
+        entry_module.entry = Some(expr);
+        Ok::<_, String>(entry_module)
+    };
+    match assertion {
+        cspx_core::ir::AssertionDecl::Property { target, kind, .. } => {
+            replay_property(*kind, &with_entry(&target.value)?, counterexample)
+        }
+        cspx_core::ir::AssertionDecl::Refinement { spec, model, impl_ } => {
+            let input = RefinementInput {
+                spec: with_entry(&spec.value)?,
+                impl_: with_entry(&impl_.value)?,
+            };
+            let model = match model {
+                cspx_core::ir::RefinementOp::T => cspx_core::check::RefinementModel::T,
+                cspx_core::ir::RefinementOp::F => cspx_core::check::RefinementModel::F,
+                cspx_core::ir::RefinementOp::FD => cspx_core::check::RefinementModel::FD,
+            };
+            replay_refinement_input(&model, &input, counterexample)
+        }
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn refinement_model_from_str(model: Option<&str>) -> Option<cspx_core::check::RefinementModel> {
+    match model? {
+        "T" => Some(cspx_core::check::RefinementModel::T),
+        "F" => Some(cspx_core::check::RefinementModel::F),
+        "FD" => Some(cspx_core::check::RefinementModel::FD),
+        _ => None,
+    }
+}
+
+fn frontend_error_status(kind: &FrontendErrorKind) -> (Status, ReasonKind) {
+    match kind {
+        FrontendErrorKind::UnsupportedSyntax => {
+            (Status::Unsupported, ReasonKind::UnsupportedSyntax)
+        }
+        FrontendErrorKind::InvalidInput => (Status::Error, ReasonKind::InvalidInput),
+        FrontendErrorKind::Timeout => (Status::Timeout, ReasonKind::Timeout),
+    }
+}
+
+fn parse_refine_side(
+    frontend: &SimpleFrontend,
+    source: &str,
+    path: &Path,
+    limits: &RunLimits,
+) -> Result<Module, FrontendError> {
+    if ExplicitLtsFormat::from_path(path).is_some() {
+        return Ok(module_from_lts_file(path));
+    }
+    frontend
+        .parse_and_typecheck_with_limits(source, &path.to_string_lossy(), limits)
+        .map(|output| output.ir)
 }
 
 fn emit_json(result: &ResultJson, output: Option<&Path>) -> Result<()> {
@@ -1268,11 +2555,73 @@ fn emit_json(result: &ResultJson, output: Option<&Path>) -> Result<()> {
 }
 
 fn emit_text(result: &ResultJson, output: Option<&Path>) -> Result<()> {
-    let summary = format!(
+    let mut summary = format!(
         "status={} exit_code={}",
         status_label(&result.status),
         result.exit_code
     );
+    if result.approximate {
+        summary.push_str(" approximate=true");
+    }
+    for check in &result.checks {
+        let counterexamples = if check.counterexamples.is_empty() {
+            check.counterexample.as_slice()
+        } else {
+            &check.counterexamples
+        };
+        let explanations = counterexamples
+            .iter()
+            .filter_map(|counterexample| counterexample.explanation.as_deref());
+        for explanation in explanations {
+            summary.push_str(&format!("\n{}: {explanation}", check.name));
+        }
+        if let Some(simulation) = &check.simulation {
+            let states = check.stats.as_ref().and_then(|stats| stats.states);
+            summary.push_str(&format!(
+                "\n{}: {} runs, {} states, events seen: {}",
+                check.name,
+                simulation.runs,
+                states.unwrap_or(0),
+                simulation.events_seen.join(", ")
+            ));
+        }
+        if let Some(replay) = &check.replay {
+            summary.push_str(&format!(
+                "\n{}: {}",
+                check.name,
+                replay_verdict_label(replay.verdict)
+            ));
+            if let Some(step) = replay.step {
+                summary.push_str(&format!(" at step {step}"));
+            }
+            if !replay.offered.is_empty() {
+                summary.push_str(&format!(" (offered: {})", replay.offered.join(", ")));
+            }
+        }
+    }
+    if let Some(coverage) = result
+        .metrics
+        .as_ref()
+        .and_then(|metrics| metrics.coverage.as_ref())
+    {
+        let sections = [
+            ("never enabled event", &coverage.never_enabled_events),
+            ("unreached process", &coverage.unreached_processes),
+            ("unreached prefix", &coverage.unreached_prefixes),
+        ];
+        for (label, items) in sections {
+            for item in items {
+                let span = &item.source_span;
+                summary.push_str(&format!(
+                    "\ncoverage: {label} {} at {}:{}:{}",
+                    item.name, span.path, span.start_line, span.start_col
+                ));
+            }
+        }
+        if !coverage.complete {
+            summary.push_str("\ncoverage: incomplete, exploration stopped by a limit");
+        }
+    }
     if let Some(path) = output {
         write_atomic(path, summary.as_bytes())?;
         return Ok(());
@@ -1320,6 +2669,10 @@ fn summary_mode(command: &str) -> &'static str {
         "typecheck" => "typecheck",
         "check" => "assertions",
         "refine" => "refine",
+        "normalise" => "normalise",
+        "replay" => "replay",
+        "conform" => "conform",
+        "simulate" => "simulate",
         _ => "unknown",
     }
 }
@@ -1380,6 +2733,7 @@ fn reason_kind_label(kind: &ReasonKind) -> &'static str {
         ReasonKind::InternalError => "internal_error",
         ReasonKind::Timeout => "timeout",
         ReasonKind::OutOfMemory => "out_of_memory",
+        ReasonKind::ApproximateSearch => "approximate_search",
     }
 }
 
@@ -1415,6 +2769,14 @@ fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
     Ok(())
 }
 
+fn replay_verdict_label(verdict: ReplayVerdict) -> &'static str {
+    match verdict {
+        ReplayVerdict::StillFails => "still_fails",
+        ReplayVerdict::NoLongerValid => "no_longer_valid",
+        ReplayVerdict::NowPasses => "now_passes",
+    }
+}
+
 fn status_label(status: &Status) -> &'static str {
     match status {
         Status::Pass => "pass",
@@ -0,0 +1,232 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 5^6 interleaved states; only the state where every counter stopped
+// deadlocks.
+const STOPPING_COUNTERS: &str = r#"channel a, b, c, d : {0..5}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> STOP
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> STOP
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> STOP
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> STOP
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> STOP
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> STOP
+System = ((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5
+assert System :[deadlock free [F]]
+assert System :[deterministic [F]]
+System
+"#;
+
+// 4^6 interleaved counters, and the same counters with the first one
+// stopping after two rounds.
+const COUNTERS: &str = r#"channel a, b, c, d : {0..5}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> C4
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> C5
+((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5
+"#;
+
+const STOPPING_IMPL: &str = r#"channel a, b, c, d : {0..5}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> a.0 -> b.0 -> c.0 -> d.0 -> STOP
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> C4
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> C5
+((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5
+"#;
+
+fn write(dir: &Path, name: &str, source: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, source).expect("write model");
+    path
+}
+
+fn run(args: &[&str], expected_code: i32) -> Value {
+    let output = cargo_bin_cmd!("cspx")
+        .args(args)
+        .output()
+        .expect("run cspx");
+    assert_eq!(
+        output.status.code(),
+        Some(expected_code),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("json")
+}
+
+#[test]
+fn interrupted_check_resumes_to_the_same_result() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write(dir.path(), "model.cspm", STOPPING_COUNTERS);
+    let model = model.to_str().unwrap();
+    let checkpoint = dir.path().join("checkpoint");
+    let checkpoint = checkpoint.to_str().unwrap();
+
+    let expected = run(&["check", "--assert", "deadlock free", model], 1);
+    let stopped = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--memory-mb",
+            "1",
+            "--checkpoint-dir",
+            checkpoint,
+            "--checkpoint-every",
+            "500",
+            model,
+        ],
+        5,
+    );
+    assert_eq!(stopped["checks"][0]["status"], "out_of_memory");
+    assert!(Path::new(checkpoint)
+        .join("check-000")
+        .join("progress.json")
+        .exists());
+
+    let resumed = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--parallel",
+            "4",
+            "--checkpoint-dir",
+            checkpoint,
+            "--resume",
+            model,
+        ],
+        1,
+    );
+    assert_eq!(resumed["checks"], expected["checks"]);
+}
+
+#[test]
+fn interrupted_refinement_resumes_to_the_same_counterexample() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let spec = write(dir.path(), "spec.cspm", COUNTERS);
+    let impl_ = write(dir.path(), "impl.cspm", STOPPING_IMPL);
+    let (spec, impl_) = (spec.to_str().unwrap(), impl_.to_str().unwrap());
+    let checkpoint = dir.path().join("checkpoint");
+    let checkpoint = checkpoint.to_str().unwrap();
+
+    let expected = run(&["refine", "--model", "F", spec, impl_], 1);
+    let stopped = run(
+        &[
+            "refine",
+            "--model",
+            "F",
+            "--memory-mb",
+            "1",
+            "--checkpoint-dir",
+            checkpoint,
+            "--checkpoint-every",
+            "100",
+            spec,
+            impl_,
+        ],
+        5,
+    );
+    assert_eq!(stopped["checks"][0]["status"], "out_of_memory");
+
+    let resumed = run(
+        &[
+            "refine",
+            "--model",
+            "F",
+            "--checkpoint-dir",
+            checkpoint,
+            "--resume",
+            spec,
+            impl_,
+        ],
+        1,
+    );
+    assert_eq!(resumed["checks"], expected["checks"]);
+}
+
+#[test]
+fn every_assertion_gets_its_own_checkpoint() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write(dir.path(), "model.cspm", STOPPING_COUNTERS);
+    let model = model.to_str().unwrap();
+    let checkpoint = dir.path().join("checkpoint");
+
+    let args = [
+        "check",
+        "--all-assertions",
+        "--checkpoint-dir",
+        checkpoint.to_str().unwrap(),
+        model,
+    ];
+    let first = run(&args, 1);
+    assert!(checkpoint.join("check-000").join("journal.log").exists());
+    assert!(checkpoint.join("check-001").join("journal.log").exists());
+
+    let mut resume = args.to_vec();
+    resume.insert(1, "--resume");
+    let resumed = run(&resume, 1);
+    assert_eq!(resumed["checks"], first["checks"]);
+}
+
+#[test]
+fn checkpoint_of_another_input_is_rejected() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write(dir.path(), "model.cspm", STOPPING_COUNTERS);
+    let model = model.to_str().unwrap();
+    let checkpoint = dir.path().join("checkpoint");
+    let checkpoint = checkpoint.to_str().unwrap();
+
+    run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--memory-mb",
+            "1",
+            "--checkpoint-dir",
+            checkpoint,
+            model,
+        ],
+        5,
+    );
+    let value = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--por",
+            "--checkpoint-dir",
+            checkpoint,
+            "--resume",
+            model,
+        ],
+        2,
+    );
+    let check = &value["checks"][0];
+    assert_eq!(check["status"], "error");
+    let message = check["reason"]["message"].as_str().unwrap();
+    assert!(message.contains("different run"), "{message}");
+}
+
+#[test]
+fn resume_requires_a_checkpoint_dir() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write(dir.path(), "model.cspm", STOPPING_COUNTERS);
+    cargo_bin_cmd!("cspx")
+        .args([
+            "check",
+            "--assert",
+            "deadlock free",
+            "--resume",
+            model.to_str().unwrap(),
+        ])
+        .assert()
+        .code(2);
+}
@@ -0,0 +1,31 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+
+#[test]
+fn refine_accepts_aut_files_as_spec_and_impl() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let spec = dir.path().join("spec.aut");
+    let impl_ = dir.path().join("impl.json");
+    std::fs::write(&spec, "des (0, 2, 2)\n(0, \"a\", 1)\n(1, \"b\", 0)\n").expect("spec");
+    std::fs::write(
+        &impl_,
+        r#"{"initial": 0, "states": 2, "transitions": [{"from": 0, "label": "a", "to": 1}]}"#,
+    )
+    .expect("impl");
+
+    let output = cargo_bin_cmd!("cspx")
+        .args([
+            "refine",
+            "--model",
+            "T",
+            spec.to_str().unwrap(),
+            impl_.to_str().unwrap(),
+            "--format",
+            "json",
+        ])
+        .output()
+        .expect("run cspx");
+    assert_eq!(output.status.code(), Some(0));
+    let value: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(value["status"], "pass");
+}
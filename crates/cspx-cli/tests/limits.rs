@@ -0,0 +1,268 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 4^6 interleaved states, all deadlock free.
+const COUNTERS: &str = r#"channel a, b, c, d : {0..5}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> C4
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> C5
+System = ((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5
+assert System :[deadlock free [F]]
+System
+"#;
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+fn write_model(dir: &Path) -> PathBuf {
+    let path = dir.join("counters.cspm");
+    fs::write(&path, COUNTERS).expect("write model");
+    path
+}
+
+fn run(args: &[&str], expected_code: i32) -> Value {
+    let output = cargo_bin_cmd!("cspx")
+        .args(args)
+        .output()
+        .expect("run cspx");
+    assert_eq!(output.status.code(), Some(expected_code));
+    serde_json::from_slice(&output.stdout).expect("json")
+}
+
+#[test]
+fn check_stops_with_out_of_memory_under_tiny_budget() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write_model(dir.path());
+    let model = model.to_str().unwrap();
+
+    let value = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--store",
+            "memory",
+            "--memory-mb",
+            "1",
+            model,
+        ],
+        5,
+    );
+    assert_eq!(value["status"], "out_of_memory");
+    let check = &value["checks"][0];
+    assert_eq!(check["status"], "out_of_memory");
+    assert_eq!(check["reason"]["kind"], "out_of_memory");
+    let states = check["stats"]["states"].as_u64().expect("partial states");
+    assert!(states > 0 && states < 4096, "states={states}");
+    let memory = &value["metrics"]["memory"];
+    assert_eq!(memory["limit_bytes"], 1024 * 1024);
+    assert!(memory["peak_estimated_bytes"].as_u64().unwrap() > 1024 * 1024);
+
+    let schema_text = fs::read_to_string(repo_root().join("schemas/cspx-result.schema.json"))
+        .expect("read schema");
+    let schema_json: Value = serde_json::from_str(&schema_text).expect("parse schema");
+    let schema = JSONSchema::compile(&schema_json).expect("compile schema");
+    assert!(schema.validate(&value).is_ok());
+
+    // The hybrid store spills its states and keeps predecessors on disk, so
+    // the same budget is enough.
+    let value = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--store",
+            "hybrid",
+            "--memory-mb",
+            "1",
+            model,
+        ],
+        0,
+    );
+    assert_eq!(value["checks"][0]["stats"]["states"], 4096);
+    let peak = value["metrics"]["memory"]["peak_estimated_bytes"]
+        .as_u64()
+        .expect("peak");
+    assert!(peak <= 1024 * 1024, "peak={peak}");
+}
+
+#[test]
+fn check_passes_within_budget() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write_model(dir.path());
+    let model = model.to_str().unwrap();
+
+    let expected = run(&["check", "--assert", "deadlock free", model], 0);
+    assert!(expected["metrics"].get("memory").is_none());
+    assert!(expected.get("approximate").is_none());
+    let value = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--memory-mb",
+            "256",
+            model,
+        ],
+        0,
+    );
+    assert_eq!(value["checks"], expected["checks"]);
+    let peak = value["metrics"]["memory"]["peak_estimated_bytes"]
+        .as_u64()
+        .unwrap();
+    assert!(peak > 0 && peak < 256 * 1024 * 1024);
+}
+
+#[test]
+fn refine_stops_with_out_of_memory_under_tiny_budget() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write_model(dir.path());
+    let model = model.to_str().unwrap();
+
+    let value = run(
+        &["refine", "--model", "T", "--memory-mb", "1", model, model],
+        5,
+    );
+    let check = &value["checks"][0];
+    assert_eq!(check["name"], "refine");
+    assert_eq!(check["status"], "out_of_memory");
+    assert_eq!(check["reason"]["kind"], "out_of_memory");
+    assert!(check["stats"]["states"].as_u64().unwrap() < 4096);
+    assert_eq!(value["metrics"]["memory"]["limit_bytes"], 1024 * 1024);
+}
+
+// 4^10 interleaved states: far more than a 200 ms budget can explore.
+const LARGE_COUNTERS: &str = r#"channel a, b, c, d : {0..9}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> C4
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> C5
+C6 = a.6 -> b.6 -> c.6 -> d.6 -> C6
+C7 = a.7 -> b.7 -> c.7 -> d.7 -> C7
+C8 = a.8 -> b.8 -> c.8 -> d.8 -> C8
+C9 = a.9 -> b.9 -> c.9 -> d.9 -> C9
+System = (((((((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5) ||| C6) ||| C7) ||| C8) ||| C9)
+assert System :[deadlock free [F]]
+System
+"#;
+
+#[test]
+fn check_stops_with_timeout_and_partial_stats() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = dir.path().join("large.cspm");
+    fs::write(&model, LARGE_COUNTERS).expect("write model");
+    let model = model.to_str().unwrap();
+
+    for parallel in ["1", "4"] {
+        let value = run(
+            &[
+                "check",
+                "--assert",
+                "deadlock free",
+                "--timeout-ms",
+                "200",
+                "--parallel",
+                parallel,
+                model,
+            ],
+            4,
+        );
+        assert_eq!(value["status"], "timeout", "parallel={parallel}");
+        assert_eq!(value["invocation"]["timeout_ms"], 200);
+        let check = &value["checks"][0];
+        assert_eq!(check["status"], "timeout");
+        assert_eq!(check["reason"]["kind"], "timeout");
+        assert_eq!(check["reason"]["message"], "timed out after 200 ms");
+        assert!(check["stats"]["states"].as_u64().unwrap() > 0);
+        assert!(check["stats"]["depth"].as_u64().is_some());
+        assert!(value["duration_ms"].as_u64().unwrap() < 10_000);
+
+        let schema_text = fs::read_to_string(repo_root().join("schemas/cspx-result.schema.json"))
+            .expect("read schema");
+        let schema_json: Value = serde_json::from_str(&schema_text).expect("parse schema");
+        let schema = JSONSchema::compile(&schema_json).expect("compile schema");
+        assert!(schema.validate(&value).is_ok());
+    }
+}
+
+#[test]
+fn normalise_stops_with_timeout() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = dir.path().join("large.cspm");
+    fs::write(&model, LARGE_COUNTERS).expect("write model");
+    let out = dir.path().join("large.norm");
+
+    let value = run(
+        &[
+            "normalise",
+            "--timeout-ms",
+            "200",
+            model.to_str().unwrap(),
+            "-o",
+            out.to_str().unwrap(),
+        ],
+        4,
+    );
+    let check = &value["checks"][0];
+    assert_eq!(check["name"], "normalise");
+    assert_eq!(check["status"], "timeout");
+    assert_eq!(check["reason"]["message"], "timed out after 200 ms");
+    assert!(value["duration_ms"].as_u64().unwrap() < 10_000);
+    assert!(!out.exists());
+}
+
+#[test]
+fn expired_deadline_stops_every_command() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write_model(dir.path());
+    let model = model.to_str().unwrap();
+
+    let value = run(&["typecheck", "--timeout-ms", "0", model], 4);
+    assert_eq!(value["checks"][0]["name"], "typecheck");
+    assert_eq!(value["checks"][0]["reason"]["kind"], "timeout");
+
+    let value = run(
+        &["check", "--all-assertions", "--timeout-ms", "0", model],
+        4,
+    );
+    assert_eq!(value["checks"][0]["status"], "timeout");
+
+    let value = run(
+        &["refine", "--model", "F", "--timeout-ms", "0", model, model],
+        4,
+    );
+    assert_eq!(value["checks"][0]["name"], "refine");
+    assert_eq!(value["checks"][0]["status"], "timeout");
+}
+
+#[test]
+fn distant_deadline_keeps_the_verdict() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = write_model(dir.path());
+    let model = model.to_str().unwrap();
+
+    let expected = run(&["check", "--assert", "deadlock free", model], 0);
+    let value = run(
+        &[
+            "check",
+            "--assert",
+            "deadlock free",
+            "--timeout-ms",
+            "3600000",
+            model,
+        ],
+        0,
+    );
+    assert_eq!(value["checks"], expected["checks"]);
+}
@@ -0,0 +1,75 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::path::PathBuf;
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+fn run_json(args: &[&str]) -> (Option<i32>, Value) {
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(repo_root())
+        .args(args)
+        .args(["--format", "json"])
+        .output()
+        .expect("run cspx");
+    let value = serde_json::from_slice(&output.stdout).expect("json");
+    (output.status.code(), value)
+}
+
+#[test]
+fn refine_accepts_normalised_spec_in_place_of_source() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let norm = dir.path().join("spec.norm");
+    let problem = "problems/P211_failures_refine_fail_refusal_mismatch";
+    let spec = format!("{problem}/spec.cspm");
+    let impl_ = format!("{problem}/impl.cspm");
+
+    let (code, value) = run_json(&["normalise", &spec, "-o", norm.to_str().unwrap()]);
+    assert_eq!(code, Some(0));
+    assert_eq!(value["invocation"]["command"], "normalise");
+    assert_eq!(value["checks"][0]["stats"]["states"], 2);
+    let written: Value =
+        serde_json::from_str(&std::fs::read_to_string(&norm).expect("read norm")).expect("norm");
+    assert_eq!(written["format"], "cspx-norm");
+
+    for model in ["T", "F", "FD"] {
+        let (source_code, source) = run_json(&["refine", "--model", model, &spec, &impl_]);
+        let (norm_code, normalised) =
+            run_json(&["refine", "--model", model, norm.to_str().unwrap(), &impl_]);
+        assert_eq!(norm_code, source_code, "model {model}");
+        assert_eq!(
+            normalised["checks"][0]["status"], source["checks"][0]["status"],
+            "model {model}"
+        );
+        assert_eq!(
+            normalised["checks"][0]["counterexample"], source["checks"][0]["counterexample"],
+            "model {model}"
+        );
+    }
+}
+
+#[test]
+fn refine_rejects_malformed_normalised_spec() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let norm = dir.path().join("bad.norm");
+    std::fs::write(
+        &norm,
+        r#"{"format": "cspx-norm", "version": 1, "initial": 2, "nodes": []}"#,
+    )
+    .expect("write");
+
+    let (code, value) = run_json(&[
+        "refine",
+        "--model",
+        "T",
+        norm.to_str().unwrap(),
+        "problems/P211_failures_refine_fail_refusal_mismatch/impl.cspm",
+    ]);
+    assert_eq!(code, Some(2));
+    assert_eq!(value["checks"][0]["reason"]["kind"], "invalid_input");
+    let message = value["checks"][0]["reason"]["message"].as_str().unwrap();
+    assert!(message.contains("initial node 2 out of range"), "{message}");
+}
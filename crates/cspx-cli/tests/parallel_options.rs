@@ -134,3 +134,43 @@ fn explore_profile_on_off_keeps_result_and_stats_consistent() {
         Value::from(2)
     );
 }
+
+#[test]
+fn refine_parallel_deterministic_matches_serial_run() {
+    let root = repo_root();
+    let run = |parallel: bool| -> Value {
+        let mut args = vec![
+            "refine",
+            "--model",
+            "FD",
+            "problems/P220_fd_refine_fail_impl_diverges/spec.cspm",
+            "problems/P220_fd_refine_fail_impl_diverges/impl.cspm",
+            "--format",
+            "json",
+        ];
+        if parallel {
+            args.extend(["--parallel", "4", "--deterministic", "--seed", "1"]);
+        }
+        let output = cargo_bin_cmd!("cspx")
+            .current_dir(&root)
+            .args(&args)
+            .output()
+            .expect("run cspx");
+        serde_json::from_slice(&output.stdout).expect("parse json")
+    };
+
+    let serial = run(false);
+    let parallel = run(true);
+
+    assert_eq!(serial["status"], "fail");
+    assert_eq!(serial["status"], parallel["status"]);
+    assert_eq!(
+        serial["checks"][0]["status"],
+        parallel["checks"][0]["status"]
+    );
+    assert_eq!(serial["checks"][0]["stats"], parallel["checks"][0]["stats"]);
+    assert_eq!(
+        serial["checks"][0]["counterexample"],
+        parallel["checks"][0]["counterexample"]
+    );
+}
@@ -0,0 +1,54 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+fn check_statuses(model: &Path, por: bool) -> Vec<(String, String)> {
+    let mut cmd = cargo_bin_cmd!("cspx");
+    // Models that never finish (P310) are cut short and left out below.
+    cmd.args(["check", "--all-assertions", "--timeout-ms", "5000"]);
+    if por {
+        cmd.arg("--por");
+    }
+    let output = cmd.arg(model).output().expect("run cspx");
+    let result: Value = serde_json::from_slice(&output.stdout).expect("parse json");
+    result["checks"]
+        .as_array()
+        .expect("checks")
+        .iter()
+        .map(|check| {
+            (
+                check["target"].as_str().unwrap_or_default().to_string(),
+                check["status"].as_str().expect("status").to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn por_keeps_every_problem_verdict() {
+    let mut models = fs::read_dir(repo_root().join("problems"))
+        .expect("read problems")
+        .map(|entry| entry.expect("problem entry").path().join("model.cspm"))
+        .filter(|model| model.is_file())
+        .collect::<Vec<_>>();
+    models.sort();
+    assert!(!models.is_empty());
+    for model in models {
+        let reduced = check_statuses(&model, true);
+        let full = check_statuses(&model, false);
+        assert_eq!(reduced.len(), full.len(), "{}", model.display());
+        for (reduced, full) in reduced.iter().zip(&full) {
+            if reduced.1 == "timeout" || full.1 == "timeout" {
+                continue;
+            }
+            assert_eq!(reduced, full, "{}", model.display());
+        }
+    }
+}
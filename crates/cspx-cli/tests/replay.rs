@@ -0,0 +1,120 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+fn run_json(args: &[&str]) -> (Option<i32>, Value) {
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(repo_root())
+        .args(args)
+        .args(["--format", "json"])
+        .output()
+        .expect("run cspx");
+    let value = serde_json::from_slice(&output.stdout).expect("json");
+    (output.status.code(), value)
+}
+
+fn store(args: &[&str], path: &Path) {
+    let (_, value) = run_json(args);
+    fs::write(path, serde_json::to_vec(&value).expect("serialize")).expect("write result");
+}
+
+fn assert_schema(value: &Value) {
+    let schema_text = fs::read_to_string(repo_root().join("schemas/cspx-result.schema.json"))
+        .expect("read schema");
+    let schema_json: Value = serde_json::from_str(&schema_text).expect("parse schema");
+    let schema = JSONSchema::compile(&schema_json).expect("compile schema");
+    assert!(schema.validate(value).is_ok());
+}
+
+#[test]
+fn replay_reports_each_stored_check_against_the_edited_model() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let result = dir.path().join("old.json");
+    let model = "problems/P104_components_ok_but_system_deadlocks/model.cspm";
+    store(&["check", "--all-assertions", model], &result);
+
+    let (code, value) = run_json(&["replay", "--result", result.to_str().unwrap(), model]);
+    assert_schema(&value);
+    assert_eq!(code, Some(1));
+    assert_eq!(value["invocation"]["command"], "replay");
+    let checks = value["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0]["target"], "System :[deadlock free [F]]");
+    assert_eq!(checks[0]["status"], "fail");
+    assert_eq!(checks[0]["replay"]["verdict"], "still_fails");
+
+    let fixed = dir.path().join("fixed.cspm");
+    let source = fs::read_to_string(repo_root().join(model)).expect("read model");
+    fs::write(&fixed, source.replace("Q = b -> Q", "Q = a -> Q")).expect("write model");
+    let (code, value) = run_json(&[
+        "replay",
+        "--result",
+        result.to_str().unwrap(),
+        fixed.to_str().unwrap(),
+    ]);
+    assert_schema(&value);
+    assert_eq!(code, Some(0));
+    assert_eq!(value["checks"][0]["status"], "pass");
+    assert_eq!(value["checks"][0]["replay"]["verdict"], "now_passes");
+    assert_eq!(value["checks"][0]["replay"]["step"], 0);
+}
+
+#[test]
+fn replay_reports_the_step_a_trace_stops_being_possible() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let result = dir.path().join("old.json");
+    let model = "problems/P101_deadlock_after_one_sync/model.cspm";
+    store(&["check", "--assert", "deadlock free", model], &result);
+
+    let edited = dir.path().join("edited.cspm");
+    let source = fs::read_to_string(repo_root().join(model)).expect("read model");
+    fs::write(&edited, source.replace("ch!1", "ch!0")).expect("write model");
+    let (code, value) = run_json(&[
+        "replay",
+        "--result",
+        result.to_str().unwrap(),
+        edited.to_str().unwrap(),
+    ]);
+    assert_schema(&value);
+    assert_eq!(code, Some(0));
+    let replay = &value["checks"][0]["replay"];
+    assert_eq!(replay["verdict"], "no_longer_valid");
+    assert_eq!(replay["step"], 0);
+    assert_eq!(replay["offered"][0], "ch.0");
+}
+
+#[test]
+fn replay_of_a_refine_result_needs_spec_and_impl() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let result = dir.path().join("old.json");
+    let problem = "problems/P211_failures_refine_fail_refusal_mismatch";
+    let spec = format!("{problem}/spec.cspm");
+    let impl_ = format!("{problem}/impl.cspm");
+    store(&["refine", "--model", "F", &spec, &impl_], &result);
+    let result = result.to_str().unwrap();
+
+    let (code, value) = run_json(&["replay", "--result", result, &spec, &impl_]);
+    assert_schema(&value);
+    assert_eq!(code, Some(1));
+    assert_eq!(value["checks"][0]["name"], "refine");
+    assert_eq!(value["checks"][0]["model"], "F");
+    assert_eq!(value["checks"][0]["replay"]["verdict"], "still_fails");
+
+    let (code, value) = run_json(&["replay", "--result", result, &spec, &spec]);
+    assert_eq!(code, Some(0));
+    assert_eq!(value["checks"][0]["replay"]["verdict"], "now_passes");
+
+    let (code, value) = run_json(&["replay", "--result", result, &spec]);
+    assert_schema(&value);
+    assert_eq!(code, Some(2));
+    assert_eq!(value["checks"][0]["name"], "replay");
+    assert_eq!(value["checks"][0]["reason"]["kind"], "invalid_input");
+}
@@ -85,6 +85,246 @@ fn schema_check_all_assertions() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn schema_check_reports_compression_metrics() {
+    let schema = load_schema();
+    let actual = run_json(&[
+        "check",
+        "--assert",
+        "deadlock free",
+        "tests/cases/compress.cspm",
+        "--format",
+        "json",
+    ]);
+    let result = schema.validate(&actual);
+    assert!(result.is_ok());
+    let compression = actual["metrics"]["compression"]
+        .as_array()
+        .expect("compression metrics");
+    assert_eq!(compression.len(), 1);
+    assert_eq!(compression[0]["function"], "sbisim");
+    assert_eq!(compression[0]["states_before"], 2);
+    assert_eq!(compression[0]["source_span"]["start_line"], 4);
+}
+
+#[test]
+fn schema_check_reports_coverage() {
+    let schema = load_schema();
+    let model = "tests/cases/coverage.cspm";
+    let actual = run_json(&["check", "--all-assertions", "--coverage", model]);
+    assert!(schema.validate(&actual).is_ok());
+    let coverage = &actual["metrics"]["coverage"];
+    assert_eq!(coverage["complete"], true);
+    let names = |key: &str| {
+        coverage[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("never_enabled_events"), ["err", "out.0", "out.2"]);
+    assert_eq!(names("unreached_processes"), ["Unused"]);
+    assert_eq!(names("unreached_prefixes"), ["err"]);
+    assert_eq!(
+        coverage["unreached_prefixes"][0]["source_span"]["start_line"],
+        4
+    );
+    assert_eq!(
+        coverage["unreached_prefixes"][0]["source_span"]["start_col"],
+        10
+    );
+
+    let typecheck = run_json(&["typecheck", "--coverage", model]);
+    assert!(schema.validate(&typecheck).is_ok());
+    assert_eq!(typecheck["metrics"]["coverage"], *coverage);
+    let plain = run_json(&["typecheck", model]);
+    assert!(plain["metrics"].get("coverage").is_none());
+
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(repo_root())
+        .args(["typecheck", "--coverage", model, "--format", "text"])
+        .output()
+        .expect("run cspx");
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert!(stdout.contains(&format!(
+        "coverage: unreached process Unused at {model}:4:1"
+    )));
+}
+
+#[test]
+fn schema_check_reports_por_metrics() {
+    let schema = load_schema();
+    let model = "problems/P904_dining_philosophers_medium/model.cspm";
+    let full = run_json(&["check", "--assert", "deadlock free", model]);
+    let reduced = run_json(&["check", "--por", "--assert", "deadlock free", model]);
+    assert!(schema.validate(&reduced).is_ok());
+    assert!(full["metrics"].get("por").is_none());
+
+    assert_eq!(reduced["status"], full["status"]);
+    let por = &reduced["metrics"]["por"];
+    assert_eq!(por["states_explored"], reduced["metrics"]["states"]);
+    assert!(por["ample_expansions"].as_u64().unwrap() > 0);
+    assert!(por["skipped_transitions"].as_u64().unwrap() > 0);
+    assert!(
+        reduced["metrics"]["states"].as_u64().unwrap()
+            < full["metrics"]["states"].as_u64().unwrap()
+    );
+}
+
+#[test]
+fn schema_check_reports_symmetry_metrics() {
+    let schema = load_schema();
+    let model = "problems/P904_dining_philosophers_medium/model.cspm";
+    let full = run_json(&["check", "--assert", "deadlock free", model]);
+    let reduced = run_json(&["check", "--symmetry", "--assert", "deadlock free", model]);
+    assert!(schema.validate(&reduced).is_ok());
+    assert!(full["metrics"].get("symmetry").is_none());
+
+    assert_eq!(reduced["status"], full["status"]);
+    let symmetry = &reduced["metrics"]["symmetry"];
+    assert!(symmetry["canonicalised_successors"].as_u64().unwrap() > 0);
+    assert_eq!(full["metrics"]["states"], 32);
+    assert_eq!(reduced["metrics"]["states"], 6);
+}
+
+#[test]
+fn schema_check_determinism_with_symmetry_keeps_the_counterexample() {
+    let schema = load_schema();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let model = dir.path().join("model.cspm");
+    fs::write(
+        &model,
+        r#"channel a, b : {0..1}
+P0 = a.0 -> ((b.0 -> P0) |~| (a.0 -> P0))
+P1 = a.1 -> ((b.1 -> P1) |~| (a.1 -> P1))
+System = P0 ||| P1
+assert System :[deterministic [F]]
+"#,
+    )
+    .expect("write model");
+    let model = model.to_str().unwrap();
+    let full = run_json(&["check", "--assert", "deterministic", model]);
+    let reduced = run_json(&["check", "--symmetry", "--assert", "deterministic", model]);
+    assert!(schema.validate(&reduced).is_ok());
+    assert_eq!(full["status"], "fail");
+    assert_eq!(reduced["status"], "fail");
+    assert_eq!(reduced["exit_code"], 1);
+    assert_eq!(reduced["checks"], full["checks"]);
+}
+
+#[test]
+fn schema_check_reports_approximation() {
+    let schema = load_schema();
+    let model = "problems/P904_dining_philosophers_medium/model.cspm";
+    for store in ["bitstate", "hash-compact"] {
+        let actual = run_json(&["check", "--store", store, "--all-assertions", model]);
+        assert!(schema.validate(&actual).is_ok(), "store={store}");
+        assert!(actual["metrics"]["approximation"].is_object());
+    }
+}
+
+#[test]
+fn schema_check_divergence_lasso() {
+    let schema = load_schema();
+    let actual = run_json(&[
+        "check",
+        "--assert",
+        "divergence free",
+        "tests/cases/divergence.cspm",
+    ]);
+    assert!(schema.validate(&actual).is_ok());
+    let counterexample = &actual["checks"][0]["counterexample"];
+    assert_eq!(counterexample["type"], "lasso");
+    assert_eq!(counterexample["lasso"]["stem"][0]["label"], "b");
+    assert_eq!(
+        counterexample["lasso"]["cycle"].as_array().unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn schema_check_deadlock_explanation() {
+    let schema = load_schema();
+    let actual = run_json(&[
+        "check",
+        "--assert",
+        "deadlock free",
+        "problems/P104_components_ok_but_system_deadlocks/model.cspm",
+    ]);
+    assert!(schema.validate(&actual).is_ok());
+    let components = &actual["checks"][0]["counterexample"]["deadlock_explanation"]["components"];
+    assert_eq!(components[0]["process"], "P");
+    assert_eq!(components[0]["offers"][0], "a");
+    assert_eq!(components[0]["blocked"][0]["sync"][1], "b");
+    assert_eq!(components[1]["process"], "Q");
+}
+
+#[test]
+fn schema_check_max_counterexamples() {
+    let schema = load_schema();
+    let actual = run_json(&[
+        "check",
+        "--assert",
+        "deadlock free",
+        "--max-counterexamples",
+        "3",
+        "problems/P304_multiple_counterexamples/model.cspm",
+    ]);
+    assert!(schema.validate(&actual).is_ok());
+    let check = &actual["checks"][0];
+    assert_eq!(check["counterexamples"].as_array().unwrap().len(), 2);
+    assert_eq!(check["counterexamples"][0], check["counterexample"]);
+}
+
+#[test]
+fn schema_conform() {
+    let schema = load_schema();
+    let actual = run_json(&[
+        "conform",
+        "--process",
+        "Server",
+        "problems/P305_trace_conformance/model.cspm",
+        "problems/P305_trace_conformance/trace.jsonl",
+    ]);
+    assert!(schema.validate(&actual).is_ok());
+    let conformance = &actual["checks"][0]["counterexample"]["conformance"];
+    assert_eq!(conformance["span"]["start_line"], 5);
+    assert_eq!(conformance["allowed"], serde_json::json!(["req"]));
+}
+
+#[test]
+fn schema_simulate() {
+    let schema = load_schema();
+    let args = [
+        "simulate",
+        "--runs",
+        "10",
+        "--depth",
+        "50",
+        "--seed",
+        "42",
+        "problems/P306_random_simulation/model.cspm",
+    ];
+    let actual = run_json(&args);
+    assert!(schema.validate(&actual).is_ok());
+    let check = &actual["checks"][0];
+    assert_eq!(check["name"], "simulate");
+    assert_eq!(check["status"], "fail");
+    assert_eq!(check["counterexample"]["tags"][0], "deadlock");
+    assert_eq!(
+        check["simulation"]["events_seen"],
+        serde_json::json!(["ack", "err", "req"])
+    );
+    assert_eq!(run_json(&args)["checks"], actual["checks"]);
+
+    let model = "problems/P100_deadlock_free_min_rendezvous/model.cspm";
+    let passed = run_json(&["simulate", "--runs", "5", model]);
+    assert!(schema.validate(&passed).is_ok());
+    assert_eq!(passed["checks"][0]["reason"]["kind"], "approximate_search");
+    assert_eq!(passed["approximate"], true);
+}
+
 #[test]
 fn schema_refine() {
     let schema = load_schema();
@@ -101,6 +341,56 @@ fn schema_refine() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn schema_refine_event_spans() {
+    let schema = load_schema();
+    let problem = "problems/P201_traces_refine_fail_extra_event";
+    let actual = run_json(&[
+        "refine",
+        "--model",
+        "T",
+        &format!("{problem}/spec.cspm"),
+        &format!("{problem}/impl.cspm"),
+    ]);
+    assert!(schema.validate(&actual).is_ok());
+    let event = &actual["checks"][0]["counterexample"]["events"][0];
+    assert_eq!(event["label"], "b");
+    assert_eq!(event["spans"].as_array().unwrap().len(), 1);
+    assert!(event["spans"][0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("impl.cspm"));
+    assert_eq!(event["spans"][0]["start_line"], 4);
+    assert_eq!(event["spans"][0]["start_col"], 21);
+}
+
+#[test]
+fn schema_refine_failure() {
+    let schema = load_schema();
+    let problem = "problems/P211_failures_refine_fail_refusal_mismatch";
+    let spec = format!("{problem}/spec.cspm");
+    let impl_ = format!("{problem}/impl.cspm");
+    let actual = run_json(&["refine", "--model", "F", &spec, &impl_]);
+    assert!(schema.validate(&actual).is_ok());
+    let counterexample = &actual["checks"][0]["counterexample"];
+    assert_eq!(counterexample["type"], "failure");
+    assert_eq!(counterexample["failure"]["refusal"][0], "b");
+    assert_eq!(
+        counterexample["explanation"],
+        "after ⟨⟩ impl can refuse {b} but spec must accept one of {a,b}"
+    );
+
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(repo_root())
+        .args(["refine", "--model", "F", &spec, &impl_, "--format", "text"])
+        .output()
+        .expect("run cspx");
+    let stdout = String::from_utf8(output.stdout).expect("utf8 stdout");
+    assert!(
+        stdout.contains("refine: after ⟨⟩ impl can refuse {b} but spec must accept one of {a,b}")
+    );
+}
+
 #[test]
 fn schema_accepts_legacy_payload_without_metrics() {
     let schema = load_schema();
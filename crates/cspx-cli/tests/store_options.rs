@@ -0,0 +1,129 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::path::PathBuf;
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+fn run_checks(extra: &[&str]) -> Value {
+    let root = repo_root();
+    let mut args = vec![
+        "check",
+        "--all-assertions",
+        "problems/P101_deadlock_after_one_sync/model.cspm",
+        "--format",
+        "json",
+    ];
+    args.extend_from_slice(extra);
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(&root)
+        .args(&args)
+        .output()
+        .expect("run cspx");
+    assert_eq!(output.status.code(), Some(1));
+    let value: Value = serde_json::from_slice(&output.stdout).expect("json");
+    value["checks"].clone()
+}
+
+#[test]
+fn check_store_backends_agree_with_memory() {
+    let expected = run_checks(&[]);
+    let store_dir = tempfile::tempdir().expect("tempdir");
+    let store_dir_arg = store_dir.path().to_str().unwrap();
+    for store in ["disk", "hybrid"] {
+        let checks = run_checks(&["--store", store, "--store-dir", store_dir_arg]);
+        assert_eq!(checks, expected, "store={store}");
+    }
+    assert_eq!(
+        std::fs::read_dir(store_dir.path())
+            .expect("read dir")
+            .count(),
+        0,
+        "scratch store directories must be removed"
+    );
+}
+
+#[test]
+fn check_parallel_matches_serial() {
+    let expected = run_checks(&[]);
+    let checks = run_checks(&["--parallel", "4"]);
+    assert_eq!(checks, expected);
+}
+
+#[test]
+fn check_approximate_stores_keep_fail_counterexamples() {
+    let expected = run_checks(&[]);
+    for store in ["bitstate", "hash-compact"] {
+        let checks = run_checks(&["--store", store]);
+        let checks = checks.as_array().expect("checks");
+        assert_eq!(checks.len(), expected.as_array().unwrap().len());
+        for (check, expected) in checks.iter().zip(expected.as_array().unwrap()) {
+            assert_eq!(check["status"], expected["status"], "store={store}");
+            assert_eq!(check["stats"], expected["stats"], "store={store}");
+            if check["status"] == "pass" {
+                assert_eq!(check["reason"]["kind"], "approximate_search");
+            } else {
+                assert_eq!(check["counterexample"], expected["counterexample"]);
+            }
+        }
+    }
+}
+
+#[test]
+fn check_bitstate_reports_missed_state_probability() {
+    let root = repo_root();
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(&root)
+        .args([
+            "check",
+            "--assert",
+            "deadlock free",
+            "--store",
+            "bitstate",
+            "--bitstate-bits",
+            "6",
+            "problems/P904_dining_philosophers_medium/model.cspm",
+        ])
+        .output()
+        .expect("run cspx");
+    assert_eq!(output.status.code(), Some(0));
+    let value: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(value["status"], "pass");
+    assert_eq!(value["approximate"], true);
+    let approximation = &value["metrics"]["approximation"];
+    assert_eq!(approximation["store"], "bitstate");
+    assert!(approximation["missed_state_probability"].as_f64().unwrap() > 0.5);
+    assert_eq!(value["checks"][0]["status"], "pass");
+    assert_eq!(value["checks"][0]["reason"]["kind"], "approximate_search");
+    assert!(value["checks"][0]["stats"]["states"].as_u64().unwrap() < 32);
+}
+
+#[test]
+fn check_bitstate_rejects_bit_arrays_over_the_memory_budget() {
+    let root = repo_root();
+    let output = cargo_bin_cmd!("cspx")
+        .current_dir(&root)
+        .args([
+            "--memory-mb",
+            "16",
+            "check",
+            "--assert",
+            "deadlock free",
+            "--store",
+            "bitstate",
+            "--bitstate-bits",
+            "40",
+            "problems/P904_dining_philosophers_medium/model.cspm",
+        ])
+        .output()
+        .expect("run cspx");
+    assert_eq!(output.status.code(), Some(2));
+    let value: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(value["status"], "error");
+    assert_eq!(value["checks"][0]["reason"]["kind"], "invalid_input");
+    let message = value["checks"][0]["reason"]["message"].as_str().unwrap();
+    assert!(message.contains("exceeds the memory budget"), "{message}");
+}
@@ -1,4 +1,4 @@
-use crate::types::{Counterexample, Reason, Stats, Status};
+use crate::types::{Counterexample, Reason, Replay, Simulation, Stats, Status};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -7,6 +7,8 @@ pub enum CheckCommand {
     Typecheck,
     Check,
     Refine,
+    Conform,
+    Simulate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -42,7 +44,17 @@ pub struct CheckResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<Reason>,
     pub counterexample: Option<Counterexample>,
+    // Every distinct counterexample found when more than one was asked for;
+    // the first is also in `counterexample`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counterexamples: Vec<Counterexample>,
     pub stats: Option<Stats>,
+    // Set by `cspx replay` for the stored counterexample it re-ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<Replay>,
+    // Set by `cspx simulate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulation: Option<Simulation>,
 }
 
 pub trait Checker<I> {
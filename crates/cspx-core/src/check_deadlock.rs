@@ -6,21 +6,45 @@ use crate::counterexample_span::module_counterexample_spans;
 use crate::explain::Explainer;
 use crate::explain_simple::BasicExplainer;
 use crate::ir::{Module, PropertyKind};
-use crate::lts::TransitionProvider;
-use crate::lts_cspm::CspmTransitionProvider;
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{CspmState, CspmTransitionProvider};
+use crate::search::{
+    bfs_search, counterexample_path, failed_result, search_error_result, stopped_result,
+    InMemorySearch, PorProperty, SearchBackend, Violations,
+};
 use crate::types::{
-    Counterexample, CounterexampleEvent, CounterexampleType, Reason, ReasonKind, Stats, Status,
+    Counterexample, CounterexampleType, Minimality, Reason, ReasonKind, SourceSpan, Stats, Status,
 };
-use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
 
 #[derive(Debug, Default)]
 pub struct DeadlockChecker;
 
 impl Checker<Module> for DeadlockChecker {
     fn check(&self, request: &CheckRequest, input: &Module) -> CheckResult {
+        let mut search = InMemorySearch::new();
+        self.check_with(request, input, &mut search.backend(1))
+    }
+}
+
+impl DeadlockChecker {
+    pub fn check_with(
+        &self,
+        request: &CheckRequest,
+        input: &Module,
+        backend: &mut SearchBackend<'_, CspmState>,
+    ) -> CheckResult {
         let module = module_for_property_check(input, PropertyKind::DeadlockFree);
         match CspmTransitionProvider::from_module(&module) {
-            Ok(provider) => deadlock_free_check(&provider, request, &module),
+            Ok(provider) => {
+                backend.record_compression(provider.compression_stats());
+                deadlock_free_check(
+                    &provider,
+                    request,
+                    module_counterexample_spans(&module),
+                    backend,
+                )
+            }
             Err(err) => CheckResult {
                 name: "check".to_string(),
                 model: None,
@@ -31,13 +55,41 @@ impl Checker<Module> for DeadlockChecker {
                     message: Some(format_invalid_input(&err.to_string(), input)),
                 }),
                 counterexample: None,
+                counterexamples: Vec::new(),
                 stats: Some(Stats {
                     states: None,
                     transitions: None,
+                    depth: None,
                 }),
+                replay: None,
+                simulation: None,
             },
         }
     }
+
+    pub fn check_provider<P>(&self, request: &CheckRequest, provider: &P) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Eq + Hash,
+        P::Transition: TransitionLabel,
+    {
+        let mut search = InMemorySearch::new();
+        self.check_provider_with(request, provider, &mut search.backend(1))
+    }
+
+    pub fn check_provider_with<P>(
+        &self,
+        request: &CheckRequest,
+        provider: &P,
+        backend: &mut SearchBackend<'_, P::State>,
+    ) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Eq,
+        P::Transition: TransitionLabel,
+    {
+        deadlock_free_check(provider, request, Vec::new(), backend)
+    }
 }
 
 fn format_invalid_input(original: &str, module: &Module) -> String {
@@ -61,104 +113,85 @@ fn format_invalid_input(original: &str, module: &Module) -> String {
     lines.join("\n")
 }
 
-fn deadlock_free_check(
-    provider: &CspmTransitionProvider,
+fn deadlock_free_check<P>(
+    provider: &P,
     request: &CheckRequest,
-    module: &Module,
-) -> CheckResult {
-    let mut visited: HashMap<
-        <CspmTransitionProvider as TransitionProvider>::State,
-        Option<(
-            <CspmTransitionProvider as TransitionProvider>::State,
-            String,
-        )>,
-    > = HashMap::new();
-    let mut queue: VecDeque<<CspmTransitionProvider as TransitionProvider>::State> =
-        VecDeque::new();
-    let mut states: u64 = 0;
-    let mut transitions: u64 = 0;
-
-    let initial = provider.initial_state();
-    visited.insert(initial.clone(), None);
-    queue.push_back(initial.clone());
-    states += 1;
-
-    let mut deadlock_state = None;
-
-    while let Some(state) = queue.pop_front() {
-        let next = provider.transitions(&state);
-        transitions += next.len() as u64;
-        if next.is_empty() {
-            deadlock_state = Some(state);
-            break;
-        }
-        for (transition, next_state) in next {
-            if visited.contains_key(&next_state) {
-                continue;
-            }
-            visited.insert(
-                next_state.clone(),
-                Some((state.clone(), transition.label.clone())),
-            );
-            queue.push_back(next_state);
-            states += 1;
+    source_spans: Vec<SourceSpan>,
+    backend: &mut SearchBackend<'_, P::State>,
+) -> CheckResult
+where
+    P: TransitionProvider + Sync,
+    P::State: Eq,
+    P::Transition: TransitionLabel,
+{
+    let mut deadlocks = Violations::new(
+        backend.max_counterexamples,
+        |_state: &P::State, next: &[(P::Transition, P::State)]| next.is_empty().then_some(None),
+    );
+    let outcome = match bfs_search(
+        provider,
+        backend,
+        Some(PorProperty::Deadlock),
+        &mut deadlocks,
+    ) {
+        Ok(outcome) => outcome,
+        Err(err) => return search_error_result(request, err),
+    };
+    if deadlocks.found.is_empty() {
+        if let Some(limit) = outcome.stopped {
+            return stopped_result(request, limit, outcome.stats);
         }
+        return CheckResult {
+            name: "check".to_string(),
+            model: None,
+            target: request.target.clone(),
+            status: Status::Pass,
+            reason: None,
+            counterexample: None,
+            counterexamples: Vec::new(),
+            stats: Some(outcome.stats),
+            replay: None,
+            simulation: None,
+        };
     }
 
-    let stats = Stats {
-        states: Some(states),
-        transitions: Some(transitions),
+    // Partial-order reduction can skip the interleaving that reaches the
+    // deadlock soonest.
+    let minimality = if backend.por.is_none() {
+        Minimality::Shortest
+    } else {
+        Minimality::None
     };
-
-    if let Some(state) = deadlock_state {
-        let events = trace_events(&visited, state);
+    let mut counterexamples = Vec::new();
+    for (state, _) in deadlocks.found {
+        let (path, deadlocked) =
+            match counterexample_path(provider, backend, state, outcome.symmetric) {
+                Ok(path) => path,
+                Err(err) => return search_error_result(request, err),
+            };
+        let events = path
+            .into_iter()
+            .filter(|event| event.label != TAU_LABEL)
+            .collect();
         let counterexample = Counterexample {
             kind: CounterexampleType::Trace,
             events,
-            is_minimized: false,
+            minimality,
             tags: vec!["deadlock".to_string()],
-            source_spans: module_counterexample_spans(module),
+            source_spans: source_spans.clone(),
+            lasso: None,
+            failure: None,
+            deadlock_explanation: provider.deadlock_explanation(&deadlocked),
+            conformance: None,
+            explanation: None,
         };
         let explainer = BasicExplainer;
-        let counterexample = explainer.explain(counterexample);
-        return CheckResult {
-            name: "check".to_string(),
-            model: None,
-            target: request.target.clone(),
-            status: Status::Fail,
-            reason: None,
-            counterexample: Some(counterexample),
-            stats: Some(stats),
-        };
-    }
-
-    CheckResult {
-        name: "check".to_string(),
-        model: None,
-        target: request.target.clone(),
-        status: Status::Pass,
-        reason: None,
-        counterexample: None,
-        stats: Some(stats),
-    }
-}
-
-fn trace_events<S>(
-    visited: &HashMap<S, Option<(S, String)>>,
-    mut current: S,
-) -> Vec<CounterexampleEvent>
-where
-    S: Eq + std::hash::Hash + Clone,
-{
-    let mut labels = Vec::new();
-    while let Some(Some((prev, label))) = visited.get(&current) {
-        labels.push(label.clone());
-        current = prev.clone();
+        counterexamples.push(explainer.explain(counterexample));
     }
-    labels.reverse();
-    labels
-        .into_iter()
-        .filter(|label| label != "tau")
-        .map(|label| CounterexampleEvent { label })
-        .collect()
+    failed_result(
+        request,
+        counterexamples,
+        backend.max_counterexamples,
+        outcome.stats,
+    )
 }
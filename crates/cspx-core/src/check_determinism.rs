@@ -6,21 +6,47 @@ use crate::counterexample_span::module_counterexample_spans;
 use crate::explain::Explainer;
 use crate::explain_simple::BasicExplainer;
 use crate::ir::{Module, PropertyKind};
-use crate::lts::TransitionProvider;
-use crate::lts_cspm::CspmTransitionProvider;
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{CspmState, CspmTransitionProvider};
+use crate::search::{
+    bfs_search, counterexample_path, failed_result, search_error_result, stopped_result,
+    InMemorySearch, SearchBackend, Violations,
+};
 use crate::types::{
-    Counterexample, CounterexampleEvent, CounterexampleType, Reason, ReasonKind, Stats, Status,
+    Counterexample, CounterexampleEvent, CounterexampleType, Minimality, Reason, ReasonKind,
+    SourceSpan, Stats, Status,
 };
-use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::hash::Hash;
 
 #[derive(Debug, Default)]
 pub struct DeterminismChecker;
 
 impl Checker<Module> for DeterminismChecker {
     fn check(&self, request: &CheckRequest, input: &Module) -> CheckResult {
+        let mut search = InMemorySearch::new();
+        self.check_with(request, input, &mut search.backend(1))
+    }
+}
+
+impl DeterminismChecker {
+    pub fn check_with(
+        &self,
+        request: &CheckRequest,
+        input: &Module,
+        backend: &mut SearchBackend<'_, CspmState>,
+    ) -> CheckResult {
         let module = module_for_property_check(input, PropertyKind::Deterministic);
         match CspmTransitionProvider::from_module(&module) {
-            Ok(provider) => determinism_check(&provider, request, &module),
+            Ok(provider) => {
+                backend.record_compression(provider.compression_stats());
+                determinism_check(
+                    &provider,
+                    request,
+                    module_counterexample_spans(&module),
+                    backend,
+                )
+            }
             Err(err) => CheckResult {
                 name: "check".to_string(),
                 model: None,
@@ -31,13 +57,41 @@ impl Checker<Module> for DeterminismChecker {
                     message: Some(format_invalid_input(&err.to_string(), input)),
                 }),
                 counterexample: None,
+                counterexamples: Vec::new(),
                 stats: Some(Stats {
                     states: None,
                     transitions: None,
+                    depth: None,
                 }),
+                replay: None,
+                simulation: None,
             },
         }
     }
+
+    pub fn check_provider<P>(&self, request: &CheckRequest, provider: &P) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Eq + Hash,
+        P::Transition: TransitionLabel,
+    {
+        let mut search = InMemorySearch::new();
+        self.check_provider_with(request, provider, &mut search.backend(1))
+    }
+
+    pub fn check_provider_with<P>(
+        &self,
+        request: &CheckRequest,
+        provider: &P,
+        backend: &mut SearchBackend<'_, P::State>,
+    ) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Eq + Hash,
+        P::Transition: TransitionLabel,
+    {
+        determinism_check(provider, request, Vec::new(), backend)
+    }
 }
 
 fn format_invalid_input(original: &str, module: &Module) -> String {
@@ -61,159 +115,154 @@ fn format_invalid_input(original: &str, module: &Module) -> String {
     lines.join("\n")
 }
 
-fn determinism_check(
-    provider: &CspmTransitionProvider,
+fn determinism_check<P>(
+    provider: &P,
     request: &CheckRequest,
-    module: &Module,
-) -> CheckResult {
-    type State = <CspmTransitionProvider as TransitionProvider>::State;
-
-    let mut index_of: HashMap<State, usize> = HashMap::new();
-    let mut states: Vec<State> = Vec::new();
-    let mut prev: Vec<Option<(usize, String)>> = Vec::new();
-    let mut transitions_from: Vec<Vec<(String, usize)>> = Vec::new();
-    let mut tau_from: Vec<Vec<usize>> = Vec::new();
-    let mut queue: VecDeque<usize> = VecDeque::new();
-
-    let mut states_count: u64 = 0;
-    let mut transitions_count: u64 = 0;
-
-    let initial = provider.initial_state();
-    index_of.insert(initial.clone(), 0);
-    states.push(initial);
-    prev.push(None);
-    transitions_from.push(Vec::new());
-    tau_from.push(Vec::new());
-    queue.push_back(0);
-    states_count += 1;
-
-    while let Some(idx) = queue.pop_front() {
-        let state = &states[idx];
-        let next = provider.transitions(state);
-        transitions_count += next.len() as u64;
-        for (transition, next_state) in next {
-            let next_idx = if let Some(&existing) = index_of.get(&next_state) {
-                existing
-            } else {
-                let new_idx = states.len();
-                index_of.insert(next_state.clone(), new_idx);
-                states.push(next_state.clone());
-                prev.push(Some((idx, transition.label.clone())));
-                transitions_from.push(Vec::new());
-                tau_from.push(Vec::new());
-                queue.push_back(new_idx);
-                states_count += 1;
-                new_idx
-            };
-
-            transitions_from[idx].push((transition.label.clone(), next_idx));
-            if transition.label == "tau" {
-                tau_from[idx].push(next_idx);
-            }
-        }
-    }
-
-    let stats = Stats {
-        states: Some(states_count),
-        transitions: Some(transitions_count),
+    source_spans: Vec<SourceSpan>,
+    backend: &mut SearchBackend<'_, P::State>,
+) -> CheckResult
+where
+    P: TransitionProvider + Sync,
+    P::State: Eq + Hash,
+    P::Transition: TransitionLabel,
+{
+    // The search runs to completion so the stats cover the whole state space.
+    let mut nondeterminism = Violations::exhaustive(
+        backend.max_counterexamples,
+        |state: &P::State, _next: &[(P::Transition, P::State)]| {
+            first_nondeterministic_label(provider, state).map(Some)
+        },
+    );
+    let outcome = match bfs_search(provider, backend, None, &mut nondeterminism) {
+        Ok(outcome) => outcome,
+        Err(err) => return search_error_result(request, err),
     };
-
-    let tau_closures = compute_tau_closures(tau_from);
-
-    for (state_idx, closure) in tau_closures.iter().enumerate() {
-        let mut by_label: BTreeMap<String, BTreeSet<Vec<usize>>> = BTreeMap::new();
-        for &u in closure {
-            for (label, next) in &transitions_from[u] {
-                if label == "tau" {
-                    continue;
-                }
-                by_label
-                    .entry(label.clone())
-                    .or_default()
-                    .insert(tau_closures[*next].clone());
-            }
+    if nondeterminism.found.is_empty() {
+        if let Some(limit) = outcome.stopped {
+            return stopped_result(request, limit, outcome.stats);
         }
+        return CheckResult {
+            name: "check".to_string(),
+            model: None,
+            target: request.target.clone(),
+            status: Status::Pass,
+            reason: None,
+            counterexample: None,
+            counterexamples: Vec::new(),
+            stats: Some(outcome.stats),
+            replay: None,
+            simulation: None,
+        };
+    }
 
-        for (label, targets) in by_label {
-            if targets.len() <= 1 {
-                continue;
-            }
-
-            let mut events = trace_visible_events(&prev, state_idx);
-            events.push(CounterexampleEvent {
-                label: label.clone(),
-            });
-            let counterexample = Counterexample {
-                kind: CounterexampleType::Trace,
-                events,
-                is_minimized: false,
-                tags: vec!["nondeterminism".to_string(), format!("label:{label}")],
-                source_spans: module_counterexample_spans(module),
+    let mut counterexamples = Vec::new();
+    for (state, label) in nondeterminism.found {
+        let label = label.unwrap_or_default();
+        let (path, concrete) =
+            match counterexample_path(provider, backend, state, outcome.symmetric) {
+                Ok(path) => path,
+                Err(err) => return search_error_result(request, err),
             };
-            let explainer = BasicExplainer;
-            let counterexample = explainer.explain(counterexample);
+        let mut events = path
+            .into_iter()
+            .filter(|event| event.label != TAU_LABEL)
+            .collect::<Vec<_>>();
+        events.push(CounterexampleEvent {
+            label: label.clone(),
+            hidden_label: None,
+            spans: label_spans(provider, &concrete, &label),
+        });
+        let counterexample = Counterexample {
+            kind: CounterexampleType::Trace,
+            events,
+            minimality: Minimality::Shortest,
+            tags: vec!["nondeterminism".to_string(), format!("label:{label}")],
+            source_spans: source_spans.clone(),
+            lasso: None,
+            failure: None,
+            deadlock_explanation: None,
+            conformance: None,
+            explanation: None,
+        };
+        let explainer = BasicExplainer;
+        counterexamples.push(explainer.explain(counterexample));
+    }
+    failed_result(
+        request,
+        counterexamples,
+        backend.max_counterexamples,
+        outcome.stats,
+    )
+}
 
-            return CheckResult {
-                name: "check".to_string(),
-                model: None,
-                target: request.target.clone(),
-                status: Status::Fail,
-                reason: None,
-                counterexample: Some(counterexample),
-                stats: Some(stats),
+pub(crate) fn first_nondeterministic_label<P>(provider: &P, state: &P::State) -> Option<String>
+where
+    P: TransitionProvider,
+    P::State: Eq + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut by_label: BTreeMap<String, Vec<HashSet<P::State>>> = BTreeMap::new();
+    for u in tau_closure(provider, state.clone()) {
+        for (transition, next) in provider.transitions(&u) {
+            let Some(label) = transition.visible_label() else {
+                continue;
             };
+            let target = tau_closure(provider, next);
+            let targets = by_label.entry(label.to_string()).or_default();
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
         }
     }
-
-    CheckResult {
-        name: "check".to_string(),
-        model: None,
-        target: request.target.clone(),
-        status: Status::Pass,
-        reason: None,
-        counterexample: None,
-        stats: Some(stats),
-    }
+    by_label
+        .into_iter()
+        .find(|(_, targets)| targets.len() > 1)
+        .map(|(label, _)| label)
 }
 
-fn compute_tau_closures(tau_from: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
-    let n = tau_from.len();
-    let mut out = Vec::new();
-    for start in 0..n {
-        let mut seen = vec![false; n];
-        let mut queue = VecDeque::new();
-        seen[start] = true;
-        queue.push_back(start);
-        let mut closure = Vec::new();
-        while let Some(u) = queue.pop_front() {
-            closure.push(u);
-            for &v in &tau_from[u] {
-                if seen[v] {
-                    continue;
+// Every prefix offering `label` after the tau closure of `state`: the
+// competing branches of a nondeterministic choice.
+pub(crate) fn label_spans<P>(provider: &P, state: &P::State, label: &str) -> Vec<SourceSpan>
+where
+    P: TransitionProvider,
+    P::State: Eq + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut spans = Vec::new();
+    for u in tau_closure(provider, state.clone()) {
+        for (transition, next) in provider.transitions(&u) {
+            if transition.label() != label {
+                continue;
+            }
+            for span in provider.event_spans(&u, &transition, &next) {
+                if !spans.contains(&span) {
+                    spans.push(span);
                 }
-                seen[v] = true;
-                queue.push_back(v);
             }
         }
-        closure.sort();
-        out.push(closure);
     }
-    out
+    spans.sort_by(|a, b| {
+        (&a.path, a.start_line, a.start_col).cmp(&(&b.path, b.start_line, b.start_col))
+    });
+    spans
 }
 
-fn trace_visible_events(
-    prev: &[Option<(usize, String)>],
-    mut current: usize,
-) -> Vec<CounterexampleEvent> {
-    let mut labels = Vec::new();
-    while let Some((prev_idx, label)) = prev[current].as_ref() {
-        labels.push(label.clone());
-        current = *prev_idx;
+fn tau_closure<P>(provider: &P, start: P::State) -> HashSet<P::State>
+where
+    P: TransitionProvider,
+    P::State: Eq + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start.clone());
+    queue.push_back(start);
+    while let Some(u) = queue.pop_front() {
+        for (transition, v) in provider.transitions(&u) {
+            if transition.is_tau() && seen.insert(v.clone()) {
+                queue.push_back(v);
+            }
+        }
     }
-    labels.reverse();
-    labels
-        .into_iter()
-        .filter(|label| label != "tau")
-        .map(|label| CounterexampleEvent { label })
-        .collect()
+    seen
 }
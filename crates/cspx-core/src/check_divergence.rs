@@ -2,25 +2,56 @@ use crate::assertion_select::{
     list_property_assertion_candidates, module_for_property_check, property_kind_str,
 };
 use crate::check::{CheckRequest, CheckResult, Checker};
+use crate::checkpoint::{decode_state, put_bytes, RecordReader};
 use crate::counterexample_span::module_counterexample_spans;
 use crate::explain::Explainer;
 use crate::explain_simple::BasicExplainer;
 use crate::ir::{Module, PropertyKind};
-use crate::lts::TransitionProvider;
-use crate::lts_cspm::CspmTransitionProvider;
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{CspmState, CspmTransitionProvider};
+use crate::search::{
+    bfs_search, concrete_step, counterexample_path_to, failed_result, search_error_result,
+    step_event, stopped_result, InMemorySearch, PorProperty, SearchBackend, SearchVisitor,
+};
+use crate::state_codec::StateCodec;
+use crate::store::fingerprint;
 use crate::types::{
-    Counterexample, CounterexampleEvent, CounterexampleType, Reason, ReasonKind, Stats, Status,
+    Counterexample, CounterexampleEvent, CounterexampleType, Lasso, Minimality, Reason, ReasonKind,
+    SourceSpan, Stats, Status,
 };
 use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::io;
+use std::ops::ControlFlow;
 
 #[derive(Debug, Default)]
 pub struct DivergenceChecker;
 
 impl Checker<Module> for DivergenceChecker {
     fn check(&self, request: &CheckRequest, input: &Module) -> CheckResult {
+        let mut search = InMemorySearch::new();
+        self.check_with(request, input, &mut search.backend(1))
+    }
+}
+
+impl DivergenceChecker {
+    pub fn check_with(
+        &self,
+        request: &CheckRequest,
+        input: &Module,
+        backend: &mut SearchBackend<'_, CspmState>,
+    ) -> CheckResult {
         let module = module_for_property_check(input, PropertyKind::DivergenceFree);
         match CspmTransitionProvider::from_module(&module) {
-            Ok(provider) => divergence_free_check(&provider, request, &module),
+            Ok(provider) => {
+                backend.record_compression(provider.compression_stats());
+                divergence_free_check(
+                    &provider,
+                    request,
+                    module_counterexample_spans(&module),
+                    backend,
+                )
+            }
             Err(err) => CheckResult {
                 name: "check".to_string(),
                 model: None,
@@ -31,13 +62,125 @@ impl Checker<Module> for DivergenceChecker {
                     message: Some(format_invalid_input(&err.to_string(), input)),
                 }),
                 counterexample: None,
+                counterexamples: Vec::new(),
                 stats: Some(Stats {
                     states: None,
                     transitions: None,
+                    depth: None,
                 }),
+                replay: None,
+                simulation: None,
             },
         }
     }
+
+    pub fn check_provider<P>(&self, request: &CheckRequest, provider: &P) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Eq + Hash,
+        P::Transition: TransitionLabel,
+    {
+        let mut search = InMemorySearch::new();
+        self.check_provider_with(request, provider, &mut search.backend(1))
+    }
+
+    pub fn check_provider_with<P>(
+        &self,
+        request: &CheckRequest,
+        provider: &P,
+        backend: &mut SearchBackend<'_, P::State>,
+    ) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Eq + Hash,
+        P::Transition: TransitionLabel,
+    {
+        divergence_free_check(provider, request, Vec::new(), backend)
+    }
+}
+
+// Tau edges between visited states, which are named by their discovery index
+// and matched to tau targets by fingerprint, so the graph holds no states.
+// Visits come in discovery order, so the visit count is the discovery index.
+#[derive(Debug, Default)]
+struct TauGraph {
+    node_of: HashMap<u64, usize>,
+    fingerprints: Vec<u64>,
+    discovered: Vec<Option<u64>>,
+    adj: Vec<Vec<usize>>,
+    edges: u64,
+    visited: u64,
+}
+
+impl TauGraph {
+    fn node(&mut self, key: u64) -> usize {
+        if let Some(idx) = self.node_of.get(&key).copied() {
+            return idx;
+        }
+        let idx = self.fingerprints.len();
+        self.fingerprints.push(key);
+        self.node_of.insert(key, idx);
+        self.discovered.push(None);
+        self.adj.push(Vec::new());
+        idx
+    }
+
+    fn record<'s, S>(&mut self, state: &S, tau_targets: impl IntoIterator<Item = &'s S>)
+    where
+        S: Hash + 's,
+    {
+        for next_state in tau_targets {
+            let from = self.node(fingerprint(state));
+            self.discovered[from] = Some(self.visited);
+            let to = self.node(fingerprint(next_state));
+            self.adj[from].push(to);
+            self.edges += 1;
+        }
+        self.visited += 1;
+    }
+}
+
+// Only the tau edges of a visit are journaled; the graph is rebuilt from them
+// on resume.
+impl<S, T> SearchVisitor<S, T> for TauGraph
+where
+    S: Hash,
+    T: TransitionLabel,
+{
+    fn visit(&mut self, state: &S, next: &[(T, S)]) -> ControlFlow<()> {
+        let tau_targets = next
+            .iter()
+            .filter(|(transition, _)| transition.is_tau())
+            .map(|(_, next_state)| next_state);
+        self.record(state, tau_targets);
+        ControlFlow::Continue(())
+    }
+
+    fn journal(&self, codec: &dyn StateCodec<S>, _state: &S, next: &[(T, S)]) -> Vec<u8> {
+        let mut record = Vec::new();
+        for (_, next_state) in next.iter().filter(|(transition, _)| transition.is_tau()) {
+            put_bytes(&mut record, &codec.encode(next_state));
+        }
+        record
+    }
+
+    fn replay(&mut self, codec: &dyn StateCodec<S>, state: &S, record: &[u8]) -> io::Result<()> {
+        let mut reader = RecordReader::new(record);
+        let mut tau_targets = Vec::new();
+        while !reader.is_empty() {
+            tau_targets.push(decode_state(codec, reader.bytes()?)?);
+        }
+        self.record(state, &tau_targets);
+        Ok(())
+    }
+
+    fn memory_bytes(&self) -> u64 {
+        let nodes = self.node_of.capacity() * 2 * std::mem::size_of::<(u64, usize)>()
+            + self.fingerprints.capacity() * std::mem::size_of::<u64>()
+            + self.discovered.capacity() * std::mem::size_of::<Option<u64>>()
+            + self.adj.capacity() * std::mem::size_of::<Vec<usize>>();
+        nodes as u64 + self.edges * std::mem::size_of::<usize>() as u64
+    }
 }
 
 fn format_invalid_input(original: &str, module: &Module) -> String {
@@ -61,72 +204,38 @@ fn format_invalid_input(original: &str, module: &Module) -> String {
     lines.join("\n")
 }
 
-fn divergence_free_check(
-    provider: &CspmTransitionProvider,
+fn divergence_free_check<P>(
+    provider: &P,
     request: &CheckRequest,
-    module: &Module,
-) -> CheckResult {
-    type State = <CspmTransitionProvider as TransitionProvider>::State;
-
-    let mut visited: HashMap<State, Option<(State, String)>> = HashMap::new();
-    let mut order: Vec<State> = Vec::new();
-    let mut tau_edges: HashMap<State, Vec<State>> = HashMap::new();
-    let mut queue: VecDeque<State> = VecDeque::new();
-    let mut states: u64 = 0;
-    let mut transitions: u64 = 0;
-
-    let initial = provider.initial_state();
-    visited.insert(initial.clone(), None);
-    order.push(initial.clone());
-    queue.push_back(initial);
-    states += 1;
-
-    while let Some(state) = queue.pop_front() {
-        let next = provider.transitions(&state);
-        transitions += next.len() as u64;
-        for (transition, next_state) in next {
-            if transition.label == "tau" {
-                tau_edges
-                    .entry(state.clone())
-                    .or_default()
-                    .push(next_state.clone());
-            }
-            if visited.contains_key(&next_state) {
-                continue;
-            }
-            visited.insert(
-                next_state.clone(),
-                Some((state.clone(), transition.label.clone())),
-            );
-            order.push(next_state.clone());
-            queue.push_back(next_state);
-            states += 1;
-        }
-    }
-
-    let stats = Stats {
-        states: Some(states),
-        transitions: Some(transitions),
+    source_spans: Vec<SourceSpan>,
+    backend: &mut SearchBackend<'_, P::State>,
+) -> CheckResult
+where
+    P: TransitionProvider + Sync,
+    P::State: Eq + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut tau_graph = TauGraph::default();
+    let outcome = match bfs_search(
+        provider,
+        backend,
+        Some(PorProperty::Divergence),
+        &mut tau_graph,
+    ) {
+        Ok(outcome) => outcome,
+        Err(err) => return search_error_result(request, err),
     };
-
-    let mut index_of = HashMap::<State, usize>::new();
-    for (idx, state) in order.iter().cloned().enumerate() {
-        index_of.insert(state, idx);
-    }
-    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); order.len()];
-    for (idx, state) in order.iter().enumerate() {
-        let Some(nexts) = tau_edges.get(state) else {
-            continue;
-        };
-        for next in nexts {
-            let Some(next_idx) = index_of.get(next).copied() else {
-                continue;
-            };
-            adj[idx].push(next_idx);
-        }
+    if let Some(limit) = outcome.stopped {
+        return stopped_result(request, limit, outcome.stats);
     }
+    let stats = outcome.stats;
 
-    let Some(cycle_state_idx) = find_first_tau_cycle_node(&adj) else {
+    let cycle_nodes = find_tau_cycle_nodes(
+        &tau_graph.adj,
+        &tau_graph.discovered,
+        backend.max_counterexamples,
+    );
+    if cycle_nodes.is_empty() {
         return CheckResult {
             name: "check".to_string(),
             model: None,
@@ -134,55 +243,130 @@ fn divergence_free_check(
             status: Status::Pass,
             reason: None,
             counterexample: None,
+            counterexamples: Vec::new(),
             stats: Some(stats),
+            replay: None,
+            simulation: None,
         };
-    };
+    }
 
-    let cycle_state = order[cycle_state_idx].clone();
-    let mut events = trace_visible_events(&visited, cycle_state);
-    events.push(CounterexampleEvent {
-        label: "tau".to_string(),
-    });
-    let counterexample = Counterexample {
-        kind: CounterexampleType::Trace,
-        events,
-        is_minimized: false,
-        tags: vec!["divergence".to_string()],
-        source_spans: module_counterexample_spans(module),
-    };
-    let explainer = BasicExplainer;
-    let counterexample = explainer.explain(counterexample);
+    let mut counterexamples = Vec::new();
+    for cycle_state_idx in cycle_nodes {
+        let lasso = match divergence_lasso(
+            provider,
+            backend,
+            &tau_graph,
+            cycle_state_idx,
+            outcome.symmetric,
+        ) {
+            Ok(lasso) => lasso,
+            Err(err) => return search_error_result(request, err),
+        };
+        let mut events = lasso
+            .stem
+            .iter()
+            .filter(|event| event.label != TAU_LABEL)
+            .cloned()
+            .collect::<Vec<_>>();
+        let first_step = lasso.cycle.first();
+        events.push(CounterexampleEvent {
+            label: TAU_LABEL.to_string(),
+            hidden_label: first_step.and_then(|step| step.hidden_label.clone()),
+            spans: first_step
+                .map(|step| step.spans.clone())
+                .unwrap_or_default(),
+        });
+        let counterexample = Counterexample {
+            kind: CounterexampleType::Lasso,
+            events,
+            minimality: Minimality::None,
+            tags: vec!["divergence".to_string()],
+            source_spans: source_spans.clone(),
+            lasso: Some(lasso),
+            failure: None,
+            deadlock_explanation: None,
+            conformance: None,
+            explanation: None,
+        };
+        let explainer = BasicExplainer;
+        counterexamples.push(explainer.explain(counterexample));
+    }
+    failed_result(request, counterexamples, backend.max_counterexamples, stats)
+}
 
-    CheckResult {
-        name: "check".to_string(),
-        model: None,
-        target: request.target.clone(),
-        status: Status::Fail,
-        reason: None,
-        counterexample: Some(counterexample),
-        stats: Some(stats),
+// The path to the cycle node followed by the shortest tau cycle through it,
+// walked from the concrete end of the path.
+fn divergence_lasso<P>(
+    provider: &P,
+    backend: &SearchBackend<'_, P::State>,
+    tau_graph: &TauGraph,
+    cycle_state_idx: usize,
+    symmetric: bool,
+) -> io::Result<Lasso>
+where
+    P: TransitionProvider,
+    P::State: Hash,
+    P::Transition: TransitionLabel,
+{
+    let index = tau_graph.discovered[cycle_state_idx]
+        .ok_or_else(|| io::Error::other("tau cycle node was never visited"))?;
+    let (stem, mut concrete) = counterexample_path_to(provider, backend, index, symmetric)?;
+    let mut cycle = Vec::new();
+    for target in shortest_cycle(&tau_graph.adj, cycle_state_idx) {
+        let (transition, next) = concrete_step(
+            provider,
+            &concrete,
+            symmetric,
+            |next| fingerprint(next) == tau_graph.fingerprints[target],
+            TransitionLabel::is_tau,
+        )?;
+        cycle.push(step_event(provider, &concrete, &transition, &next));
+        concrete = next;
     }
+    Ok(Lasso { stem, cycle })
 }
 
-fn find_first_tau_cycle_node(adj: &[Vec<usize>]) -> Option<usize> {
-    let sccs = tarjan_scc(adj);
-    let mut in_cycle = vec![false; adj.len()];
-    for scc in sccs {
-        if scc.len() > 1 {
-            for v in scc {
-                in_cycle[v] = true;
+// The nodes of a shortest cycle through `start`, in order and ending with
+// `start`; empty when `start` is on no cycle.
+fn shortest_cycle(adj: &[Vec<usize>], start: usize) -> Vec<usize> {
+    let mut parent = vec![None; adj.len()];
+    let mut queue = VecDeque::from([start]);
+    while let Some(v) = queue.pop_front() {
+        for &w in &adj[v] {
+            if parent[w].is_some() {
+                continue;
             }
-            continue;
-        }
-        let v = scc[0];
-        if adj[v].contains(&v) {
-            in_cycle[v] = true;
+            parent[w] = Some(v);
+            if w == start {
+                let mut cycle = vec![start];
+                let mut current = v;
+                while current != start {
+                    cycle.push(current);
+                    current = parent[current].unwrap_or(start);
+                }
+                cycle.reverse();
+                return cycle;
+            }
+            queue.push_back(w);
         }
     }
-    in_cycle.iter().position(|&v| v)
+    Vec::new()
 }
 
-fn tarjan_scc(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
+// The earliest discovered node of each tau cycle, one per strongly connected
+// component, in discovery order and at most `max` of them.
+fn find_tau_cycle_nodes(adj: &[Vec<usize>], discovered: &[Option<u64>], max: usize) -> Vec<usize> {
+    let mut entries = tarjan_scc(adj)
+        .into_iter()
+        .filter(|scc| scc.len() > 1 || adj[scc[0]].contains(&scc[0]))
+        .filter_map(|scc| scc.into_iter().min_by_key(|&v| (discovered[v], v)))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|&v| (discovered[v], v));
+    entries.truncate(max.max(1));
+    entries
+}
+
+pub(crate) fn tarjan_scc(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
     #[allow(clippy::too_many_arguments)]
     fn strong_connect(
         v: usize,
@@ -249,23 +433,3 @@ fn tarjan_scc(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
     }
     out
 }
-
-fn trace_visible_events<S>(
-    visited: &HashMap<S, Option<(S, String)>>,
-    mut current: S,
-) -> Vec<CounterexampleEvent>
-where
-    S: Eq + std::hash::Hash + Clone,
-{
-    let mut labels = Vec::new();
-    while let Some(Some((prev, label))) = visited.get(&current) {
-        labels.push(label.clone());
-        current = prev.clone();
-    }
-    labels.reverse();
-    labels
-        .into_iter()
-        .filter(|label| label != "tau")
-        .map(|label| CounterexampleEvent { label })
-        .collect()
-}
@@ -1,21 +1,42 @@
 use crate::check::{CheckRequest, CheckResult, Checker, RefinementModel};
-use crate::counterexample_span::refinement_counterexample_spans;
+use crate::checkpoint::{
+    invalid, put_label, put_states, put_u64, read_states, CheckpointOptions, Journal, RecordReader,
+    RefinementCheckpoint,
+};
+use crate::compress::CompressionStats;
+use crate::counterexample_span::{merge_counterexample_spans, refinement_counterexample_spans};
 use crate::explain::Explainer;
 use crate::explain_simple::BasicExplainer;
 use crate::ir::Module;
-use crate::lts::TransitionProvider;
+use crate::limits::{LimitExceeded, RunLimits, ENTRY_OVERHEAD_BYTES};
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
 use crate::lts_cspm::{CspmStateCodec, CspmTransitionProvider};
 use crate::minimize::Minimizer;
 use crate::minimize_simple::TraceHeuristicMinimizer;
-use crate::state_codec::StateCodec;
+use crate::normalise::{NormalStateCodec, NormalisedSpec};
 use crate::types::{
-    Counterexample, CounterexampleEvent, CounterexampleType, Reason, ReasonKind, Stats, Status,
+    Counterexample, CounterexampleEvent, CounterexampleType, Failure, Minimality, Reason,
+    ReasonKind, Replay, ReplayVerdict, SourceSpan, Stats, Status,
 };
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::io;
+
+pub(crate) trait LabelledProvider:
+    TransitionProvider<State: Ord + Hash, Transition: TransitionLabel> + Sync
+{
+}
 
-const TAU: &str = "tau";
+impl<P> LabelledProvider for P where
+    P: TransitionProvider<State: Ord + Hash, Transition: TransitionLabel> + Sync
+{
+}
 
-type State = <CspmTransitionProvider as TransitionProvider>::State;
+pub(crate) type State<P> = <P as TransitionProvider>::State;
 
 #[derive(Debug, Default)]
 pub struct RefinementChecker;
@@ -28,10 +49,10 @@ pub struct RefinementInput {
 
 #[derive(Clone, Debug)]
 struct RefinementOutcome {
-    pub refines: bool,
-    pub failure: Option<RefinementFailure>,
+    pub failures: Vec<RefinementFailure>,
     pub stats: Stats,
     pub diagnostic_tags: Vec<String>,
+    pub stopped: Option<LimitExceeded>,
 }
 
 #[derive(Clone, Debug)]
@@ -41,104 +62,172 @@ struct RefinementFailure {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct NodeKey {
-    impl_sig: Vec<Vec<u8>>,
-    spec_sig: Vec<Vec<u8>>,
+struct NodeKey<SS, IS> {
+    impl_sig: Vec<IS>,
+    spec_sig: Vec<SS>,
 }
 
 #[derive(Clone, Debug)]
-struct Closure {
-    states: Vec<State>,
-    sig: Vec<Vec<u8>>,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-enum ProviderSide {
-    Spec,
-    Impl,
+pub(crate) struct Closure<St> {
+    pub(crate) states: Vec<St>,
 }
 
 enum NodeAction {
     Continue,
     Prune,
-    Fail(RefinementFailure),
+    Fail { tags: Vec<String>, diverges: bool },
 }
 
-type NextClosureByLabel = HashMap<String, Closure>;
-type NextClosureBySig = HashMap<Vec<Vec<u8>>, NextClosureByLabel>;
+type Predecessors<SS, IS> = HashMap<NodeKey<SS, IS>, (NodeKey<SS, IS>, String)>;
 
-#[derive(Debug, Default)]
-struct NextClosureCache {
-    entries: HashMap<ProviderSide, NextClosureBySig>,
-    hits: u64,
-    misses: u64,
+#[derive(Debug)]
+struct ClosureCache<St> {
+    next: HashMap<Vec<St>, HashMap<String, Closure<St>>>,
+    divergence: HashMap<Vec<St>, bool>,
+    bytes: u64,
 }
 
-impl NextClosureCache {
-    fn next_by_label(
+impl<St> Default for ClosureCache<St> {
+    fn default() -> Self {
+        Self {
+            next: HashMap::new(),
+            divergence: HashMap::new(),
+            bytes: 0,
+        }
+    }
+}
+
+impl<St: Clone + Ord + Hash> ClosureCache<St> {
+    fn next_by_label<P>(&self, provider: &P, from_closure: &Closure<St>, label: &str) -> Closure<St>
+    where
+        P: LabelledProvider<State = St>,
+    {
+        self.next
+            .get(from_closure.states.as_slice())
+            .and_then(|by_label| by_label.get(label))
+            .cloned()
+            .unwrap_or_else(|| next_by_label(provider, &from_closure.states, label))
+    }
+
+    fn has_tau_cycle<P>(&self, provider: &P, closure_states: &[St]) -> bool
+    where
+        P: LabelledProvider<State = St>,
+    {
+        self.divergence
+            .get(closure_states)
+            .copied()
+            .unwrap_or_else(|| closure_has_tau_cycle(provider, closure_states))
+    }
+
+    fn record_next(
         &mut self,
-        side: ProviderSide,
-        provider: &CspmTransitionProvider,
-        from_closure: &Closure,
+        from_closure: &Closure<St>,
         label: &str,
-    ) -> Closure {
-        if let Some(by_sig) = self.entries.get(&side) {
-            if let Some(by_label) = by_sig.get(from_closure.sig.as_slice()) {
-                if let Some(cached) = by_label.get(label) {
-                    self.hits = self.hits.saturating_add(1);
-                    return cached.clone();
-                }
-            }
+        next: &Closure<St>,
+        states_bytes: impl Fn(&[St]) -> u64,
+    ) -> bool {
+        if !self.next.contains_key(from_closure.states.as_slice()) {
+            self.bytes += states_bytes(&from_closure.states) + ENTRY_OVERHEAD_BYTES;
         }
-        self.misses = self.misses.saturating_add(1);
-        let computed = next_by_label(provider, &from_closure.states, label);
-        self.entries
-            .entry(side)
-            .or_default()
-            .entry(from_closure.sig.clone())
-            .or_default()
-            .insert(label.to_string(), computed.clone());
-        computed
+        let by_label = self.next.entry(from_closure.states.clone()).or_default();
+        if by_label.contains_key(label) {
+            return true;
+        }
+        self.bytes += label.len() as u64 + states_bytes(&next.states) + ENTRY_OVERHEAD_BYTES;
+        by_label.insert(label.to_string(), next.clone());
+        false
     }
-}
 
-type DivergenceBySig = HashMap<Vec<Vec<u8>>, bool>;
+    fn record_divergence(
+        &mut self,
+        closure_states: &[St],
+        diverges: bool,
+        states_bytes: impl Fn(&[St]) -> u64,
+    ) -> bool {
+        if self.divergence.contains_key(closure_states) {
+            return true;
+        }
+        self.bytes += states_bytes(closure_states) + ENTRY_OVERHEAD_BYTES;
+        self.divergence.insert(closure_states.to_vec(), diverges);
+        false
+    }
+
+    fn evict(&mut self) {
+        *self = Self::default();
+    }
+}
 
 #[derive(Debug, Default)]
-struct DivergenceCache {
-    entries: HashMap<ProviderSide, DivergenceBySig>,
+struct CacheCounters {
     hits: u64,
     misses: u64,
 }
 
-impl DivergenceCache {
-    fn has_tau_cycle(
-        &mut self,
-        side: ProviderSide,
-        closure_sig: &[Vec<u8>],
-        provider: &CspmTransitionProvider,
-        closure_states: &[State],
-    ) -> bool {
-        if let Some(by_sig) = self.entries.get(&side) {
-            if let Some(cached) = by_sig.get(closure_sig).copied() {
-                self.hits = self.hits.saturating_add(1);
-                return cached;
-            }
+impl CacheCounters {
+    fn record(&mut self, hit: bool) {
+        if hit {
+            self.hits = self.hits.saturating_add(1);
+        } else {
+            self.misses = self.misses.saturating_add(1);
+        }
+    }
+}
+
+// The search settings of a refinement check, as `SearchBackend` is for the
+// property checks.
+#[derive(Debug)]
+pub struct RefinementOptions<'a> {
+    pub workers: usize,
+    pub limits: &'a RunLimits,
+    pub checkpoint: Option<&'a CheckpointOptions>,
+    pub max_counterexamples: usize,
+    pub compression: Option<&'a mut Vec<CompressionStats>>,
+}
+
+impl<'a> RefinementOptions<'a> {
+    pub fn new(workers: usize, limits: &'a RunLimits) -> Self {
+        Self {
+            workers,
+            limits,
+            checkpoint: None,
+            max_counterexamples: 1,
+            compression: None,
         }
-        self.misses = self.misses.saturating_add(1);
-        let computed = closure_has_tau_cycle(provider, closure_states);
-        self.entries
-            .entry(side)
-            .or_default()
-            .insert(closure_sig.to_vec(), computed);
-        computed
     }
 }
 
 impl Checker<RefinementInput> for RefinementChecker {
     fn check(&self, request: &CheckRequest, input: &RefinementInput) -> CheckResult {
-        let model = request.model.clone().unwrap_or(RefinementModel::T);
+        self.check_with(request, input, 1)
+    }
+}
+
+impl RefinementChecker {
+    pub fn check_with(
+        &self,
+        request: &CheckRequest,
+        input: &RefinementInput,
+        workers: usize,
+    ) -> CheckResult {
+        self.check_with_limits(request, input, workers, &RunLimits::unlimited())
+    }
 
+    pub fn check_with_limits(
+        &self,
+        request: &CheckRequest,
+        input: &RefinementInput,
+        workers: usize,
+        limits: &RunLimits,
+    ) -> CheckResult {
+        self.check_with_options(request, input, RefinementOptions::new(workers, limits))
+    }
+
+    pub fn check_with_options(
+        &self,
+        request: &CheckRequest,
+        input: &RefinementInput,
+        mut options: RefinementOptions<'_>,
+    ) -> CheckResult {
         let spec_provider = match CspmTransitionProvider::from_module(&input.spec) {
             Ok(provider) => provider,
             Err(err) => {
@@ -151,73 +240,295 @@ impl Checker<RefinementInput> for RefinementChecker {
                 return invalid_input_result(request, err.to_string());
             }
         };
+        if let Some(out) = options.compression.take() {
+            out.extend_from_slice(spec_provider.compression_stats());
+            out.extend_from_slice(impl_provider.compression_stats());
+        }
 
-        let outcome = match model {
-            RefinementModel::T => trace_includes(&spec_provider, &impl_provider),
-            RefinementModel::F => failures_includes(&spec_provider, &impl_provider),
-            RefinementModel::FD => failures_divergences_includes(&spec_provider, &impl_provider),
-        };
+        let checkpoint = options.checkpoint.map(|options| RefinementCheckpoint {
+            options: options.clone(),
+            spec_codec: &CspmStateCodec,
+            impl_codec: &CspmStateCodec,
+        });
+        refinement_check(
+            request,
+            &spec_provider,
+            &impl_provider,
+            refinement_counterexample_spans(&input.spec, &input.impl_),
+            &options,
+            checkpoint.as_ref(),
+        )
+    }
 
-        if outcome.refines {
-            return CheckResult {
-                name: "refine".to_string(),
-                model: Some(model.as_str().to_string()),
-                target: request.target.clone(),
-                status: Status::Pass,
-                reason: None,
-                counterexample: None,
-                stats: Some(outcome.stats),
-            };
+    pub fn check_normalised(
+        &self,
+        request: &CheckRequest,
+        spec: &NormalisedSpec,
+        impl_: &Module,
+        workers: usize,
+    ) -> CheckResult {
+        self.check_normalised_with_limits(request, spec, impl_, workers, &RunLimits::unlimited())
+    }
+
+    pub fn check_normalised_with_limits(
+        &self,
+        request: &CheckRequest,
+        spec: &NormalisedSpec,
+        impl_: &Module,
+        workers: usize,
+        limits: &RunLimits,
+    ) -> CheckResult {
+        self.check_normalised_with_options(
+            request,
+            spec,
+            impl_,
+            RefinementOptions::new(workers, limits),
+        )
+    }
+
+    pub fn check_normalised_with_options(
+        &self,
+        request: &CheckRequest,
+        spec: &NormalisedSpec,
+        impl_: &Module,
+        mut options: RefinementOptions<'_>,
+    ) -> CheckResult {
+        let impl_provider = match CspmTransitionProvider::from_module(impl_) {
+            Ok(provider) => provider,
+            Err(err) => {
+                return invalid_input_result(request, err.to_string());
+            }
+        };
+        if let Some(out) = options.compression.take() {
+            out.extend_from_slice(impl_provider.compression_stats());
         }
 
-        let failure = outcome.failure.unwrap_or(RefinementFailure {
-            trace: Vec::new(),
-            tags: Vec::new(),
+        let checkpoint = options.checkpoint.map(|options| RefinementCheckpoint {
+            options: options.clone(),
+            spec_codec: &NormalStateCodec,
+            impl_codec: &CspmStateCodec,
         });
-        let events = failure
-            .trace
-            .into_iter()
-            .map(|label| CounterexampleEvent { label })
-            .collect::<Vec<_>>();
-
-        let mut tags = vec![
-            "refinement".to_string(),
-            format!("model:{}", model.as_str()),
-        ];
-        tags.extend(outcome.diagnostic_tags);
-        tags.extend(failure.tags);
-        let required_tags = tags.clone();
-
-        let counterexample = Counterexample {
-            kind: CounterexampleType::Trace,
-            events,
-            is_minimized: false,
-            tags,
-            source_spans: refinement_counterexample_spans(&input.spec, &input.impl_),
+        refinement_check(
+            request,
+            spec,
+            &impl_provider,
+            merge_counterexample_spans(spec.source_spans().to_vec(), impl_),
+            &options,
+            checkpoint.as_ref(),
+        )
+    }
+
+    pub fn check_providers<S, I>(&self, request: &CheckRequest, spec: &S, impl_: &I) -> CheckResult
+    where
+        S: TransitionProvider + Sync,
+        S::State: Ord + Hash,
+        S::Transition: TransitionLabel,
+        I: TransitionProvider + Sync,
+        I::State: Ord + Hash,
+        I::Transition: TransitionLabel,
+    {
+        self.check_providers_with(request, spec, impl_, 1)
+    }
+
+    pub fn check_providers_with<S, I>(
+        &self,
+        request: &CheckRequest,
+        spec: &S,
+        impl_: &I,
+        workers: usize,
+    ) -> CheckResult
+    where
+        S: TransitionProvider + Sync,
+        S::State: Ord + Hash,
+        S::Transition: TransitionLabel,
+        I: TransitionProvider + Sync,
+        I::State: Ord + Hash,
+        I::Transition: TransitionLabel,
+    {
+        refinement_check(
+            request,
+            spec,
+            impl_,
+            Vec::new(),
+            &RefinementOptions::new(workers, &RunLimits::unlimited()),
+            None,
+        )
+    }
+}
+
+fn refinement_check<S, I>(
+    request: &CheckRequest,
+    spec_provider: &S,
+    impl_provider: &I,
+    source_spans: Vec<SourceSpan>,
+    options: &RefinementOptions<'_>,
+    checkpoint: Option<&RefinementCheckpoint<'_, State<S>, State<I>>>,
+) -> CheckResult
+where
+    S: LabelledProvider,
+    I: LabelledProvider,
+{
+    let RefinementOptions {
+        workers,
+        limits,
+        max_counterexamples,
+        ..
+    } = *options;
+    let model = request.model.clone().unwrap_or(RefinementModel::T);
+    let outcome = match bfs_refinement(
+        spec_provider,
+        impl_provider,
+        &model,
+        workers,
+        limits,
+        checkpoint,
+        max_counterexamples.max(1),
+    ) {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            let mut result = invalid_input_result(request, err.to_string());
+            if let Some(reason) = result.reason.as_mut() {
+                reason.kind = ReasonKind::InternalError;
+            }
+            return result;
+        }
+    };
+
+    if outcome.failures.is_empty() {
+        let (status, reason) = match outcome.stopped {
+            Some(limit) => (limit.status(), Some(limit.reason())),
+            None => (Status::Pass, None),
+        };
+        return CheckResult {
+            name: "refine".to_string(),
+            model: Some(model.as_str().to_string()),
+            target: request.target.clone(),
+            status,
+            reason,
+            counterexample: None,
+            counterexamples: Vec::new(),
+            stats: Some(outcome.stats),
+            replay: None,
+            simulation: None,
         };
+    }
+
+    let mut counterexamples = Vec::new();
+    for failure in outcome.failures {
+        let counterexample = refinement_counterexample(
+            &model,
+            spec_provider,
+            impl_provider,
+            failure,
+            &outcome.diagnostic_tags,
+            source_spans.clone(),
+            limits,
+        );
+        // Minimisation can shorten two failures to the same trace.
+        if !counterexamples.contains(&counterexample) {
+            counterexamples.push(counterexample);
+        }
+    }
+    let counterexample = counterexamples.first().cloned();
+    if max_counterexamples <= 1 {
+        counterexamples.clear();
+    }
+
+    CheckResult {
+        name: "refine".to_string(),
+        model: Some(model.as_str().to_string()),
+        target: request.target.clone(),
+        status: Status::Fail,
+        reason: None,
+        counterexample,
+        counterexamples,
+        stats: Some(outcome.stats),
+        replay: None,
+        simulation: None,
+    }
+}
+
+fn refinement_counterexample<S, I>(
+    model: &RefinementModel,
+    spec_provider: &S,
+    impl_provider: &I,
+    failure: RefinementFailure,
+    diagnostic_tags: &[String],
+    source_spans: Vec<SourceSpan>,
+    limits: &RunLimits,
+) -> Counterexample
+where
+    S: LabelledProvider,
+    I: LabelledProvider,
+{
+    let events = failure
+        .trace
+        .into_iter()
+        .map(|label| CounterexampleEvent {
+            label,
+            hidden_label: None,
+            spans: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut tags = vec![
+        "refinement".to_string(),
+        format!("model:{}", model.as_str()),
+    ];
+    tags.extend(diagnostic_tags.iter().cloned());
+    tags.extend(failure.tags);
+    let required_tags = tags.clone();
+    // A trace mismatch is found one level early, where under F and FD a failing
+    // node one event shorter may still be pending.
+    let shortest =
+        *model == RefinementModel::T || !required_tags.iter().any(|tag| tag == "trace_mismatch");
+
+    let counterexample = Counterexample {
+        kind: CounterexampleType::Trace,
+        events,
+        minimality: Minimality::None,
+        tags,
+        source_spans,
+        lasso: None,
+        failure: None,
+        deadlock_explanation: None,
+        conformance: None,
+        explanation: None,
+    };
+    let mut counterexample = if shortest {
+        Counterexample {
+            minimality: Minimality::Shortest,
+            ..counterexample
+        }
+    } else {
+        // A deadline during minimisation keeps the failing verdict, unminimised.
+        let interrupted = Cell::new(false);
         let minimizer = TraceHeuristicMinimizer;
-        let counterexample = minimizer.minimize_with_oracle(counterexample, |candidate| {
+        let mut counterexample = minimizer.minimize_with_oracle(counterexample, |candidate| {
+            if interrupted.get() || limits.check_deadline().is_err() {
+                interrupted.set(true);
+                return false;
+            }
             counterexample_still_fails(
-                &model,
-                &spec_provider,
-                &impl_provider,
+                model,
+                spec_provider,
+                impl_provider,
                 candidate,
                 &required_tags,
             )
         });
-        let explainer = BasicExplainer;
-        let counterexample = explainer.explain(counterexample);
-
-        CheckResult {
-            name: "refine".to_string(),
-            model: Some(model.as_str().to_string()),
-            target: request.target.clone(),
-            status: Status::Fail,
-            reason: None,
-            counterexample: Some(counterexample),
-            stats: Some(outcome.stats),
+        if interrupted.get() {
+            counterexample.minimality = Minimality::None;
         }
+        counterexample
+    };
+    if let Some(failure) = failure_detail(spec_provider, impl_provider, &counterexample) {
+        counterexample.kind = CounterexampleType::Failure;
+        counterexample.failure = Some(failure);
     }
+    attach_impl_spans(impl_provider, &mut counterexample.events);
+    let explainer = BasicExplainer;
+    explainer.explain(counterexample)
 }
 
 fn invalid_input_result(request: &CheckRequest, message: String) -> CheckResult {
@@ -235,18 +546,23 @@ fn invalid_input_result(request: &CheckRequest, message: String) -> CheckResult
             message: Some(message),
         }),
         counterexample: None,
+        counterexamples: Vec::new(),
         stats: Some(Stats {
             states: None,
             transitions: None,
+            depth: None,
         }),
+        replay: None,
+        simulation: None,
     }
 }
 
-fn tau_closure(provider: &CspmTransitionProvider, seeds: Vec<State>) -> Closure {
-    let codec = CspmStateCodec;
-
-    let mut visited = HashSet::<State>::new();
-    let mut queue = VecDeque::<State>::new();
+pub(crate) fn tau_closure<P: LabelledProvider>(
+    provider: &P,
+    seeds: Vec<State<P>>,
+) -> Closure<State<P>> {
+    let mut visited = HashSet::<State<P>>::new();
+    let mut queue = VecDeque::<State<P>>::new();
     for seed in seeds {
         if visited.insert(seed.clone()) {
             queue.push_back(seed);
@@ -255,7 +571,7 @@ fn tau_closure(provider: &CspmTransitionProvider, seeds: Vec<State>) -> Closure
 
     while let Some(state) = queue.pop_front() {
         for (transition, next_state) in provider.transitions(&state) {
-            if transition.label != TAU {
+            if !transition.is_tau() {
                 continue;
             }
             if visited.insert(next_state.clone()) {
@@ -264,40 +580,35 @@ fn tau_closure(provider: &CspmTransitionProvider, seeds: Vec<State>) -> Closure
         }
     }
 
-    let mut pairs = visited
-        .into_iter()
-        .map(|state| (codec.encode(&state), state))
-        .collect::<Vec<_>>();
-    pairs.sort_by(|(a_bytes, _), (b_bytes, _)| a_bytes.cmp(b_bytes));
-
-    Closure {
-        sig: pairs.iter().map(|(bytes, _)| bytes.clone()).collect(),
-        states: pairs.into_iter().map(|(_bytes, state)| state).collect(),
-    }
+    let mut states = visited.into_iter().collect::<Vec<_>>();
+    states.sort();
+    Closure { states }
 }
 
-fn enabled_visible_labels(provider: &CspmTransitionProvider, states: &[State]) -> BTreeSet<String> {
+pub(crate) fn enabled_visible_labels<P: LabelledProvider>(
+    provider: &P,
+    states: &[State<P>],
+) -> BTreeSet<String> {
     let mut labels = BTreeSet::new();
     for state in states {
         for (transition, _next) in provider.transitions(state) {
-            if transition.label == TAU {
-                continue;
+            if let Some(label) = transition.visible_label() {
+                labels.insert(label.to_string());
             }
-            labels.insert(transition.label);
         }
     }
     labels
 }
 
-fn next_by_label(
-    provider: &CspmTransitionProvider,
-    from_closure: &[State],
+pub(crate) fn next_by_label<P: LabelledProvider>(
+    provider: &P,
+    from_closure: &[State<P>],
     label: &str,
-) -> Closure {
+) -> Closure<State<P>> {
     let mut seeds = Vec::new();
     for state in from_closure {
         for (transition, next_state) in provider.transitions(state) {
-            if transition.label == label {
+            if transition.label() == label {
                 seeds.push(next_state);
             }
         }
@@ -305,10 +616,14 @@ fn next_by_label(
     tau_closure(provider, seeds)
 }
 
-fn reconstruct_trace(
-    predecessor: &HashMap<NodeKey, (NodeKey, String)>,
-    to: &NodeKey,
-) -> Vec<String> {
+fn reconstruct_trace<SS, IS>(
+    predecessor: &Predecessors<SS, IS>,
+    to: &NodeKey<SS, IS>,
+) -> Vec<String>
+where
+    SS: Clone + Eq + Hash,
+    IS: Clone + Eq + Hash,
+{
     let mut out = Vec::new();
     let mut cur = to.clone();
     while let Some((prev, label)) = predecessor.get(&cur) {
@@ -319,29 +634,24 @@ fn reconstruct_trace(
     out
 }
 
-fn is_stable(provider: &CspmTransitionProvider, state: &State) -> bool {
+fn is_stable<P: LabelledProvider>(provider: &P, state: &State<P>) -> bool {
     provider
         .transitions(state)
         .into_iter()
-        .all(|(transition, _)| transition.label != TAU)
+        .all(|(transition, _)| !transition.is_tau())
 }
 
-fn offered_visible_labels(provider: &CspmTransitionProvider, state: &State) -> BTreeSet<String> {
+fn offered_visible_labels<P: LabelledProvider>(provider: &P, state: &State<P>) -> BTreeSet<String> {
     provider
         .transitions(state)
         .into_iter()
-        .filter_map(|(transition, _next)| {
-            if transition.label == TAU {
-                return None;
-            }
-            Some(transition.label)
-        })
+        .filter_map(|(transition, _next)| transition.visible_label().map(str::to_string))
         .collect()
 }
 
-fn stable_offer_sets(
-    provider: &CspmTransitionProvider,
-    closure_states: &[State],
+pub(crate) fn stable_offer_sets<P: LabelledProvider>(
+    provider: &P,
+    closure_states: &[State<P>],
 ) -> Vec<BTreeSet<String>> {
     let mut out = Vec::new();
     for state in closure_states {
@@ -353,7 +663,10 @@ fn stable_offer_sets(
     out
 }
 
-fn closure_has_tau_cycle(provider: &CspmTransitionProvider, closure_states: &[State]) -> bool {
+pub(crate) fn closure_has_tau_cycle<P: LabelledProvider>(
+    provider: &P,
+    closure_states: &[State<P>],
+) -> bool {
     if closure_states.is_empty() {
         return false;
     }
@@ -361,7 +674,7 @@ fn closure_has_tau_cycle(provider: &CspmTransitionProvider, closure_states: &[St
     const GRAY: u8 = 1;
     const BLACK: u8 = 2;
 
-    let mut index_of = HashMap::<State, usize>::new();
+    let mut index_of = HashMap::<State<P>, usize>::new();
     for (idx, state) in closure_states.iter().cloned().enumerate() {
         index_of.insert(state, idx);
     }
@@ -373,14 +686,14 @@ fn closure_has_tau_cycle(provider: &CspmTransitionProvider, closure_states: &[St
         cursor: usize,
     }
 
-    fn tau_successor_indices(
-        provider: &CspmTransitionProvider,
-        state: &State,
-        index_of: &HashMap<State, usize>,
+    fn tau_successor_indices<P: LabelledProvider>(
+        provider: &P,
+        state: &State<P>,
+        index_of: &HashMap<State<P>, usize>,
     ) -> Vec<usize> {
         let mut out = Vec::new();
         for (transition, next_state) in provider.transitions(state) {
-            if transition.label != TAU {
+            if !transition.is_tau() {
                 continue;
             }
             let Some(next_idx) = index_of.get(&next_state).copied() else {
@@ -434,255 +747,590 @@ fn closure_has_tau_cycle(provider: &CspmTransitionProvider, closure_states: &[St
     false
 }
 
-fn bfs_refinement<F>(
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
-    mut node_check: F,
-    mut next_closure_cache: Option<&mut NextClosureCache>,
-) -> RefinementOutcome
+type NodeKeyOf<S, I> = NodeKey<State<S>, State<I>>;
+type FrontierNode<S, I> = (NodeKeyOf<S, I>, Closure<State<I>>, Closure<State<S>>);
+type Frontier<S, I> = Vec<FrontierNode<S, I>>;
+
+struct NodeExpansion<SS, IS> {
+    divergence: Option<(bool, bool)>,
+    action: NodeAction,
+    successors: Vec<(String, Closure<IS>, Closure<SS>)>,
+}
+
+// One merged node: divergence verdicts, pruned/failed/evicted flags, edges
+// (target index, or the closures of a newly discovered target) and tags.
+const NEW_NODE: u64 = u64::MAX;
+const MISMATCH: u64 = u64::MAX - 1;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RefineProgress {
+    states: u64,
+}
+
+fn encode_merge(
+    divergence: Option<(bool, bool)>,
+    action: &NodeAction,
+    evicted: bool,
+    edge_count: u64,
+    edges: &[u8],
+) -> Vec<u8> {
+    let divergence = match divergence {
+        Some((spec_diverges, impl_diverges)) => {
+            1 | (u8::from(spec_diverges) << 1) | (u8::from(impl_diverges) << 2)
+        }
+        None => 0,
+    };
+    let action_byte = match action {
+        NodeAction::Continue => 0,
+        NodeAction::Prune => 1,
+        NodeAction::Fail { .. } => 2,
+    };
+    let mut record = vec![divergence, action_byte, u8::from(evicted)];
+    put_u64(&mut record, edge_count);
+    record.extend_from_slice(edges);
+    if let NodeAction::Fail { tags, diverges } = action {
+        record.push(u8::from(*diverges));
+        for tag in tags {
+            put_label(&mut record, Some(tag));
+        }
+    }
+    record
+}
+
+fn decode_merge<SS: Clone, IS: Clone>(
+    record: &[u8],
+    checkpoint: &RefinementCheckpoint<'_, SS, IS>,
+    keys: &[NodeKey<SS, IS>],
+) -> io::Result<(NodeExpansion<SS, IS>, bool)> {
+    let mut reader = RecordReader::new(record);
+    let divergence = match reader.u8()? {
+        0 => None,
+        bits => Some((bits & 2 != 0, bits & 4 != 0)),
+    };
+    let action_byte = reader.u8()?;
+    if action_byte > 2 {
+        return Err(invalid("invalid refinement checkpoint record"));
+    }
+    let evicted = reader.u8()? != 0;
+    let edge_count = reader.u64()?;
+    let mut fresh = Vec::new();
+    let mut successors = Vec::new();
+    for _ in 0..edge_count {
+        let label = reader
+            .label()?
+            .ok_or_else(|| invalid("refinement checkpoint edge without label"))?;
+        let target = reader.u64()?;
+        if target == MISMATCH {
+            successors.push((
+                label,
+                Closure {
+                    states: read_states(&mut reader, checkpoint.impl_codec)?,
+                },
+                Closure { states: Vec::new() },
+            ));
+            continue;
+        }
+        let key = if target == NEW_NODE {
+            fresh.push(NodeKey {
+                impl_sig: read_states(&mut reader, checkpoint.impl_codec)?,
+                spec_sig: read_states(&mut reader, checkpoint.spec_codec)?,
+            });
+            fresh.last()
+        } else {
+            usize::try_from(target).ok().and_then(|target| {
+                keys.get(target)
+                    .or_else(|| fresh.get(target.checked_sub(keys.len())?))
+            })
+        };
+        let key = key.ok_or_else(|| invalid("refinement checkpoint names an unknown node"))?;
+        successors.push((
+            label,
+            Closure {
+                states: key.impl_sig.clone(),
+            },
+            Closure {
+                states: key.spec_sig.clone(),
+            },
+        ));
+    }
+    let action = match action_byte {
+        0 => NodeAction::Continue,
+        1 => NodeAction::Prune,
+        _ => {
+            let diverges = reader.u8()? != 0;
+            let mut tags = Vec::new();
+            while !reader.is_empty() {
+                tags.extend(reader.label()?);
+            }
+            NodeAction::Fail { tags, diverges }
+        }
+    };
+    let expansion = NodeExpansion {
+        divergence,
+        action,
+        successors,
+    };
+    Ok((expansion, evicted))
+}
+
+// Nodes of a level are expanded in parallel but merged in queue order, so
+// the result does not depend on `workers`. Resuming feeds journaled nodes
+// through the same merge.
+fn bfs_refinement<S, I>(
+    spec: &S,
+    impl_: &I,
+    model: &RefinementModel,
+    workers: usize,
+    limits: &RunLimits,
+    checkpoint: Option<&RefinementCheckpoint<'_, State<S>, State<I>>>,
+    max_counterexamples: usize,
+) -> io::Result<RefinementOutcome>
 where
-    F: FnMut(&NodeKey, &[State], &[State], &HashMap<NodeKey, (NodeKey, String)>) -> NodeAction,
+    S: LabelledProvider,
+    I: LabelledProvider,
 {
+    let budget = limits.memory.as_ref();
+    let spec_bytes = |states: &[State<S>]| match budget {
+        Some(_) => states_bytes(spec, states),
+        None => 0,
+    };
+    let impl_bytes = |states: &[State<I>]| match budget {
+        Some(_) => states_bytes(impl_, states),
+        None => 0,
+    };
+    let key_bytes = |key: &NodeKeyOf<S, I>| impl_bytes(&key.impl_sig) + spec_bytes(&key.spec_sig);
+
+    let mut journal = None;
+    let mut replay = VecDeque::new();
+    let mut expected_states = 1;
+    if let Some(checkpoint) = checkpoint {
+        let (opened, progress) = Journal::open::<RefineProgress>(&checkpoint.options, |record| {
+            replay.push_back(record.to_vec());
+            Ok(())
+        })?;
+        journal = Some(opened);
+        expected_states = progress.unwrap_or_default().states.max(1);
+    }
+
     let impl0 = tau_closure(impl_, vec![impl_.initial_state()]);
     let spec0 = tau_closure(spec, vec![spec.initial_state()]);
 
     let initial_key = NodeKey {
-        impl_sig: impl0.sig.clone(),
-        spec_sig: spec0.sig.clone(),
+        impl_sig: impl0.states.clone(),
+        spec_sig: spec0.states.clone(),
     };
-    let mut visited = HashSet::<NodeKey>::new();
-    visited.insert(initial_key.clone());
+    let mut visited = HashMap::<NodeKeyOf<S, I>, u64>::new();
+    visited.insert(initial_key.clone(), 0);
+    let mut replay_keys = Vec::new();
+    if !replay.is_empty() {
+        replay_keys.push(initial_key.clone());
+    }
 
-    let mut predecessor = HashMap::<NodeKey, (NodeKey, String)>::new();
-    let mut queue = VecDeque::<(NodeKey, Closure, Closure)>::new();
-    queue.push_back((initial_key.clone(), impl0, spec0));
+    let mut predecessor = Predecessors::<State<S>, State<I>>::new();
+    let mut graph_bytes = key_bytes(&initial_key) + ENTRY_OVERHEAD_BYTES;
+    let mut frontier_bytes = 2 * key_bytes(&initial_key);
+    let mut frontier: Frontier<S, I> = vec![(initial_key, impl0, spec0)];
+
+    let mut spec_cache = ClosureCache::default();
+    let mut impl_cache = ClosureCache::default();
+    let mut closure_counters = CacheCounters::default();
+    let mut divergence_counters = CacheCounters::default();
+    let mut divergence_checks = 0u64;
+    let mut divergence_prunes = 0u64;
+    let mut impl_closure_max = 0u64;
+    let mut spec_closure_max = 0u64;
 
     let mut states_count: u64 = 1;
     let mut transitions_count: u64 = 0;
+    let mut depth: u64 = 0;
+
+    let pool = if workers > 1 {
+        Some(
+            ThreadPoolBuilder::new()
+                .num_threads(workers)
+                .build()
+                .map_err(|err| io::Error::other(err.to_string()))?,
+        )
+    } else {
+        None
+    };
 
-    while let Some((node_key, impl_closure, spec_closure)) = queue.pop_front() {
-        match node_check(
-            &node_key,
-            &impl_closure.states,
-            &spec_closure.states,
-            &predecessor,
-        ) {
-            NodeAction::Continue => {}
-            NodeAction::Prune => continue,
-            NodeAction::Fail(failure) => {
-                return RefinementOutcome {
-                    refines: false,
-                    failure: Some(failure),
-                    stats: Stats {
-                        states: Some(states_count),
-                        transitions: Some(transitions_count),
-                    },
-                    diagnostic_tags: Vec::new(),
-                }
+    let mut failures = Vec::new();
+    let mut failed_nodes = HashSet::new();
+    let mut mismatches = HashSet::new();
+    let mut stopped = None;
+    'levels: while !frontier.is_empty() {
+        let replayed = frontier.len().min(replay.len());
+        let expand = |(_key, impl_closure, spec_closure): &FrontierNode<S, I>| {
+            if limits.check_deadline().is_err() {
+                return None;
             }
-        }
-
-        let labels = enabled_visible_labels(impl_, &impl_closure.states);
-        for label in labels {
-            transitions_count += 1;
-
-            let impl_next = if let Some(cache) = next_closure_cache.as_deref_mut() {
-                cache.next_by_label(ProviderSide::Impl, impl_, &impl_closure, &label)
-            } else {
-                next_by_label(impl_, &impl_closure.states, &label)
+            Some(expand_node(
+                spec,
+                impl_,
+                model,
+                &spec_cache,
+                &impl_cache,
+                impl_closure,
+                spec_closure,
+            ))
+        };
+        let live = &frontier[replayed..];
+        let expansions = match &pool {
+            Some(pool) => pool.install(|| live.par_iter().map(expand).collect::<Vec<_>>()),
+            None => live.iter().map(expand).collect::<Vec<_>>(),
+        };
+        let expansions = std::iter::repeat_with(|| None)
+            .take(replayed)
+            .chain(expansions);
+
+        let mut next_frontier = Vec::new();
+        let mut next_frontier_bytes = 0u64;
+        for ((node_key, impl_closure, spec_closure), expansion) in
+            frontier.into_iter().zip(expansions)
+        {
+            let decoded = match replay.pop_front() {
+                Some(record) => {
+                    let checkpoint = checkpoint.expect("replay needs a checkpoint");
+                    Some(decode_merge(&record, checkpoint, &replay_keys)?)
+                }
+                None => None,
             };
-            let spec_next = if let Some(cache) = next_closure_cache.as_deref_mut() {
-                cache.next_by_label(ProviderSide::Spec, spec, &spec_closure, &label)
-            } else {
-                next_by_label(spec, &spec_closure.states, &label)
+            let (expansion, replayed_eviction) = match decoded {
+                Some((expansion, evicted)) => (expansion, Some(evicted)),
+                None => {
+                    if let Err(limit) = limits.check_deadline() {
+                        if let Some(journal) = journal.as_mut() {
+                            journal.commit(&RefineProgress {
+                                states: states_count,
+                            })?;
+                        }
+                        stopped = Some(limit);
+                        break 'levels;
+                    }
+                    let Some(expansion) = expansion else {
+                        continue;
+                    };
+                    (expansion, None)
+                }
             };
-            if spec_next.states.is_empty() {
-                let mut trace = reconstruct_trace(&predecessor, &node_key);
-                trace.push(label);
-                return RefinementOutcome {
-                    refines: false,
-                    failure: Some(RefinementFailure {
-                        trace,
-                        tags: vec!["trace_mismatch".to_string()],
-                    }),
-                    stats: Stats {
-                        states: Some(states_count),
-                        transitions: Some(transitions_count),
-                    },
-                    diagnostic_tags: Vec::new(),
-                };
+            if let Some((spec_diverges, impl_diverges)) = expansion.divergence {
+                impl_closure_max = impl_closure_max.max(impl_closure.states.len() as u64);
+                spec_closure_max = spec_closure_max.max(spec_closure.states.len() as u64);
+                divergence_checks = divergence_checks.saturating_add(2);
+                divergence_counters.record(spec_cache.record_divergence(
+                    &spec_closure.states,
+                    spec_diverges,
+                    spec_bytes,
+                ));
+                divergence_counters.record(impl_cache.record_divergence(
+                    &impl_closure.states,
+                    impl_diverges,
+                    impl_bytes,
+                ));
             }
 
-            let next_key = NodeKey {
-                impl_sig: impl_next.sig.clone(),
-                spec_sig: spec_next.sig.clone(),
+            let pruned = match &expansion.action {
+                NodeAction::Continue => false,
+                NodeAction::Prune => {
+                    divergence_prunes = divergence_prunes.saturating_add(1);
+                    true
+                }
+                NodeAction::Fail { tags, diverges } => {
+                    let mut trace = reconstruct_trace(&predecessor, &node_key);
+                    if *diverges {
+                        trace.push(TAU_LABEL.to_string());
+                    }
+                    let failure = (trace.clone(), tags.clone(), node_key.impl_sig.clone());
+                    if failed_nodes.insert(failure) {
+                        failures.push(RefinementFailure {
+                            trace,
+                            tags: tags.clone(),
+                        });
+                        if failures.len() >= max_counterexamples {
+                            break 'levels;
+                        }
+                    }
+                    true
+                }
             };
-            if visited.insert(next_key.clone()) {
-                predecessor.insert(next_key.clone(), (node_key.clone(), label));
-                queue.push_back((next_key, impl_next, spec_next));
-                states_count += 1;
-            }
-        }
-    }
 
-    RefinementOutcome {
-        refines: true,
-        failure: None,
-        stats: Stats {
-            states: Some(states_count),
-            transitions: Some(transitions_count),
-        },
-        diagnostic_tags: Vec::new(),
-    }
-}
-
-fn trace_includes(
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
-) -> RefinementOutcome {
-    bfs_refinement(
-        spec,
-        impl_,
-        |_node_key, _impl_states, _spec_states, _pred| NodeAction::Continue,
-        None,
-    )
-}
-
-fn failures_includes(
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
-) -> RefinementOutcome {
-    bfs_refinement(
-        spec,
-        impl_,
-        |node_key, impl_states, spec_states, pred| {
-            let spec_stable_offers = stable_offer_sets(spec, spec_states);
-
-            for impl_state in impl_states {
-                if !is_stable(impl_, impl_state) {
+            let mut edges = (journal.is_some() && replayed_eviction.is_none()).then(Vec::new);
+            let mut edge_count = 0u64;
+            for (label, impl_next, spec_next) in expansion.successors {
+                transitions_count += 1;
+                closure_counters.record(impl_cache.record_next(
+                    &impl_closure,
+                    &label,
+                    &impl_next,
+                    impl_bytes,
+                ));
+                closure_counters.record(spec_cache.record_next(
+                    &spec_closure,
+                    &label,
+                    &spec_next,
+                    spec_bytes,
+                ));
+
+                if spec_next.states.is_empty() {
+                    if mismatches.insert((label.clone(), impl_next.states.clone())) {
+                        let mut trace = reconstruct_trace(&predecessor, &node_key);
+                        trace.push(label.clone());
+                        failures.push(RefinementFailure {
+                            trace,
+                            tags: vec!["trace_mismatch".to_string()],
+                        });
+                        if failures.len() >= max_counterexamples {
+                            break 'levels;
+                        }
+                    }
+                    if let Some(edges) = edges.as_mut() {
+                        let checkpoint = checkpoint.expect("journal needs a checkpoint");
+                        edge_count += 1;
+                        put_label(edges, Some(&label));
+                        put_u64(edges, MISMATCH);
+                        put_states(edges, checkpoint.impl_codec, &impl_next.states);
+                    }
                     continue;
                 }
-                let impl_offer = offered_visible_labels(impl_, impl_state);
-                let ok = spec_stable_offers
-                    .iter()
-                    .any(|spec_offer| spec_offer.is_subset(&impl_offer));
-                if ok {
-                    continue;
+
+                let next_key = NodeKey {
+                    impl_sig: impl_next.states.clone(),
+                    spec_sig: spec_next.states.clone(),
+                };
+                let known = visited.get(&next_key).copied();
+                if let Some(edges) = edges.as_mut() {
+                    edge_count += 1;
+                    put_label(edges, Some(&label));
+                    match known {
+                        Some(index) => put_u64(edges, index),
+                        None => {
+                            let checkpoint = checkpoint.expect("journal needs a checkpoint");
+                            put_u64(edges, NEW_NODE);
+                            put_states(edges, checkpoint.impl_codec, &impl_next.states);
+                            put_states(edges, checkpoint.spec_codec, &spec_next.states);
+                        }
+                    }
+                }
+                if known.is_none() {
+                    visited.insert(next_key.clone(), states_count);
+                    if replayed_eviction.is_some() {
+                        replay_keys.push(next_key.clone());
+                    }
+                    if budget.is_some() {
+                        let bytes = key_bytes(&next_key);
+                        graph_bytes += 2 * bytes
+                            + key_bytes(&node_key)
+                            + label.len() as u64
+                            + 2 * ENTRY_OVERHEAD_BYTES;
+                        next_frontier_bytes += 2 * bytes;
+                    }
+                    predecessor.insert(next_key.clone(), (node_key.clone(), label));
+                    next_frontier.push((next_key, impl_next, spec_next));
+                    states_count += 1;
                 }
+            }
 
-                return NodeAction::Fail(RefinementFailure {
-                    trace: reconstruct_trace(pred, node_key),
-                    tags: refusal_mismatch_tags(&spec_stable_offers, &impl_offer),
-                });
+            let mut evicted = false;
+            let mut limit = None;
+            match (replayed_eviction, budget) {
+                (Some(true), _) => {
+                    spec_cache.evict();
+                    impl_cache.evict();
+                }
+                (None, Some(budget)) if !pruned => {
+                    let held = graph_bytes + frontier_bytes + next_frontier_bytes;
+                    if budget.should_spill(held + spec_cache.bytes + impl_cache.bytes) {
+                        spec_cache.evict();
+                        impl_cache.evict();
+                        evicted = true;
+                    }
+                    limit = budget
+                        .update(held + spec_cache.bytes + impl_cache.bytes)
+                        .err();
+                }
+                _ => {}
             }
-            NodeAction::Continue
-        },
-        None,
-    )
-}
-
-fn failures_divergences_includes(
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
-) -> RefinementOutcome {
-    let mut next_closure_cache = NextClosureCache::default();
-    let mut divergence_cache = DivergenceCache::default();
-    let mut divergence_checks = 0u64;
-    let mut divergence_prunes = 0u64;
-    let mut impl_closure_max = 0u64;
-    let mut spec_closure_max = 0u64;
 
-    let mut outcome = bfs_refinement(
-        spec,
-        impl_,
-        |node_key, impl_states, spec_states, pred| {
-            impl_closure_max = impl_closure_max.max(impl_states.len() as u64);
-            spec_closure_max = spec_closure_max.max(spec_states.len() as u64);
-            divergence_checks = divergence_checks.saturating_add(2);
-
-            let spec_diverges = divergence_cache.has_tau_cycle(
-                ProviderSide::Spec,
-                &node_key.spec_sig,
-                spec,
-                spec_states,
-            );
-            let impl_diverges = divergence_cache.has_tau_cycle(
-                ProviderSide::Impl,
-                &node_key.impl_sig,
-                impl_,
-                impl_states,
-            );
-            if impl_diverges && !spec_diverges {
-                let mut trace = reconstruct_trace(pred, node_key);
-                trace.push(TAU.to_string());
-                return NodeAction::Fail(RefinementFailure {
-                    trace,
-                    tags: vec!["divergence_mismatch".to_string(), "divergence".to_string()],
-                });
+            if replayed_eviction.is_some() {
+                if replay.is_empty() {
+                    if states_count != expected_states {
+                        return Err(invalid("checkpoint journal does not match its progress"));
+                    }
+                    replay_keys = Vec::new();
+                }
+            } else if let Some(journal) = journal.as_mut() {
+                let edges = edges.unwrap_or_default();
+                let record = encode_merge(
+                    expansion.divergence,
+                    &expansion.action,
+                    evicted,
+                    edge_count,
+                    &edges,
+                );
+                journal.append(&record)?;
+                if limit.is_some() || journal.tick() {
+                    journal.commit(&RefineProgress {
+                        states: states_count,
+                    })?;
+                }
             }
-            if spec_diverges {
-                divergence_prunes = divergence_prunes.saturating_add(1);
-                return NodeAction::Prune;
+            if let Some(limit) = limit {
+                stopped = Some(limit);
+                break 'levels;
             }
+        }
+        frontier = next_frontier;
+        frontier_bytes = next_frontier_bytes;
+        depth += 1;
+    }
 
-            let spec_stable_offers = stable_offer_sets(spec, spec_states);
-            for impl_state in impl_states {
-                if !is_stable(impl_, impl_state) {
-                    continue;
-                }
-                let impl_offer = offered_visible_labels(impl_, impl_state);
-                let ok = spec_stable_offers
-                    .iter()
-                    .any(|spec_offer| spec_offer.is_subset(&impl_offer));
-                if ok {
-                    continue;
+    let stats = Stats {
+        states: Some(states_count),
+        transitions: Some(transitions_count),
+        depth: stopped.map(|_| depth),
+    };
+    let diagnostic_tags = if *model == RefinementModel::FD {
+        vec![
+            format!("fd_nodes:{states_count}"),
+            format!("fd_edges:{transitions_count}"),
+            format!("fd_divergence_checks:{divergence_checks}"),
+            format!("fd_pruned_nodes:{divergence_prunes}"),
+            format!("fd_impl_closure_max:{impl_closure_max}"),
+            format!("fd_spec_closure_max:{spec_closure_max}"),
+            format!("fd_closure_cache_hits:{}", closure_counters.hits),
+            format!("fd_closure_cache_misses:{}", closure_counters.misses),
+            format!("fd_divergence_cache_hits:{}", divergence_counters.hits),
+            format!("fd_divergence_cache_misses:{}", divergence_counters.misses),
+        ]
+    } else {
+        Vec::new()
+    };
+
+    Ok(RefinementOutcome {
+        failures,
+        stats,
+        diagnostic_tags,
+        stopped,
+    })
+}
+
+fn states_bytes<P: LabelledProvider>(provider: &P, states: &[State<P>]) -> u64 {
+    let owned = states
+        .iter()
+        .map(|state| provider.state_bytes(state) as u64)
+        .sum::<u64>();
+    owned + std::mem::size_of::<Vec<State<P>>>() as u64
+}
+
+fn expand_node<S, I>(
+    spec: &S,
+    impl_: &I,
+    model: &RefinementModel,
+    spec_cache: &ClosureCache<State<S>>,
+    impl_cache: &ClosureCache<State<I>>,
+    impl_closure: &Closure<State<I>>,
+    spec_closure: &Closure<State<S>>,
+) -> NodeExpansion<State<S>, State<I>>
+where
+    S: LabelledProvider,
+    I: LabelledProvider,
+{
+    let mut divergence = None;
+    let action = match model {
+        RefinementModel::T => NodeAction::Continue,
+        RefinementModel::F => {
+            refusal_action(spec, impl_, &spec_closure.states, &impl_closure.states)
+        }
+        RefinementModel::FD => {
+            let spec_diverges = spec_cache.has_tau_cycle(spec, &spec_closure.states);
+            let impl_diverges = impl_cache.has_tau_cycle(impl_, &impl_closure.states);
+            divergence = Some((spec_diverges, impl_diverges));
+            if impl_diverges && !spec_diverges {
+                NodeAction::Fail {
+                    tags: vec!["divergence_mismatch".to_string(), "divergence".to_string()],
+                    diverges: true,
                 }
-                return NodeAction::Fail(RefinementFailure {
-                    trace: reconstruct_trace(pred, node_key),
-                    tags: refusal_mismatch_tags(&spec_stable_offers, &impl_offer),
-                });
+            } else if spec_diverges {
+                NodeAction::Prune
+            } else {
+                refusal_action(spec, impl_, &spec_closure.states, &impl_closure.states)
             }
+        }
+    };
 
-            NodeAction::Continue
-        },
-        Some(&mut next_closure_cache),
-    );
-
-    outcome.diagnostic_tags = vec![
-        format!("fd_nodes:{}", outcome.stats.states.unwrap_or_default()),
-        format!("fd_edges:{}", outcome.stats.transitions.unwrap_or_default()),
-        format!("fd_divergence_checks:{divergence_checks}"),
-        format!("fd_pruned_nodes:{divergence_prunes}"),
-        format!("fd_impl_closure_max:{impl_closure_max}"),
-        format!("fd_spec_closure_max:{spec_closure_max}"),
-        format!("fd_closure_cache_hits:{}", next_closure_cache.hits),
-        format!("fd_closure_cache_misses:{}", next_closure_cache.misses),
-        format!("fd_divergence_cache_hits:{}", divergence_cache.hits),
-        format!("fd_divergence_cache_misses:{}", divergence_cache.misses),
-    ];
-    outcome
+    let successors = if matches!(action, NodeAction::Continue) {
+        enabled_visible_labels(impl_, &impl_closure.states)
+            .into_iter()
+            .map(|label| {
+                let impl_next = impl_cache.next_by_label(impl_, impl_closure, &label);
+                let spec_next = spec_cache.next_by_label(spec, spec_closure, &label);
+                (label, impl_next, spec_next)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    NodeExpansion {
+        divergence,
+        action,
+        successors,
+    }
+}
+
+fn refusal_action<S, I>(
+    spec: &S,
+    impl_: &I,
+    spec_states: &[State<S>],
+    impl_states: &[State<I>],
+) -> NodeAction
+where
+    S: LabelledProvider,
+    I: LabelledProvider,
+{
+    let spec_stable_offers = stable_offer_sets(spec, spec_states);
+    for impl_state in impl_states {
+        if !is_stable(impl_, impl_state) {
+            continue;
+        }
+        let impl_offer = offered_visible_labels(impl_, impl_state);
+        let ok = spec_stable_offers
+            .iter()
+            .any(|spec_offer| spec_offer.is_subset(&impl_offer));
+        if ok {
+            continue;
+        }
+        return NodeAction::Fail {
+            tags: refusal_mismatch_tags(&spec_stable_offers, &impl_offer),
+            diverges: false,
+        };
+    }
+    NodeAction::Continue
 }
 
 #[derive(Clone, Debug)]
-struct ReplayOutcome {
-    impl_closure: Closure,
-    spec_closure: Closure,
+struct ReplayOutcome<SS, IS> {
+    impl_closure: Closure<IS>,
+    spec_closure: Closure<SS>,
     trace_mismatch: bool,
 }
 
-fn replay_trace(
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
+type ReplayOutcomeOf<S, I> = ReplayOutcome<State<S>, State<I>>;
+
+fn replay_trace<S: LabelledProvider, I: LabelledProvider>(
+    spec: &S,
+    impl_: &I,
     trace: &[String],
-) -> Option<ReplayOutcome> {
+) -> Result<ReplayOutcomeOf<S, I>, (usize, BTreeSet<String>)> {
     let mut impl_closure = tau_closure(impl_, vec![impl_.initial_state()]);
     let mut spec_closure = tau_closure(spec, vec![spec.initial_state()]);
     let mut trace_mismatch = false;
 
-    for label in trace {
+    for (step, label) in trace.iter().enumerate() {
         let impl_next = next_by_label(impl_, &impl_closure.states, label);
         if impl_next.states.is_empty() {
-            return None;
+            return Err((step, enabled_visible_labels(impl_, &impl_closure.states)));
         }
         if !trace_mismatch {
             let spec_next = next_by_label(spec, &spec_closure.states, label);
@@ -694,17 +1342,17 @@ fn replay_trace(
         impl_closure = impl_next;
     }
 
-    Some(ReplayOutcome {
+    Ok(ReplayOutcome {
         impl_closure,
         spec_closure,
         trace_mismatch,
     })
 }
 
-fn has_refusal_mismatch(
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
-    replay: &ReplayOutcome,
+fn has_refusal_mismatch<S: LabelledProvider, I: LabelledProvider>(
+    spec: &S,
+    impl_: &I,
+    replay: &ReplayOutcome<State<S>, State<I>>,
 ) -> bool {
     let spec_stable_offers = stable_offer_sets(spec, &replay.spec_closure.states);
     for impl_state in &replay.impl_closure.states {
@@ -722,10 +1370,10 @@ fn has_refusal_mismatch(
     false
 }
 
-fn counterexample_still_fails(
+fn counterexample_still_fails<S: LabelledProvider, I: LabelledProvider>(
     model: &RefinementModel,
-    spec: &CspmTransitionProvider,
-    impl_: &CspmTransitionProvider,
+    spec: &S,
+    impl_: &I,
     counterexample: &Counterexample,
     required_tags: &[String],
 ) -> bool {
@@ -738,20 +1386,29 @@ fn counterexample_still_fails(
         .map(|event| event.label.clone())
         .collect::<Vec<_>>();
     if required_tags.iter().any(|tag| tag == "divergence_mismatch")
-        && !trace.iter().any(|label| label == TAU)
+        && !trace.iter().any(|label| label == TAU_LABEL)
     {
         return false;
     }
-    let Some(replay) = replay_trace(spec, impl_, &trace) else {
-        return false;
-    };
+    match replay_trace(spec, impl_, &trace) {
+        Ok(replay) => replay_fails(model, spec, impl_, &replay),
+        Err(_) => false,
+    }
+}
+
+fn replay_fails<S: LabelledProvider, I: LabelledProvider>(
+    model: &RefinementModel,
+    spec: &S,
+    impl_: &I,
+    replay: &ReplayOutcome<State<S>, State<I>>,
+) -> bool {
     if replay.trace_mismatch {
         return true;
     }
 
     match model {
         RefinementModel::T => false,
-        RefinementModel::F => has_refusal_mismatch(spec, impl_, &replay),
+        RefinementModel::F => has_refusal_mismatch(spec, impl_, replay),
         RefinementModel::FD => {
             let spec_diverges = closure_has_tau_cycle(spec, &replay.spec_closure.states);
             let impl_diverges = closure_has_tau_cycle(impl_, &replay.impl_closure.states);
@@ -761,9 +1418,241 @@ fn counterexample_still_fails(
             if spec_diverges {
                 return false;
             }
-            has_refusal_mismatch(spec, impl_, &replay)
+            has_refusal_mismatch(spec, impl_, replay)
+        }
+    }
+}
+
+pub(crate) fn replay_refinement<S: LabelledProvider, I: LabelledProvider>(
+    model: &RefinementModel,
+    spec: &S,
+    impl_: &I,
+    counterexample: &Counterexample,
+) -> Replay {
+    let trace = counterexample
+        .events
+        .iter()
+        .map(|event| event.label.clone())
+        .collect::<Vec<_>>();
+    let replay = match replay_trace(spec, impl_, &trace) {
+        Ok(replay) => replay,
+        Err((step, offered)) => {
+            return Replay {
+                verdict: ReplayVerdict::NoLongerValid,
+                step: Some(step),
+                offered: offered.into_iter().collect(),
+            }
+        }
+    };
+    if replay_fails(model, spec, impl_, &replay) {
+        return Replay {
+            verdict: ReplayVerdict::StillFails,
+            step: None,
+            offered: Vec::new(),
+        };
+    }
+    let on_last_event = counterexample
+        .tags
+        .iter()
+        .any(|tag| tag == "trace_mismatch")
+        || trace.last().is_some_and(|label| label == TAU_LABEL);
+    Replay {
+        verdict: ReplayVerdict::NowPasses,
+        step: Some(trace.len().saturating_sub(usize::from(on_last_event))),
+        offered: Vec::new(),
+    }
+}
+
+pub(crate) enum TraceCheck {
+    Continue,
+    Prune,
+    Fail(Box<Counterexample>),
+}
+
+pub(crate) fn check_refinement_trace<S: LabelledProvider, I: LabelledProvider>(
+    model: &RefinementModel,
+    spec: &S,
+    impl_: &I,
+    trace: &[String],
+    source_spans: Vec<SourceSpan>,
+) -> TraceCheck {
+    let Ok(replay) = replay_trace(spec, impl_, trace) else {
+        return TraceCheck::Continue;
+    };
+    let action = if replay.trace_mismatch {
+        NodeAction::Fail {
+            tags: vec!["trace_mismatch".to_string()],
+            diverges: false,
+        }
+    } else {
+        match model {
+            RefinementModel::T => NodeAction::Continue,
+            RefinementModel::F => refusal_action(
+                spec,
+                impl_,
+                &replay.spec_closure.states,
+                &replay.impl_closure.states,
+            ),
+            RefinementModel::FD => {
+                let spec_diverges = closure_has_tau_cycle(spec, &replay.spec_closure.states);
+                let impl_diverges = closure_has_tau_cycle(impl_, &replay.impl_closure.states);
+                if impl_diverges && !spec_diverges {
+                    NodeAction::Fail {
+                        tags: vec!["divergence_mismatch".to_string(), "divergence".to_string()],
+                        diverges: true,
+                    }
+                } else if spec_diverges {
+                    NodeAction::Prune
+                } else {
+                    refusal_action(
+                        spec,
+                        impl_,
+                        &replay.spec_closure.states,
+                        &replay.impl_closure.states,
+                    )
+                }
+            }
+        }
+    };
+    let (tags, diverges) = match action {
+        NodeAction::Continue => return TraceCheck::Continue,
+        NodeAction::Prune => return TraceCheck::Prune,
+        NodeAction::Fail { tags, diverges } => (tags, diverges),
+    };
+
+    let mut events = trace
+        .iter()
+        .map(|label| CounterexampleEvent {
+            label: label.clone(),
+            hidden_label: None,
+            spans: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    if diverges {
+        events.push(CounterexampleEvent {
+            label: TAU_LABEL.to_string(),
+            hidden_label: None,
+            spans: Vec::new(),
+        });
+    }
+    let mut counterexample = Counterexample {
+        kind: CounterexampleType::Trace,
+        events,
+        minimality: Minimality::None,
+        tags: [
+            vec![
+                "refinement".to_string(),
+                format!("model:{}", model.as_str()),
+            ],
+            tags,
+        ]
+        .concat(),
+        source_spans,
+        lasso: None,
+        failure: None,
+        deadlock_explanation: None,
+        conformance: None,
+        explanation: None,
+    };
+    if let Some(failure) = failure_detail(spec, impl_, &counterexample) {
+        counterexample.kind = CounterexampleType::Failure;
+        counterexample.failure = Some(failure);
+    }
+    attach_impl_spans(impl_, &mut counterexample.events);
+    let explainer = BasicExplainer;
+    TraceCheck::Fail(Box::new(explainer.explain(counterexample)))
+}
+
+fn attach_impl_spans<I: LabelledProvider>(impl_: &I, events: &mut [CounterexampleEvent]) {
+    let mut closure = tau_closure(impl_, vec![impl_.initial_state()]);
+    for event in events {
+        if event.label == TAU_LABEL {
+            break;
+        }
+        let mut seeds = Vec::new();
+        for state in &closure.states {
+            for (transition, next) in impl_.transitions(state) {
+                if transition.label() != event.label {
+                    continue;
+                }
+                for span in impl_.event_spans(state, &transition, &next) {
+                    if !event.spans.contains(&span) {
+                        event.spans.push(span);
+                    }
+                }
+                seeds.push(next);
+            }
+        }
+        closure = tau_closure(impl_, seeds);
+    }
+}
+
+fn failure_detail<S: LabelledProvider, I: LabelledProvider>(
+    spec: &S,
+    impl_: &I,
+    counterexample: &Counterexample,
+) -> Option<Failure> {
+    let has_tag = |tag: &str| counterexample.tags.iter().any(|t| t == tag);
+    let divergence = has_tag("divergence_mismatch");
+    if !divergence && !has_tag("refusal_mismatch") {
+        return None;
+    }
+    let trace = counterexample
+        .events
+        .iter()
+        .map(|event| event.label.as_str())
+        .filter(|label| *label != TAU_LABEL)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let replay = replay_trace(spec, impl_, &trace).ok()?;
+    if replay.trace_mismatch {
+        return None;
+    }
+    let acceptances = minimal_acceptances(stable_offer_sets(spec, &replay.spec_closure.states));
+    if divergence {
+        return Some(Failure {
+            refusal: Vec::new(),
+            acceptances: acceptances.into_iter().map(Vec::from_iter).collect(),
+            divergence: true,
+        });
+    }
+    let accepted = acceptances.iter().flatten().collect::<BTreeSet<_>>();
+    let refusal = replay
+        .impl_closure
+        .states
+        .iter()
+        .filter(|state| is_stable(impl_, state))
+        .map(|state| offered_visible_labels(impl_, state))
+        .find(|offer| {
+            !acceptances
+                .iter()
+                .any(|spec_offer| spec_offer.is_subset(offer))
+        })
+        .map(|offer| {
+            accepted
+                .iter()
+                .filter(|label| !offer.contains(label.as_str()))
+                .map(|label| label.to_string())
+                .collect::<Vec<_>>()
+        })?;
+    Some(Failure {
+        refusal,
+        acceptances: acceptances.into_iter().map(Vec::from_iter).collect(),
+        divergence: false,
+    })
+}
+
+fn minimal_acceptances(offers: Vec<BTreeSet<String>>) -> Vec<BTreeSet<String>> {
+    let mut minimal = Vec::<BTreeSet<String>>::new();
+    for offer in offers {
+        if minimal.iter().any(|kept| kept.is_subset(&offer)) {
+            continue;
         }
+        minimal.retain(|kept| !offer.is_subset(kept));
+        minimal.push(offer);
     }
+    minimal.sort();
+    minimal
 }
 
 fn refusal_mismatch_tags(
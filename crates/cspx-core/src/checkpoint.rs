@@ -0,0 +1,299 @@
+use crate::disk_store::{
+    create_segment, replace_file, segment_header, write_record, RECORD_HEADER_LEN,
+    SEGMENT_HEADER_LEN,
+};
+use crate::queue_disk::read_record;
+use crate::state_codec::StateCodec;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+const PROGRESS_VERSION: u32 = 1;
+const JOURNAL_FILE: &str = "journal.log";
+const PROGRESS_FILE: &str = "progress.json";
+
+pub const DEFAULT_CHECKPOINT_EVERY: u64 = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointOptions {
+    pub dir: PathBuf,
+    pub every: u64,
+    pub resume: bool,
+    pub fingerprint: String,
+}
+
+impl CheckpointOptions {
+    pub fn new(dir: impl Into<PathBuf>, fingerprint: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            every: DEFAULT_CHECKPOINT_EVERY,
+            resume: false,
+            fingerprint: fingerprint.into(),
+        }
+    }
+}
+
+pub struct SearchCheckpoint<'a, S> {
+    pub options: CheckpointOptions,
+    pub codec: &'a dyn StateCodec<S>,
+}
+
+pub(crate) struct RefinementCheckpoint<'a, SS, IS> {
+    pub(crate) options: CheckpointOptions,
+    pub(crate) spec_codec: &'a dyn StateCodec<SS>,
+    pub(crate) impl_codec: &'a dyn StateCodec<IS>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Progress<P> {
+    version: u32,
+    fingerprint: String,
+    journal_bytes: u64,
+    journal_records: u64,
+    search: P,
+}
+
+// Append-only record log plus the progress file that commits a prefix of it.
+pub(crate) struct Journal {
+    dir: PathBuf,
+    fingerprint: String,
+    every: u64,
+    writer: BufWriter<fs::File>,
+    bytes: u64,
+    records: u64,
+    pending: u64,
+}
+
+impl Journal {
+    pub(crate) fn open<P>(
+        options: &CheckpointOptions,
+        mut replay: impl FnMut(&[u8]) -> io::Result<()>,
+    ) -> io::Result<(Self, Option<P>)>
+    where
+        P: DeserializeOwned,
+    {
+        if options.every == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "checkpoint interval must be at least 1",
+            ));
+        }
+        fs::create_dir_all(&options.dir)?;
+        let journal_path = options.dir.join(JOURNAL_FILE);
+        let progress_path = options.dir.join(PROGRESS_FILE);
+
+        let committed = if options.resume && progress_path.exists() {
+            let text = fs::read_to_string(&progress_path)?;
+            let progress: Progress<P> = serde_json::from_str(&text)
+                .map_err(|err| invalid(format!("invalid checkpoint progress: {err}")))?;
+            if progress.version != PROGRESS_VERSION {
+                return Err(invalid("unsupported checkpoint version"));
+            }
+            if progress.fingerprint != options.fingerprint {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "checkpoint in {} was written for a different run",
+                        options.dir.display()
+                    ),
+                ));
+            }
+            Some(progress)
+        } else {
+            None
+        };
+
+        let Some(progress) = committed else {
+            let _ = fs::remove_file(&progress_path);
+            let _ = fs::remove_file(&journal_path);
+            let writer = create_segment(&journal_path)?;
+            return Ok((Self::new(options, writer, SEGMENT_HEADER_LEN, 0), None));
+        };
+
+        let mut reader = BufReader::new(fs::File::open(&journal_path)?);
+        let mut header = [0u8; SEGMENT_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if header != segment_header() {
+            return Err(invalid("invalid checkpoint journal header"));
+        }
+        let mut bytes = SEGMENT_HEADER_LEN;
+        for _ in 0..progress.journal_records {
+            let record = read_record(&mut reader)?;
+            bytes += RECORD_HEADER_LEN + record.len() as u64;
+            replay(&record)?;
+        }
+        if bytes != progress.journal_bytes {
+            return Err(invalid("checkpoint journal does not match its progress"));
+        }
+        drop(reader);
+
+        // Records appended after the last commit are dropped.
+        let mut file = OpenOptions::new().write(true).open(&journal_path)?;
+        file.set_len(bytes)?;
+        file.seek(SeekFrom::End(0))?;
+        let journal = Self::new(
+            options,
+            BufWriter::new(file),
+            bytes,
+            progress.journal_records,
+        );
+        Ok((journal, Some(progress.search)))
+    }
+
+    fn new(
+        options: &CheckpointOptions,
+        writer: BufWriter<fs::File>,
+        bytes: u64,
+        records: u64,
+    ) -> Self {
+        Self {
+            dir: options.dir.clone(),
+            fingerprint: options.fingerprint.clone(),
+            every: options.every,
+            writer,
+            bytes,
+            records,
+            pending: 0,
+        }
+    }
+
+    pub(crate) fn append(&mut self, record: &[u8]) -> io::Result<()> {
+        write_record(&mut self.writer, record)?;
+        self.bytes += RECORD_HEADER_LEN + record.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    pub(crate) fn tick(&mut self) -> bool {
+        self.pending += 1;
+        self.pending >= self.every
+    }
+
+    pub(crate) fn commit<P>(&mut self, search: &P) -> io::Result<()>
+    where
+        P: Serialize,
+    {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        let progress = Progress {
+            version: PROGRESS_VERSION,
+            fingerprint: self.fingerprint.clone(),
+            journal_bytes: self.bytes,
+            journal_records: self.records,
+            search,
+        };
+        let text = serde_json::to_string(&progress).map_err(io::Error::other)?;
+        let path = self.dir.join(PROGRESS_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        replace_file(&tmp_path, &path)?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+pub(crate) fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+pub(crate) fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+pub(crate) fn put_label(out: &mut Vec<u8>, label: Option<&str>) {
+    match label {
+        Some(label) => put_bytes(out, label.as_bytes()),
+        None => out.extend_from_slice(&u32::MAX.to_le_bytes()),
+    }
+}
+
+pub(crate) struct RecordReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> RecordReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid("truncated checkpoint record"));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<u64> {
+        let mut value = [0u8; 8];
+        value.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(value))
+    }
+
+    pub(crate) fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub(crate) fn label(&mut self) -> io::Result<Option<String>> {
+        let len = self.u32()?;
+        if len == u32::MAX {
+            return Ok(None);
+        }
+        let bytes = self.take(len as usize)?;
+        String::from_utf8(bytes.to_vec())
+            .map(Some)
+            .map_err(|_| invalid("invalid label in checkpoint record"))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.bytes)
+    }
+}
+
+pub(crate) fn put_states<S>(out: &mut Vec<u8>, codec: &dyn StateCodec<S>, states: &[S]) {
+    put_u64(out, states.len() as u64);
+    for state in states {
+        put_bytes(out, &codec.encode(state));
+    }
+}
+
+pub(crate) fn read_states<S>(
+    reader: &mut RecordReader<'_>,
+    codec: &dyn StateCodec<S>,
+) -> io::Result<Vec<S>> {
+    let len = reader.u64()?;
+    (0..len)
+        .map(|_| decode_state(codec, reader.bytes()?))
+        .collect()
+}
+
+pub(crate) fn decode_state<S>(codec: &dyn StateCodec<S>, bytes: &[u8]) -> io::Result<S> {
+    codec
+        .decode(bytes)
+        .map_err(|err| invalid(format!("invalid state in checkpoint: {err}")))
+}
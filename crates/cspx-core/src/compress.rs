@@ -0,0 +1,243 @@
+use crate::check_divergence::tarjan_scc;
+use crate::ir::CompressionKind;
+use crate::lts::{StateId, TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_explicit::{ExplicitLts, ExplicitLtsProvider};
+use crate::normalise::NormalisedSpec;
+use crate::types::SourceSpan;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionStats {
+    pub function: CompressionKind,
+    pub span: Option<SourceSpan>,
+    pub states_before: usize,
+    pub transitions_before: usize,
+    pub states_after: usize,
+    pub transitions_after: usize,
+}
+
+pub fn explicate<P>(provider: &P) -> ExplicitLts
+where
+    P: TransitionProvider,
+    P::State: Eq + Hash,
+    P::Transition: TransitionLabel,
+{
+    let initial = provider.initial_state();
+    let mut index_of = HashMap::from([(initial.clone(), 0 as StateId)]);
+    let mut queue = VecDeque::from([initial]);
+    let mut edges = Vec::new();
+    let mut from: StateId = 0;
+    while let Some(state) = queue.pop_front() {
+        for (transition, next) in provider.transitions(&state) {
+            let next_id = index_of.len() as StateId;
+            let to = *index_of.entry(next.clone()).or_insert_with(|| {
+                queue.push_back(next);
+                next_id
+            });
+            edges.push((from, transition.label().to_string(), to));
+        }
+        from += 1;
+    }
+    ExplicitLts::new(0, index_of.len(), edges).expect("explicated states are in range")
+}
+
+pub fn compress(kind: CompressionKind, lts: &ExplicitLts) -> ExplicitLts {
+    match kind {
+        CompressionKind::Explicate => renumber(lts.clone()),
+        CompressionKind::Sbisim => strong_bisimulation(lts),
+        CompressionKind::Normal => explicate(&NormalisedSpec::from_provider(
+            &ExplicitLtsProvider::new(lts.clone()),
+        )),
+        CompressionKind::Diamond => diamond(lts),
+        CompressionKind::TauLoopFactor => tau_loop_factor(lts),
+    }
+}
+
+fn renumber(lts: ExplicitLts) -> ExplicitLts {
+    explicate(&ExplicitLtsProvider::new(lts))
+}
+
+fn is_tau(label: &str) -> bool {
+    label == TAU_LABEL
+}
+
+fn tau_successors(lts: &ExplicitLts) -> Vec<Vec<usize>> {
+    (0..lts.state_count())
+        .map(|state| {
+            lts.transitions_from(state as StateId)
+                .iter()
+                .filter(|(label, _)| is_tau(label))
+                .map(|(_, next)| *next as usize)
+                .collect()
+        })
+        .collect()
+}
+
+fn quotient(
+    lts: &ExplicitLts,
+    block: &[usize],
+    extra: Vec<(StateId, String, StateId)>,
+) -> ExplicitLts {
+    let block_count = block.iter().max().map_or(0, |max| max + 1);
+    let mut edges = extra;
+    for (state, &from) in block.iter().enumerate() {
+        for (label, next) in lts.transitions_from(state as StateId) {
+            edges.push((
+                from as StateId,
+                label.clone(),
+                block[*next as usize] as StateId,
+            ));
+        }
+    }
+    let initial = block[lts.initial() as usize] as StateId;
+    renumber(ExplicitLts::new(initial, block_count, edges).expect("blocks are in range"))
+}
+
+fn strong_bisimulation(lts: &ExplicitLts) -> ExplicitLts {
+    let state_count = lts.state_count();
+    let mut block = vec![0usize; state_count];
+    let mut block_count = 1;
+    loop {
+        let mut ids = HashMap::<(usize, Vec<(&str, usize)>), usize>::new();
+        let next = (0..state_count)
+            .map(|state| {
+                let mut signature = lts
+                    .transitions_from(state as StateId)
+                    .iter()
+                    .map(|(label, next)| (label.as_str(), block[*next as usize]))
+                    .collect::<Vec<_>>();
+                signature.sort();
+                signature.dedup();
+                let id = ids.len();
+                *ids.entry((block[state], signature)).or_insert(id)
+            })
+            .collect::<Vec<_>>();
+        block = next;
+        if ids.len() == block_count {
+            break;
+        }
+        block_count = ids.len();
+    }
+    quotient(lts, &block, Vec::new())
+}
+
+fn tau_cyclic_states(tau: &[Vec<usize>]) -> Vec<bool> {
+    let mut cyclic = vec![false; tau.len()];
+    for scc in tarjan_scc(tau) {
+        if scc.len() > 1 || tau[scc[0]].contains(&scc[0]) {
+            for state in scc {
+                cyclic[state] = true;
+            }
+        }
+    }
+    cyclic
+}
+
+// States on one tau-SCC are identified; a collapsed cycle keeps a tau
+// self-loop so divergence is preserved.
+fn tau_loop_factor(lts: &ExplicitLts) -> ExplicitLts {
+    let tau = tau_successors(lts);
+    let cyclic = tau_cyclic_states(&tau);
+    let mut block = vec![0usize; lts.state_count()];
+    let mut divergent = BTreeSet::new();
+    for (id, scc) in tarjan_scc(&tau).into_iter().enumerate() {
+        for state in scc {
+            block[state] = id;
+            if cyclic[state] {
+                divergent.insert(id);
+            }
+        }
+    }
+    let mut reduced = Vec::new();
+    for (state, &from) in block.iter().enumerate() {
+        for (label, next) in lts.transitions_from(state as StateId) {
+            if is_tau(label) && block[*next as usize] == from {
+                continue;
+            }
+            reduced.push((state as StateId, label.clone(), *next));
+        }
+    }
+    let collapsed =
+        ExplicitLts::new(lts.initial(), lts.state_count(), reduced).expect("states are in range");
+    let loops = divergent
+        .into_iter()
+        .map(|id| (id as StateId, TAU_LABEL.to_string(), id as StateId))
+        .collect();
+    quotient(&collapsed, &block, loops)
+}
+
+// Only the initial state and targets of visible transitions survive. Each
+// keeps the visible transitions of its tau-closure, tau edges to the stable
+// states with minimal acceptance sets, and a tau self-loop if it can diverge.
+fn diamond(lts: &ExplicitLts) -> ExplicitLts {
+    let tau = tau_successors(lts);
+    let cyclic = tau_cyclic_states(&tau);
+    let closure = |state: usize| {
+        let mut seen = BTreeSet::from([state]);
+        let mut queue = VecDeque::from([state]);
+        while let Some(current) = queue.pop_front() {
+            for &next in &tau[current] {
+                if seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
+    };
+    let acceptance = |state: usize| {
+        lts.transitions_from(state as StateId)
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect::<BTreeSet<_>>()
+    };
+
+    let initial = lts.initial() as usize;
+    let mut index_of = HashMap::from([(initial, 0 as StateId)]);
+    let mut queue = VecDeque::from([initial]);
+    let mut edges = Vec::new();
+    let mut node = |state: usize, queue: &mut VecDeque<usize>| {
+        let next_id = index_of.len() as StateId;
+        *index_of.entry(state).or_insert_with(|| {
+            queue.push_back(state);
+            next_id
+        })
+    };
+    let mut from: StateId = 0;
+    while let Some(state) = queue.pop_front() {
+        let members = closure(state);
+        for &member in &members {
+            for (label, next) in lts.transitions_from(member as StateId) {
+                if !is_tau(label) {
+                    let to = node(*next as usize, &mut queue);
+                    edges.push((from, label.clone(), to));
+                }
+            }
+        }
+        if !tau[state].is_empty() {
+            let stable = members
+                .iter()
+                .filter(|&&member| tau[member].is_empty())
+                .map(|&member| (member, acceptance(member)))
+                .collect::<Vec<_>>();
+            let mut kept = Vec::<&BTreeSet<&str>>::new();
+            for (member, offer) in &stable {
+                let subsumed = stable
+                    .iter()
+                    .any(|(_, other)| other.len() < offer.len() && other.is_subset(offer))
+                    || kept.contains(&offer);
+                if subsumed {
+                    continue;
+                }
+                kept.push(offer);
+                let to = node(*member, &mut queue);
+                edges.push((from, TAU_LABEL.to_string(), to));
+            }
+            if members.iter().any(|&member| cyclic[member]) {
+                edges.push((from, TAU_LABEL.to_string(), from));
+            }
+        }
+        from += 1;
+    }
+    ExplicitLts::new(0, index_of.len(), edges).expect("diamond states are in range")
+}
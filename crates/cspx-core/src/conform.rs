@@ -0,0 +1,241 @@
+use crate::check::{CheckRequest, CheckResult, Checker};
+use crate::check_refine::{enabled_visible_labels, tau_closure};
+use crate::counterexample_span::module_counterexample_spans;
+use crate::explain::Explainer;
+use crate::explain_simple::BasicExplainer;
+use crate::ir::Module;
+use crate::limits::RunLimits;
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::CspmTransitionProvider;
+use crate::types::{
+    Conformance, Counterexample, CounterexampleEvent, CounterexampleType, Minimality, Reason,
+    ReasonKind, SourceSpan, Stats, Status,
+};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+
+// One event of a recorded log and the line it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEvent {
+    pub label: String,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceLogError {
+    pub message: String,
+    pub span: SourceSpan,
+}
+
+impl Display for TraceLogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.span.path, self.span.start_line, self.span.start_col, self.message
+        )
+    }
+}
+
+impl std::error::Error for TraceLogError {}
+
+#[derive(Deserialize)]
+struct JsonLogEvent {
+    label: String,
+}
+
+// A log holds one event label per line, or one JSON object with a `label`
+// field per line (JSONL; other fields are ignored). Blank lines are skipped.
+pub fn parse_trace_log(input: &str, path: &str) -> Result<Vec<LogEvent>, TraceLogError> {
+    let mut events = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let span = SourceSpan {
+            path: path.to_string(),
+            start_line: idx as u32 + 1,
+            start_col: 1,
+            end_line: idx as u32 + 1,
+            end_col: (line.chars().count() as u32).max(1),
+        };
+        let label = if text.starts_with('{') {
+            serde_json::from_str::<JsonLogEvent>(text)
+                .map_err(|err| TraceLogError {
+                    message: format!("invalid log event JSON: {err}"),
+                    span: span.clone(),
+                })?
+                .label
+        } else {
+            text.to_string()
+        };
+        if label.is_empty() || label == TAU_LABEL {
+            return Err(TraceLogError {
+                message: format!("`{label}` is not an observable event"),
+                span,
+            });
+        }
+        events.push(LogEvent { label, span });
+    }
+    Ok(events)
+}
+
+#[derive(Debug, Default)]
+pub struct ConformanceChecker;
+
+#[derive(Debug, Clone)]
+pub struct ConformanceInput {
+    pub spec: Module,
+    pub log: Vec<LogEvent>,
+}
+
+impl Checker<ConformanceInput> for ConformanceChecker {
+    fn check(&self, request: &CheckRequest, input: &ConformanceInput) -> CheckResult {
+        self.check_with_limits(request, input, &RunLimits::unlimited())
+    }
+}
+
+impl ConformanceChecker {
+    pub fn check_with_limits(
+        &self,
+        request: &CheckRequest,
+        input: &ConformanceInput,
+        limits: &RunLimits,
+    ) -> CheckResult {
+        match CspmTransitionProvider::from_module(&input.spec) {
+            Ok(provider) => conformance_check(
+                &provider,
+                request,
+                module_counterexample_spans(&input.spec),
+                &input.log,
+                limits,
+            ),
+            Err(err) => conform_result(
+                request,
+                Status::Error,
+                Some(Reason {
+                    kind: ReasonKind::InvalidInput,
+                    message: Some(err.to_string()),
+                }),
+                None,
+            ),
+        }
+    }
+
+    pub fn check_provider<P>(
+        &self,
+        request: &CheckRequest,
+        provider: &P,
+        log: &[LogEvent],
+    ) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Ord + Hash,
+        P::Transition: TransitionLabel,
+    {
+        conformance_check(provider, request, Vec::new(), log, &RunLimits::unlimited())
+    }
+}
+
+// Follows the log through the tau closures of the spec, like the replay of
+// a refinement counterexample; the log conforms when every event is possible
+// after the ones before it.
+fn conformance_check<P>(
+    provider: &P,
+    request: &CheckRequest,
+    source_spans: Vec<SourceSpan>,
+    log: &[LogEvent],
+    limits: &RunLimits,
+) -> CheckResult
+where
+    P: TransitionProvider + Sync,
+    P::State: Ord + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut closure = tau_closure(provider, vec![provider.initial_state()]);
+    let mut events = Vec::new();
+    for entry in log {
+        if let Err(limit) = limits.check_deadline() {
+            return conform_result(request, limit.status(), Some(limit.reason()), None);
+        }
+        let mut seeds = Vec::new();
+        let mut spans = Vec::new();
+        for state in &closure.states {
+            for (transition, next) in provider.transitions(state) {
+                if transition.label() != entry.label {
+                    continue;
+                }
+                for span in provider.event_spans(state, &transition, &next) {
+                    if !spans.contains(&span) {
+                        spans.push(span);
+                    }
+                }
+                seeds.push(next);
+            }
+        }
+        if seeds.is_empty() {
+            events.push(CounterexampleEvent {
+                label: entry.label.clone(),
+                hidden_label: None,
+                spans: Vec::new(),
+            });
+            let counterexample = Counterexample {
+                kind: CounterexampleType::Trace,
+                events,
+                minimality: Minimality::None,
+                tags: vec!["conformance".to_string(), format!("label:{}", entry.label)],
+                source_spans,
+                lasso: None,
+                failure: None,
+                deadlock_explanation: None,
+                conformance: Some(Conformance {
+                    span: entry.span.clone(),
+                    allowed: enabled_visible_labels(provider, &closure.states)
+                        .into_iter()
+                        .collect(),
+                }),
+                explanation: None,
+            };
+            let explainer = BasicExplainer;
+            return conform_result(
+                request,
+                Status::Fail,
+                None,
+                Some(explainer.explain(counterexample)),
+            );
+        }
+        events.push(CounterexampleEvent {
+            label: entry.label.clone(),
+            hidden_label: None,
+            spans,
+        });
+        closure = tau_closure(provider, seeds);
+    }
+    conform_result(request, Status::Pass, None, None)
+}
+
+fn conform_result(
+    request: &CheckRequest,
+    status: Status,
+    reason: Option<Reason>,
+    counterexample: Option<Counterexample>,
+) -> CheckResult {
+    CheckResult {
+        name: "conform".to_string(),
+        model: None,
+        target: request.target.clone(),
+        status,
+        reason,
+        counterexample,
+        counterexamples: Vec::new(),
+        stats: Some(Stats {
+            states: None,
+            transitions: None,
+            depth: None,
+        }),
+        replay: None,
+        simulation: None,
+    }
+}
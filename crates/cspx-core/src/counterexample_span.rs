@@ -12,7 +12,13 @@ pub(crate) fn module_counterexample_spans(module: &Module) -> Vec<SourceSpan> {
 }
 
 pub(crate) fn refinement_counterexample_spans(spec: &Module, impl_: &Module) -> Vec<SourceSpan> {
-    let mut spans = module_counterexample_spans(spec);
+    merge_counterexample_spans(module_counterexample_spans(spec), impl_)
+}
+
+pub(crate) fn merge_counterexample_spans(
+    mut spans: Vec<SourceSpan>,
+    impl_: &Module,
+) -> Vec<SourceSpan> {
     spans.extend(module_counterexample_spans(impl_));
     spans.sort_by(|left, right| {
         (
@@ -0,0 +1,183 @@
+use crate::ir::{ChannelDomain, EventInput, EventSeg, EventValue, Module, ProcessExpr, Spanned};
+use crate::limits::RunLimits;
+use crate::lts::{TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{CspmLtsError, CspmTransitionProvider, ReachedSites};
+use crate::types::SourceSpan;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageEntry {
+    pub name: String,
+    pub span: SourceSpan,
+}
+
+// What the reachable states of a model never exercise. `complete` is false
+// when a limit stopped the exploration, so the lists may over-report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelCoverage {
+    pub never_enabled_events: Vec<CoverageEntry>,
+    pub unreached_processes: Vec<CoverageEntry>,
+    pub unreached_prefixes: Vec<CoverageEntry>,
+    pub complete: bool,
+}
+
+// Explores the entry process and every process an assertion names, then
+// compares what was reached against the declarations. An event counts as
+// enabled once some reachable state performs it, hidden events included.
+pub fn model_coverage(module: &Module, limits: &RunLimits) -> Result<ModelCoverage, CspmLtsError> {
+    let mut enabled = BTreeSet::new();
+    let mut reached = ReachedSites::default();
+    let mut complete = true;
+    for root in coverage_roots(module) {
+        let provider = CspmTransitionProvider::from_module(&root)?;
+        let initial = provider.initial_state();
+        let mut visited = HashSet::from([initial.clone()]);
+        let mut queue = VecDeque::from([initial]);
+        while let Some(state) = queue.pop_front() {
+            if limits.check_deadline().is_err() {
+                complete = false;
+                break;
+            }
+            for (transition, next) in provider.transitions(&state) {
+                let label = if transition.label == TAU_LABEL {
+                    provider.hidden_label(&state, &next)
+                } else {
+                    Some(transition.label)
+                };
+                enabled.extend(label);
+                if visited.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        let sites = provider.reached_sites(&visited);
+        reached.processes.extend(sites.processes);
+        reached.prefixes.extend(sites.prefixes);
+        if !complete {
+            break;
+        }
+    }
+
+    let never_enabled_events = channel_events(module)
+        .into_iter()
+        .filter(|entry| !enabled.contains(&entry.name))
+        .collect();
+    let unreached_processes = module
+        .declarations
+        .iter()
+        .filter(|decl| !reached.processes.contains(&decl.name.value))
+        .map(|decl| CoverageEntry {
+            name: decl.name.value.clone(),
+            span: decl.name.span.clone(),
+        })
+        .collect();
+    let mut prefixes = Vec::new();
+    for decl in &module.declarations {
+        collect_prefixes(&decl.expr, &mut prefixes);
+    }
+    if let Some(entry) = &module.entry {
+        if !module
+            .declarations
+            .iter()
+            .any(|decl| decl.expr.span == entry.span)
+        {
+            collect_prefixes(entry, &mut prefixes);
+        }
+    }
+    let unreached_prefixes = prefixes
+        .into_iter()
+        .filter(|entry| !reached.prefixes.contains(&entry.span))
+        .collect();
+    Ok(ModelCoverage {
+        never_enabled_events,
+        unreached_processes,
+        unreached_prefixes,
+        complete,
+    })
+}
+
+// One module per explored root, each with that root as its entry.
+fn coverage_roots(module: &Module) -> Vec<Module> {
+    let mut names = Vec::new();
+    for assertion in &module.assertions {
+        match assertion {
+            crate::ir::AssertionDecl::Property { target, .. } => names.push(&target.value),
+            crate::ir::AssertionDecl::Refinement { spec, impl_, .. } => {
+                names.extend([&spec.value, &impl_.value])
+            }
+        }
+    }
+    let mut roots = Vec::new();
+    if module.entry.is_some() || module.declarations.len() == 1 {
+        roots.push(module.clone());
+    }
+    let mut seen = BTreeSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            continue;
+        }
+        if let Some(decl) = module.declarations.iter().find(|d| &d.name.value == name) {
+            let mut root = module.clone();
+            root.entry = Some(decl.expr.clone());
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+fn channel_events(module: &Module) -> Vec<CoverageEntry> {
+    let mut out = Vec::new();
+    for decl in &module.channels {
+        for name in &decl.names {
+            let entry = |label: String| CoverageEntry {
+                name: label,
+                span: name.span.clone(),
+            };
+            match decl.domain.as_ref().map(|domain| &domain.value) {
+                None => out.push(entry(name.value.clone())),
+                Some(ChannelDomain::IntRange { min, max }) => out.extend(
+                    (min.value..=max.value).map(|value| entry(format!("{}.{value}", name.value))),
+                ),
+                Some(ChannelDomain::NamedType(_)) => {}
+            }
+        }
+    }
+    out
+}
+
+fn collect_prefixes(expr: &Spanned<ProcessExpr>, out: &mut Vec<CoverageEntry>) {
+    match &expr.value {
+        ProcessExpr::Stop | ProcessExpr::Ref(_) | ProcessExpr::LoadLts { .. } => {}
+        ProcessExpr::Prefix { event, next } => {
+            let channel = &event.value.channel.value;
+            let name = match &event.value.seg {
+                None => channel.clone(),
+                Some(EventSeg::Dot(value)) => format!("{channel}.{}", event_value(&value.value)),
+                Some(EventSeg::Out(value)) => format!("{channel}!{}", event_value(&value.value)),
+                Some(EventSeg::In(input)) => match &input.value {
+                    EventInput::Int(n) => format!("{channel}?{n}"),
+                    EventInput::Bind(var) => format!("{channel}?{var}"),
+                },
+            };
+            out.push(CoverageEntry {
+                name,
+                span: expr.span.clone(),
+            });
+            collect_prefixes(next, out);
+        }
+        ProcessExpr::Choice { left, right, .. } | ProcessExpr::Parallel { left, right, .. } => {
+            collect_prefixes(left, out);
+            collect_prefixes(right, out);
+        }
+        ProcessExpr::Hide { inner, .. } | ProcessExpr::Compress { inner, .. } => {
+            collect_prefixes(inner, out)
+        }
+    }
+}
+
+fn event_value(value: &EventValue) -> String {
+    match value {
+        EventValue::Int(n) => n.to_string(),
+        EventValue::Ident(name) => name.clone(),
+    }
+}
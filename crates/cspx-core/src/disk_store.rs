@@ -1,14 +1,22 @@
+use crate::limits::ENTRY_OVERHEAD_BYTES;
 use crate::state_codec::StateCodec;
 use crate::store::StateStore;
 use std::collections::HashSet;
 use std::fs::{self, OpenOptions};
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::{Duration, Instant};
 
-const INDEX_MAGIC: &str = "cspx-disk-index-v1";
+const FORMAT_VERSION: u32 = 2;
+const MANIFEST_MAGIC: &str = "cspx-disk-log";
+const INDEX_MAGIC: &[u8; 8] = b"CSPXIDX\0";
+const INDEX_ENTRY: u8 = 0;
+const INDEX_COMMIT: u8 = 1;
+const SEGMENT_MAGIC: &[u8; 8] = b"CSPXSEG\0";
+pub(crate) const SEGMENT_HEADER_LEN: u64 = 12;
+pub(crate) const RECORD_HEADER_LEN: u64 = 8;
 
 #[derive(Debug)]
 struct StorePaths {
@@ -25,6 +33,48 @@ impl StorePaths {
             log_path,
         }
     }
+
+    fn segment_path(&self, generation: u64, segment: u32) -> PathBuf {
+        self.log_path
+            .with_extension(format!("{generation}.{segment:06}.seg"))
+    }
+
+    fn stray_segments(&self, generation: u64) -> io::Result<Vec<PathBuf>> {
+        let Some(stem) = self.log_path.file_stem().and_then(|stem| stem.to_str()) else {
+            return Ok(Vec::new());
+        };
+        let dir = match self.log_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut stray = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let Some(segment) = name
+                .strip_prefix(stem)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .and_then(|rest| rest.strip_suffix(".seg"))
+            else {
+                continue;
+            };
+            let Some((segment_generation, number)) = segment.split_once('.') else {
+                continue;
+            };
+            let (Ok(segment_generation), Ok(_)) =
+                (segment_generation.parse::<u64>(), number.parse::<u32>())
+            else {
+                continue;
+            };
+            if segment_generation != generation {
+                stray.push(entry.path());
+            }
+        }
+        Ok(stray)
+    }
 }
 
 #[derive(Debug)]
@@ -66,6 +116,8 @@ pub struct DiskStateStoreOpenOptions {
     pub lock_retry_count: u32,
     pub lock_retry_backoff: Duration,
     pub index_flush_every: u32,
+    pub segment_bytes: u64,
+    pub compact_after_segments: u32,
 }
 
 impl Default for DiskStateStoreOpenOptions {
@@ -73,7 +125,9 @@ impl Default for DiskStateStoreOpenOptions {
         Self {
             lock_retry_count: 0,
             lock_retry_backoff: Duration::ZERO,
-            index_flush_every: 1,
+            index_flush_every: 64 * 1024,
+            segment_bytes: 64 * 1024 * 1024,
+            compact_after_segments: 16,
         }
     }
 }
@@ -95,6 +149,10 @@ pub struct DiskStateStoreMetrics {
     pub log_write_ops: u64,
     pub log_write_ns: u64,
     pub log_write_bytes: u64,
+    pub log_truncated_bytes: u64,
+    pub log_segments: u64,
+    pub compactions: u64,
+    pub compaction_ns: u64,
     pub index_write_ops: u64,
     pub index_write_ns: u64,
     pub index_write_bytes: u64,
@@ -107,6 +165,13 @@ impl DiskStateStoreMetrics {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LogPosition {
+    generation: u64,
+    segments: u32,
+    tail_len: u64,
+}
+
 #[derive(Debug)]
 pub struct DiskStateStore<S, C>
 where
@@ -115,10 +180,16 @@ where
     paths: StorePaths,
     codec: C,
     index: HashSet<Vec<u8>>,
+    index_bytes: u64,
     metrics: DiskStateStoreMetrics,
     index_flush_every: u32,
+    segment_bytes: u64,
     pending_index_updates: u32,
-    current_log_len: u64,
+    position: LogPosition,
+    index_log: BufWriter<fs::File>,
+    // Each open appends to a fresh segment, so sealed segments never change
+    // and only the last one can end in a torn record.
+    active: Option<BufWriter<fs::File>>,
     _lock: LockGuard,
     _marker: PhantomData<S>,
 }
@@ -142,6 +213,12 @@ where
                 "index_flush_every must be >= 1",
             ));
         }
+        if options.segment_bytes <= SEGMENT_HEADER_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("segment_bytes must be > {SEGMENT_HEADER_LEN}"),
+            ));
+        }
         let open_start = Instant::now();
         let paths = StorePaths::new(path.as_ref().to_path_buf());
         if let Some(parent) = paths
@@ -174,13 +251,31 @@ where
             }
         };
 
-        let log_file = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .truncate(false)
-            .open(&paths.log_path)?;
-        let log_len = log_file.metadata()?.len();
+        let generation = match read_manifest(&paths.log_path)? {
+            Some(generation) => generation,
+            None => {
+                write_manifest(&paths.log_path, 0)?;
+                0
+            }
+        };
+        for stray in paths.stray_segments(generation)? {
+            fs::remove_file(stray)?;
+        }
+        let mut segments = 0u32;
+        while paths.segment_path(generation, segments).exists() {
+            segments = segments.checked_add(1).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "too many log segments")
+            })?;
+        }
+        let tail_len = match segments.checked_sub(1) {
+            Some(last) => fs::metadata(paths.segment_path(generation, last))?.len(),
+            None => 0,
+        };
+        let on_disk = LogPosition {
+            generation,
+            segments,
+            tail_len,
+        };
 
         let mut metrics = DiskStateStoreMetrics {
             lock_wait_ns: duration_ns(lock_wait_start.elapsed()),
@@ -190,58 +285,194 @@ where
         };
 
         let index_load_start = Instant::now();
-        let (index, current_log_len) = match load_index_from_file(&paths.idx_path, &codec, log_len)?
-        {
-            Some(index) => {
-                metrics.index_entries_loaded = usize_to_u64(index.len())?;
-                metrics.index_read_bytes = fs::metadata(&paths.idx_path)
-                    .map(|metadata| metadata.len())
-                    .unwrap_or_default();
-                (index, log_len)
-            }
-            None => {
-                metrics.log_read_bytes = log_len;
-                let rebuild_start = Instant::now();
-                let (rebuilt, normalized_log_len) =
-                    rebuild_index_from_log(&paths.log_path, &codec)?;
-                metrics.index_rebuild_ns = duration_ns(rebuild_start.elapsed());
-                metrics.index_entries_rebuilt = usize_to_u64(rebuilt.len())?;
-
-                let index_write_start = Instant::now();
-                let bytes_written =
-                    write_index_file(&paths.idx_path, &rebuilt, normalized_log_len)?;
-                metrics.index_write_ns = metrics
-                    .index_write_ns
-                    .saturating_add(duration_ns(index_write_start.elapsed()));
-                metrics.index_write_ops = metrics.index_write_ops.saturating_add(1);
-                metrics.index_write_bytes = metrics.index_write_bytes.saturating_add(bytes_written);
-                (rebuilt, normalized_log_len)
-            }
-        };
+        let (index, position, index_log) =
+            match load_index_from_file(&paths.idx_path, &codec, on_disk)? {
+                Some(loaded) => {
+                    metrics.index_entries_loaded = usize_to_u64(loaded.index.len())?;
+                    metrics.index_read_bytes = fs::metadata(&paths.idx_path)
+                        .map(|metadata| metadata.len())
+                        .unwrap_or_default();
+                    let index_log = open_index_log(&paths.idx_path, loaded.committed_len)?;
+                    (loaded.index, on_disk, index_log)
+                }
+                None => {
+                    let rebuild_start = Instant::now();
+                    let rebuilt = rebuild_index_from_log(&paths, on_disk, &codec)?;
+                    metrics.index_rebuild_ns = duration_ns(rebuild_start.elapsed());
+                    metrics.index_entries_rebuilt = usize_to_u64(rebuilt.index.len())?;
+                    metrics.log_read_bytes = rebuilt.read_bytes;
+                    metrics.log_truncated_bytes = rebuilt.truncated_bytes;
+
+                    let index_write_start = Instant::now();
+                    let bytes_written =
+                        write_index_file(&paths.idx_path, &rebuilt.index, rebuilt.position)?;
+                    metrics.index_write_ns = metrics
+                        .index_write_ns
+                        .saturating_add(duration_ns(index_write_start.elapsed()));
+                    metrics.index_write_ops = metrics.index_write_ops.saturating_add(1);
+                    metrics.index_write_bytes =
+                        metrics.index_write_bytes.saturating_add(bytes_written);
+                    let index_log = open_index_log(&paths.idx_path, bytes_written)?;
+                    (rebuilt.index, rebuilt.position, index_log)
+                }
+            };
         metrics.index_load_ns = duration_ns(index_load_start.elapsed());
-        metrics.open_ns = duration_ns(open_start.elapsed());
+        metrics.log_segments = u64::from(position.segments);
         metrics.pending_index_updates = 0;
 
-        Ok(Self {
+        let index_bytes = index.iter().map(|bytes| index_entry_bytes(bytes)).sum();
+        let mut store = Self {
             paths,
             codec,
             index,
+            index_bytes,
             metrics,
             index_flush_every: options.index_flush_every,
+            segment_bytes: options.segment_bytes,
             pending_index_updates: 0,
-            current_log_len,
+            position,
+            index_log,
+            active: None,
             _lock: lock,
             _marker: PhantomData,
-        })
+        };
+        if options.compact_after_segments > 0
+            && store.position.segments > options.compact_after_segments
+        {
+            store.compact()?;
+        }
+        store.metrics.open_ns = duration_ns(open_start.elapsed());
+        Ok(store)
     }
 
+    pub fn metrics(&self) -> &DiskStateStoreMetrics {
+        &self.metrics
+    }
+
+    pub fn segment_count(&self) -> u32 {
+        self.position.segments
+    }
+
+    // A crash leaves either the old or the new generation intact.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let compaction_start = Instant::now();
+        self.seal_active()?;
+        let generation =
+            self.position.generation.checked_add(1).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "log generation overflow")
+            })?;
+
+        let mut records = self.index.iter().collect::<Vec<_>>();
+        records.sort();
+        let mut segments = 0u32;
+        let mut tail_len = 0u64;
+        let mut writer: Option<BufWriter<fs::File>> = None;
+        for bytes in records {
+            let record_len = RECORD_HEADER_LEN.saturating_add(usize_to_u64(bytes.len())?);
+            let full = tail_len > SEGMENT_HEADER_LEN
+                && tail_len.saturating_add(record_len) > self.segment_bytes;
+            if writer.is_none() || full {
+                if let Some(writer) = writer.take() {
+                    finish_segment(writer)?;
+                }
+                writer = Some(create_segment(
+                    &self.paths.segment_path(generation, segments),
+                )?);
+                segments += 1;
+                tail_len = SEGMENT_HEADER_LEN;
+            }
+            if let Some(writer) = writer.as_mut() {
+                write_record(writer, bytes)?;
+            }
+            tail_len = tail_len.saturating_add(record_len);
+        }
+        if let Some(writer) = writer.take() {
+            finish_segment(writer)?;
+        }
+
+        write_manifest(&self.paths.log_path, generation)?;
+        for segment in 0..self.position.segments {
+            let path = self.paths.segment_path(self.position.generation, segment);
+            match fs::remove_file(path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.position = LogPosition {
+            generation,
+            segments,
+            tail_len,
+        };
+        self.write_index_snapshot()?;
+
+        self.metrics.log_segments = u64::from(segments);
+        self.metrics.compactions = self.metrics.compactions.saturating_add(1);
+        self.metrics.compaction_ns = self
+            .metrics
+            .compaction_ns
+            .saturating_add(duration_ns(compaction_start.elapsed()));
+        Ok(())
+    }
+
+    fn seal_active(&mut self) -> io::Result<()> {
+        if let Some(writer) = self.active.take() {
+            finish_segment(writer)?;
+        }
+        Ok(())
+    }
+
+    fn append_record(&mut self, bytes: &[u8]) -> io::Result<u64> {
+        let record_len = RECORD_HEADER_LEN.saturating_add(usize_to_u64(bytes.len())?);
+        let full = self.position.tail_len > SEGMENT_HEADER_LEN
+            && self.position.tail_len.saturating_add(record_len) > self.segment_bytes;
+        let mut written = 0u64;
+        if self.active.is_none() || full {
+            self.seal_active()?;
+            let segment = self.position.segments;
+            let next = segment.checked_add(1).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "too many log segments")
+            })?;
+            let writer =
+                create_segment(&self.paths.segment_path(self.position.generation, segment))?;
+            self.active = Some(writer);
+            self.position.segments = next;
+            self.position.tail_len = SEGMENT_HEADER_LEN;
+            self.metrics.log_segments = u64::from(next);
+            written = SEGMENT_HEADER_LEN;
+        }
+        let Some(writer) = self.active.as_mut() else {
+            return Err(io::Error::other("no active log segment"));
+        };
+        write_record(writer, bytes)?;
+        self.position.tail_len = self.position.tail_len.saturating_add(record_len);
+        Ok(written.saturating_add(record_len))
+    }
+
+    // Commits the entries appended since the last commit.
     fn flush_index_snapshot(&mut self) -> io::Result<()> {
         if self.pending_index_updates == 0 {
             return Ok(());
         }
+        if let Some(writer) = self.active.as_mut() {
+            writer.flush()?;
+        }
         let index_write_start = Instant::now();
-        let bytes_written =
-            write_index_file(&self.paths.idx_path, &self.index, self.current_log_len)?;
+        let bytes_written = write_index_commit(&mut self.index_log, self.position)?;
+        self.index_log.flush()?;
+        self.record_index_write(index_write_start, bytes_written);
+        Ok(())
+    }
+
+    fn write_index_snapshot(&mut self) -> io::Result<()> {
+        let index_write_start = Instant::now();
+        let bytes_written = write_index_file(&self.paths.idx_path, &self.index, self.position)?;
+        self.index_log = open_index_log(&self.paths.idx_path, bytes_written)?;
+        self.record_index_write(index_write_start, bytes_written);
+        Ok(())
+    }
+
+    fn record_index_write(&mut self, index_write_start: Instant, bytes_written: u64) {
         self.metrics.index_write_ns = self
             .metrics
             .index_write_ns
@@ -251,11 +482,6 @@ where
             self.metrics.index_write_bytes.saturating_add(bytes_written);
         self.pending_index_updates = 0;
         self.metrics.pending_index_updates = 0;
-        Ok(())
-    }
-
-    pub fn metrics(&self) -> &DiskStateStoreMetrics {
-        &self.metrics
     }
 }
 
@@ -265,6 +491,7 @@ where
 {
     fn drop(&mut self) {
         let _ = self.flush_index_snapshot();
+        let _ = self.seal_active();
     }
 }
 
@@ -281,18 +508,17 @@ where
         }
 
         let log_write_start = Instant::now();
-        let append = append_log_record(&self.paths.log_path, &bytes)?;
+        let written_bytes = self.append_record(&bytes)?;
         self.metrics.log_write_ns = self
             .metrics
             .log_write_ns
             .saturating_add(duration_ns(log_write_start.elapsed()));
         self.metrics.log_write_ops = self.metrics.log_write_ops.saturating_add(1);
-        self.metrics.log_write_bytes = self
-            .metrics
-            .log_write_bytes
-            .saturating_add(append.written_bytes);
-        self.current_log_len = append.log_len;
+        self.metrics.log_write_bytes = self.metrics.log_write_bytes.saturating_add(written_bytes);
 
+        let index_bytes = write_index_entry(&mut self.index_log, &bytes)?;
+        self.metrics.index_write_bytes = self.metrics.index_write_bytes.saturating_add(index_bytes);
+        self.index_bytes = self.index_bytes.saturating_add(index_entry_bytes(&bytes));
         self.index.insert(bytes);
         self.pending_index_updates = self.pending_index_updates.saturating_add(1);
         self.metrics.pending_index_updates = u64::from(self.pending_index_updates);
@@ -306,157 +532,348 @@ where
     fn len(&self) -> usize {
         self.index.len()
     }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        Some(self.index_bytes)
+    }
 }
 
-struct AppendLogResult {
-    log_len: u64,
-    written_bytes: u64,
+fn index_entry_bytes(bytes: &[u8]) -> u64 {
+    bytes.len() as u64 + std::mem::size_of::<Vec<u8>>() as u64 + ENTRY_OVERHEAD_BYTES
 }
 
-fn append_log_record(path: &Path, bytes: &[u8]) -> io::Result<AppendLogResult> {
-    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
-    let encoded = hex::encode(bytes);
-    writeln!(file, "{encoded}")?;
-    file.flush()?;
-    Ok(AppendLogResult {
-        log_len: file.metadata()?.len(),
-        written_bytes: (encoded.len() as u64).saturating_add(1),
-    })
+fn read_manifest(path: &Path) -> io::Result<Option<u64>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let text = std::str::from_utf8(&data)
+        .map_err(|_| invalid(format!("unrecognised state log: {}", path.display())))?;
+    let mut lines = text.lines();
+    let version = lines
+        .next()
+        .and_then(|line| line.strip_prefix(MANIFEST_MAGIC))
+        .and_then(|rest| rest.strip_prefix(" v"))
+        .and_then(|version| version.parse::<u32>().ok())
+        .ok_or_else(|| invalid(format!("unrecognised state log: {}", path.display())))?;
+    if version != FORMAT_VERSION {
+        return Err(invalid(format!(
+            "unsupported state log version {version} (expected {FORMAT_VERSION}): {}",
+            path.display()
+        )));
+    }
+    lines
+        .next()
+        .and_then(|line| line.strip_prefix("generation="))
+        .and_then(|generation| generation.parse().ok())
+        .map(Some)
+        .ok_or_else(|| invalid(format!("invalid state log manifest: {}", path.display())))
+}
+
+fn write_manifest(path: &Path, generation: u64) -> io::Result<()> {
+    let tmp_path = path.with_extension("manifest.tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    writeln!(file, "{MANIFEST_MAGIC} v{FORMAT_VERSION}")?;
+    writeln!(file, "generation={generation}")?;
+    file.sync_all()?;
+    drop(file);
+    replace_file(&tmp_path, path)
+}
+
+pub(crate) fn segment_header() -> [u8; SEGMENT_HEADER_LEN as usize] {
+    let mut header = [0u8; SEGMENT_HEADER_LEN as usize];
+    header[..SEGMENT_MAGIC.len()].copy_from_slice(SEGMENT_MAGIC);
+    header[SEGMENT_MAGIC.len()..].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header
+}
+
+pub(crate) fn create_segment(path: &Path) -> io::Result<BufWriter<fs::File>> {
+    let mut file = OpenOptions::new().create_new(true).write(true).open(path)?;
+    file.write_all(&segment_header())?;
+    Ok(BufWriter::new(file))
 }
 
+fn finish_segment(writer: BufWriter<fs::File>) -> io::Result<()> {
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()
+}
+
+pub(crate) fn write_record(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "state record too large"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&crc32fast::hash(bytes).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+struct LoadedIndex {
+    index: HashSet<Vec<u8>>,
+    committed_len: u64,
+}
+
+// Entries after the last commit record are dropped; they may describe log
+// records that never reached the disk.
 fn load_index_from_file<S, C>(
     idx_path: &Path,
     codec: &C,
-    expected_log_len: u64,
-) -> io::Result<Option<HashSet<Vec<u8>>>>
+    expected: LogPosition,
+) -> io::Result<Option<LoadedIndex>>
 where
     C: StateCodec<S>,
 {
-    if !idx_path.exists() {
-        return Ok(None);
-    }
-
-    let file = fs::File::open(idx_path)?;
-    let mut lines = BufReader::new(file).lines();
-    let Some(header) = lines.next() else {
-        return Ok(None);
-    };
-    let Some(log_len) = parse_index_header(&header?) else {
-        return Ok(None);
+    let data = match fs::read(idx_path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
     };
-    if log_len != expected_log_len {
+    let header_len = SEGMENT_HEADER_LEN as usize;
+    if data.get(..header_len) != Some(&index_header()[..]) {
         return Ok(None);
     }
 
-    let mut index = HashSet::new();
-    for line in lines {
-        let line = line?;
-        if line.is_empty() {
-            continue;
+    let mut entries = Vec::new();
+    let mut committed = None;
+    let mut offset = header_len;
+    while let Record::Valid(payload, next) = parse_record(&data, offset) {
+        match payload.split_first() {
+            Some((&INDEX_ENTRY, bytes)) if is_valid_record(bytes, codec) => entries.push(bytes),
+            Some((&INDEX_COMMIT, position)) => match parse_index_position(position) {
+                Some(position) => committed = Some((position, entries.len(), next)),
+                None => break,
+            },
+            _ => break,
         }
-        let bytes = decode_validated_record(&line, codec).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("invalid index record in {}", idx_path.display()),
-            )
-        })?;
-        index.insert(bytes);
+        offset = next;
+    }
+    match committed {
+        Some((position, count, len)) if position == expected => Ok(Some(LoadedIndex {
+            index: entries[..count]
+                .iter()
+                .map(|bytes| bytes.to_vec())
+                .collect(),
+            committed_len: usize_to_u64(len)?,
+        })),
+        _ => Ok(None),
     }
-    Ok(Some(index))
 }
 
-fn parse_index_header(line: &str) -> Option<u64> {
-    let value = line
-        .trim_end_matches('\r')
-        .strip_prefix(INDEX_MAGIC)?
-        .strip_prefix(" log_len=")?;
-    value.parse().ok()
+fn index_header() -> [u8; SEGMENT_HEADER_LEN as usize] {
+    let mut header = [0u8; SEGMENT_HEADER_LEN as usize];
+    header[..INDEX_MAGIC.len()].copy_from_slice(INDEX_MAGIC);
+    header[INDEX_MAGIC.len()..].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header
+}
+
+fn parse_index_position(bytes: &[u8]) -> Option<LogPosition> {
+    if bytes.len() != 20 {
+        return None;
+    }
+    Some(LogPosition {
+        generation: u64::from_le_bytes(bytes[..8].try_into().ok()?),
+        segments: u32::from_le_bytes(bytes[8..12].try_into().ok()?),
+        tail_len: u64::from_le_bytes(bytes[12..].try_into().ok()?),
+    })
+}
+
+fn write_index_entry(writer: &mut impl Write, bytes: &[u8]) -> io::Result<u64> {
+    let mut payload = Vec::with_capacity(bytes.len() + 1);
+    payload.push(INDEX_ENTRY);
+    payload.extend_from_slice(bytes);
+    write_record(writer, &payload)?;
+    Ok(RECORD_HEADER_LEN.saturating_add(usize_to_u64(payload.len())?))
 }
 
-fn rebuild_index_from_log<S, C>(log_path: &Path, codec: &C) -> io::Result<(HashSet<Vec<u8>>, u64)>
+fn write_index_commit(writer: &mut impl Write, position: LogPosition) -> io::Result<u64> {
+    let mut payload = vec![INDEX_COMMIT];
+    payload.extend_from_slice(&position.generation.to_le_bytes());
+    payload.extend_from_slice(&position.segments.to_le_bytes());
+    payload.extend_from_slice(&position.tail_len.to_le_bytes());
+    write_record(writer, &payload)?;
+    Ok(RECORD_HEADER_LEN.saturating_add(usize_to_u64(payload.len())?))
+}
+
+struct RebuiltIndex {
+    index: HashSet<Vec<u8>>,
+    position: LogPosition,
+    read_bytes: u64,
+    truncated_bytes: u64,
+}
+
+fn rebuild_index_from_log<S, C>(
+    paths: &StorePaths,
+    on_disk: LogPosition,
+    codec: &C,
+) -> io::Result<RebuiltIndex>
 where
     C: StateCodec<S>,
 {
-    let data = fs::read(log_path)?;
-    let mut index = HashSet::new();
-    let mut line_start = 0usize;
-    let mut normalized_len = 0u64;
-
-    for (cursor, byte) in data.iter().enumerate() {
-        if *byte != b'\n' {
-            continue;
+    let mut rebuilt = RebuiltIndex {
+        index: HashSet::new(),
+        position: on_disk,
+        read_bytes: 0,
+        truncated_bytes: 0,
+    };
+    for segment in 0..on_disk.segments {
+        let path = paths.segment_path(on_disk.generation, segment);
+        let data = fs::read(&path)?;
+        rebuilt.read_bytes = rebuilt.read_bytes.saturating_add(usize_to_u64(data.len())?);
+        let is_last = segment + 1 == on_disk.segments;
+        let invalid = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{message} in {}", path.display()),
+            )
+        };
+
+        let header_len = SEGMENT_HEADER_LEN as usize;
+        let header = segment_header();
+        if data.len() < header_len {
+            // A segment is created right before its first record, so a crash
+            // can leave the newest one without a complete header.
+            if !is_last || !header.starts_with(&data) {
+                return Err(invalid("truncated segment header"));
+            }
+            fs::remove_file(&path)?;
+            rebuilt.truncated_bytes = usize_to_u64(data.len())?;
+            rebuilt.position.segments = segment;
+            rebuilt.position.tail_len = match segment.checked_sub(1) {
+                Some(previous) => {
+                    fs::metadata(paths.segment_path(on_disk.generation, previous))?.len()
+                }
+                None => 0,
+            };
+            break;
+        }
+        if data[..SEGMENT_MAGIC.len()] != SEGMENT_MAGIC[..] {
+            return Err(invalid("invalid segment header"));
+        }
+        if data[..header_len] != header[..] {
+            return Err(invalid("unsupported segment version"));
         }
 
-        let line = &data[line_start..cursor];
-        if !line.is_empty() {
-            let text = std::str::from_utf8(line).map_err(|err| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("invalid utf8 record: {err}"),
-                )
-            })?;
-            let text = text.trim_end_matches('\r');
-            if !text.is_empty() {
-                let bytes = decode_validated_record(text, codec).ok_or_else(|| {
-                    io::Error::new(io::ErrorKind::InvalidData, "invalid log record")
-                })?;
-                index.insert(bytes);
+        let mut offset = header_len;
+        while offset < data.len() {
+            let record = parse_record(&data, offset);
+            match record {
+                Record::Valid(bytes, next) => {
+                    if !is_valid_record(bytes, codec) {
+                        return Err(invalid("invalid log record"));
+                    }
+                    rebuilt.index.insert(bytes.to_vec());
+                    offset = next;
+                }
+                // Only the final record of the newest segment can be torn:
+                // keep every complete record before it.
+                Record::Torn | Record::Corrupt(_) if is_last && record.is_final(data.len()) => {
+                    let file = OpenOptions::new().write(true).open(&path)?;
+                    file.set_len(usize_to_u64(offset)?)?;
+                    file.sync_all()?;
+                    rebuilt.truncated_bytes = usize_to_u64(data.len() - offset)?;
+                    break;
+                }
+                Record::Torn => return Err(invalid("truncated record in sealed segment")),
+                Record::Corrupt(_) => return Err(invalid("log record checksum mismatch")),
             }
         }
-        line_start = cursor + 1;
-        normalized_len = usize_to_u64(line_start)?;
+        if is_last {
+            rebuilt.position.tail_len = usize_to_u64(offset)?;
+        }
     }
+    Ok(rebuilt)
+}
 
-    if line_start < data.len() {
-        let file = OpenOptions::new().write(true).open(log_path)?;
-        file.set_len(normalized_len)?;
-    } else {
-        normalized_len = usize_to_u64(data.len())?;
+enum Record<'a> {
+    Valid(&'a [u8], usize),
+    Torn,
+    Corrupt(usize),
+}
+
+impl Record<'_> {
+    // A record that fails its checksum is only a torn write when nothing
+    // follows it; otherwise the log is corrupt.
+    fn is_final(&self, data_len: usize) -> bool {
+        match self {
+            Record::Valid(_, next) | Record::Corrupt(next) => *next == data_len,
+            Record::Torn => true,
+        }
     }
+}
 
-    Ok((index, normalized_len))
+fn parse_record(data: &[u8], offset: usize) -> Record<'_> {
+    let header_end = offset + RECORD_HEADER_LEN as usize;
+    let Some(header) = data.get(offset..header_end) else {
+        return Record::Torn;
+    };
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let Some(end) = usize::try_from(len)
+        .ok()
+        .and_then(|len| header_end.checked_add(len))
+    else {
+        return Record::Torn;
+    };
+    let Some(bytes) = data.get(header_end..end) else {
+        return Record::Torn;
+    };
+    if crc32fast::hash(bytes) == crc {
+        Record::Valid(bytes, end)
+    } else {
+        Record::Corrupt(end)
+    }
 }
 
-fn write_index_file(path: &Path, index: &HashSet<Vec<u8>>, log_len: u64) -> io::Result<u64> {
+// A full snapshot: every entry followed by one commit record.
+fn write_index_file(
+    path: &Path,
+    index: &HashSet<Vec<u8>>,
+    position: LogPosition,
+) -> io::Result<u64> {
     let tmp_path = path.with_extension("idx.tmp");
-    let mut file = fs::File::create(&tmp_path)?;
-    let mut written_bytes = 0u64;
-    let header = format!("{INDEX_MAGIC} log_len={log_len}");
-    writeln!(file, "{header}")?;
-    written_bytes = written_bytes.saturating_add((header.len() as u64).saturating_add(1));
-
-    let mut records = index.iter().map(hex::encode).collect::<Vec<_>>();
-    records.sort();
-    for record in records {
-        writeln!(file, "{record}")?;
-        written_bytes = written_bytes.saturating_add((record.len() as u64).saturating_add(1));
+    let mut file = BufWriter::new(fs::File::create(&tmp_path)?);
+    file.write_all(&index_header())?;
+    let mut written_bytes = SEGMENT_HEADER_LEN;
+    for bytes in index {
+        written_bytes = written_bytes.saturating_add(write_index_entry(&mut file, bytes)?);
     }
+    written_bytes = written_bytes.saturating_add(write_index_commit(&mut file, position)?);
     file.flush()?;
+    drop(file);
+
+    replace_file(&tmp_path, path)?;
+    Ok(written_bytes)
+}
 
-    match fs::rename(&tmp_path, path) {
-        Ok(()) => Ok(written_bytes),
+// Appends to the index after its last commit record.
+fn open_index_log(path: &Path, committed_len: u64) -> io::Result<BufWriter<fs::File>> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.set_len(committed_len)?;
+    file.seek(SeekFrom::End(0))?;
+    Ok(BufWriter::new(file))
+}
+
+pub(crate) fn replace_file(tmp_path: &Path, path: &Path) -> io::Result<()> {
+    match fs::rename(tmp_path, path) {
+        Ok(()) => Ok(()),
         Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
             fs::remove_file(path)?;
-            fs::rename(&tmp_path, path)?;
-            Ok(written_bytes)
+            fs::rename(tmp_path, path)
         }
         Err(err) => {
-            let _ = fs::remove_file(&tmp_path);
+            let _ = fs::remove_file(tmp_path);
             Err(err)
         }
     }
 }
 
-fn decode_validated_record<S, C>(line: &str, codec: &C) -> Option<Vec<u8>>
+fn is_valid_record<S, C>(bytes: &[u8], codec: &C) -> bool
 where
     C: StateCodec<S>,
 {
-    let bytes = hex::decode(line).ok()?;
-    if bytes.is_empty() {
-        return None;
-    }
-    codec.decode(&bytes).ok()?;
-    Some(bytes)
+    !bytes.is_empty() && codec.decode(bytes).is_ok()
 }
 
 fn usize_to_u64(value: usize) -> io::Result<u64> {
@@ -1,5 +1,5 @@
 use crate::explain::Explainer;
-use crate::types::Counterexample;
+use crate::types::{Conformance, Counterexample, CounterexampleEvent, CounterexampleType, Failure};
 use std::collections::BTreeSet;
 
 #[derive(Debug, Default)]
@@ -8,7 +8,13 @@ pub struct BasicExplainer;
 impl Explainer for BasicExplainer {
     fn explain(&self, mut counterexample: Counterexample) -> Counterexample {
         let mut tags = counterexample.tags.clone();
-        for kind in ["deadlock", "divergence", "nondeterminism", "refinement"] {
+        for kind in [
+            "deadlock",
+            "divergence",
+            "nondeterminism",
+            "refinement",
+            "conformance",
+        ] {
             if tags.iter().any(|tag| tag == kind) {
                 tags.push(format!("kind:{kind}"));
             }
@@ -17,22 +23,82 @@ impl Explainer for BasicExplainer {
         let mut dedup = BTreeSet::<String>::new();
         tags.retain(|tag| dedup.insert(tag.clone()));
         counterexample.tags = tags;
+        if counterexample.kind == CounterexampleType::Failure {
+            if let Some(failure) = &counterexample.failure {
+                let trace = counterexample
+                    .events
+                    .iter()
+                    .map(|event| event.label.as_str())
+                    .filter(|label| *label != "tau")
+                    .collect::<Vec<_>>();
+                counterexample.explanation = Some(render_failure(&trace, failure));
+            }
+        }
+        if let Some(conformance) = &counterexample.conformance {
+            counterexample.explanation =
+                Some(render_conformance(&counterexample.events, conformance));
+        }
         counterexample
     }
 }
 
+fn render_failure(trace: &[&str], failure: &Failure) -> String {
+    let after = format!("after ⟨{}⟩", trace.join(","));
+    if failure.divergence {
+        return format!("{after} impl can diverge but spec cannot");
+    }
+    let spec = if failure.acceptances.is_empty() {
+        "spec has no stable state".to_string()
+    } else {
+        let acceptances = failure
+            .acceptances
+            .iter()
+            .map(|set| render_set(set))
+            .collect::<Vec<_>>();
+        format!("spec must accept one of {}", acceptances.join(","))
+    };
+    format!(
+        "{after} impl can refuse {} but {spec}",
+        render_set(&failure.refusal)
+    )
+}
+
+fn render_conformance(events: &[CounterexampleEvent], conformance: &Conformance) -> String {
+    let Some((event, trace)) = events.split_last() else {
+        return String::new();
+    };
+    let trace = trace
+        .iter()
+        .map(|event| event.label.as_str())
+        .collect::<Vec<_>>();
+    format!(
+        "after ⟨{}⟩ log event {} ({}:{}) is not allowed; spec allows {}",
+        trace.join(","),
+        event.label,
+        conformance.span.path,
+        conformance.span.start_line,
+        render_set(&conformance.allowed)
+    )
+}
+
+fn render_set(labels: &[String]) -> String {
+    format!("{{{}}}", labels.join(","))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{CounterexampleEvent, CounterexampleType, SourceSpan};
+    use crate::types::{Minimality, SourceSpan};
 
     fn sample_counterexample(tags: Vec<String>) -> Counterexample {
         Counterexample {
             kind: CounterexampleType::Trace,
             events: vec![CounterexampleEvent {
                 label: "a".to_string(),
+                hidden_label: None,
+                spans: Vec::new(),
             }],
-            is_minimized: false,
+            minimality: Minimality::None,
             tags,
             source_spans: vec![SourceSpan {
                 path: "model.cspm".to_string(),
@@ -41,6 +107,11 @@ mod tests {
                 end_line: 1,
                 end_col: 1,
             }],
+            lasso: None,
+            failure: None,
+            deadlock_explanation: None,
+            conformance: None,
+            explanation: None,
         }
     }
 
@@ -82,4 +153,27 @@ mod tests {
             1
         );
     }
+
+    #[test]
+    fn basic_explainer_renders_failures() {
+        let explainer = BasicExplainer;
+        let mut counterexample = sample_counterexample(vec!["refinement".to_string()]);
+        counterexample.kind = CounterexampleType::Failure;
+        counterexample.events.push(CounterexampleEvent {
+            label: "b".to_string(),
+            hidden_label: None,
+            spans: Vec::new(),
+        });
+        counterexample.failure = Some(Failure {
+            refusal: vec!["c".to_string()],
+            acceptances: vec![vec!["c".to_string()], vec!["d".to_string()]],
+            divergence: false,
+        });
+        let output = explainer.explain(counterexample);
+
+        assert_eq!(
+            output.explanation.as_deref(),
+            Some("after ⟨a,b⟩ impl can refuse {c} but spec must accept one of {c},{d}")
+        );
+    }
 }
@@ -1,9 +1,10 @@
+use crate::limits::{LimitExceeded, RunLimits};
 use crate::lts::TransitionProvider;
 use crate::queue::WorkQueue;
-use crate::store::StateStore;
+use crate::store::{ConcurrentStateStore, LockedStateStore, StateStore};
 use crate::types::Stats;
 use rayon::prelude::*;
-use rayon::ThreadPoolBuilder;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::io;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
@@ -47,6 +48,13 @@ pub struct ExploreHotspotProfile {
     pub estimated_wait_ns: u64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExploreOutcome {
+    pub stats: Stats,
+    pub stopped: Option<LimitExceeded>,
+    pub profile: Option<ExploreHotspotProfile>,
+}
+
 #[derive(Debug)]
 struct TransitionBatch<S> {
     generated_transitions: u64,
@@ -82,7 +90,8 @@ where
     SStore: StateStore<P::State>,
     Q: WorkQueue<P::State>,
 {
-    explore_serial_internal(provider, store, queue, None)
+    explore_serial_internal(provider, store, queue, &RunLimits::unlimited(), None)
+        .map(|(stats, _)| stats)
 }
 
 pub fn explore_profiled<P, SStore, Q>(
@@ -97,7 +106,13 @@ where
     Q: WorkQueue<P::State>,
 {
     let mut profile = ExploreHotspotProfile::new(ExploreProfileMode::Serial, 1);
-    let stats = explore_serial_internal(provider, store, queue, Some(&mut profile))?;
+    let (stats, _) = explore_serial_internal(
+        provider,
+        store,
+        queue,
+        &RunLimits::unlimited(),
+        Some(&mut profile),
+    )?;
     Ok((stats, profile))
 }
 
@@ -110,9 +125,16 @@ where
     P: TransitionProvider + Sync,
     P::State: Clone + Send + Sync,
     P::Transition: Send + Sync,
-    SStore: StateStore<P::State>,
+    SStore: StateStore<P::State> + Send,
 {
-    explore_parallel_nondeterministic_internal(provider, store, workers.max(1), None)
+    explore_parallel_nondeterministic_internal(
+        provider,
+        &LockedStateStore::new(store),
+        workers.max(1),
+        &RunLimits::unlimited(),
+        None,
+    )
+    .map(|(stats, _)| stats)
 }
 
 pub fn explore_parallel_profiled<P, SStore>(
@@ -124,14 +146,15 @@ where
     P: TransitionProvider + Sync,
     P::State: Clone + Send + Sync,
     P::Transition: Send + Sync,
-    SStore: StateStore<P::State>,
+    SStore: StateStore<P::State> + Send,
 {
     let worker_count = workers.max(1);
     let mut profile = ExploreHotspotProfile::new(ExploreProfileMode::Parallel, worker_count);
-    let stats = explore_parallel_nondeterministic_internal(
+    let (stats, _) = explore_parallel_nondeterministic_internal(
         provider,
-        store,
+        &LockedStateStore::new(store),
         worker_count,
+        &RunLimits::unlimited(),
         Some(&mut profile),
     )?;
     Ok((stats, profile))
@@ -146,19 +169,10 @@ where
     P: TransitionProvider + Sync,
     P::State: Clone + Send + Sync + Ord,
     P::Transition: Send + Sync,
-    SStore: StateStore<P::State>,
+    SStore: StateStore<P::State> + Send,
 {
-    if options.deterministic {
-        return explore_parallel_deterministic_internal(
-            provider,
-            store,
-            options.workers.max(1),
-            options.seed,
-            None,
-        );
-    }
-
-    explore_parallel_nondeterministic_internal(provider, store, options.workers.max(1), None)
+    explore_parallel_with_limits(provider, store, options, &RunLimits::unlimited(), false)
+        .map(|outcome| outcome.stats)
 }
 
 pub fn explore_parallel_profiled_with_options<P, SStore>(
@@ -170,7 +184,91 @@ where
     P: TransitionProvider + Sync,
     P::State: Clone + Send + Sync + Ord,
     P::Transition: Send + Sync,
+    SStore: StateStore<P::State> + Send,
+{
+    let outcome =
+        explore_parallel_with_limits(provider, store, options, &RunLimits::unlimited(), true)?;
+    Ok((outcome.stats, outcome.profile.expect("profile requested")))
+}
+
+pub fn explore_with_limits<P, SStore, Q>(
+    provider: &P,
+    store: &mut SStore,
+    queue: &mut Q,
+    limits: &RunLimits,
+    profile: bool,
+) -> std::io::Result<ExploreOutcome>
+where
+    P: TransitionProvider,
+    P::State: Clone,
     SStore: StateStore<P::State>,
+    Q: WorkQueue<P::State>,
+{
+    let mut profile = profile.then(|| ExploreHotspotProfile::new(ExploreProfileMode::Serial, 1));
+    let (stats, stopped) =
+        explore_serial_internal(provider, store, queue, limits, profile.as_mut())?;
+    Ok(ExploreOutcome {
+        stats,
+        stopped,
+        profile,
+    })
+}
+
+pub fn explore_parallel_with_limits<P, SStore>(
+    provider: &P,
+    store: &mut SStore,
+    options: ParallelExploreOptions,
+    limits: &RunLimits,
+    profile: bool,
+) -> std::io::Result<ExploreOutcome>
+where
+    P: TransitionProvider + Sync,
+    P::State: Clone + Send + Sync + Ord,
+    P::Transition: Send + Sync,
+    SStore: StateStore<P::State> + Send,
+{
+    explore_parallel_concurrent_with_limits(
+        provider,
+        &LockedStateStore::new(store),
+        options,
+        limits,
+        profile,
+    )
+}
+
+pub fn explore_parallel_concurrent<P, C>(
+    provider: &P,
+    store: &C,
+    options: ParallelExploreOptions,
+) -> std::io::Result<Stats>
+where
+    P: TransitionProvider + Sync,
+    P::State: Clone + Send + Sync + Ord,
+    P::Transition: Send + Sync,
+    C: ConcurrentStateStore<P::State> + ?Sized,
+{
+    explore_parallel_concurrent_with_limits(
+        provider,
+        store,
+        options,
+        &RunLimits::unlimited(),
+        false,
+    )
+    .map(|outcome| outcome.stats)
+}
+
+pub fn explore_parallel_concurrent_with_limits<P, C>(
+    provider: &P,
+    store: &C,
+    options: ParallelExploreOptions,
+    limits: &RunLimits,
+    profile: bool,
+) -> std::io::Result<ExploreOutcome>
+where
+    P: TransitionProvider + Sync,
+    P::State: Clone + Send + Sync + Ord,
+    P::Transition: Send + Sync,
+    C: ConcurrentStateStore<P::State> + ?Sized,
 {
     let worker_count = options.workers.max(1);
     let mode = if options.deterministic {
@@ -178,32 +276,39 @@ where
     } else {
         ExploreProfileMode::Parallel
     };
-    let mut profile = ExploreHotspotProfile::new(mode, worker_count);
-    let stats = if options.deterministic {
+    let mut profile = profile.then(|| ExploreHotspotProfile::new(mode, worker_count));
+    let (stats, stopped) = if options.deterministic {
         explore_parallel_deterministic_internal(
             provider,
             store,
             worker_count,
             options.seed,
-            Some(&mut profile),
+            limits,
+            profile.as_mut(),
         )?
     } else {
         explore_parallel_nondeterministic_internal(
             provider,
             store,
             worker_count,
-            Some(&mut profile),
+            limits,
+            profile.as_mut(),
         )?
     };
-    Ok((stats, profile))
+    Ok(ExploreOutcome {
+        stats,
+        stopped,
+        profile,
+    })
 }
 
 fn explore_serial_internal<P, SStore, Q>(
     provider: &P,
     store: &mut SStore,
     queue: &mut Q,
+    limits: &RunLimits,
     mut profile: Option<&mut ExploreHotspotProfile>,
-) -> std::io::Result<Stats>
+) -> std::io::Result<(Stats, Option<LimitExceeded>)>
 where
     P: TransitionProvider,
     P::State: Clone,
@@ -226,14 +331,32 @@ where
         store.insert(initial.clone())?
     };
     if inserted {
-        queue.push(initial);
+        queue.push(initial)?;
         states += 1;
         if let Some(p) = profile.as_deref_mut() {
             p.discovered_states = p.discovered_states.saturating_add(1);
         }
     }
 
-    while let Some(state) = queue.pop() {
+    let mut processed: u64 = 0;
+    let mut depth: u64 = 0;
+    let mut level_end = states;
+    while let Some(state) = queue.pop()? {
+        if processed == level_end {
+            depth += 1;
+            level_end = states;
+        }
+        processed += 1;
+        if let Err(limit) = limits.check_deadline() {
+            return Ok((
+                Stats {
+                    states: Some(states),
+                    transitions: Some(transitions),
+                    depth: Some(depth),
+                },
+                Some(limit),
+            ));
+        }
         if let Some(p) = profile.as_deref_mut() {
             p.expanded_states = p.expanded_states.saturating_add(1);
         }
@@ -267,7 +390,7 @@ where
                 store.insert(next_state.clone())?
             };
             if inserted {
-                queue.push(next_state);
+                queue.push(next_state)?;
                 states += 1;
                 if let Some(p) = profile.as_deref_mut() {
                     p.discovered_states = p.discovered_states.saturating_add(1);
@@ -276,39 +399,34 @@ where
         }
     }
 
-    Ok(Stats {
-        states: Some(states),
-        transitions: Some(transitions),
-    })
+    Ok((
+        Stats {
+            states: Some(states),
+            transitions: Some(transitions),
+            depth: None,
+        },
+        None,
+    ))
 }
 
-fn explore_parallel_nondeterministic_internal<P, SStore>(
+fn explore_parallel_nondeterministic_internal<P, C>(
     provider: &P,
-    store: &mut SStore,
+    store: &C,
     workers: usize,
+    limits: &RunLimits,
     mut profile: Option<&mut ExploreHotspotProfile>,
-) -> std::io::Result<Stats>
+) -> std::io::Result<(Stats, Option<LimitExceeded>)>
 where
     P: TransitionProvider + Sync,
     P::State: Clone + Send + Sync,
     P::Transition: Send + Sync,
-    SStore: StateStore<P::State>,
+    C: ConcurrentStateStore<P::State> + ?Sized,
 {
     let mut states: u64 = 0;
     let mut transitions: u64 = 0;
 
     let initial = provider.initial_state();
-    let inserted = if let Some(p) = profile.as_deref_mut() {
-        let insert_start = Instant::now();
-        let inserted = store.insert(initial.clone())?;
-        add_ns(
-            &mut p.visited_insert_ns,
-            duration_ns(insert_start.elapsed()),
-        );
-        inserted
-    } else {
-        store.insert(initial.clone())?
-    };
+    let inserted = insert_initial(store, initial.clone(), profile.as_deref_mut())?;
     if inserted {
         states += 1;
         if let Some(p) = profile.as_deref_mut() {
@@ -322,134 +440,133 @@ where
         .build()
         .map_err(|err| io::Error::other(err.to_string()))?;
 
+    let mut depth: u64 = 0;
     while !frontier.is_empty() {
+        if let Err(limit) = limits.check_deadline() {
+            return Ok((
+                Stats {
+                    states: Some(states),
+                    transitions: Some(transitions),
+                    depth: Some(depth),
+                },
+                Some(limit),
+            ));
+        }
         if let Some(p) = profile.as_deref_mut() {
             p.levels = p.levels.saturating_add(1);
             p.expanded_states = p.expanded_states.saturating_add(frontier.len() as u64);
         }
 
+        // Workers insert their successors themselves and keep the new ones,
+        // so the next frontier is in no particular order.
         let batch = frontier;
-        let batches = if profile.is_some() {
-            let generation_worker_ns = AtomicU64::new(0);
-            let generation_wall_start = Instant::now();
-            let batches = pool.install(|| {
-                batch
-                    .par_iter()
-                    .map(|state| {
-                        let generation_start = Instant::now();
-                        let generated = provider.transitions(state);
-                        generation_worker_ns
-                            .fetch_add(duration_ns(generation_start.elapsed()), Ordering::Relaxed);
-                        let generated_transitions = generated.len() as u64;
-                        let mut states = Vec::with_capacity(generated.len());
-                        for (_label, next_state) in generated {
-                            states.push(next_state);
-                        }
-                        TransitionBatch {
-                            generated_transitions,
-                            states,
+        let generation_worker_ns = AtomicU64::new(0);
+        let insert_worker_ns = AtomicU64::new(0);
+        let timers = profile
+            .is_some()
+            .then_some((&generation_worker_ns, &insert_worker_ns));
+        let generation_wall_start = Instant::now();
+        let batches = pool.install(|| {
+            batch
+                .par_iter()
+                .map(|state| {
+                    if limits.check_deadline().is_err() {
+                        return Ok(TransitionBatch {
+                            generated_transitions: 0,
+                            states: Vec::new(),
+                        });
+                    }
+                    let generated = generate(provider, state, timers.map(|(ns, _)| ns));
+                    let generated_transitions = generated.len() as u64;
+                    let mut discovered = Vec::new();
+                    for (_label, next_state) in generated {
+                        if insert_timed(store, next_state.clone(), timers.map(|(_, ns)| ns))? {
+                            discovered.push(next_state);
                         }
+                    }
+                    Ok(TransitionBatch {
+                        generated_transitions,
+                        states: discovered,
                     })
-                    .collect::<Vec<_>>()
-            });
+                })
+                .collect::<io::Result<Vec<_>>>()
+        })?;
+        if let Some(p) = profile.as_deref_mut() {
             let generation_wall_ns = duration_ns(generation_wall_start.elapsed());
             let generation_ns = generation_worker_ns.load(Ordering::Relaxed);
-            if let Some(p) = profile.as_deref_mut() {
-                add_ns(&mut p.state_generation_ns, generation_ns);
-                add_ns(&mut p.state_generation_wall_ns, generation_wall_ns);
-                add_ns(
-                    &mut p.estimated_wait_ns,
-                    estimate_wait_ns(generation_wall_ns, workers.max(1), generation_ns),
-                );
-            }
-            batches
-        } else {
-            pool.install(|| {
-                batch
-                    .par_iter()
-                    .map(|state| {
-                        let generated = provider.transitions(state);
-                        let generated_transitions = generated.len() as u64;
-                        let mut states = Vec::with_capacity(generated.len());
-                        for (_label, next_state) in generated {
-                            states.push(next_state);
-                        }
-                        TransitionBatch {
-                            generated_transitions,
-                            states,
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            })
-        };
+            let insert_ns = insert_worker_ns.load(Ordering::Relaxed);
+            add_ns(&mut p.state_generation_ns, generation_ns);
+            add_ns(&mut p.state_generation_wall_ns, generation_wall_ns);
+            add_ns(&mut p.visited_insert_ns, insert_ns);
+            add_ns(
+                &mut p.estimated_wait_ns,
+                estimate_wait_ns(
+                    generation_wall_ns,
+                    workers.max(1),
+                    generation_ns.saturating_add(insert_ns),
+                ),
+            );
+        }
 
+        // The store may still hold the states a discarded level inserted.
+        if let Err(limit) = limits.check_deadline() {
+            return Ok((
+                Stats {
+                    states: Some(states),
+                    transitions: Some(transitions),
+                    depth: Some(depth),
+                },
+                Some(limit),
+            ));
+        }
         let mut next_frontier =
             Vec::with_capacity(batches.iter().map(|batch| batch.states.len()).sum());
-        for batch in batches {
+        for mut batch in batches {
             transitions = transitions.saturating_add(batch.generated_transitions);
+            states = states.saturating_add(batch.states.len() as u64);
             if let Some(p) = profile.as_deref_mut() {
                 p.generated_transitions = p
                     .generated_transitions
                     .saturating_add(batch.generated_transitions);
+                p.discovered_states = p
+                    .discovered_states
+                    .saturating_add(batch.states.len() as u64);
             }
-            for next_state in batch.states {
-                let inserted = if let Some(p) = profile.as_deref_mut() {
-                    let insert_start = Instant::now();
-                    let inserted = store.insert(next_state.clone())?;
-                    add_ns(
-                        &mut p.visited_insert_ns,
-                        duration_ns(insert_start.elapsed()),
-                    );
-                    inserted
-                } else {
-                    store.insert(next_state.clone())?
-                };
-                if inserted {
-                    next_frontier.push(next_state);
-                    states += 1;
-                    if let Some(p) = profile.as_deref_mut() {
-                        p.discovered_states = p.discovered_states.saturating_add(1);
-                    }
-                }
-            }
+            next_frontier.append(&mut batch.states);
         }
         frontier = next_frontier;
+        depth += 1;
     }
 
-    Ok(Stats {
-        states: Some(states),
-        transitions: Some(transitions),
-    })
+    Ok((
+        Stats {
+            states: Some(states),
+            transitions: Some(transitions),
+            depth: None,
+        },
+        None,
+    ))
 }
 
-fn explore_parallel_deterministic_internal<P, SStore>(
+fn explore_parallel_deterministic_internal<P, C>(
     provider: &P,
-    store: &mut SStore,
+    store: &C,
     workers: usize,
     _seed: u64,
+    limits: &RunLimits,
     mut profile: Option<&mut ExploreHotspotProfile>,
-) -> std::io::Result<Stats>
+) -> std::io::Result<(Stats, Option<LimitExceeded>)>
 where
     P: TransitionProvider + Sync,
     P::State: Clone + Send + Sync + Ord,
     P::Transition: Send + Sync,
-    SStore: StateStore<P::State>,
+    C: ConcurrentStateStore<P::State> + ?Sized,
 {
     let mut states: u64 = 0;
     let mut transitions: u64 = 0;
 
     let initial = provider.initial_state();
-    let inserted = if let Some(p) = profile.as_deref_mut() {
-        let insert_start = Instant::now();
-        let inserted = store.insert(initial.clone())?;
-        add_ns(
-            &mut p.visited_insert_ns,
-            duration_ns(insert_start.elapsed()),
-        );
-        inserted
-    } else {
-        store.insert(initial.clone())?
-    };
+    let inserted = insert_initial(store, initial.clone(), profile.as_deref_mut())?;
     if inserted {
         states += 1;
         if let Some(p) = profile.as_deref_mut() {
@@ -463,7 +580,18 @@ where
         .build()
         .map_err(|err| io::Error::other(err.to_string()))?;
 
+    let mut depth: u64 = 0;
     while !frontier.is_empty() {
+        if let Err(limit) = limits.check_deadline() {
+            return Ok((
+                Stats {
+                    states: Some(states),
+                    transitions: Some(transitions),
+                    depth: Some(depth),
+                },
+                Some(limit),
+            ));
+        }
         if let Some(p) = profile.as_deref_mut() {
             p.levels = p.levels.saturating_add(1);
             p.expanded_states = p.expanded_states.saturating_add(frontier.len() as u64);
@@ -473,85 +601,67 @@ where
 
         let batch = frontier;
         let chunk_size = batch.len().div_ceil(workers).max(1);
-        let chunks = if profile.is_some() {
-            let generation_worker_ns = AtomicU64::new(0);
-            let frontier_worker_ns = AtomicU64::new(0);
-            let generation_wall_start = Instant::now();
-            let chunks = pool.install(|| {
-                batch
-                    .par_chunks(chunk_size)
-                    .map(|chunk| {
-                        let mut states = Vec::new();
-                        let mut generated_transitions = 0u64;
-                        for state in chunk {
-                            let generation_start = Instant::now();
-                            let generated = provider.transitions(state);
-                            generation_worker_ns.fetch_add(
-                                duration_ns(generation_start.elapsed()),
-                                Ordering::Relaxed,
-                            );
-                            generated_transitions =
-                                generated_transitions.saturating_add(generated.len() as u64);
-                            states.reserve(generated.len());
-                            for (_label, next_state) in generated {
-                                states.push(next_state);
-                            }
+        let generation_worker_ns = AtomicU64::new(0);
+        let frontier_worker_ns = AtomicU64::new(0);
+        let timers = profile
+            .is_some()
+            .then_some((&generation_worker_ns, &frontier_worker_ns));
+        let generation_wall_start = Instant::now();
+        let chunks = pool.install(|| {
+            batch
+                .par_chunks(chunk_size)
+                .map(|chunk| {
+                    let mut states = Vec::new();
+                    let mut generated_transitions = 0u64;
+                    for state in chunk {
+                        if limits.check_deadline().is_err() {
+                            break;
                         }
-                        let local_frontier_start = Instant::now();
-                        states.sort();
-                        states.dedup();
-                        frontier_worker_ns.fetch_add(
-                            duration_ns(local_frontier_start.elapsed()),
-                            Ordering::Relaxed,
-                        );
-                        TransitionBatch {
-                            generated_transitions,
-                            states,
+                        let generated = generate(provider, state, timers.map(|(ns, _)| ns));
+                        generated_transitions =
+                            generated_transitions.saturating_add(generated.len() as u64);
+                        states.reserve(generated.len());
+                        for (_label, next_state) in generated {
+                            states.push(next_state);
                         }
-                    })
-                    .collect::<Vec<_>>()
-            });
+                    }
+                    let local_frontier_start = timers.map(|_| Instant::now());
+                    states.sort();
+                    states.dedup();
+                    if let (Some((_, ns)), Some(start)) = (timers, local_frontier_start) {
+                        ns.fetch_add(duration_ns(start.elapsed()), Ordering::Relaxed);
+                    }
+                    TransitionBatch {
+                        generated_transitions,
+                        states,
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        if let Some(p) = profile.as_deref_mut() {
             let generation_wall_ns = duration_ns(generation_wall_start.elapsed());
             let generation_ns = generation_worker_ns.load(Ordering::Relaxed);
             let frontier_ns = frontier_worker_ns.load(Ordering::Relaxed);
             let worker_busy_ns = generation_ns.saturating_add(frontier_ns);
-            if let Some(p) = profile.as_deref_mut() {
-                add_ns(&mut p.state_generation_ns, generation_ns);
-                add_ns(&mut p.state_generation_wall_ns, generation_wall_ns);
-                add_ns(&mut p.frontier_maintenance_ns, frontier_ns);
-                add_ns(
-                    &mut p.estimated_wait_ns,
-                    estimate_wait_ns(generation_wall_ns, workers.max(1), worker_busy_ns),
-                );
-            }
-            chunks
-        } else {
-            pool.install(|| {
-                batch
-                    .par_chunks(chunk_size)
-                    .map(|chunk| {
-                        let mut states = Vec::new();
-                        let mut generated_transitions = 0u64;
-                        for state in chunk {
-                            let generated = provider.transitions(state);
-                            generated_transitions =
-                                generated_transitions.saturating_add(generated.len() as u64);
-                            states.reserve(generated.len());
-                            for (_label, next_state) in generated {
-                                states.push(next_state);
-                            }
-                        }
-                        states.sort();
-                        states.dedup();
-                        TransitionBatch {
-                            generated_transitions,
-                            states,
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            })
-        };
+            add_ns(&mut p.state_generation_ns, generation_ns);
+            add_ns(&mut p.state_generation_wall_ns, generation_wall_ns);
+            add_ns(&mut p.frontier_maintenance_ns, frontier_ns);
+            add_ns(
+                &mut p.estimated_wait_ns,
+                estimate_wait_ns(generation_wall_ns, workers.max(1), worker_busy_ns),
+            );
+        }
 
+        if let Err(limit) = limits.check_deadline() {
+            return Ok((
+                Stats {
+                    states: Some(states),
+                    transitions: Some(transitions),
+                    depth: Some(depth),
+                },
+                Some(limit),
+            ));
+        }
         let mut candidates =
             Vec::with_capacity(chunks.iter().map(|batch| batch.states.len()).sum());
         for mut chunk in chunks {
@@ -563,47 +673,142 @@ where
             }
             candidates.append(&mut chunk.states);
         }
+        let dedup_start = Instant::now();
+        candidates.sort();
+        candidates.dedup();
         if let Some(p) = profile.as_deref_mut() {
-            let dedup_start = Instant::now();
-            candidates.sort();
-            candidates.dedup();
             add_ns(
                 &mut p.frontier_maintenance_ns,
                 duration_ns(dedup_start.elapsed()),
             );
-        } else {
-            candidates.sort();
-            candidates.dedup();
         }
 
-        let mut next_frontier = Vec::new();
-        for next_state in candidates {
-            let inserted = if let Some(p) = profile.as_deref_mut() {
-                let insert_start = Instant::now();
-                let inserted = store.insert(next_state.clone())?;
-                add_ns(
-                    &mut p.visited_insert_ns,
-                    duration_ns(insert_start.elapsed()),
-                );
-                inserted
-            } else {
-                store.insert(next_state.clone())?
-            };
-            if inserted {
-                states += 1;
-                next_frontier.push(next_state);
-                if let Some(p) = profile.as_deref_mut() {
-                    p.discovered_states = p.discovered_states.saturating_add(1);
-                }
-            }
+        let insert_worker_ns = AtomicU64::new(0);
+        let next_frontier = insert_by_shard(
+            &pool,
+            store,
+            candidates,
+            profile.is_some().then_some(&insert_worker_ns),
+        )?;
+        states = states.saturating_add(next_frontier.len() as u64);
+        if let Some(p) = profile.as_deref_mut() {
+            add_ns(
+                &mut p.visited_insert_ns,
+                insert_worker_ns.load(Ordering::Relaxed),
+            );
+            p.discovered_states = p
+                .discovered_states
+                .saturating_add(next_frontier.len() as u64);
         }
         frontier = next_frontier;
+        depth += 1;
     }
 
-    Ok(Stats {
-        states: Some(states),
-        transitions: Some(transitions),
-    })
+    Ok((
+        Stats {
+            states: Some(states),
+            transitions: Some(transitions),
+            depth: None,
+        },
+        None,
+    ))
+}
+
+// Every shard sees the same insertion sequence under any schedule.
+fn insert_by_shard<S, C>(
+    pool: &ThreadPool,
+    store: &C,
+    candidates: Vec<S>,
+    insert_ns: Option<&AtomicU64>,
+) -> io::Result<Vec<S>>
+where
+    S: Clone + Send + Sync,
+    C: ConcurrentStateStore<S> + ?Sized,
+{
+    let shards = store.shard_count().max(1);
+    if shards == 1 || pool.current_num_threads() == 1 {
+        let mut discovered = Vec::new();
+        for state in candidates {
+            if insert_timed(store, state.clone(), insert_ns)? {
+                discovered.push(state);
+            }
+        }
+        return Ok(discovered);
+    }
+
+    let mut buckets = vec![Vec::new(); shards];
+    for (index, state) in candidates.iter().enumerate() {
+        buckets[store.shard_of(state) % shards].push(index);
+    }
+    let inserted = pool.install(|| {
+        buckets
+            .par_iter()
+            .map(|bucket| {
+                let mut inserted = Vec::new();
+                for &index in bucket {
+                    if insert_timed(store, candidates[index].clone(), insert_ns)? {
+                        inserted.push(index);
+                    }
+                }
+                Ok(inserted)
+            })
+            .collect::<io::Result<Vec<_>>>()
+    })?;
+    let mut is_new = vec![false; candidates.len()];
+    for index in inserted.into_iter().flatten() {
+        is_new[index] = true;
+    }
+    Ok(candidates
+        .into_iter()
+        .zip(is_new)
+        .filter_map(|(state, new)| new.then_some(state))
+        .collect())
+}
+
+fn insert_initial<S, C>(
+    store: &C,
+    state: S,
+    profile: Option<&mut ExploreHotspotProfile>,
+) -> io::Result<bool>
+where
+    C: ConcurrentStateStore<S> + ?Sized,
+{
+    let insert_ns = AtomicU64::new(0);
+    let inserted = insert_timed(store, state, profile.is_some().then_some(&insert_ns))?;
+    if let Some(p) = profile {
+        add_ns(&mut p.visited_insert_ns, insert_ns.load(Ordering::Relaxed));
+    }
+    Ok(inserted)
+}
+
+fn insert_timed<S, C>(store: &C, state: S, insert_ns: Option<&AtomicU64>) -> io::Result<bool>
+where
+    C: ConcurrentStateStore<S> + ?Sized,
+{
+    let Some(insert_ns) = insert_ns else {
+        return store.insert(state);
+    };
+    let insert_start = Instant::now();
+    let inserted = store.insert(state);
+    insert_ns.fetch_add(duration_ns(insert_start.elapsed()), Ordering::Relaxed);
+    inserted
+}
+
+fn generate<P>(
+    provider: &P,
+    state: &P::State,
+    generation_ns: Option<&AtomicU64>,
+) -> Vec<(P::Transition, P::State)>
+where
+    P: TransitionProvider,
+{
+    let Some(generation_ns) = generation_ns else {
+        return provider.transitions(state);
+    };
+    let generation_start = Instant::now();
+    let generated = provider.transitions(state);
+    generation_ns.fetch_add(duration_ns(generation_start.elapsed()), Ordering::Relaxed);
+    generated
 }
 
 fn duration_ns(duration: Duration) -> u64 {
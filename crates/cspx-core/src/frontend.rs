@@ -1,3 +1,4 @@
+use crate::limits::RunLimits;
 use crate::types::Diagnostic;
 
 pub struct FrontendOutput<IR> {
@@ -14,4 +15,16 @@ pub trait Frontend {
         input: &str,
         path: &str,
     ) -> Result<FrontendOutput<Self::Ir>, Self::Error>;
+
+    // Frontends that can stop between phases override this; the default runs
+    // to completion and leaves the limits to the caller.
+    fn parse_and_typecheck_with_limits(
+        &self,
+        input: &str,
+        path: &str,
+        limits: &RunLimits,
+    ) -> Result<FrontendOutput<Self::Ir>, Self::Error> {
+        let _ = limits;
+        self.parse_and_typecheck(input, path)
+    }
 }
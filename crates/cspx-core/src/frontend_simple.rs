@@ -1,17 +1,21 @@
 use crate::frontend::{Frontend, FrontendOutput};
 use crate::ir::{
-    AssertionDecl, ChannelDecl, ChannelDomain, ChoiceKind, Event, EventInput, EventSeg, EventSet,
-    EventValue, Module, ParallelKind, ProcessDecl, ProcessExpr, PropertyKind, PropertyModel,
-    RefinementOp, Spanned,
+    AssertionDecl, ChannelDecl, ChannelDomain, ChoiceKind, CompressionKind, Event, EventInput,
+    EventSeg, EventSet, EventValue, Module, ParallelKind, ProcessDecl, ProcessExpr, PropertyKind,
+    PropertyModel, RefinementOp, Spanned,
 };
+use crate::limits::{LimitExceeded, RunLimits};
+use crate::lts_explicit::{resolve_lts_path, ExplicitLts};
 use crate::types::SourceSpan;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FrontendErrorKind {
     UnsupportedSyntax,
     InvalidInput,
+    Timeout,
 }
 
 #[derive(Debug, Clone)]
@@ -36,6 +40,16 @@ impl Display for FrontendError {
 
 impl std::error::Error for FrontendError {}
 
+impl From<LimitExceeded> for FrontendError {
+    fn from(limit: LimitExceeded) -> Self {
+        Self {
+            kind: FrontendErrorKind::Timeout,
+            message: limit.reason().message.unwrap_or_default(),
+            span: None,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SimpleFrontend;
 
@@ -48,7 +62,16 @@ impl Frontend for SimpleFrontend {
         input: &str,
         path: &str,
     ) -> Result<FrontendOutput<Self::Ir>, Self::Error> {
-        let module = parse_and_typecheck_module(input, path)?;
+        self.parse_and_typecheck_with_limits(input, path, &RunLimits::unlimited())
+    }
+
+    fn parse_and_typecheck_with_limits(
+        &self,
+        input: &str,
+        path: &str,
+        limits: &RunLimits,
+    ) -> Result<FrontendOutput<Self::Ir>, Self::Error> {
+        let module = parse_and_typecheck_module(input, path, limits)?;
 
         Ok(FrontendOutput {
             ir: module,
@@ -57,10 +80,19 @@ impl Frontend for SimpleFrontend {
     }
 }
 
-fn parse_and_typecheck_module(input: &str, path: &str) -> Result<Module, FrontendError> {
+// The deadline is checked between phases and between declarations; a single
+// declaration is never interrupted.
+fn parse_and_typecheck_module(
+    input: &str,
+    path: &str,
+    limits: &RunLimits,
+) -> Result<Module, FrontendError> {
+    limits.check_deadline()?;
     let tokens = lex(input, path)?;
+    limits.check_deadline()?;
     let mut parser = Parser::new(tokens, path);
     let parsed = parser.parse_module()?;
+    limits.check_deadline()?;
 
     if parsed.channels.is_empty()
         && parsed.declarations.is_empty()
@@ -74,10 +106,10 @@ fn parse_and_typecheck_module(input: &str, path: &str) -> Result<Module, Fronten
         });
     }
 
-    typecheck_module(parsed)
+    typecheck_module(parsed, limits)
 }
 
-fn typecheck_module(parsed: ParsedModule) -> Result<Module, FrontendError> {
+fn typecheck_module(mut parsed: ParsedModule, limits: &RunLimits) -> Result<Module, FrontendError> {
     if let Some(unsupported) = parsed.unsupported.first() {
         return Err(FrontendError {
             kind: FrontendErrorKind::UnsupportedSyntax,
@@ -143,10 +175,11 @@ fn typecheck_module(parsed: ParsedModule) -> Result<Module, FrontendError> {
     }
 
     let empty_vars = HashMap::<String, ChannelType>::new();
-    for decl in &parsed.declarations {
-        typecheck_process_expr(&decl.expr, &channels, &processes, &empty_vars)?;
+    for decl in &mut parsed.declarations {
+        limits.check_deadline()?;
+        typecheck_process_expr(&mut decl.expr, &channels, &processes, &empty_vars)?;
     }
-    if let Some(entry) = &parsed.entry {
+    if let Some(entry) = &mut parsed.entry {
         typecheck_process_expr(entry, &channels, &processes, &empty_vars)?;
     }
 
@@ -184,12 +217,12 @@ fn typecheck_module(parsed: ParsedModule) -> Result<Module, FrontendError> {
 }
 
 fn typecheck_process_expr(
-    expr: &Spanned<ProcessExpr>,
+    expr: &mut Spanned<ProcessExpr>,
     channels: &HashMap<String, ChannelType>,
     processes: &HashSet<String>,
     vars: &HashMap<String, ChannelType>,
 ) -> Result<(), FrontendError> {
-    match &expr.value {
+    match &mut expr.value {
         ProcessExpr::Stop => Ok(()),
         ProcessExpr::Ref(name) => {
             if !processes.contains(&name.value) {
@@ -226,6 +259,19 @@ fn typecheck_process_expr(
             typecheck_event_set(hide, channels)?;
             Ok(())
         }
+        ProcessExpr::LoadLts { path, lts } => {
+            let resolved = resolve_lts_path(&path.span.path, &path.value);
+            let loaded = ExplicitLts::load(&resolved).map_err(|err| FrontendError {
+                kind: FrontendErrorKind::InvalidInput,
+                message: format!("load_lts failed: {err}"),
+                span: Some(path.span.clone()),
+            })?;
+            *lts = Some(Arc::new(loaded));
+            Ok(())
+        }
+        ProcessExpr::Compress { inner, .. } => {
+            typecheck_process_expr(inner, channels, processes, vars)
+        }
     }
 }
 
@@ -367,6 +413,7 @@ enum TokenKind {
 
     Ident(String),
     Int(u64),
+    Str(String),
     Stop,
 
     Channel,
@@ -780,6 +827,34 @@ fn lex(input: &str, path: &str) -> Result<Vec<Token>, FrontendError> {
                 );
                 continue;
             }
+            b'"' => {
+                bump(&mut idx, &mut line, &mut col);
+                let start = idx;
+                while idx < bytes.len() && bytes[idx] != b'"' && bytes[idx] != b'\n' {
+                    bump(&mut idx, &mut line, &mut col);
+                }
+                if bytes.get(idx) != Some(&b'"') {
+                    return Err(FrontendError {
+                        kind: FrontendErrorKind::InvalidInput,
+                        message: "unterminated string literal".to_string(),
+                        span: Some(make_span(start_line, start_col, line, col)),
+                    });
+                }
+                let text = std::str::from_utf8(&bytes[start..idx])
+                    .map_err(|_| FrontendError {
+                        kind: FrontendErrorKind::InvalidInput,
+                        message: "invalid utf8 in string literal".to_string(),
+                        span: Some(make_span(start_line, start_col, line, col)),
+                    })?
+                    .to_string();
+                bump(&mut idx, &mut line, &mut col);
+                let span = make_span(start_line, start_col, start_line, col - 1);
+                tokens.push(Token {
+                    kind: TokenKind::Str(text),
+                    span,
+                });
+                continue;
+            }
             b'0'..=b'9' => {
                 let start = idx;
                 while idx < bytes.len() && bytes[idx].is_ascii_digit() {
@@ -1161,6 +1236,21 @@ impl Parser {
             });
         }
 
+        if matches!(
+            (self.peek_kind(), self.peek_kind_n(1)),
+            (Some(TokenKind::Ident(name)), Some(TokenKind::LParen)) if name == "load_lts"
+        ) {
+            return self.parse_load_lts();
+        }
+
+        if let (Some(TokenKind::Ident(name)), Some(TokenKind::LParen)) =
+            (self.peek_kind(), self.peek_kind_n(1))
+        {
+            if let Some(kind) = CompressionKind::from_name(name) {
+                return self.parse_compress(kind);
+            }
+        }
+
         let name = self.expect_ident_spanned("process reference")?;
         let span = name.span.clone();
         Ok(Spanned {
@@ -1169,6 +1259,47 @@ impl Parser {
         })
     }
 
+    fn parse_load_lts(&mut self) -> Result<Spanned<ProcessExpr>, FrontendError> {
+        let keyword = self.expect_ident_spanned("load_lts")?;
+        self.expect(TokenKind::LParen, "expected '(' after load_lts")?;
+        let path = match self.next() {
+            Some(Token {
+                kind: TokenKind::Str(value),
+                span,
+            }) => Spanned { value, span },
+            Some(token) => {
+                return Err(self.invalid_input(Some(token.span), "expected string literal path"))
+            }
+            None => {
+                return Err(self.invalid_input(self.peek_span(), "expected string literal path"))
+            }
+        };
+        let rparen = self.expect(TokenKind::RParen, "expected ')'")?;
+        let span = merge_span(&keyword.span, &rparen.span);
+        Ok(Spanned {
+            value: ProcessExpr::LoadLts { path, lts: None },
+            span,
+        })
+    }
+
+    fn parse_compress(
+        &mut self,
+        kind: CompressionKind,
+    ) -> Result<Spanned<ProcessExpr>, FrontendError> {
+        let keyword = self.expect_ident_spanned(kind.as_str())?;
+        self.expect(TokenKind::LParen, "expected '(' after compression function")?;
+        let inner = self.parse_expr()?;
+        let rparen = self.expect(TokenKind::RParen, "expected ')'")?;
+        let span = merge_span(&keyword.span, &rparen.span);
+        Ok(Spanned {
+            value: ProcessExpr::Compress {
+                kind,
+                inner: Box::new(inner),
+            },
+            span,
+        })
+    }
+
     fn parse_event(&mut self) -> Result<Spanned<Event>, FrontendError> {
         let channel = self.expect_ident_spanned("event channel")?;
         let mut end_span = channel.span.clone();
@@ -9,14 +9,18 @@
 //! IR で扱う予定の要素（v0.2）
 //! - channel 宣言（名前、値域 `{0..N}` のみ）
 //! - process 式（STOP / prefix / choice / internal choice / interleaving / interface parallel / hiding / proc ref）
+//! - 外部 LTS の取り込み（`load_lts("comp.aut")`、`.aut` / JSON）
+//! - 圧縮関数（`sbisim(P)` / `normal(P)` / `diamond(P)` / `explicate(P)` / `tau_loop_factor(P)`）
 //! - assert 宣言（deadlock/divergence/deterministic、refinement T/F/FD）
 //!
 //! エラー分類は `docs/frontend.md`（`unsupported_syntax` / `invalid_input`）に従う。
 //! Span は `SourceSpan`（1-based, inclusive）を基本とし、原因箇所（識別子/リテラル）へ最短距離で付与する。
 //! 詳細な IR 形状案は `docs/ir.md` を参照。
 
+use crate::lts_explicit::ExplicitLts;
 use crate::types::SourceSpan;
 use std::fmt::Debug;
+use std::sync::Arc;
 
 pub trait CoreIr: Debug + Send + Sync {}
 
@@ -83,6 +87,38 @@ pub enum ParallelKind {
     Interface,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionKind {
+    Sbisim,
+    Normal,
+    Diamond,
+    Explicate,
+    TauLoopFactor,
+}
+
+impl CompressionKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sbisim" => Some(Self::Sbisim),
+            "normal" => Some(Self::Normal),
+            "diamond" => Some(Self::Diamond),
+            "explicate" => Some(Self::Explicate),
+            "tau_loop_factor" => Some(Self::TauLoopFactor),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sbisim => "sbisim",
+            Self::Normal => "normal",
+            Self::Diamond => "diamond",
+            Self::Explicate => "explicate",
+            Self::TauLoopFactor => "tau_loop_factor",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ProcessExpr {
     Stop,
@@ -106,6 +142,15 @@ pub enum ProcessExpr {
         inner: Box<Spanned<ProcessExpr>>,
         hide: EventSet,
     },
+    LoadLts {
+        path: Spanned<String>,
+        // Loaded by the frontend's typecheck, so the file is parsed once.
+        lts: Option<Arc<ExplicitLts>>,
+    },
+    Compress {
+        kind: CompressionKind,
+        inner: Box<Spanned<ProcessExpr>>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -4,7 +4,11 @@ pub mod check_deadlock;
 pub mod check_determinism;
 pub mod check_divergence;
 pub mod check_refine;
+pub mod checkpoint;
+pub mod compress;
+pub mod conform;
 pub mod counterexample_span;
+pub mod coverage;
 pub mod disk_store;
 pub mod explain;
 pub mod explain_simple;
@@ -12,50 +16,93 @@ pub mod explore;
 pub mod frontend;
 pub mod frontend_simple;
 pub mod ir;
+pub mod limits;
 pub mod lts;
 pub mod lts_cspm;
+pub mod lts_explicit;
 pub mod lts_simple;
 pub mod minimize;
 pub mod minimize_simple;
+pub mod normalise;
+pub mod predecessor;
+pub mod predecessor_disk;
+pub mod predecessor_hashcompact;
+pub mod predecessor_inmemory;
 pub mod queue;
+pub mod queue_disk;
+pub mod queue_hybrid;
 pub mod queue_inmemory;
+pub mod replay;
+pub mod search;
+pub mod simulate;
 pub mod state_codec;
 pub mod store;
+pub mod store_bitstate;
+pub mod store_hashcompact;
 pub mod store_hybrid;
 pub mod store_inmemory;
+pub mod store_sharded;
+mod symmetry_cspm;
 pub mod types;
 
 pub use check::{CheckRequest, CheckResult, Checker, RefinementModel};
 pub use check_deadlock::DeadlockChecker;
 pub use check_determinism::DeterminismChecker;
 pub use check_divergence::DivergenceChecker;
-pub use check_refine::{RefinementChecker, RefinementInput};
+pub use check_refine::{RefinementChecker, RefinementInput, RefinementOptions};
+pub use checkpoint::{CheckpointOptions, SearchCheckpoint};
+pub use compress::CompressionStats;
+pub use conform::{parse_trace_log, ConformanceChecker, ConformanceInput, LogEvent, TraceLogError};
+pub use coverage::{model_coverage, CoverageEntry, ModelCoverage};
 pub use disk_store::{DiskStateStore, DiskStateStoreMetrics, DiskStateStoreOpenOptions};
 pub use explain::Explainer;
 pub use explain_simple::BasicExplainer;
 pub use explore::{
-    explore, explore_parallel, explore_parallel_profiled, explore_parallel_profiled_with_options,
-    explore_parallel_with_options, explore_profiled, ExploreHotspotProfile, ExploreProfileMode,
-    ParallelExploreOptions,
+    explore, explore_parallel, explore_parallel_concurrent,
+    explore_parallel_concurrent_with_limits, explore_parallel_profiled,
+    explore_parallel_profiled_with_options, explore_parallel_with_limits,
+    explore_parallel_with_options, explore_profiled, explore_with_limits, ExploreHotspotProfile,
+    ExploreOutcome, ExploreProfileMode, ParallelExploreOptions,
 };
 pub use frontend::{Frontend, FrontendOutput};
 pub use frontend_simple::{FrontendError, FrontendErrorKind, SimpleFrontend};
 pub use ir::CoreIr;
-pub use lts::{StateId, Transition, TransitionProvider};
+pub use limits::{Deadline, LimitExceeded, MemoryBudget, RunLimits};
+pub use lts::{StateId, Transition, TransitionLabel, TransitionProvider, TAU_LABEL};
 pub use lts_cspm::{CspmLtsError, CspmState, CspmStateCodec, CspmTransitionProvider};
+pub use lts_explicit::{
+    ExplicitLts, ExplicitLtsError, ExplicitLtsFormat, ExplicitLtsProvider, ExplicitStateCodec,
+};
 pub use lts_simple::SimpleStateCodec;
 pub use lts_simple::{LtsError, SimpleState, SimpleTransitionProvider};
 pub use minimize::Minimizer;
 #[allow(deprecated)]
 pub use minimize_simple::IdentityMinimizer;
 pub use minimize_simple::TraceHeuristicMinimizer;
+pub use normalise::{
+    NormalNode, NormalState, NormalStateCodec, NormaliseError, NormalisedSpec, NormalisedSpecError,
+};
+pub use predecessor::{PathStep, PathTarget, PredecessorStore};
+pub use predecessor_disk::DiskPredecessorStore;
+pub use predecessor_hashcompact::HashCompactPredecessorStore;
+pub use predecessor_inmemory::InMemoryPredecessorStore;
 pub use queue::WorkQueue;
+pub use queue_disk::{DiskWorkQueue, DiskWorkQueueMetrics, DiskWorkQueueOptions};
+pub use queue_hybrid::{HybridWorkQueue, HybridWorkQueueOptions};
 pub use queue_inmemory::VecWorkQueue;
+pub use replay::{
+    replay_normalised_refinement, replay_property, replay_provider, replay_refinement_input,
+};
+pub use search::{InMemorySearch, PorStats, SearchBackend, SymmetryStats};
+pub use simulate::{SimulateOptions, Simulator};
 pub use state_codec::StateCodec;
-pub use store::StateStore;
+pub use store::{ConcurrentStateStore, StateStore};
+pub use store_bitstate::{BitstateStore, BitstateStoreOptions};
+pub use store_hashcompact::HashCompactStateStore;
 pub use store_hybrid::{HybridStateStore, HybridStateStoreOptions};
 pub use store_inmemory::InMemoryStateStore;
+pub use store_sharded::{ShardedDiskStateStore, ShardedStateStore, DEFAULT_STORE_SHARDS};
 pub use types::{
-    Counterexample, CounterexampleEvent, CounterexampleType, Diagnostic, Reason, ReasonKind,
-    SourceSpan, Stats, Status,
+    Conformance, Counterexample, CounterexampleEvent, CounterexampleType, Diagnostic, Lasso,
+    Reason, ReasonKind, Replay, ReplayVerdict, Simulation, SourceSpan, Stats, Status,
 };
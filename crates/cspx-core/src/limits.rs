@@ -0,0 +1,176 @@
+use crate::types::{Reason, ReasonKind, Status};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Rough per-entry cost of a hash table slot plus its allocation header, added
+// to the encoded or estimated size of every state a component holds.
+pub(crate) const ENTRY_OVERHEAD_BYTES: u64 = 32;
+
+// Components spill or evict once the estimate reaches this share of the
+// budget, before the budget itself is exhausted.
+const SPILL_PERCENT: u64 = 75;
+
+// Byte budget for the data held by one exploration: visited states, frontier,
+// predecessors and refinement caches. Sizes are estimates of heap usage, not
+// the resident set of the process.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    limit_bytes: u64,
+    used_bytes: AtomicU64,
+    peak_bytes: AtomicU64,
+}
+
+impl MemoryBudget {
+    pub fn new(limit_bytes: u64) -> Self {
+        Self {
+            limit_bytes,
+            used_bytes: AtomicU64::new(0),
+            peak_bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_mib(mib: u64) -> Self {
+        Self::new(mib.saturating_mul(1024 * 1024))
+    }
+
+    pub fn limit_bytes(&self) -> u64 {
+        self.limit_bytes
+    }
+
+    pub fn used_bytes(&self) -> u64 {
+        self.used_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn peak_bytes(&self) -> u64 {
+        self.peak_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn spill_threshold_bytes(&self) -> u64 {
+        (u128::from(self.limit_bytes) * u128::from(SPILL_PERCENT) / 100) as u64
+    }
+
+    pub fn should_spill(&self, used_bytes: u64) -> bool {
+        used_bytes >= self.spill_threshold_bytes()
+    }
+
+    // Records the current estimate and fails once it exceeds the limit.
+    pub fn update(&self, used_bytes: u64) -> Result<(), LimitExceeded> {
+        self.used_bytes.store(used_bytes, Ordering::Relaxed);
+        self.peak_bytes.fetch_max(used_bytes, Ordering::Relaxed);
+        if used_bytes > self.limit_bytes {
+            return Err(LimitExceeded::Memory {
+                used_bytes,
+                limit_bytes: self.limit_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Wall-clock deadline measured from the moment the run started.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    started: Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            timeout,
+        }
+    }
+
+    pub fn from_millis(ms: u64) -> Self {
+        Self::after(Duration::from_millis(ms))
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.started.elapsed() >= self.timeout
+    }
+}
+
+// Resource limits shared by every check of one run. Searches poll
+// `check_deadline` between states, so a deadline or `cancel` stops them at
+// the next state boundary rather than mid-expansion.
+#[derive(Debug, Default)]
+pub struct RunLimits {
+    pub memory: Option<MemoryBudget>,
+    pub deadline: Option<Deadline>,
+    cancelled: AtomicBool,
+}
+
+impl RunLimits {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_memory_mib(mut self, mib: u64) -> Self {
+        self.memory = Some(MemoryBudget::from_mib(mib));
+        self
+    }
+
+    pub fn with_timeout_ms(mut self, ms: u64) -> Self {
+        self.deadline = Some(Deadline::from_millis(ms));
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn check_deadline(&self) -> Result<(), LimitExceeded> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(LimitExceeded::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if deadline.is_expired() => Err(LimitExceeded::Timeout {
+                timeout_ms: deadline.timeout().as_millis() as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Memory { used_bytes: u64, limit_bytes: u64 },
+    Timeout { timeout_ms: u64 },
+    Cancelled,
+}
+
+impl LimitExceeded {
+    pub fn status(&self) -> Status {
+        match self {
+            Self::Memory { .. } => Status::OutOfMemory,
+            Self::Timeout { .. } | Self::Cancelled => Status::Timeout,
+        }
+    }
+
+    pub fn reason(&self) -> Reason {
+        match self {
+            Self::Memory {
+                used_bytes,
+                limit_bytes,
+            } => Reason {
+                kind: ReasonKind::OutOfMemory,
+                message: Some(format!(
+                    "memory budget exhausted: estimated {used_bytes} bytes > limit {limit_bytes} bytes"
+                )),
+            },
+            Self::Timeout { timeout_ms } => Reason {
+                kind: ReasonKind::Timeout,
+                message: Some(format!("timed out after {timeout_ms} ms")),
+            },
+            Self::Cancelled => Reason {
+                kind: ReasonKind::Timeout,
+                message: Some("run cancelled".to_string()),
+            },
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::types::{DeadlockExplanation, SourceSpan};
+
 pub type StateId = u64;
 
 #[derive(Debug, Clone)]
@@ -11,4 +13,63 @@ pub trait TransitionProvider {
 
     fn initial_state(&self) -> Self::State;
     fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)>;
+
+    /// A subset of `transitions(state)` independent of every transition outside
+    /// it (tau only with `tau_only`); `None` disables reduction for the state.
+    fn ample_transitions(
+        &self,
+        _state: &Self::State,
+        _tau_only: bool,
+    ) -> Option<Vec<(Self::Transition, Self::State)>> {
+        None
+    }
+
+    /// The orbit representative of `state`, or `None` when `state` is it.
+    fn canonical_state(&self, _state: &Self::State) -> Option<Self::State> {
+        None
+    }
+
+    /// Estimated bytes of one copy of `state`, for memory budgeting.
+    fn state_bytes(&self, _state: &Self::State) -> usize {
+        std::mem::size_of::<Self::State>()
+    }
+
+    /// The label a hidden tau transition had before hiding.
+    fn hidden_label(&self, _state: &Self::State, _next: &Self::State) -> Option<String> {
+        None
+    }
+
+    /// Spans of the prefixes that performed `transition`, one per process.
+    fn event_spans(
+        &self,
+        _state: &Self::State,
+        _transition: &Self::Transition,
+        _next: &Self::State,
+    ) -> Vec<SourceSpan> {
+        Vec::new()
+    }
+
+    fn deadlock_explanation(&self, _state: &Self::State) -> Option<DeadlockExplanation> {
+        None
+    }
+}
+
+pub const TAU_LABEL: &str = "tau";
+
+pub trait TransitionLabel {
+    fn label(&self) -> &str;
+
+    fn is_tau(&self) -> bool {
+        self.label() == TAU_LABEL
+    }
+
+    fn visible_label(&self) -> Option<&str> {
+        (!self.is_tau()).then(|| self.label())
+    }
+}
+
+impl TransitionLabel for Transition {
+    fn label(&self) -> &str {
+        &self.label
+    }
 }
@@ -1,14 +1,20 @@
+use crate::compress::{compress, explicate, CompressionStats};
 use crate::ir::{
-    ChoiceKind, EventInput, EventSeg, EventValue, Module, ParallelKind, ProcessExpr, Spanned,
+    ChoiceKind, CompressionKind, EventInput, EventSeg, EventValue, Module, ParallelKind,
+    ProcessExpr, Spanned,
 };
-use crate::lts::{Transition, TransitionProvider};
+use crate::lts::{StateId, Transition, TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_explicit::{resolve_lts_path, ExplicitLts};
 use crate::state_codec::{StateCodec, StateCodecError};
-use crate::types::SourceSpan;
+use crate::symmetry_cspm::CspmSymmetry;
+use crate::types::{BlockedEvent, DeadlockComponent, DeadlockExplanation, SourceSpan};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
 
 type ExprId = u32;
 type ProcId = u32;
+type ComponentId = u32;
 type ProcIds = BTreeMap<String, ProcId>;
 type ProcExprs<'a> = BTreeMap<String, &'a Spanned<ProcessExpr>>;
 
@@ -52,13 +58,45 @@ enum ExprNode {
         inner: ExprId,
         hide: BTreeSet<String>,
     },
+    Explicit(ComponentId),
 }
 
 #[derive(Debug)]
 struct Program {
     channels: BTreeMap<String, ChannelDomain>,
     exprs: Vec<ExprNode>,
+    spans: Vec<Option<SourceSpan>>,
     resolved: Vec<ExprId>,
+    explicit: Vec<ExplicitLts>,
+    proc_names: Vec<String>,
+    proc_roots: Vec<ExprId>,
+    sites: Vec<Vec<Site>>,
+}
+
+// Structurally equal expressions share an interned node, so every occurrence
+// is kept per node.
+#[derive(Debug)]
+struct Site {
+    span: SourceSpan,
+    owner: Option<ProcId>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ReachedSites {
+    pub(crate) processes: BTreeSet<String>,
+    pub(crate) prefixes: Vec<SourceSpan>,
+}
+
+#[derive(Debug)]
+enum Layout {
+    Leaf(Option<String>),
+    Parallel(Box<Layout>, Box<Layout>),
+    Hide(Box<Layout>),
+}
+
+enum Enclosing<'a> {
+    Sync(&'a BTreeSet<String>, &'a CspmState),
+    Hide(&'a BTreeSet<String>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -76,6 +114,10 @@ pub enum CspmState {
         hide: BTreeSet<String>,
         inner: Box<CspmState>,
     },
+    Explicit {
+        component: ComponentId,
+        state: StateId,
+    },
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -114,6 +156,11 @@ impl StateCodec<CspmState> for CspmStateCodec {
                 }
                 out.extend_from_slice(&self.encode(inner));
             }
+            CspmState::Explicit { component, state } => {
+                out.push(4);
+                out.extend_from_slice(&component.to_be_bytes());
+                out.extend_from_slice(&state.to_be_bytes());
+            }
         }
         out
     }
@@ -194,6 +241,11 @@ impl StateCodec<CspmState> for CspmStateCodec {
                         inner: Box::new(inner),
                     })
                 }
+                4 => {
+                    let component = take_u32(bytes, "invalid component bytes")?;
+                    let state = take_u64(bytes, "invalid explicit state bytes")?;
+                    Ok(CspmState::Explicit { component, state })
+                }
                 _ => Err(StateCodecError::new("unknown CspmState tag")),
             }
         }
@@ -228,50 +280,300 @@ impl Display for CspmLtsError {
 
 impl std::error::Error for CspmLtsError {}
 
+#[derive(Debug)]
+struct PendingCompression {
+    component: ComponentId,
+    kind: CompressionKind,
+    inner: ExprId,
+    span: SourceSpan,
+}
+
 #[derive(Debug)]
 pub struct CspmTransitionProvider {
     program: Program,
     initial: CspmState,
+    layout: Option<Layout>,
+    compression: Vec<CompressionStats>,
+    compressed: HashMap<ComponentId, ExprId>,
+    entry: Option<(ExprId, Option<ProcId>)>,
+    symmetry: OnceLock<Option<CspmSymmetry>>,
 }
 
 impl CspmTransitionProvider {
     pub fn from_module(module: &Module) -> Result<Self, CspmLtsError> {
+        let initial_expr = initial_expr(module)?;
+        let (mut provider, expr) = Self::compile(module, initial_expr)?;
+        provider.initial = state_from_expr(&provider.program, expr, BTreeMap::new());
+        let name = module
+            .declarations
+            .iter()
+            .find(|decl| decl.expr.span == initial_expr.span)
+            .map(|decl| decl.name.value.as_str());
+        provider.layout = Some(layout_from_expr(&provider.program, expr, name));
+        let proc_id = name.and_then(|name| {
+            provider
+                .program
+                .proc_names
+                .binary_search_by(|candidate| candidate.as_str().cmp(name))
+                .ok()
+        });
+        provider.entry = Some((expr, proc_id.map(|id| id as ProcId)));
+        Ok(provider)
+    }
+
+    pub fn compression_stats(&self) -> &[CompressionStats] {
+        &self.compression
+    }
+
+    fn compile(
+        module: &Module,
+        entry: &Spanned<ProcessExpr>,
+    ) -> Result<(Self, ExprId), CspmLtsError> {
         let channels = compile_channels(module)?;
         let (proc_ids, proc_exprs) = collect_processes(module)?;
         let mut builder = ProgramBuilder::new(channels, &proc_ids)?;
-        let initial_expr = initial_expr(module)?;
-        let initial_expr_id = builder.compile_expr(initial_expr)?;
+        let initial_expr_id = builder.compile_expr(entry)?;
 
         for (name, proc_id) in proc_ids.iter() {
             let expr = proc_exprs.get(name).ok_or_else(|| CspmLtsError {
                 message: format!("undefined process: {name}"),
                 span: None,
             })?;
+            builder.owner = Some(*proc_id);
             let root = builder.compile_expr(expr)?;
             builder.proc_roots[*proc_id as usize] = root;
         }
 
-        let program = builder.finish()?;
-        let initial = state_from_expr(&program, initial_expr_id, BTreeMap::new());
+        let (program, pending) = builder.finish()?;
+        let mut provider = Self {
+            program,
+            initial: CspmState::Expr {
+                expr: 0,
+                env: BTreeMap::new(),
+            },
+            layout: None,
+            compression: Vec::new(),
+            compressed: HashMap::new(),
+            entry: None,
+            symmetry: OnceLock::new(),
+        };
+        provider.apply_compressions(pending)?;
+        Ok((provider, initial_expr_id))
+    }
+
+    fn apply_compressions(&mut self, pending: Vec<PendingCompression>) -> Result<(), CspmLtsError> {
+        for idx in compression_order(&self.program, &pending)? {
+            let job = &pending[idx];
+            let before = explicate(&SubProcess {
+                provider: self,
+                initial: state_from_expr(&self.program, job.inner, BTreeMap::new()),
+            });
+            let after = compress(job.kind, &before);
+            self.compression.push(CompressionStats {
+                function: job.kind,
+                span: Some(job.span.clone()),
+                states_before: before.state_count(),
+                transitions_before: before.transition_count(),
+                states_after: after.state_count(),
+                transitions_after: after.transition_count(),
+            });
+            self.program.explicit[job.component as usize] = after;
+            self.compressed.insert(job.component, job.inner);
+        }
+        Ok(())
+    }
+
+    // Operands offered together are entered with their parent; the continuation
+    // of a prefix or internal choice only once its leaves occur in `states`.
+    pub(crate) fn reached_sites<'s>(
+        &self,
+        states: impl IntoIterator<Item = &'s CspmState>,
+    ) -> ReachedSites {
+        let mut leaves = HashSet::new();
+        let mut components = HashSet::new();
+        let mut stack = states.into_iter().cloned().collect::<Vec<_>>();
+        let mut seen = stack.iter().cloned().collect::<HashSet<_>>();
+        while let Some(state) = stack.pop() {
+            match state {
+                CspmState::Expr { expr, .. } => {
+                    leaves.insert(expr);
+                }
+                CspmState::Parallel { left, right, .. } => {
+                    stack.extend([*left, *right]);
+                }
+                CspmState::Hide { inner, .. } => stack.push(*inner),
+                CspmState::Explicit { component, .. } => {
+                    if !components.insert(component) {
+                        continue;
+                    }
+                    let Some(inner) = self.compressed.get(&component) else {
+                        continue;
+                    };
+                    let initial = state_from_expr(&self.program, *inner, BTreeMap::new());
+                    let mut queue = vec![initial];
+                    while let Some(state) = queue.pop() {
+                        if !seen.insert(state.clone()) {
+                            continue;
+                        }
+                        queue.extend(self.transitions_for(&state).into_iter().map(|(_, n)| n));
+                        stack.push(state);
+                    }
+                }
+            }
+        }
+
+        let program = &self.program;
+        let appears = |expr: ExprId| {
+            let mut pending = vec![expr];
+            while let Some(id) = pending.pop() {
+                match &program.exprs[id as usize] {
+                    ExprNode::Ref(_) => pending.push(program.resolved[id as usize]),
+                    ExprNode::Parallel { left, right, .. } => pending.extend([*left, *right]),
+                    ExprNode::Hide { inner, .. } => pending.push(*inner),
+                    ExprNode::Explicit(component) => {
+                        if !components.contains(component) {
+                            return false;
+                        }
+                    }
+                    _ => {
+                        if !leaves.contains(&id) {
+                            return false;
+                        }
+                    }
+                }
+            }
+            true
+        };
+
+        let mut procs = BTreeSet::new();
+        let mut entered = HashSet::new();
+        let mut pending = Vec::new();
+        if let Some((expr, proc_id)) = self.entry {
+            pending.push(expr);
+            procs.extend(proc_id);
+        }
+        while let Some(id) = pending.pop() {
+            if !entered.insert(id) {
+                continue;
+            }
+            match &program.exprs[id as usize] {
+                ExprNode::Stop => {}
+                ExprNode::Ref(proc_id) => {
+                    procs.insert(*proc_id);
+                    pending.push(program.proc_roots[*proc_id as usize]);
+                }
+                ExprNode::Prefix { next, .. } => {
+                    if appears(*next) {
+                        pending.push(*next);
+                    }
+                }
+                ExprNode::ChoiceInternal { left, right } => {
+                    pending.extend([*left, *right].into_iter().filter(|&side| appears(side)));
+                }
+                ExprNode::ChoiceExternal { left, right }
+                | ExprNode::Parallel { left, right, .. } => pending.extend([*left, *right]),
+                ExprNode::Hide { inner, .. } => pending.push(*inner),
+                ExprNode::Explicit(component) => {
+                    pending.extend(self.compressed.get(component).copied());
+                }
+            }
+        }
 
-        Ok(Self { program, initial })
+        ReachedSites {
+            processes: procs
+                .iter()
+                .map(|id| program.proc_names[*id as usize].clone())
+                .collect(),
+            prefixes: program
+                .sites
+                .iter()
+                .enumerate()
+                .filter(|(id, _)| matches!(program.exprs[*id], ExprNode::Prefix { .. }))
+                .filter(|(id, _)| entered.contains(&(*id as ExprId)))
+                .flat_map(|(_, sites)| sites)
+                .filter(|site| site.owner.is_none_or(|owner| procs.contains(&owner)))
+                .map(|site| site.span.clone())
+                .collect(),
+        }
     }
 
     fn transitions_for(&self, state: &CspmState) -> Vec<(Transition, CspmState)> {
         let mut out = Vec::new();
         self.transitions_for_state_unordered(state, &mut out);
-        out.sort_by(|(a_t, a_s), (b_t, b_s)| {
-            let label_cmp = a_t.label.cmp(&b_t.label);
-            if label_cmp != std::cmp::Ordering::Equal {
-                return label_cmp;
-            }
-            let a_bytes = CspmStateCodec.encode(a_s);
-            let b_bytes = CspmStateCodec.encode(b_s);
-            a_bytes.cmp(&b_bytes)
-        });
+        sort_transitions(&mut out);
         out
     }
 
+    // Each candidate holds every move of one leaf process that offers no event
+    // synchronised by an enclosing parallel, so it is an ample set.
+    fn local_components(&self, state: &CspmState) -> Vec<Vec<(Transition, CspmState)>> {
+        match state {
+            CspmState::Expr { .. } | CspmState::Explicit { .. } => {
+                let mut out = Vec::new();
+                self.transitions_for_state_unordered(state, &mut out);
+                if out.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![out]
+                }
+            }
+            CspmState::Parallel { sync, left, right } => {
+                let is_local = |moves: &Vec<(Transition, CspmState)>| {
+                    moves.iter().all(|(transition, _)| {
+                        transition.is_tau() || !sync.contains(label_channel(&transition.label))
+                    })
+                };
+                let lift = |moves: Vec<(Transition, CspmState)>, on_left: bool| {
+                    moves
+                        .into_iter()
+                        .map(|(transition, next)| {
+                            let (left, right) = if on_left {
+                                (Box::new(next), right.clone())
+                            } else {
+                                (left.clone(), Box::new(next))
+                            };
+                            let state = CspmState::Parallel {
+                                sync: sync.clone(),
+                                left,
+                                right,
+                            };
+                            (transition, state)
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let mut out = Vec::new();
+                for (side, on_left) in [(left, true), (right, false)] {
+                    out.extend(
+                        self.local_components(side)
+                            .into_iter()
+                            .filter(is_local)
+                            .map(|moves| lift(moves, on_left)),
+                    );
+                }
+                out
+            }
+            CspmState::Hide { hide, inner } => self
+                .local_components(inner)
+                .into_iter()
+                .map(|moves| {
+                    moves
+                        .into_iter()
+                        .map(|(transition, next)| {
+                            let label = if !transition.is_tau()
+                                && hide.contains(label_channel(&transition.label))
+                            {
+                                TAU_LABEL.to_string()
+                            } else {
+                                transition.label
+                            };
+                            (Transition { label }, make_hide_state(hide.clone(), next))
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
     fn transitions_for_state_unordered(
         &self,
         state: &CspmState,
@@ -285,6 +587,9 @@ impl CspmTransitionProvider {
             CspmState::Hide { hide, inner } => {
                 self.transitions_for_hide_unordered(hide, inner, out)
             }
+            CspmState::Explicit { component, state } => {
+                self.transitions_for_explicit_unordered(*component, *state, out)
+            }
         }
     }
 
@@ -319,7 +624,7 @@ impl CspmTransitionProvider {
                 for target in [*left, *right] {
                     out.push((
                         Transition {
-                            label: "tau".to_string(),
+                            label: TAU_LABEL.to_string(),
                         },
                         state_from_expr(&self.program, target, env.clone()),
                     ));
@@ -340,6 +645,30 @@ impl CspmTransitionProvider {
                 };
                 self.transitions_for_state_unordered(&state, out);
             }
+            ExprNode::Explicit(component) => {
+                let initial = self.program.explicit[*component as usize].initial();
+                self.transitions_for_explicit_unordered(*component, initial, out);
+            }
+        }
+    }
+
+    fn transitions_for_explicit_unordered(
+        &self,
+        component: ComponentId,
+        state: StateId,
+        out: &mut Vec<(Transition, CspmState)>,
+    ) {
+        let lts = &self.program.explicit[component as usize];
+        for (label, next) in lts.transitions_from(state) {
+            out.push((
+                Transition {
+                    label: label.clone(),
+                },
+                CspmState::Explicit {
+                    component,
+                    state: *next,
+                },
+            ));
         }
     }
 
@@ -350,12 +679,8 @@ impl CspmTransitionProvider {
         right: &CspmState,
         out: &mut Vec<(Transition, CspmState)>,
     ) {
-        fn label_channel(label: &str) -> &str {
-            label.split_once('.').map(|(ch, _)| ch).unwrap_or(label)
-        }
-
         fn is_sync_event(sync: &BTreeSet<String>, label: &str) -> bool {
-            if label == "tau" {
+            if label == TAU_LABEL {
                 return false;
             }
             sync.contains(label_channel(label))
@@ -427,19 +752,14 @@ impl CspmTransitionProvider {
         inner: &CspmState,
         out: &mut Vec<(Transition, CspmState)>,
     ) {
-        fn label_channel(label: &str) -> &str {
-            label.split_once('.').map(|(ch, _)| ch).unwrap_or(label)
-        }
-
         let mut inner_next = Vec::new();
         self.transitions_for_state_unordered(inner, &mut inner_next);
         for (transition, next_inner) in inner_next {
-            let label =
-                if transition.label != "tau" && hide.contains(label_channel(&transition.label)) {
-                    "tau".to_string()
-                } else {
-                    transition.label
-                };
+            let label = if !transition.is_tau() && hide.contains(label_channel(&transition.label)) {
+                TAU_LABEL.to_string()
+            } else {
+                transition.label
+            };
             out.push((
                 Transition { label },
                 make_hide_state(hide.clone(), next_inner),
@@ -447,6 +767,335 @@ impl CspmTransitionProvider {
         }
     }
 
+    fn hidden_label_for(&self, state: &CspmState, next: &CspmState) -> Option<String> {
+        match state {
+            CspmState::Expr { expr, env } => match &self.program.exprs[*expr as usize] {
+                ExprNode::Ref(_) => {
+                    let target = self.program.resolved[*expr as usize];
+                    let state = state_from_expr(&self.program, target, BTreeMap::new());
+                    self.hidden_label_for(&state, next)
+                }
+                ExprNode::ChoiceExternal { left, right } => {
+                    [*left, *right].iter().find_map(|branch| {
+                        let state = state_from_expr(&self.program, *branch, env.clone());
+                        self.hidden_label_for(&state, next)
+                    })
+                }
+                ExprNode::Parallel { .. } | ExprNode::Hide { .. } => {
+                    let state = state_from_expr(&self.program, *expr, env.clone());
+                    self.hidden_label_for(&state, next)
+                }
+                _ => None,
+            },
+            CspmState::Parallel { left, right, .. } => {
+                let CspmState::Parallel {
+                    left: next_left,
+                    right: next_right,
+                    ..
+                } = next
+                else {
+                    return None;
+                };
+                let from_left = (next_right == right)
+                    .then(|| self.hidden_label_for(left, next_left))
+                    .flatten();
+                from_left.or_else(|| {
+                    (next_left == left)
+                        .then(|| self.hidden_label_for(right, next_right))
+                        .flatten()
+                })
+            }
+            CspmState::Hide { hide, inner } => {
+                let mut inner_next = Vec::new();
+                self.transitions_for_state_unordered(inner, &mut inner_next);
+                sort_transitions(&mut inner_next);
+                inner_next
+                    .into_iter()
+                    .filter(|(_, next_inner)| {
+                        make_hide_state(hide.clone(), next_inner.clone()) == *next
+                    })
+                    .find_map(|(transition, next_inner)| {
+                        if transition.is_tau() {
+                            self.hidden_label_for(inner, &next_inner)
+                        } else {
+                            hide.contains(label_channel(&transition.label))
+                                .then_some(transition.label)
+                        }
+                    })
+            }
+            CspmState::Explicit { .. } => None,
+        }
+    }
+
+    // Both sides of a synchronised event contribute their prefixes.
+    fn event_spans_for(
+        &self,
+        state: &CspmState,
+        label: &str,
+        next: &CspmState,
+        layout: Option<&Layout>,
+        name: Option<&str>,
+    ) -> Vec<SourceSpan> {
+        match state {
+            CspmState::Expr { expr, env } => {
+                let name = match layout {
+                    Some(Layout::Leaf(leaf)) => leaf.as_deref(),
+                    _ => name,
+                };
+                match &self.program.exprs[*expr as usize] {
+                    ExprNode::Prefix { event, next: after } => {
+                        let fired = self.eval_event(event, env).into_iter().any(|(fired, env)| {
+                            fired == label && state_from_expr(&self.program, *after, env) == *next
+                        });
+                        if fired {
+                            self.site_span(*expr, name).into_iter().collect()
+                        } else {
+                            Vec::new()
+                        }
+                    }
+                    ExprNode::Ref(proc_id) => {
+                        let target = self.program.resolved[*expr as usize];
+                        let state = state_from_expr(&self.program, target, BTreeMap::new());
+                        let name = &self.program.proc_names[*proc_id as usize];
+                        self.event_spans_for(&state, label, next, None, Some(name))
+                    }
+                    ExprNode::ChoiceExternal { left, right } => [*left, *right]
+                        .iter()
+                        .map(|branch| {
+                            let state = state_from_expr(&self.program, *branch, env.clone());
+                            self.event_spans_for(&state, label, next, None, name)
+                        })
+                        .find(|spans| !spans.is_empty())
+                        .unwrap_or_default(),
+                    ExprNode::ChoiceInternal { .. } => {
+                        self.site_span(*expr, name).into_iter().collect()
+                    }
+                    ExprNode::Parallel { .. } | ExprNode::Hide { .. } => {
+                        let state = state_from_expr(&self.program, *expr, env.clone());
+                        self.event_spans_for(&state, label, next, None, name)
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            CspmState::Parallel { sync, left, right } => {
+                let CspmState::Parallel {
+                    left: next_left,
+                    right: next_right,
+                    ..
+                } = next
+                else {
+                    return Vec::new();
+                };
+                let (left_layout, right_layout) = match layout {
+                    Some(Layout::Parallel(l, r)) => (Some(&**l), Some(&**r)),
+                    _ => (None, None),
+                };
+                let left_spans = || self.event_spans_for(left, label, next_left, left_layout, name);
+                let right_spans =
+                    || self.event_spans_for(right, label, next_right, right_layout, name);
+                if label != TAU_LABEL && sync.contains(label_channel(label)) {
+                    let mut spans = left_spans();
+                    spans.extend(right_spans());
+                    return spans;
+                }
+                let from_left = if next_right == right {
+                    left_spans()
+                } else {
+                    Vec::new()
+                };
+                if !from_left.is_empty() || next_left != left {
+                    return from_left;
+                }
+                right_spans()
+            }
+            CspmState::Hide { hide, inner } => {
+                let inner_layout = match layout {
+                    Some(Layout::Hide(inner)) => Some(&**inner),
+                    _ => None,
+                };
+                let mut inner_next = Vec::new();
+                self.transitions_for_state_unordered(inner, &mut inner_next);
+                sort_transitions(&mut inner_next);
+                inner_next
+                    .into_iter()
+                    .filter(|(transition, next_inner)| {
+                        let hidden =
+                            !transition.is_tau() && hide.contains(label_channel(&transition.label));
+                        let visible_as = if hidden { TAU_LABEL } else { &transition.label };
+                        visible_as == label
+                            && make_hide_state(hide.clone(), next_inner.clone()) == *next
+                    })
+                    .map(|(transition, next_inner)| {
+                        self.event_spans_for(
+                            inner,
+                            &transition.label,
+                            &next_inner,
+                            inner_layout,
+                            name,
+                        )
+                    })
+                    .find(|spans| !spans.is_empty())
+                    .unwrap_or_default()
+            }
+            CspmState::Explicit { component, .. } => {
+                self.component_span(*component).into_iter().collect()
+            }
+        }
+    }
+
+    fn component_span(&self, component: ComponentId) -> Option<SourceSpan> {
+        self.program
+            .exprs
+            .iter()
+            .position(|node| *node == ExprNode::Explicit(component))
+            .and_then(|expr| self.program.spans[expr].clone())
+    }
+
+    fn site_span(&self, expr: ExprId, process: Option<&str>) -> Option<SourceSpan> {
+        let sites = &self.program.sites[expr as usize];
+        let proc_id = process.and_then(|name| {
+            self.program
+                .proc_names
+                .binary_search_by(|candidate| candidate.as_str().cmp(name))
+                .ok()
+        });
+        if sites.len() > 1 {
+            if let Some(proc_id) = proc_id {
+                let reachable = self.referenced_procs(proc_id as ProcId);
+                let site = reachable
+                    .iter()
+                    .find_map(|proc_id| sites.iter().find(|site| site.owner == Some(*proc_id)));
+                if let Some(site) = site {
+                    return Some(site.span.clone());
+                }
+            }
+        }
+        sites
+            .first()
+            .map(|site| site.span.clone())
+            .or_else(|| self.program.spans[expr as usize].clone())
+    }
+
+    fn referenced_procs(&self, proc_id: ProcId) -> Vec<ProcId> {
+        let program = &self.program;
+        let mut procs = vec![proc_id];
+        let mut idx = 0;
+        while idx < procs.len() {
+            let mut pending = vec![program.proc_roots[procs[idx] as usize]];
+            let mut seen = HashSet::new();
+            while let Some(id) = pending.pop() {
+                if !seen.insert(id) {
+                    continue;
+                }
+                match &program.exprs[id as usize] {
+                    ExprNode::Ref(target) => {
+                        if !procs.contains(target) {
+                            procs.push(*target);
+                        }
+                    }
+                    ExprNode::Prefix { next, .. } => pending.push(*next),
+                    ExprNode::ChoiceExternal { left, right }
+                    | ExprNode::ChoiceInternal { left, right }
+                    | ExprNode::Parallel { left, right, .. } => pending.extend([*left, *right]),
+                    ExprNode::Hide { inner, .. } => pending.push(*inner),
+                    ExprNode::Stop | ExprNode::Explicit(_) => {}
+                }
+            }
+            idx += 1;
+        }
+        procs
+    }
+
+    fn explain_leaves<'a>(
+        &self,
+        state: &'a CspmState,
+        layout: Option<&Layout>,
+        name: Option<&str>,
+        enclosing: &mut Vec<Enclosing<'a>>,
+        out: &mut Vec<DeadlockComponent>,
+    ) {
+        match state {
+            CspmState::Parallel { sync, left, right } => {
+                let (left_layout, right_layout) = match layout {
+                    Some(Layout::Parallel(l, r)) => (Some(&**l), Some(&**r)),
+                    _ => (None, None),
+                };
+                enclosing.push(Enclosing::Sync(sync, right));
+                self.explain_leaves(left, left_layout, name, enclosing, out);
+                enclosing.pop();
+                enclosing.push(Enclosing::Sync(sync, left));
+                self.explain_leaves(right, right_layout, name, enclosing, out);
+                enclosing.pop();
+            }
+            CspmState::Hide { hide, inner } => {
+                let inner_layout = match layout {
+                    Some(Layout::Hide(inner)) => Some(&**inner),
+                    _ => None,
+                };
+                enclosing.push(Enclosing::Hide(hide));
+                self.explain_leaves(inner, inner_layout, name, enclosing, out);
+                enclosing.pop();
+            }
+            CspmState::Expr { .. } | CspmState::Explicit { .. } => {
+                let name = match layout {
+                    Some(Layout::Leaf(leaf)) => leaf.as_deref(),
+                    _ => name,
+                };
+                let span = match state {
+                    CspmState::Expr { expr, .. } => self.site_span(*expr, name),
+                    CspmState::Explicit { component, .. } => self.component_span(*component),
+                    _ => None,
+                };
+                let mut moves = Vec::new();
+                self.transitions_for_state_unordered(state, &mut moves);
+                let offers = moves
+                    .into_iter()
+                    .map(|(transition, _)| transition.label)
+                    .filter(|label| label != TAU_LABEL)
+                    .collect::<BTreeSet<_>>();
+                let blocked = offers
+                    .iter()
+                    .filter_map(|event| self.blocking_sync(event, enclosing))
+                    .collect();
+                out.push(DeadlockComponent {
+                    process: name.map(str::to_string),
+                    span,
+                    offers: offers.into_iter().collect(),
+                    blocked,
+                });
+            }
+        }
+    }
+
+    fn blocking_sync(&self, event: &str, enclosing: &[Enclosing<'_>]) -> Option<BlockedEvent> {
+        for frame in enclosing.iter().rev() {
+            match frame {
+                Enclosing::Hide(hide) => {
+                    if hide.contains(label_channel(event)) {
+                        return None;
+                    }
+                }
+                Enclosing::Sync(sync, partner) => {
+                    if !sync.contains(label_channel(event)) {
+                        continue;
+                    }
+                    let mut moves = Vec::new();
+                    self.transitions_for_state_unordered(partner, &mut moves);
+                    if !moves
+                        .iter()
+                        .any(|(transition, _)| transition.label == event)
+                    {
+                        return Some(BlockedEvent {
+                            event: event.to_string(),
+                            sync: sync.iter().cloned().collect(),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
     fn eval_event(
         &self,
         event: &EventPat,
@@ -483,6 +1132,108 @@ impl CspmTransitionProvider {
     }
 }
 
+struct SubProcess<'a> {
+    provider: &'a CspmTransitionProvider,
+    initial: CspmState,
+}
+
+impl TransitionProvider for SubProcess<'_> {
+    type State = CspmState;
+    type Transition = Transition;
+
+    fn initial_state(&self) -> Self::State {
+        self.initial.clone()
+    }
+
+    fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)> {
+        self.provider.transitions_for(state)
+    }
+}
+
+// Compressed components are explored from the program itself, so a component
+// must be computed before any compression whose argument reaches it.
+fn compression_order(
+    program: &Program,
+    pending: &[PendingCompression],
+) -> Result<Vec<usize>, CspmLtsError> {
+    fn reached_components(program: &Program, root: ExprId) -> BTreeSet<ComponentId> {
+        let mut seen = HashSet::from([root]);
+        let mut stack = vec![root];
+        let mut out = BTreeSet::new();
+        while let Some(id) = stack.pop() {
+            let children = match &program.exprs[id as usize] {
+                ExprNode::Stop => Vec::new(),
+                ExprNode::Ref(_) => vec![program.resolved[id as usize]],
+                ExprNode::Prefix { next, .. } => vec![*next],
+                ExprNode::ChoiceExternal { left, right }
+                | ExprNode::ChoiceInternal { left, right }
+                | ExprNode::Parallel { left, right, .. } => vec![*left, *right],
+                ExprNode::Hide { inner, .. } => vec![*inner],
+                ExprNode::Explicit(component) => {
+                    out.insert(*component);
+                    Vec::new()
+                }
+            };
+            for child in children {
+                if seen.insert(child) {
+                    stack.push(child);
+                }
+            }
+        }
+        out
+    }
+
+    fn visit(
+        idx: usize,
+        deps: &[Vec<usize>],
+        pending: &[PendingCompression],
+        marks: &mut [u8],
+        out: &mut Vec<usize>,
+    ) -> Result<(), CspmLtsError> {
+        match marks[idx] {
+            2 => return Ok(()),
+            1 => {
+                return Err(CspmLtsError {
+                    message: format!(
+                        "recursive {} compression is not supported",
+                        pending[idx].kind.as_str()
+                    ),
+                    span: Some(pending[idx].span.clone()),
+                })
+            }
+            _ => {}
+        }
+        marks[idx] = 1;
+        for &dep in &deps[idx] {
+            visit(dep, deps, pending, marks, out)?;
+        }
+        marks[idx] = 2;
+        out.push(idx);
+        Ok(())
+    }
+
+    let by_component = pending
+        .iter()
+        .enumerate()
+        .map(|(idx, job)| (job.component, idx))
+        .collect::<HashMap<_, _>>();
+    let deps = pending
+        .iter()
+        .map(|job| {
+            reached_components(program, job.inner)
+                .into_iter()
+                .filter_map(|component| by_component.get(&component).copied())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut marks = vec![0u8; pending.len()];
+    let mut out = Vec::with_capacity(pending.len());
+    for idx in 0..pending.len() {
+        visit(idx, &deps, pending, &mut marks, &mut out)?;
+    }
+    Ok(out)
+}
+
 impl TransitionProvider for CspmTransitionProvider {
     type State = CspmState;
     type Transition = Transition;
@@ -494,6 +1245,96 @@ impl TransitionProvider for CspmTransitionProvider {
     fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)> {
         self.transitions_for(state)
     }
+
+    fn ample_transitions(
+        &self,
+        state: &Self::State,
+        tau_only: bool,
+    ) -> Option<Vec<(Self::Transition, Self::State)>> {
+        let mut ample = self
+            .local_components(state)
+            .into_iter()
+            .filter(|moves| !tau_only || moves.iter().all(|(t, _)| t.is_tau()))
+            .min_by_key(Vec::len)?;
+        sort_transitions(&mut ample);
+        Some(ample)
+    }
+
+    fn canonical_state(&self, state: &Self::State) -> Option<Self::State> {
+        self.symmetry
+            .get_or_init(|| CspmSymmetry::detect(self, &self.initial))
+            .as_ref()?
+            .canonical(state)
+    }
+
+    fn state_bytes(&self, state: &Self::State) -> usize {
+        state.size_bytes()
+    }
+
+    fn hidden_label(&self, state: &Self::State, next: &Self::State) -> Option<String> {
+        self.hidden_label_for(state, next)
+    }
+
+    fn event_spans(
+        &self,
+        state: &Self::State,
+        transition: &Self::Transition,
+        next: &Self::State,
+    ) -> Vec<SourceSpan> {
+        self.event_spans_for(state, &transition.label, next, self.layout.as_ref(), None)
+    }
+
+    fn deadlock_explanation(&self, state: &Self::State) -> Option<DeadlockExplanation> {
+        let mut components = Vec::new();
+        self.explain_leaves(
+            state,
+            self.layout.as_ref(),
+            None,
+            &mut Vec::new(),
+            &mut components,
+        );
+        Some(DeadlockExplanation { components })
+    }
+}
+
+impl CspmState {
+    fn size_bytes(&self) -> usize {
+        const NODE_OVERHEAD: usize = 2 * std::mem::size_of::<usize>();
+        let names = |names: &BTreeSet<String>| {
+            names
+                .iter()
+                .map(|name| name.capacity() + std::mem::size_of::<String>() + NODE_OVERHEAD)
+                .sum::<usize>()
+        };
+        let owned = match self {
+            CspmState::Expr { env, .. } => env
+                .keys()
+                .map(|name| name.capacity() + std::mem::size_of::<(String, u64)>() + NODE_OVERHEAD)
+                .sum(),
+            CspmState::Parallel { sync, left, right } => {
+                names(sync) + left.size_bytes() + right.size_bytes()
+            }
+            CspmState::Hide { hide, inner } => names(hide) + inner.size_bytes(),
+            CspmState::Explicit { .. } => 0,
+        };
+        std::mem::size_of::<CspmState>() + owned
+    }
+}
+
+pub(crate) fn label_channel(label: &str) -> &str {
+    label.split_once('.').map(|(ch, _)| ch).unwrap_or(label)
+}
+
+fn sort_transitions(out: &mut [(Transition, CspmState)]) {
+    out.sort_by(|(a_t, a_s), (b_t, b_s)| {
+        let label_cmp = a_t.label.cmp(&b_t.label);
+        if label_cmp != std::cmp::Ordering::Equal {
+            return label_cmp;
+        }
+        let a_bytes = CspmStateCodec.encode(a_s);
+        let b_bytes = CspmStateCodec.encode(b_s);
+        a_bytes.cmp(&b_bytes)
+    });
 }
 
 fn state_from_expr(program: &Program, expr: ExprId, env: BTreeMap<String, u64>) -> CspmState {
@@ -510,10 +1351,37 @@ fn state_from_expr(program: &Program, expr: ExprId, env: BTreeMap<String, u64>)
         ExprNode::Hide { inner, hide } => {
             make_hide_state(hide.clone(), state_from_expr(program, *inner, env))
         }
+        ExprNode::Explicit(component) => CspmState::Explicit {
+            component: *component,
+            state: program.explicit[*component as usize].initial(),
+        },
         _ => CspmState::Expr { expr, env },
     }
 }
 
+fn layout_from_expr(program: &Program, expr: ExprId, name: Option<&str>) -> Layout {
+    match &program.exprs[expr as usize] {
+        ExprNode::Ref(proc_id) => {
+            let target = program.resolved[expr as usize];
+            layout_from_expr(
+                program,
+                target,
+                Some(&program.proc_names[*proc_id as usize]),
+            )
+        }
+        ExprNode::Parallel { left, right, .. } => Layout::Parallel(
+            Box::new(layout_from_expr(program, *left, name)),
+            Box::new(layout_from_expr(program, *right, name)),
+        ),
+        ExprNode::Hide { inner, hide } => match layout_from_expr(program, *inner, name) {
+            inner if hide.is_empty() => inner,
+            Layout::Hide(inner) => Layout::Hide(inner),
+            inner => Layout::Hide(Box::new(inner)),
+        },
+        _ => Layout::Leaf(name.map(str::to_string)),
+    }
+}
+
 fn make_hide_state(hide: BTreeSet<String>, inner: CspmState) -> CspmState {
     if hide.is_empty() {
         return inner;
@@ -617,6 +1485,12 @@ struct ProgramBuilder<'a> {
     expr_spans: Vec<Option<SourceSpan>>,
     intern: HashMap<ExprNode, ExprId>,
     proc_roots: Vec<ExprId>,
+    explicit: Vec<ExplicitLts>,
+    explicit_ids: HashMap<std::path::PathBuf, ComponentId>,
+    compressions: Vec<PendingCompression>,
+    compression_ids: HashMap<(CompressionKind, ExprId), ComponentId>,
+    owner: Option<ProcId>,
+    sites: Vec<Vec<Site>>,
 }
 
 impl<'a> ProgramBuilder<'a> {
@@ -632,26 +1506,48 @@ impl<'a> ProgramBuilder<'a> {
             expr_spans: Vec::new(),
             intern: HashMap::new(),
             proc_roots,
+            explicit: Vec::new(),
+            explicit_ids: HashMap::new(),
+            compressions: Vec::new(),
+            compression_ids: HashMap::new(),
+            owner: None,
+            sites: Vec::new(),
         })
     }
 
-    fn finish(self) -> Result<Program, CspmLtsError> {
+    fn finish(self) -> Result<(Program, Vec<PendingCompression>), CspmLtsError> {
         let resolved = compute_resolved(&self.exprs, &self.proc_roots, &self.expr_spans)?;
-        Ok(Program {
+        let program = Program {
             channels: self.channels,
             exprs: self.exprs,
+            spans: self.expr_spans,
             resolved,
-        })
+            explicit: self.explicit,
+            proc_names: self.proc_ids.keys().cloned().collect(),
+            proc_roots: self.proc_roots,
+            sites: self.sites,
+        };
+        Ok((program, self.compressions))
     }
 
     fn intern(&mut self, node: ExprNode, span: Option<SourceSpan>) -> ExprId {
-        if let Some(id) = self.intern.get(&node) {
-            return *id;
+        let id = match self.intern.get(&node) {
+            Some(id) => *id,
+            None => {
+                let id = self.exprs.len() as ExprId;
+                self.exprs.push(node.clone());
+                self.expr_spans.push(span.clone());
+                self.sites.push(Vec::new());
+                self.intern.insert(node, id);
+                id
+            }
+        };
+        if let Some(span) = span {
+            self.sites[id as usize].push(Site {
+                span,
+                owner: self.owner,
+            });
         }
-        let id = self.exprs.len() as ExprId;
-        self.exprs.push(node.clone());
-        self.expr_spans.push(span);
-        self.intern.insert(node, id);
         id
     }
 
@@ -733,6 +1629,99 @@ impl<'a> ProgramBuilder<'a> {
                     Some(expr.span.clone()),
                 ))
             }
+            ProcessExpr::LoadLts { path, lts } => {
+                let component = self.load_explicit(path, lts.as_deref())?;
+                Ok(self.intern(ExprNode::Explicit(component), Some(expr.span.clone())))
+            }
+            ProcessExpr::Compress { kind, inner } => {
+                if let Some(var) = free_variable(inner, &BTreeSet::new()) {
+                    return Err(CspmLtsError {
+                        message: format!(
+                            "{} argument must not use the bound variable `{}`",
+                            kind.as_str(),
+                            var.value
+                        ),
+                        span: Some(var.span.clone()),
+                    });
+                }
+                let inner = self.compile_expr(inner)?;
+                let component = match self.compression_ids.get(&(*kind, inner)) {
+                    Some(component) => *component,
+                    None => {
+                        let component = self.explicit.len() as ComponentId;
+                        self.explicit
+                            .push(ExplicitLts::new(0, 1, []).expect("placeholder component"));
+                        self.compression_ids.insert((*kind, inner), component);
+                        self.compressions.push(PendingCompression {
+                            component,
+                            kind: *kind,
+                            inner,
+                            span: expr.span.clone(),
+                        });
+                        component
+                    }
+                };
+                Ok(self.intern(ExprNode::Explicit(component), Some(expr.span.clone())))
+            }
+        }
+    }
+
+    fn load_explicit(
+        &mut self,
+        path: &Spanned<String>,
+        loaded: Option<&ExplicitLts>,
+    ) -> Result<ComponentId, CspmLtsError> {
+        let resolved = resolve_lts_path(&path.span.path, &path.value);
+        if let Some(id) = self.explicit_ids.get(&resolved) {
+            return Ok(*id);
+        }
+        let lts = match loaded {
+            Some(lts) => lts.clone(),
+            None => ExplicitLts::load(&resolved).map_err(|err| CspmLtsError {
+                message: format!("load_lts failed: {err}"),
+                span: Some(path.span.clone()),
+            })?,
+        };
+        let id = self.explicit.len() as ComponentId;
+        self.explicit.push(lts);
+        self.explicit_ids.insert(resolved, id);
+        Ok(id)
+    }
+}
+
+fn free_variable<'a>(
+    expr: &'a Spanned<ProcessExpr>,
+    bound: &BTreeSet<&'a str>,
+) -> Option<Spanned<&'a str>> {
+    match &expr.value {
+        ProcessExpr::Stop | ProcessExpr::Ref(_) | ProcessExpr::LoadLts { .. } => None,
+        ProcessExpr::Prefix { event, next } => {
+            let mut bound = bound.clone();
+            match &event.value.seg {
+                Some(EventSeg::Dot(value)) | Some(EventSeg::Out(value)) => {
+                    if let EventValue::Ident(name) = &value.value {
+                        if !bound.contains(name.as_str()) {
+                            return Some(Spanned {
+                                value: name.as_str(),
+                                span: value.span.clone(),
+                            });
+                        }
+                    }
+                }
+                Some(EventSeg::In(input)) => {
+                    if let EventInput::Bind(name) = &input.value {
+                        bound.insert(name.as_str());
+                    }
+                }
+                None => {}
+            }
+            free_variable(next, &bound)
+        }
+        ProcessExpr::Choice { left, right, .. } | ProcessExpr::Parallel { left, right, .. } => {
+            free_variable(left, bound).or_else(|| free_variable(right, bound))
+        }
+        ProcessExpr::Hide { inner, .. } | ProcessExpr::Compress { inner, .. } => {
+            free_variable(inner, bound)
         }
     }
 }
@@ -0,0 +1,361 @@
+use crate::ir::{Module, ProcessExpr, Spanned};
+use crate::lts::{StateId, Transition, TransitionProvider, TAU_LABEL};
+use crate::state_codec::{StateCodec, StateCodecError};
+use crate::types::SourceSpan;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplicitLtsFormat {
+    Aut,
+    Json,
+}
+
+impl ExplicitLtsFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "aut" => Some(Self::Aut),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExplicitLtsError {
+    pub message: String,
+    pub span: Option<SourceSpan>,
+}
+
+impl Display for ExplicitLtsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.span {
+            Some(span) => write!(
+                f,
+                "{}:{}:{}: {}",
+                span.path, span.start_line, span.start_col, self.message
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ExplicitLtsError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplicitLts {
+    initial: StateId,
+    transitions: Vec<Vec<(String, StateId)>>,
+}
+
+impl ExplicitLts {
+    pub fn new(
+        initial: StateId,
+        state_count: usize,
+        edges: impl IntoIterator<Item = (StateId, String, StateId)>,
+    ) -> Result<Self, ExplicitLtsError> {
+        if initial as usize >= state_count {
+            return Err(ExplicitLtsError {
+                message: format!("initial state {initial} out of range (states: {state_count})"),
+                span: None,
+            });
+        }
+        let mut transitions = vec![Vec::new(); state_count];
+        for (from, label, to) in edges {
+            for state in [from, to] {
+                if state as usize >= state_count {
+                    return Err(ExplicitLtsError {
+                        message: format!("state {state} out of range (states: {state_count})"),
+                        span: None,
+                    });
+                }
+            }
+            transitions[from as usize].push((label, to));
+        }
+        for outgoing in &mut transitions {
+            outgoing.sort();
+            outgoing.dedup();
+        }
+        Ok(Self {
+            initial,
+            transitions,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ExplicitLtsError> {
+        let display = path.to_string_lossy().to_string();
+        let Some(format) = ExplicitLtsFormat::from_path(path) else {
+            return Err(ExplicitLtsError {
+                message: format!("unknown LTS format (expected .aut or .json): {display}"),
+                span: None,
+            });
+        };
+        let input = std::fs::read_to_string(path).map_err(|err| ExplicitLtsError {
+            message: format!("{display}: {err}"),
+            span: None,
+        })?;
+        match format {
+            ExplicitLtsFormat::Aut => Self::parse_aut(&input, &display),
+            ExplicitLtsFormat::Json => Self::parse_json(&input, &display),
+        }
+    }
+
+    pub fn parse_aut(input: &str, path: &str) -> Result<Self, ExplicitLtsError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx as u32 + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let Some((header_line, header)) = lines.next() else {
+            return Err(ExplicitLtsError {
+                message: "empty .aut file".to_string(),
+                span: Some(line_span(path, 1, "")),
+            });
+        };
+        let (initial, transition_count, state_count) =
+            parse_aut_header(header).ok_or_else(|| ExplicitLtsError {
+                message: "expected header `des (<initial>, <transitions>, <states>)`".to_string(),
+                span: Some(line_span(path, header_line, header)),
+            })?;
+
+        let mut edges = Vec::with_capacity(transition_count);
+        for (line_no, line) in lines {
+            let (from, label, to) = parse_aut_edge(line).ok_or_else(|| ExplicitLtsError {
+                message: "expected transition `(<from>, \"<label>\", <to>)`".to_string(),
+                span: Some(line_span(path, line_no, line)),
+            })?;
+            for state in [from, to] {
+                if state as usize >= state_count {
+                    return Err(ExplicitLtsError {
+                        message: format!("state {state} out of range (states: {state_count})"),
+                        span: Some(line_span(path, line_no, line)),
+                    });
+                }
+            }
+            edges.push((from, normalize_aut_label(&label), to));
+        }
+        if edges.len() != transition_count {
+            return Err(ExplicitLtsError {
+                message: format!(
+                    "transition count mismatch: header declares {transition_count}, found {}",
+                    edges.len()
+                ),
+                span: Some(line_span(path, header_line, header)),
+            });
+        }
+
+        Self::new(initial, state_count, edges).map_err(|err| ExplicitLtsError {
+            span: Some(line_span(path, header_line, header)),
+            ..err
+        })
+    }
+
+    pub fn parse_json(input: &str, path: &str) -> Result<Self, ExplicitLtsError> {
+        let parsed: JsonLts = serde_json::from_str(input).map_err(|err| ExplicitLtsError {
+            message: format!("invalid LTS JSON: {err}"),
+            span: Some(SourceSpan {
+                path: path.to_string(),
+                start_line: err.line().max(1) as u32,
+                start_col: err.column().max(1) as u32,
+                end_line: err.line().max(1) as u32,
+                end_col: err.column().max(1) as u32,
+            }),
+        })?;
+        Self::new(
+            parsed.initial,
+            parsed.states,
+            parsed
+                .transitions
+                .into_iter()
+                .map(|edge| (edge.from, edge.label, edge.to)),
+        )
+        .map_err(|err| ExplicitLtsError {
+            span: Some(line_span(path, 1, "")),
+            ..err
+        })
+    }
+
+    pub fn initial(&self) -> StateId {
+        self.initial
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn transition_count(&self) -> usize {
+        self.transitions.iter().map(Vec::len).sum()
+    }
+
+    pub fn transitions_from(&self, state: StateId) -> &[(String, StateId)] {
+        self.transitions
+            .get(state as usize)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonLts {
+    initial: StateId,
+    states: usize,
+    transitions: Vec<JsonEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonEdge {
+    from: StateId,
+    label: String,
+    to: StateId,
+}
+
+fn line_span(path: &str, line: u32, text: &str) -> SourceSpan {
+    SourceSpan {
+        path: path.to_string(),
+        start_line: line,
+        start_col: 1,
+        end_line: line,
+        end_col: (text.chars().count() as u32).max(1),
+    }
+}
+
+fn parse_aut_header(line: &str) -> Option<(StateId, usize, usize)> {
+    let body = line.strip_prefix("des")?.trim_start();
+    let body = body.strip_prefix('(')?.strip_suffix(')')?;
+    let mut parts = body.split(',').map(str::trim);
+    let initial = parts.next()?.parse().ok()?;
+    let transitions = parts.next()?.parse().ok()?;
+    let states = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((initial, transitions, states))
+}
+
+fn parse_aut_edge(line: &str) -> Option<(StateId, String, StateId)> {
+    let body = line.strip_prefix('(')?.strip_suffix(')')?;
+    let (from, rest) = body.split_once(',')?;
+    let (label, to) = rest.rsplit_once(',')?;
+    let label = label.trim();
+    let label = match label.strip_prefix('"') {
+        Some(quoted) => quoted.strip_suffix('"')?,
+        None => label,
+    };
+    if label.is_empty() {
+        return None;
+    }
+    Some((
+        from.trim().parse().ok()?,
+        label.to_string(),
+        to.trim().parse().ok()?,
+    ))
+}
+
+fn normalize_aut_label(label: &str) -> String {
+    if label == "i" {
+        return TAU_LABEL.to_string();
+    }
+    label.to_string()
+}
+
+pub fn resolve_lts_path(source_path: &str, literal: &str) -> PathBuf {
+    let literal_path = Path::new(literal);
+    if literal_path.is_absolute() {
+        return literal_path.to_path_buf();
+    }
+    match Path::new(source_path).parent() {
+        Some(parent) => parent.join(literal_path),
+        None => literal_path.to_path_buf(),
+    }
+}
+
+pub fn module_from_lts_file(path: &Path) -> Module {
+    let display = path.to_string_lossy().to_string();
+    let literal = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| display.clone());
+    let span = SourceSpan {
+        path: display.clone(),
+        start_line: 1,
+        start_col: 1,
+        end_line: 1,
+        end_col: 1,
+    };
+    Module {
+        channels: Vec::new(),
+        declarations: Vec::new(),
+        assertions: Vec::new(),
+        entry: Some(Spanned {
+            value: ProcessExpr::LoadLts {
+                path: Spanned {
+                    value: literal,
+                    span: span.clone(),
+                },
+                lts: None,
+            },
+            span,
+        }),
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExplicitStateCodec;
+
+impl StateCodec<StateId> for ExplicitStateCodec {
+    fn encode(&self, state: &StateId) -> Vec<u8> {
+        state.to_be_bytes().to_vec()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<StateId, StateCodecError> {
+        let bytes: [u8; 8] = bytes
+            .try_into()
+            .map_err(|_| StateCodecError::new("invalid explicit state bytes"))?;
+        Ok(StateId::from_be_bytes(bytes))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExplicitLtsProvider {
+    lts: ExplicitLts,
+}
+
+impl ExplicitLtsProvider {
+    pub fn new(lts: ExplicitLts) -> Self {
+        Self { lts }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ExplicitLtsError> {
+        Ok(Self::new(ExplicitLts::load(path)?))
+    }
+
+    pub fn lts(&self) -> &ExplicitLts {
+        &self.lts
+    }
+}
+
+impl TransitionProvider for ExplicitLtsProvider {
+    type State = StateId;
+    type Transition = Transition;
+
+    fn initial_state(&self) -> Self::State {
+        self.lts.initial()
+    }
+
+    fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)> {
+        self.lts
+            .transitions_from(*state)
+            .iter()
+            .map(|(label, next)| {
+                (
+                    Transition {
+                        label: label.clone(),
+                    },
+                    *next,
+                )
+            })
+            .collect()
+    }
+}
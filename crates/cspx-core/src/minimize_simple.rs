@@ -1,12 +1,17 @@
 use crate::minimize::Minimizer;
-use crate::types::{Counterexample, CounterexampleType};
+use crate::types::{Counterexample, CounterexampleEvent, CounterexampleType, Minimality};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
+// Delta debugging (ddmin) over the event sequence. The result is 1-minimal:
+// dropping any single remaining event loses the failure. Oracle answers are
+// cached per label sequence because ddmin revisits candidates.
 #[derive(Debug, Default)]
 pub struct TraceHeuristicMinimizer;
 
 impl Minimizer for TraceHeuristicMinimizer {
     fn minimize(&self, mut counterexample: Counterexample) -> Counterexample {
-        counterexample.is_minimized = false;
+        counterexample.minimality = Minimality::None;
         counterexample
     }
 
@@ -19,35 +24,73 @@ impl Minimizer for TraceHeuristicMinimizer {
         F: Fn(&Counterexample) -> bool,
     {
         if counterexample.kind != CounterexampleType::Trace {
-            counterexample.is_minimized = false;
+            counterexample.minimality = Minimality::None;
             return counterexample;
         }
-        if !preserves_failure(&counterexample) {
-            counterexample.is_minimized = false;
+
+        let cache = RefCell::new(HashMap::<Vec<String>, bool>::new());
+        let template = counterexample.clone();
+        let test = |events: &[CounterexampleEvent]| -> bool {
+            let key = events
+                .iter()
+                .map(|event| event.label.clone())
+                .collect::<Vec<_>>();
+            if let Some(&cached) = cache.borrow().get(&key) {
+                return cached;
+            }
+            let mut candidate = template.clone();
+            candidate.events = events.to_vec();
+            let result = preserves_failure(&candidate);
+            cache.borrow_mut().insert(key, result);
+            result
+        };
+
+        if !test(&counterexample.events) {
+            counterexample.minimality = Minimality::None;
             return counterexample;
         }
 
-        let mut best = counterexample;
-        loop {
-            let mut reduced = false;
-            for idx in 0..best.events.len() {
-                let mut candidate = best.clone();
-                candidate.events.remove(idx);
-                if !preserves_failure(&candidate) {
-                    continue;
+        counterexample.events = ddmin(counterexample.events, test);
+        counterexample.minimality = Minimality::LocallyMinimal;
+        counterexample
+    }
+}
+
+fn ddmin<T: Clone>(mut items: Vec<T>, test: impl Fn(&[T]) -> bool) -> Vec<T> {
+    let mut granularity = 2;
+    'outer: while !items.is_empty() {
+        let n = granularity.min(items.len());
+        let bounds = (0..=n).map(|idx| idx * items.len() / n).collect::<Vec<_>>();
+
+        if n > 1 {
+            for chunk in bounds.windows(2) {
+                let subset = &items[chunk[0]..chunk[1]];
+                if test(subset) {
+                    items = subset.to_vec();
+                    granularity = 2;
+                    continue 'outer;
                 }
-                best = candidate;
-                reduced = true;
-                break;
             }
-            if !reduced {
-                break;
+        }
+        for chunk in bounds.windows(2) {
+            let complement = items[..chunk[0]]
+                .iter()
+                .chain(&items[chunk[1]..])
+                .cloned()
+                .collect::<Vec<_>>();
+            if test(&complement) {
+                items = complement;
+                granularity = (n - 1).max(2);
+                continue 'outer;
             }
         }
 
-        best.is_minimized = true;
-        best
+        if n == items.len() {
+            break;
+        }
+        granularity = (n * 2).min(items.len());
     }
+    items
 }
 
 #[deprecated(
@@ -59,7 +102,9 @@ pub type IdentityMinimizer = TraceHeuristicMinimizer;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{CounterexampleEvent, SourceSpan};
+    use crate::types::SourceSpan;
+    use std::cell::Cell;
+    use std::collections::HashSet;
 
     fn trace(labels: &[&str]) -> Counterexample {
         Counterexample {
@@ -68,9 +113,11 @@ mod tests {
                 .iter()
                 .map(|label| CounterexampleEvent {
                     label: (*label).to_string(),
+                    hidden_label: None,
+                    spans: Vec::new(),
                 })
                 .collect(),
-            is_minimized: false,
+            minimality: Minimality::None,
             tags: vec!["refinement".to_string()],
             source_spans: vec![SourceSpan {
                 path: "spec.cspm".to_string(),
@@ -79,6 +126,11 @@ mod tests {
                 end_line: 1,
                 end_col: 1,
             }],
+            lasso: None,
+            failure: None,
+            deadlock_explanation: None,
+            conformance: None,
+            explanation: None,
         }
     }
 
@@ -98,7 +150,7 @@ mod tests {
         });
 
         assert_eq!(labels(&output), vec!["a".to_string()]);
-        assert!(output.is_minimized);
+        assert_eq!(output.minimality, Minimality::LocallyMinimal);
     }
 
     #[test]
@@ -107,7 +159,7 @@ mod tests {
         let output = minimizer.minimize(trace(&["a", "b"]));
 
         assert_eq!(labels(&output), vec!["a".to_string(), "b".to_string()]);
-        assert!(!output.is_minimized);
+        assert_eq!(output.minimality, Minimality::None);
     }
 
     #[test]
@@ -116,7 +168,7 @@ mod tests {
         let output = minimizer.minimize_with_oracle(trace(&["a"]), |_candidate| false);
 
         assert_eq!(labels(&output), vec!["a".to_string()]);
-        assert!(!output.is_minimized);
+        assert_eq!(output.minimality, Minimality::None);
     }
 
     #[test]
@@ -127,6 +179,42 @@ mod tests {
         });
 
         assert_eq!(labels(&output), vec!["tau".to_string()]);
-        assert!(output.is_minimized);
+        assert_eq!(output.minimality, Minimality::LocallyMinimal);
+    }
+
+    #[test]
+    fn minimize_with_oracle_keeps_events_that_are_jointly_required() {
+        let minimizer = TraceHeuristicMinimizer;
+        let input = trace(&["a", "x", "y", "b", "z", "w", "c", "v"]);
+        let output = minimizer.minimize_with_oracle(input, |candidate| {
+            let seen = labels(candidate);
+            ["a", "b", "c"]
+                .iter()
+                .all(|label| seen.iter().any(|event| event == label))
+        });
+
+        assert_eq!(labels(&output), vec!["a", "b", "c"]);
+        assert_eq!(output.minimality, Minimality::LocallyMinimal);
+    }
+
+    #[test]
+    fn minimize_with_oracle_asks_each_candidate_once() {
+        let minimizer = TraceHeuristicMinimizer;
+        let calls = Cell::new(0usize);
+        let asked = RefCell::new(HashSet::new());
+        let input = trace(&["p", "q", "r", "s", "t", "u", "v", "w", "a", "x", "y", "z"]);
+        let output = minimizer.minimize_with_oracle(input, |candidate| {
+            calls.set(calls.get() + 1);
+            assert!(
+                asked.borrow_mut().insert(labels(candidate)),
+                "oracle asked twice for {:?}",
+                labels(candidate)
+            );
+            candidate.events.iter().any(|event| event.label == "a")
+        });
+
+        assert_eq!(labels(&output), vec!["a"]);
+        // One-at-a-time removal would need at least one call per event.
+        assert!(calls.get() < 12, "oracle calls: {}", calls.get());
     }
 }
@@ -0,0 +1,375 @@
+use crate::check_refine::{
+    closure_has_tau_cycle, enabled_visible_labels, next_by_label, stable_offer_sets, tau_closure,
+};
+use crate::counterexample_span::module_counterexample_spans;
+use crate::ir::Module;
+use crate::limits::{LimitExceeded, RunLimits};
+use crate::lts::{Transition, TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{CspmLtsError, CspmTransitionProvider};
+use crate::state_codec::{StateCodec, StateCodecError};
+use crate::types::SourceSpan;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::path::Path;
+
+const NORM_FORMAT: &str = "cspx-norm";
+const NORM_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum NormaliseError {
+    Model(CspmLtsError),
+    Limit(LimitExceeded),
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalisedSpecError {
+    pub message: String,
+    pub span: Option<SourceSpan>,
+}
+
+impl Display for NormalisedSpecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.span {
+            Some(span) => write!(
+                f,
+                "{}:{}:{}: {}",
+                span.path, span.start_line, span.start_col, self.message
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for NormalisedSpecError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalNode {
+    pub acceptances: Vec<BTreeSet<String>>,
+    pub divergent: bool,
+    pub transitions: BTreeMap<String, usize>,
+}
+
+impl NormalNode {
+    fn is_stable(&self) -> bool {
+        !self.divergent
+            && self.acceptances.len() == 1
+            && self.acceptances[0].iter().eq(self.transitions.keys())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalisedSpec {
+    initial: usize,
+    nodes: Vec<NormalNode>,
+    source_spans: Vec<SourceSpan>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NormFile {
+    format: String,
+    version: u32,
+    initial: usize,
+    #[serde(default)]
+    source_spans: Vec<SourceSpan>,
+    nodes: Vec<NormalNode>,
+}
+
+impl NormalisedSpec {
+    pub fn new(
+        initial: usize,
+        nodes: Vec<NormalNode>,
+        source_spans: Vec<SourceSpan>,
+    ) -> Result<Self, NormalisedSpecError> {
+        let invalid = |message: String| NormalisedSpecError {
+            message,
+            span: None,
+        };
+        let node_count = nodes.len();
+        if initial >= node_count {
+            return Err(invalid(format!(
+                "initial node {initial} out of range (nodes: {node_count})"
+            )));
+        }
+        for (idx, node) in nodes.iter().enumerate() {
+            if node.transitions.contains_key(TAU_LABEL) {
+                return Err(invalid(format!("node {idx}: `{TAU_LABEL}` transition")));
+            }
+            if let Some(target) = node.transitions.values().find(|&&to| to >= node_count) {
+                return Err(invalid(format!(
+                    "node {idx}: target {target} out of range (nodes: {node_count})"
+                )));
+            }
+            for acceptance in &node.acceptances {
+                if let Some(label) = acceptance
+                    .iter()
+                    .find(|label| !node.transitions.contains_key(*label))
+                {
+                    return Err(invalid(format!(
+                        "node {idx}: acceptance label `{label}` has no transition"
+                    )));
+                }
+            }
+        }
+        Ok(Self {
+            initial,
+            nodes,
+            source_spans,
+        })
+    }
+
+    pub fn from_provider<P>(provider: &P) -> Self
+    where
+        P: TransitionProvider + Sync,
+        P::State: Ord + Hash,
+        P::Transition: TransitionLabel,
+    {
+        Self::from_provider_with_limits(provider, &RunLimits::unlimited())
+            .expect("unlimited normalisation")
+    }
+
+    // The deadline is polled once per subset node, like `bfs_search` does
+    // per state.
+    pub fn from_provider_with_limits<P>(
+        provider: &P,
+        limits: &RunLimits,
+    ) -> Result<Self, LimitExceeded>
+    where
+        P: TransitionProvider + Sync,
+        P::State: Ord + Hash,
+        P::Transition: TransitionLabel,
+    {
+        limits.check_deadline()?;
+        let initial = tau_closure(provider, vec![provider.initial_state()]).states;
+        let mut index_of = HashMap::from([(initial.clone(), 0usize)]);
+        let mut queue = VecDeque::from([initial]);
+        let mut nodes = Vec::new();
+
+        while let Some(closure) = queue.pop_front() {
+            limits.check_deadline()?;
+            let mut acceptances = Vec::<BTreeSet<String>>::new();
+            for offer in stable_offer_sets(provider, &closure) {
+                if !acceptances.contains(&offer) {
+                    acceptances.push(offer);
+                }
+            }
+            let mut transitions = BTreeMap::new();
+            for label in enabled_visible_labels(provider, &closure) {
+                let next = next_by_label(provider, &closure, &label).states;
+                let next_idx = index_of.len();
+                let target = *index_of.entry(next.clone()).or_insert_with(|| {
+                    queue.push_back(next);
+                    next_idx
+                });
+                transitions.insert(label, target);
+            }
+            nodes.push(NormalNode {
+                acceptances,
+                divergent: closure_has_tau_cycle(provider, &closure),
+                transitions,
+            });
+        }
+
+        Ok(Self {
+            initial: 0,
+            nodes,
+            source_spans: Vec::new(),
+        })
+    }
+
+    pub fn from_module(module: &Module) -> Result<Self, CspmLtsError> {
+        let provider = CspmTransitionProvider::from_module(module)?;
+        let mut spec = Self::from_provider(&provider);
+        spec.source_spans = module_counterexample_spans(module);
+        Ok(spec)
+    }
+
+    pub fn from_module_with_limits(
+        module: &Module,
+        limits: &RunLimits,
+    ) -> Result<Self, NormaliseError> {
+        let provider =
+            CspmTransitionProvider::from_module(module).map_err(NormaliseError::Model)?;
+        let mut spec =
+            Self::from_provider_with_limits(&provider, limits).map_err(NormaliseError::Limit)?;
+        spec.source_spans = module_counterexample_spans(module);
+        Ok(spec)
+    }
+
+    pub fn is_norm_path(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("norm"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, NormalisedSpecError> {
+        let display = path.to_string_lossy().to_string();
+        let input = std::fs::read_to_string(path).map_err(|err| NormalisedSpecError {
+            message: format!("{display}: {err}"),
+            span: None,
+        })?;
+        Self::parse(&input, &display)
+    }
+
+    pub fn parse(input: &str, path: &str) -> Result<Self, NormalisedSpecError> {
+        let file_span = |line: usize, col: usize| SourceSpan {
+            path: path.to_string(),
+            start_line: line.max(1) as u32,
+            start_col: col.max(1) as u32,
+            end_line: line.max(1) as u32,
+            end_col: col.max(1) as u32,
+        };
+        let parsed: NormFile = serde_json::from_str(input).map_err(|err| NormalisedSpecError {
+            message: format!("invalid normalised spec: {err}"),
+            span: Some(file_span(err.line(), err.column())),
+        })?;
+        if parsed.format != NORM_FORMAT || parsed.version != NORM_VERSION {
+            return Err(NormalisedSpecError {
+                message: format!(
+                    "unsupported normalised spec format `{}` v{} (expected `{NORM_FORMAT}` v{NORM_VERSION})",
+                    parsed.format, parsed.version
+                ),
+                span: Some(file_span(1, 1)),
+            });
+        }
+        Self::new(parsed.initial, parsed.nodes, parsed.source_spans).map_err(|err| {
+            NormalisedSpecError {
+                span: Some(file_span(1, 1)),
+                ..err
+            }
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let file = NormFile {
+            format: NORM_FORMAT.to_string(),
+            version: NORM_VERSION,
+            initial: self.initial,
+            source_spans: self.source_spans.clone(),
+            nodes: self.nodes.clone(),
+        };
+        serde_json::to_string_pretty(&file).expect("serialize normalised spec")
+    }
+
+    pub fn initial(&self) -> usize {
+        self.initial
+    }
+
+    pub fn nodes(&self) -> &[NormalNode] {
+        &self.nodes
+    }
+
+    pub fn source_spans(&self) -> &[SourceSpan] {
+        &self.source_spans
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn transition_count(&self) -> usize {
+        self.nodes.iter().map(|node| node.transitions.len()).sum()
+    }
+}
+
+// Each node is exposed as an unstable state with one stable state per
+// acceptance set (or as a single stable state when it has exactly one), so
+// the refinement engine's closure and refusal checks apply unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NormalState {
+    Node(usize),
+    Acceptance { node: usize, index: usize },
+}
+
+impl TransitionProvider for NormalisedSpec {
+    type State = NormalState;
+    type Transition = Transition;
+
+    fn initial_state(&self) -> Self::State {
+        NormalState::Node(self.initial)
+    }
+
+    fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)> {
+        let visible = |label: &str, node: &NormalNode| {
+            (
+                Transition {
+                    label: label.to_string(),
+                },
+                NormalState::Node(node.transitions[label]),
+            )
+        };
+        match *state {
+            NormalState::Node(idx) => {
+                let node = &self.nodes[idx];
+                if node.is_stable() {
+                    return node
+                        .transitions
+                        .keys()
+                        .map(|label| visible(label, node))
+                        .collect();
+                }
+                let tau = |next| {
+                    (
+                        Transition {
+                            label: TAU_LABEL.to_string(),
+                        },
+                        next,
+                    )
+                };
+                let mut out = (0..node.acceptances.len())
+                    .map(|index| tau(NormalState::Acceptance { node: idx, index }))
+                    .collect::<Vec<_>>();
+                if node.divergent {
+                    out.push(tau(NormalState::Node(idx)));
+                }
+                out.extend(node.transitions.keys().map(|label| visible(label, node)));
+                out
+            }
+            NormalState::Acceptance { node, index } => {
+                let node = &self.nodes[node];
+                node.acceptances[index]
+                    .iter()
+                    .map(|label| visible(label, node))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NormalStateCodec;
+
+impl StateCodec<NormalState> for NormalStateCodec {
+    fn encode(&self, state: &NormalState) -> Vec<u8> {
+        let (tag, node, index) = match *state {
+            NormalState::Node(node) => (0u8, node, 0),
+            NormalState::Acceptance { node, index } => (1u8, node, index),
+        };
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&(node as u64).to_be_bytes());
+        bytes.extend_from_slice(&(index as u64).to_be_bytes());
+        bytes
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<NormalState, StateCodecError> {
+        let invalid = || StateCodecError::new("invalid normal state bytes");
+        if bytes.len() != 17 {
+            return Err(invalid());
+        }
+        let field = |range: std::ops::Range<usize>| {
+            let mut value = [0u8; 8];
+            value.copy_from_slice(&bytes[range]);
+            usize::try_from(u64::from_be_bytes(value)).map_err(|_| invalid())
+        };
+        let node = field(1..9)?;
+        match bytes[0] {
+            0 => Ok(NormalState::Node(node)),
+            1 => Ok(NormalState::Acceptance {
+                node,
+                index: field(9..17)?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathTarget<S> {
+    State(S),
+    Fingerprint(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStep<S> {
+    pub target: PathTarget<S>,
+    pub label: Option<String>,
+}
+
+pub trait PredecessorStore<S> {
+    /// `parent_index` is the parent's discovery index, the initial state being 0.
+    fn record(
+        &mut self,
+        state: &S,
+        parent: &S,
+        parent_index: u64,
+        label: Option<String>,
+    ) -> std::io::Result<()>;
+
+    /// The steps from the initial state to `state`, first step first.
+    fn path(&self, state: &S) -> std::io::Result<Vec<PathStep<S>>>;
+
+    /// The steps from the initial state to the state with discovery index `index`.
+    fn path_to(&self, index: u64) -> std::io::Result<Vec<PathStep<S>>>;
+
+    fn reaches(&self, step: &PathStep<S>, state: &S) -> bool;
+
+    fn memory_bytes(&self) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,184 @@
+use crate::checkpoint::{put_label, put_u64, RecordReader};
+use crate::disk_store::{
+    create_segment, segment_header, write_record, RECORD_HEADER_LEN, SEGMENT_HEADER_LEN,
+};
+use crate::predecessor::{PathStep, PathTarget, PredecessorStore};
+use crate::queue_disk::read_record;
+use crate::state_codec::StateCodec;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+// Record `i` holds the state discovered `i + 1`-th with its parent's discovery
+// index and label; only the record offsets stay in memory.
+#[derive(Debug)]
+pub struct DiskPredecessorStore<S, C>
+where
+    C: StateCodec<S>,
+{
+    path: PathBuf,
+    codec: C,
+    writer: RefCell<BufWriter<fs::File>>,
+    offsets: Vec<u64>,
+    end: u64,
+    _marker: PhantomData<S>,
+}
+
+impl<S, C> DiskPredecessorStore<S, C>
+where
+    C: StateCodec<S>,
+{
+    pub fn open(path: impl AsRef<Path>, codec: C) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let writer = create_segment(&path)?;
+        Ok(Self {
+            path,
+            codec,
+            writer: RefCell::new(writer),
+            offsets: Vec::new(),
+            end: SEGMENT_HEADER_LEN,
+            _marker: PhantomData,
+        })
+    }
+
+    fn reader(&self) -> io::Result<BufReader<fs::File>> {
+        self.writer.borrow_mut().flush()?;
+        let mut reader = BufReader::new(fs::File::open(&self.path)?);
+        let mut header = [0u8; SEGMENT_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if header != segment_header() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid predecessor log header: {}", self.path.display()),
+            ));
+        }
+        Ok(reader)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> io::Result<S> {
+        self.codec
+            .decode(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    // Walks parent links back from record `found`.
+    fn steps(
+        &self,
+        reader: &mut BufReader<fs::File>,
+        found: Option<usize>,
+    ) -> io::Result<Vec<PathStep<S>>> {
+        let mut steps = Vec::new();
+        let mut next = found;
+        while let Some(index) = next {
+            reader.seek(SeekFrom::Start(self.offsets[index]))?;
+            let record = read_record(reader)?;
+            let mut fields = RecordReader::new(&record);
+            let parent = fields.u64()?;
+            let label = fields.label()?;
+            steps.push(PathStep {
+                target: PathTarget::State(self.decode(fields.rest())?),
+                label,
+            });
+            next = match parent {
+                0 => None,
+                parent if parent as usize <= index => Some(parent as usize - 1),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "predecessor log names a later parent",
+                    ))
+                }
+            };
+        }
+        steps.reverse();
+        Ok(steps)
+    }
+}
+
+impl<S, C> PredecessorStore<S> for DiskPredecessorStore<S, C>
+where
+    C: StateCodec<S>,
+{
+    fn record(
+        &mut self,
+        state: &S,
+        _parent: &S,
+        parent_index: u64,
+        label: Option<String>,
+    ) -> io::Result<()> {
+        if parent_index > self.offsets.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "predecessor recorded before its parent",
+            ));
+        }
+        let mut record = Vec::new();
+        put_u64(&mut record, parent_index);
+        put_label(&mut record, label.as_deref());
+        record.extend_from_slice(&self.codec.encode(state));
+        write_record(self.writer.get_mut(), &record)?;
+        self.offsets.push(self.end);
+        self.end += RECORD_HEADER_LEN + record.len() as u64;
+        Ok(())
+    }
+
+    fn path(&self, state: &S) -> io::Result<Vec<PathStep<S>>> {
+        let target = self.codec.encode(state);
+        let mut reader = self.reader()?;
+        let mut found = None;
+        for index in 0..self.offsets.len() {
+            let record = read_record(&mut reader)?;
+            let mut fields = RecordReader::new(&record);
+            fields.u64()?;
+            fields.label()?;
+            if fields.rest() == target.as_slice() {
+                found = Some(index);
+                break;
+            }
+        }
+        self.steps(&mut reader, found)
+    }
+
+    fn path_to(&self, index: u64) -> io::Result<Vec<PathStep<S>>> {
+        if index > self.offsets.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no predecessor recorded for the discovery index",
+            ));
+        }
+        let mut reader = self.reader()?;
+        self.steps(
+            &mut reader,
+            index.checked_sub(1).map(|entry| entry as usize),
+        )
+    }
+
+    fn reaches(&self, step: &PathStep<S>, state: &S) -> bool {
+        match &step.target {
+            PathTarget::State(target) => self.codec.encode(target) == self.codec.encode(state),
+            PathTarget::Fingerprint(_) => false,
+        }
+    }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        let offsets = self.offsets.capacity() * std::mem::size_of::<u64>();
+        Some((offsets + self.writer.borrow().capacity()) as u64)
+    }
+}
+
+impl<S, C> Drop for DiskPredecessorStore<S, C>
+where
+    C: StateCodec<S>,
+{
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
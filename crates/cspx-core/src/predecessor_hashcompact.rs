@@ -0,0 +1,121 @@
+use crate::predecessor::{PathStep, PathTarget, PredecessorStore};
+use crate::store::fingerprint;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+const TAU: u32 = u32::MAX;
+
+// Parent links between state fingerprints, for stores that keep no states.
+#[derive(Debug, Default)]
+pub struct HashCompactPredecessorStore<S>
+where
+    S: Hash,
+{
+    parents: HashMap<u64, (u64, u32)>,
+    // The fingerprint of the state discovered `i + 1`-th.
+    order: Vec<u64>,
+    labels: Vec<String>,
+    label_ids: HashMap<String, u32>,
+    _marker: PhantomData<S>,
+}
+
+impl<S> HashCompactPredecessorStore<S>
+where
+    S: Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            parents: HashMap::new(),
+            order: Vec::new(),
+            labels: Vec::new(),
+            label_ids: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn intern(&mut self, label: Option<String>) -> u32 {
+        let Some(label) = label else {
+            return TAU;
+        };
+        if let Some(id) = self.label_ids.get(&label) {
+            return *id;
+        }
+        let id = self.labels.len() as u32;
+        self.labels.push(label.clone());
+        self.label_ids.insert(label, id);
+        id
+    }
+
+    fn steps(&self, mut current: u64) -> std::io::Result<Vec<PathStep<S>>> {
+        let mut steps = Vec::new();
+        while let Some((parent, label)) = self.parents.get(&current) {
+            if steps.len() >= self.parents.len() {
+                return Err(std::io::Error::other(
+                    "predecessor fingerprints form a cycle",
+                ));
+            }
+            steps.push(PathStep {
+                target: PathTarget::Fingerprint(current),
+                label: (*label != TAU).then(|| self.labels[*label as usize].clone()),
+            });
+            current = *parent;
+        }
+        steps.reverse();
+        Ok(steps)
+    }
+}
+
+impl<S> PredecessorStore<S> for HashCompactPredecessorStore<S>
+where
+    S: Hash,
+{
+    fn record(
+        &mut self,
+        state: &S,
+        parent: &S,
+        _parent_index: u64,
+        label: Option<String>,
+    ) -> std::io::Result<()> {
+        let label = self.intern(label);
+        let key = fingerprint(state);
+        if let Entry::Vacant(entry) = self.parents.entry(key) {
+            entry.insert((fingerprint(parent), label));
+            self.order.push(key);
+        }
+        Ok(())
+    }
+
+    fn path(&self, state: &S) -> std::io::Result<Vec<PathStep<S>>> {
+        self.steps(fingerprint(state))
+    }
+
+    fn path_to(&self, index: u64) -> std::io::Result<Vec<PathStep<S>>> {
+        match index.checked_sub(1) {
+            None => Ok(Vec::new()),
+            Some(entry) => match self.order.get(entry as usize) {
+                Some(&key) => self.steps(key),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "no predecessor recorded for the discovery index",
+                )),
+            },
+        }
+    }
+
+    fn reaches(&self, step: &PathStep<S>, state: &S) -> bool {
+        matches!(step.target, PathTarget::Fingerprint(target) if target == fingerprint(state))
+    }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        let links = self.parents.capacity() * 2 * std::mem::size_of::<(u64, u64, u32)>()
+            + self.order.capacity() * std::mem::size_of::<u64>();
+        let labels = self
+            .labels
+            .iter()
+            .map(|label| 2 * label.capacity() + 2 * std::mem::size_of::<String>())
+            .sum::<usize>();
+        Some((links + labels) as u64)
+    }
+}
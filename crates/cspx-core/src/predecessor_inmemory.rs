@@ -0,0 +1,94 @@
+use crate::predecessor::{PathStep, PathTarget, PredecessorStore};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Entry `i` holds the state discovered `i + 1`-th with its parent's discovery
+// index and label.
+#[derive(Debug, Default)]
+pub struct InMemoryPredecessorStore<S>
+where
+    S: Eq + Hash,
+{
+    index_of: HashMap<S, u64>,
+    entries: Vec<(S, u64, Option<String>)>,
+}
+
+impl<S> InMemoryPredecessorStore<S>
+where
+    S: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            index_of: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn steps(&self, index: u64) -> Vec<PathStep<S>>
+    where
+        S: Clone,
+    {
+        let mut steps = Vec::new();
+        let mut current = index;
+        while let Some((state, parent, label)) = current
+            .checked_sub(1)
+            .and_then(|entry| self.entries.get(entry as usize))
+        {
+            steps.push(PathStep {
+                target: PathTarget::State(state.clone()),
+                label: label.clone(),
+            });
+            current = *parent;
+        }
+        steps.reverse();
+        steps
+    }
+}
+
+impl<S> PredecessorStore<S> for InMemoryPredecessorStore<S>
+where
+    S: Clone + Eq + Hash,
+{
+    fn record(
+        &mut self,
+        state: &S,
+        _parent: &S,
+        parent_index: u64,
+        label: Option<String>,
+    ) -> std::io::Result<()> {
+        if self.index_of.contains_key(state) {
+            return Ok(());
+        }
+        if parent_index > self.entries.len() as u64 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "predecessor recorded before its parent",
+            ));
+        }
+        self.entries.push((state.clone(), parent_index, label));
+        self.index_of
+            .insert(state.clone(), self.entries.len() as u64);
+        Ok(())
+    }
+
+    fn path(&self, state: &S) -> std::io::Result<Vec<PathStep<S>>> {
+        Ok(self
+            .index_of
+            .get(state)
+            .map_or_else(Vec::new, |&index| self.steps(index)))
+    }
+
+    fn path_to(&self, index: u64) -> std::io::Result<Vec<PathStep<S>>> {
+        if index > self.entries.len() as u64 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "no predecessor recorded for the discovery index",
+            ));
+        }
+        Ok(self.steps(index))
+    }
+
+    fn reaches(&self, step: &PathStep<S>, state: &S) -> bool {
+        matches!(&step.target, PathTarget::State(target) if target == state)
+    }
+}
@@ -1,5 +1,16 @@
+use std::io;
+
 pub trait WorkQueue<S> {
-    fn push(&mut self, state: S);
-    fn pop(&mut self) -> Option<S>;
+    fn push(&mut self, state: S) -> io::Result<()>;
+    fn pop(&mut self) -> io::Result<Option<S>>;
     fn is_empty(&self) -> bool;
+
+    fn memory_bytes(&self) -> Option<u64> {
+        None
+    }
+
+    /// Sends further pushes to disk when the queue can.
+    fn spill(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
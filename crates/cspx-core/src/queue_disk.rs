@@ -0,0 +1,287 @@
+use crate::disk_store::{
+    create_segment, segment_header, write_record, RECORD_HEADER_LEN, SEGMENT_HEADER_LEN,
+};
+use crate::queue::WorkQueue;
+use crate::state_codec::StateCodec;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskWorkQueueOptions {
+    pub segment_records: u64,
+}
+
+impl Default for DiskWorkQueueOptions {
+    fn default() -> Self {
+        Self {
+            segment_records: 64 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskWorkQueueMetrics {
+    pub pushed: u64,
+    pub popped: u64,
+    pub segments_written: u64,
+    pub write_bytes: u64,
+    pub read_bytes: u64,
+}
+
+#[derive(Debug)]
+struct SegmentWriter {
+    segment: u32,
+    writer: BufWriter<fs::File>,
+    records: u64,
+}
+
+#[derive(Debug)]
+struct SegmentReader {
+    segment: u32,
+    reader: BufReader<fs::File>,
+    remaining: u64,
+}
+
+// FIFO queue of encoded states in segment files next to `path`, using the
+// record framing of `DiskStateStore`. Only sealed segments are read, and a
+// segment is deleted once it has been fully popped, so at most one write
+// buffer and one read buffer are held in memory.
+#[derive(Debug)]
+pub struct DiskWorkQueue<S, C>
+where
+    C: StateCodec<S>,
+{
+    path: PathBuf,
+    codec: C,
+    segment_records: u64,
+    writer: Option<SegmentWriter>,
+    reader: Option<SegmentReader>,
+    sealed: VecDeque<(u32, u64)>,
+    next_segment: u32,
+    len: u64,
+    metrics: DiskWorkQueueMetrics,
+    _marker: PhantomData<S>,
+}
+
+impl<S, C> DiskWorkQueue<S, C>
+where
+    C: StateCodec<S>,
+{
+    pub fn open(path: impl AsRef<Path>, codec: C) -> io::Result<Self> {
+        Self::open_with_options(path, codec, DiskWorkQueueOptions::default())
+    }
+
+    pub fn open_with_options(
+        path: impl AsRef<Path>,
+        codec: C,
+        options: DiskWorkQueueOptions,
+    ) -> io::Result<Self> {
+        if options.segment_records == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "segment_records must be >= 1",
+            ));
+        }
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let queue = Self {
+            path,
+            codec,
+            segment_records: options.segment_records,
+            writer: None,
+            reader: None,
+            sealed: VecDeque::new(),
+            next_segment: 0,
+            len: 0,
+            metrics: DiskWorkQueueMetrics::default(),
+            _marker: PhantomData,
+        };
+        let first = queue.segment_path(0);
+        if first.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "queue segment already exists; use a fresh path: {}",
+                    first.display()
+                ),
+            ));
+        }
+        Ok(queue)
+    }
+
+    pub fn metrics(&self) -> &DiskWorkQueueMetrics {
+        &self.metrics
+    }
+
+    fn segment_path(&self, segment: u32) -> PathBuf {
+        self.path.with_extension(format!("{segment:06}.seg"))
+    }
+
+    fn seal_writer(&mut self) -> io::Result<()> {
+        if let Some(mut current) = self.writer.take() {
+            current.writer.flush()?;
+            self.sealed.push_back((current.segment, current.records));
+        }
+        Ok(())
+    }
+
+    fn open_reader(&mut self, segment: u32, records: u64) -> io::Result<()> {
+        let mut reader = BufReader::new(fs::File::open(self.segment_path(segment))?);
+        let mut header = [0u8; SEGMENT_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if header != segment_header() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid queue segment header: {}",
+                    self.segment_path(segment).display()
+                ),
+            ));
+        }
+        self.metrics.read_bytes = self.metrics.read_bytes.saturating_add(SEGMENT_HEADER_LEN);
+        self.reader = Some(SegmentReader {
+            segment,
+            reader,
+            remaining: records,
+        });
+        Ok(())
+    }
+
+    fn read_next(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(current) = self.reader.as_mut() {
+                let bytes = read_record(&mut current.reader)?;
+                current.remaining -= 1;
+                self.metrics.read_bytes = self
+                    .metrics
+                    .read_bytes
+                    .saturating_add(RECORD_HEADER_LEN + bytes.len() as u64);
+                if current.remaining == 0 {
+                    let segment = current.segment;
+                    self.reader = None;
+                    fs::remove_file(self.segment_path(segment))?;
+                }
+                return Ok(Some(bytes));
+            }
+            if let Some((segment, records)) = self.sealed.pop_front() {
+                self.open_reader(segment, records)?;
+                continue;
+            }
+            if self.writer.is_some() {
+                self.seal_writer()?;
+                continue;
+            }
+            return Ok(None);
+        }
+    }
+}
+
+impl<S, C> WorkQueue<S> for DiskWorkQueue<S, C>
+where
+    C: StateCodec<S>,
+{
+    fn push(&mut self, state: S) -> io::Result<()> {
+        let bytes = self.codec.encode(&state);
+        if self.writer.is_none() {
+            let segment = self.next_segment;
+            let writer = create_segment(&self.segment_path(segment))?;
+            self.next_segment = segment.checked_add(1).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "too many queue segments")
+            })?;
+            self.writer = Some(SegmentWriter {
+                segment,
+                writer,
+                records: 0,
+            });
+            self.metrics.segments_written = self.metrics.segments_written.saturating_add(1);
+            self.metrics.write_bytes = self.metrics.write_bytes.saturating_add(SEGMENT_HEADER_LEN);
+        }
+        let Some(current) = self.writer.as_mut() else {
+            return Err(io::Error::other("no active queue segment"));
+        };
+        write_record(&mut current.writer, &bytes)?;
+        current.records += 1;
+        self.len += 1;
+        self.metrics.pushed = self.metrics.pushed.saturating_add(1);
+        self.metrics.write_bytes = self
+            .metrics
+            .write_bytes
+            .saturating_add(RECORD_HEADER_LEN + bytes.len() as u64);
+        if current.records >= self.segment_records {
+            self.seal_writer()?;
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> io::Result<Option<S>> {
+        let Some(bytes) = self.read_next()? else {
+            return Ok(None);
+        };
+        let state = self
+            .codec
+            .decode(&bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        self.len -= 1;
+        self.metrics.popped = self.metrics.popped.saturating_add(1);
+        Ok(Some(state))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Only the write and read buffers and the list of sealed segments.
+    fn memory_bytes(&self) -> Option<u64> {
+        let buffers = self
+            .writer
+            .as_ref()
+            .map_or(0, |current| current.writer.capacity())
+            + self
+                .reader
+                .as_ref()
+                .map_or(0, |current| current.reader.capacity());
+        let sealed = self.sealed.len() * std::mem::size_of::<(u32, u64)>();
+        Some((buffers + sealed) as u64)
+    }
+}
+
+impl<S, C> Drop for DiskWorkQueue<S, C>
+where
+    C: StateCodec<S>,
+{
+    fn drop(&mut self) {
+        self.writer = None;
+        self.reader = None;
+        for segment in 0..self.next_segment {
+            let _ = fs::remove_file(self.segment_path(segment));
+        }
+    }
+}
+
+// Segments are only read by the process that wrote them, so any framing or
+// checksum problem is an error rather than a torn tail.
+pub(crate) fn read_record(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut header = [0u8; RECORD_HEADER_LEN as usize];
+    reader.read_exact(&mut header)?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let len = usize::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "queue record too large"))?;
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    if crc32fast::hash(&bytes) != crc {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "queue record checksum mismatch",
+        ));
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,143 @@
+use crate::queue::WorkQueue;
+use crate::queue_disk::{DiskWorkQueue, DiskWorkQueueMetrics, DiskWorkQueueOptions};
+use crate::state_codec::StateCodec;
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct HybridWorkQueueOptions {
+    pub spill_threshold: usize,
+    pub disk_options: DiskWorkQueueOptions,
+}
+
+impl Default for HybridWorkQueueOptions {
+    fn default() -> Self {
+        Self {
+            spill_threshold: 100_000,
+            disk_options: DiskWorkQueueOptions::default(),
+        }
+    }
+}
+
+// Keeps the head of the queue in memory and appends to a `DiskWorkQueue` once
+// `spill_threshold` states are held. While the spill is non-empty every push
+// goes to disk, which keeps FIFO order with at most `spill_threshold` states
+// in memory. After `spill` every push goes to disk.
+#[derive(Debug)]
+pub struct HybridWorkQueue<S, C>
+where
+    C: StateCodec<S> + Clone,
+{
+    in_memory: VecDeque<(S, u64)>,
+    in_memory_bytes: u64,
+    forced_spill: bool,
+    spill_path: PathBuf,
+    codec: C,
+    options: HybridWorkQueueOptions,
+    spill_queue: Option<DiskWorkQueue<S, C>>,
+}
+
+impl<S, C> HybridWorkQueue<S, C>
+where
+    C: StateCodec<S> + Clone,
+{
+    pub fn open(
+        spill_path: impl AsRef<Path>,
+        codec: C,
+        options: HybridWorkQueueOptions,
+    ) -> io::Result<Self> {
+        if options.spill_threshold == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "spill_threshold must be >= 1",
+            ));
+        }
+        Ok(Self {
+            in_memory: VecDeque::new(),
+            in_memory_bytes: 0,
+            forced_spill: false,
+            spill_path: spill_path.as_ref().to_path_buf(),
+            codec,
+            options,
+            spill_queue: None,
+        })
+    }
+
+    pub fn is_spilling(&self) -> bool {
+        self.spill_queue.is_some()
+    }
+
+    pub fn spill_metrics(&self) -> Option<&DiskWorkQueueMetrics> {
+        self.spill_queue.as_ref().map(DiskWorkQueue::metrics)
+    }
+
+    pub fn spill_path(&self) -> &Path {
+        &self.spill_path
+    }
+
+    pub fn in_memory_len(&self) -> usize {
+        self.in_memory.len()
+    }
+
+    fn spill_is_empty(&self) -> bool {
+        self.spill_queue.as_ref().is_none_or(WorkQueue::is_empty)
+    }
+}
+
+impl<S, C> WorkQueue<S> for HybridWorkQueue<S, C>
+where
+    C: StateCodec<S> + Clone,
+{
+    fn push(&mut self, state: S) -> io::Result<()> {
+        if !self.forced_spill
+            && self.spill_is_empty()
+            && self.in_memory.len() < self.options.spill_threshold
+        {
+            let bytes = self.codec.encode(&state).len() as u64 + std::mem::size_of::<S>() as u64;
+            self.in_memory_bytes = self.in_memory_bytes.saturating_add(bytes);
+            self.in_memory.push_back((state, bytes));
+            return Ok(());
+        }
+        if self.spill_queue.is_none() {
+            self.spill_queue = Some(DiskWorkQueue::open_with_options(
+                &self.spill_path,
+                self.codec.clone(),
+                self.options.disk_options,
+            )?);
+        }
+        match self.spill_queue.as_mut() {
+            Some(spill_queue) => spill_queue.push(state),
+            None => Err(io::Error::other("spill queue is not open")),
+        }
+    }
+
+    fn pop(&mut self) -> io::Result<Option<S>> {
+        if let Some((state, bytes)) = self.in_memory.pop_front() {
+            self.in_memory_bytes -= bytes;
+            return Ok(Some(state));
+        }
+        match self.spill_queue.as_mut() {
+            Some(spill_queue) => spill_queue.pop(),
+            None => Ok(None),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.in_memory.is_empty() && self.spill_is_empty()
+    }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        let spilled = self
+            .spill_queue
+            .as_ref()
+            .and_then(WorkQueue::memory_bytes)
+            .unwrap_or(0);
+        Some(self.in_memory_bytes.saturating_add(spilled))
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.forced_spill = true;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::queue::WorkQueue;
 use std::collections::VecDeque;
+use std::io;
 
 #[derive(Debug, Default)]
 pub struct VecWorkQueue<S> {
@@ -15,12 +16,13 @@ impl<S> VecWorkQueue<S> {
 }
 
 impl<S> WorkQueue<S> for VecWorkQueue<S> {
-    fn push(&mut self, state: S) {
+    fn push(&mut self, state: S) -> io::Result<()> {
         self.queue.push_back(state);
+        Ok(())
     }
 
-    fn pop(&mut self) -> Option<S> {
-        self.queue.pop_front()
+    fn pop(&mut self) -> io::Result<Option<S>> {
+        Ok(self.queue.pop_front())
     }
 
     fn is_empty(&self) -> bool {
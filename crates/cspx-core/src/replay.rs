@@ -0,0 +1,156 @@
+use crate::assertion_select::module_for_property_check;
+use crate::check::RefinementModel;
+use crate::check_determinism::first_nondeterministic_label;
+use crate::check_refine::{
+    closure_has_tau_cycle, enabled_visible_labels, next_by_label, replay_refinement, tau_closure,
+    Closure, RefinementInput, State,
+};
+use crate::ir::{Module, PropertyKind};
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{CspmLtsError, CspmTransitionProvider};
+use crate::normalise::NormalisedSpec;
+use crate::types::{Counterexample, CounterexampleEvent, Replay, ReplayVerdict};
+use std::hash::Hash;
+
+// Re-runs a counterexample of a `check` of `kind` against `module`, picking
+// the entry process the same way the checker does.
+pub fn replay_property(
+    kind: PropertyKind,
+    module: &Module,
+    counterexample: &Counterexample,
+) -> Result<Replay, CspmLtsError> {
+    let module = module_for_property_check(module, kind);
+    let provider = CspmTransitionProvider::from_module(&module)?;
+    Ok(replay_provider(kind, &provider, counterexample))
+}
+
+// Events are matched up to tau steps, so a counterexample survives changes
+// to the internal behaviour between its visible events.
+pub fn replay_provider<P>(
+    kind: PropertyKind,
+    provider: &P,
+    counterexample: &Counterexample,
+) -> Replay
+where
+    P: TransitionProvider + Sync,
+    P::State: Ord + Hash,
+    P::Transition: TransitionLabel,
+{
+    let events = &counterexample.events;
+    match kind {
+        PropertyKind::DeadlockFree => {
+            let closure = match walk(provider, events) {
+                Ok(closure) => closure,
+                Err(replay) => return replay,
+            };
+            let deadlocks = closure
+                .states
+                .iter()
+                .any(|state| provider.transitions(state).is_empty());
+            verdict(deadlocks, events.len())
+        }
+        PropertyKind::DivergenceFree => {
+            let closure = match walk(provider, events) {
+                Ok(closure) => closure,
+                Err(replay) => return replay,
+            };
+            let diverges = closure_has_tau_cycle(provider, &closure.states);
+            let step = match events.last() {
+                Some(event) if event.label == TAU_LABEL => events.len() - 1,
+                _ => events.len(),
+            };
+            verdict(diverges, step)
+        }
+        PropertyKind::Deterministic => {
+            // The last event is the label the process chose nondeterministically.
+            let Some((last, trace)) = events.split_last() else {
+                return verdict(false, 0);
+            };
+            let closure = match walk(provider, trace) {
+                Ok(closure) => closure,
+                Err(replay) => return replay,
+            };
+            if next_by_label(provider, &closure.states, &last.label)
+                .states
+                .is_empty()
+            {
+                return no_longer_valid(provider, &closure, trace.len());
+            }
+            let nondeterministic = closure
+                .states
+                .iter()
+                .any(|state| first_nondeterministic_label(provider, state).is_some());
+            verdict(nondeterministic, trace.len())
+        }
+    }
+}
+
+pub fn replay_refinement_input(
+    model: &RefinementModel,
+    input: &RefinementInput,
+    counterexample: &Counterexample,
+) -> Result<Replay, CspmLtsError> {
+    let spec = CspmTransitionProvider::from_module(&input.spec)?;
+    let impl_ = CspmTransitionProvider::from_module(&input.impl_)?;
+    Ok(replay_refinement(model, &spec, &impl_, counterexample))
+}
+
+pub fn replay_normalised_refinement(
+    model: &RefinementModel,
+    spec: &NormalisedSpec,
+    impl_: &Module,
+    counterexample: &Counterexample,
+) -> Result<Replay, CspmLtsError> {
+    let impl_ = CspmTransitionProvider::from_module(impl_)?;
+    Ok(replay_refinement(model, spec, &impl_, counterexample))
+}
+
+fn walk<P>(provider: &P, events: &[CounterexampleEvent]) -> Result<Closure<State<P>>, Replay>
+where
+    P: TransitionProvider + Sync,
+    P::State: Ord + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut closure = tau_closure(provider, vec![provider.initial_state()]);
+    for (step, event) in events.iter().enumerate() {
+        if event.label == TAU_LABEL {
+            continue;
+        }
+        let next = next_by_label(provider, &closure.states, &event.label);
+        if next.states.is_empty() {
+            return Err(no_longer_valid(provider, &closure, step));
+        }
+        closure = next;
+    }
+    Ok(closure)
+}
+
+fn no_longer_valid<P>(provider: &P, closure: &Closure<State<P>>, step: usize) -> Replay
+where
+    P: TransitionProvider + Sync,
+    P::State: Ord + Hash,
+    P::Transition: TransitionLabel,
+{
+    Replay {
+        verdict: ReplayVerdict::NoLongerValid,
+        step: Some(step),
+        offered: enabled_visible_labels(provider, &closure.states)
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn verdict(fails: bool, step: usize) -> Replay {
+    if fails {
+        return Replay {
+            verdict: ReplayVerdict::StillFails,
+            step: None,
+            offered: Vec::new(),
+        };
+    }
+    Replay {
+        verdict: ReplayVerdict::NowPasses,
+        step: Some(step),
+        offered: Vec::new(),
+    }
+}
@@ -0,0 +1,838 @@
+use crate::check::{CheckRequest, CheckResult};
+use crate::checkpoint::{
+    decode_state, invalid, put_label, put_u64, Journal, RecordReader, SearchCheckpoint,
+};
+use crate::compress::CompressionStats;
+use crate::limits::{LimitExceeded, MemoryBudget, RunLimits, ENTRY_OVERHEAD_BYTES};
+use crate::lts::{TransitionLabel, TransitionProvider};
+use crate::predecessor::{PathStep, PredecessorStore};
+use crate::predecessor_inmemory::InMemoryPredecessorStore;
+use crate::queue::WorkQueue;
+use crate::queue_inmemory::VecWorkQueue;
+use crate::state_codec::StateCodec;
+use crate::store::StateStore;
+use crate::store_inmemory::InMemoryStateStore;
+use crate::types::{Counterexample, CounterexampleEvent, Reason, ReasonKind, Stats, Status};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use std::io;
+use std::ops::ControlFlow;
+
+pub struct SearchBackend<'a, S> {
+    pub store: &'a mut dyn StateStore<S>,
+    pub queue: &'a mut dyn WorkQueue<S>,
+    pub predecessors: &'a mut dyn PredecessorStore<S>,
+    pub workers: usize,
+    pub por: Option<&'a mut PorStats>,
+    pub symmetry: Option<&'a mut SymmetryStats>,
+    pub limits: Option<&'a RunLimits>,
+    pub checkpoint: Option<&'a SearchCheckpoint<'a, S>>,
+    pub compression: Option<&'a mut Vec<CompressionStats>>,
+    pub max_counterexamples: usize,
+}
+
+impl<S> SearchBackend<'_, S> {
+    pub(crate) fn record_compression(&mut self, stats: &[CompressionStats]) {
+        if let Some(out) = self.compression.as_deref_mut() {
+            out.extend_from_slice(stats);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PorStats {
+    pub ample_expansions: u64,
+    pub full_expansions: u64,
+    pub skipped_transitions: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymmetryStats {
+    pub canonicalised_successors: u64,
+}
+
+// Divergence also needs tau-only ample sets and the cycle proviso.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PorProperty {
+    Deadlock,
+    Divergence,
+}
+
+#[derive(Debug, Default)]
+pub struct InMemorySearch<S>
+where
+    S: Eq + Hash,
+{
+    store: InMemoryStateStore<S>,
+    queue: VecWorkQueue<S>,
+    predecessors: InMemoryPredecessorStore<S>,
+}
+
+impl<S> InMemorySearch<S>
+where
+    S: Clone + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            store: InMemoryStateStore::new(),
+            queue: VecWorkQueue::new(),
+            predecessors: InMemoryPredecessorStore::new(),
+        }
+    }
+
+    pub fn backend(&mut self, workers: usize) -> SearchBackend<'_, S> {
+        SearchBackend {
+            store: &mut self.store,
+            queue: &mut self.queue,
+            predecessors: &mut self.predecessors,
+            workers,
+            por: None,
+            symmetry: None,
+            limits: None,
+            checkpoint: None,
+            compression: None,
+            max_counterexamples: 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SearchOutcome {
+    pub stats: Stats,
+    pub stopped: Option<LimitExceeded>,
+    // Whether stored states are orbit representatives.
+    pub symmetric: bool,
+}
+
+// Estimates for the components that do not report their own memory use.
+#[derive(Debug, Default)]
+struct MemoryEstimate {
+    store: u64,
+    queue: u64,
+    predecessors: u64,
+    spilled: bool,
+}
+
+impl MemoryEstimate {
+    fn total<S>(&self, backend: &SearchBackend<'_, S>, visitor: u64) -> u64 {
+        let store = backend.store.memory_bytes().unwrap_or(self.store);
+        let queue = backend.queue.memory_bytes().unwrap_or(self.queue);
+        let predecessors = backend
+            .predecessors
+            .memory_bytes()
+            .unwrap_or(self.predecessors);
+        store
+            .saturating_add(queue)
+            .saturating_add(predecessors)
+            .saturating_add(visitor)
+    }
+
+    fn enforce<S>(
+        &mut self,
+        budget: &MemoryBudget,
+        backend: &mut SearchBackend<'_, S>,
+        visitor: u64,
+    ) -> io::Result<Result<(), LimitExceeded>> {
+        let mut used = self.total(backend, visitor);
+        if !self.spilled && budget.should_spill(used) {
+            backend.store.spill()?;
+            backend.queue.spill()?;
+            self.spilled = true;
+            used = self.total(backend, visitor);
+        }
+        Ok(budget.update(used))
+    }
+}
+
+const DISCOVERED: u8 = 0;
+const EXPANDED: u8 = 1;
+const NO_PARENT: u64 = u64::MAX;
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+struct SearchProgress {
+    processed: u64,
+    states: u64,
+    transitions: u64,
+    depth: u64,
+    level_end: u64,
+    por: Option<PorStats>,
+    symmetry: Option<SymmetryStats>,
+}
+
+impl SearchProgress {
+    fn stats(&self, depth: Option<u64>) -> Stats {
+        Stats {
+            states: Some(self.states),
+            transitions: Some(self.transitions),
+            depth,
+        }
+    }
+}
+
+struct SearchJournal<'c, S> {
+    journal: Journal,
+    codec: &'c dyn StateCodec<S>,
+}
+
+impl<S> SearchJournal<'_, S> {
+    fn discovered(&mut self, parent: u64, label: Option<&str>, state: &S) -> io::Result<()> {
+        let mut record = vec![DISCOVERED];
+        put_u64(&mut record, parent);
+        put_label(&mut record, label);
+        record.extend_from_slice(&self.codec.encode(state));
+        self.journal.append(&record)
+    }
+
+    fn expanded(&mut self, visit: Vec<u8>) -> io::Result<()> {
+        let mut record = Vec::with_capacity(1 + visit.len());
+        record.push(EXPANDED);
+        record.extend_from_slice(&visit);
+        self.journal.append(&record)
+    }
+
+    fn commit(
+        &mut self,
+        progress: &SearchProgress,
+        backend: &SearchBackend<'_, S>,
+    ) -> io::Result<()> {
+        self.journal.commit(&SearchProgress {
+            por: backend.por.as_deref().copied(),
+            symmetry: backend.symmetry.as_deref().copied(),
+            ..*progress
+        })
+    }
+}
+
+struct Tracking<'c, S> {
+    memory: Option<MemoryEstimate>,
+    journal: Option<SearchJournal<'c, S>>,
+}
+
+impl<S> Tracking<'_, S> {
+    fn commit(
+        &mut self,
+        progress: &SearchProgress,
+        backend: &SearchBackend<'_, S>,
+    ) -> io::Result<()> {
+        match self.journal.as_mut() {
+            Some(journal) => journal.commit(progress, backend),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Parent<'s, S> {
+    state: &'s S,
+    bytes: u64,
+    index: u64,
+}
+
+fn enqueue<P>(
+    provider: &P,
+    backend: &mut SearchBackend<'_, P::State>,
+    tracking: &mut Tracking<'_, P::State>,
+    parent: &Parent<'_, P::State>,
+    transition: &P::Transition,
+    next: P::State,
+) -> io::Result<bool>
+where
+    P: TransitionProvider,
+    P::Transition: TransitionLabel,
+{
+    if !backend.store.insert(next.clone())? {
+        return Ok(false);
+    }
+    let label = transition.visible_label().map(str::to_string);
+    if let Some(estimate) = tracking.memory.as_mut() {
+        let bytes = provider.state_bytes(&next) as u64;
+        let label_bytes = label.as_ref().map_or(0, String::capacity) as u64;
+        estimate.store += bytes + ENTRY_OVERHEAD_BYTES;
+        estimate.queue += bytes;
+        estimate.predecessors += bytes + parent.bytes + label_bytes + ENTRY_OVERHEAD_BYTES;
+    }
+    if let Some(journal) = tracking.journal.as_mut() {
+        journal.discovered(parent.index, label.as_deref(), &next)?;
+    }
+    backend
+        .predecessors
+        .record(&next, parent.state, parent.index, label)?;
+    backend.queue.push(next)?;
+    Ok(true)
+}
+
+// Rebuilds the backend and visitor from the committed journal.
+fn resume_search<P, V>(
+    provider: &P,
+    backend: &mut SearchBackend<'_, P::State>,
+    memory: &mut Option<MemoryEstimate>,
+    visitor: &mut V,
+    checkpoint: &SearchCheckpoint<'_, P::State>,
+) -> io::Result<(Journal, Option<SearchProgress>)>
+where
+    P: TransitionProvider,
+    V: SearchVisitor<P::State, P::Transition>,
+{
+    let codec = checkpoint.codec;
+    let mut discovered: Vec<P::State> = Vec::new();
+    let mut expanded = 0usize;
+    let (journal, progress) = Journal::open::<SearchProgress>(&checkpoint.options, |record| {
+        let mut reader = RecordReader::new(record);
+        match reader.u8()? {
+            DISCOVERED => {
+                let parent_index = reader.u64()?;
+                let label = reader.label()?;
+                let state = decode_state(codec, reader.rest())?;
+                let bytes = provider.state_bytes(&state) as u64;
+                backend.store.insert(state.clone())?;
+                if parent_index != NO_PARENT {
+                    let parent = discovered
+                        .get(parent_index as usize)
+                        .ok_or_else(|| invalid("checkpoint record names an unknown parent"))?;
+                    if let Some(estimate) = memory.as_mut() {
+                        let label_bytes = label.as_ref().map_or(0, String::capacity) as u64;
+                        estimate.predecessors += bytes
+                            + provider.state_bytes(parent) as u64
+                            + label_bytes
+                            + ENTRY_OVERHEAD_BYTES;
+                    }
+                    backend
+                        .predecessors
+                        .record(&state, parent, parent_index, label)?;
+                }
+                if let Some(estimate) = memory.as_mut() {
+                    estimate.store += bytes + ENTRY_OVERHEAD_BYTES;
+                }
+                discovered.push(state);
+            }
+            EXPANDED => {
+                let state = discovered
+                    .get(expanded)
+                    .ok_or_else(|| invalid("checkpoint expands an undiscovered state"))?;
+                visitor.replay(codec, state, reader.rest())?;
+                expanded += 1;
+            }
+            _ => return Err(invalid("unknown checkpoint record")),
+        }
+        Ok(())
+    })?;
+    let Some(progress) = progress else {
+        return Ok((journal, None));
+    };
+    if discovered.len() as u64 != progress.states || expanded as u64 != progress.processed {
+        return Err(invalid("checkpoint journal does not match its progress"));
+    }
+    for state in discovered.drain(expanded..) {
+        if let Some(estimate) = memory.as_mut() {
+            estimate.queue += provider.state_bytes(&state) as u64;
+        }
+        backend.queue.push(state)?;
+    }
+    if let (Some(stats), Some(saved)) = (backend.por.as_deref_mut(), progress.por) {
+        *stats = saved;
+    }
+    if let (Some(stats), Some(saved)) = (backend.symmetry.as_deref_mut(), progress.symmetry) {
+        *stats = saved;
+    }
+    Ok((journal, Some(progress)))
+}
+
+// A visitor that accumulates state describes each visit in `journal` so a
+// resumed search can rebuild it through `replay`.
+pub(crate) trait SearchVisitor<S, T> {
+    fn visit(&mut self, state: &S, next: &[(T, S)]) -> ControlFlow<()>;
+
+    fn journal(&self, _codec: &dyn StateCodec<S>, _state: &S, _next: &[(T, S)]) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn replay(&mut self, _codec: &dyn StateCodec<S>, _state: &S, _record: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Bytes the visitor holds, counted against the memory budget.
+    fn memory_bytes(&self) -> u64 {
+        0
+    }
+}
+
+impl<S, T, F> SearchVisitor<S, T> for F
+where
+    F: FnMut(&S, &[(T, S)]) -> ControlFlow<()>,
+{
+    fn visit(&mut self, state: &S, next: &[(T, S)]) -> ControlFlow<()> {
+        self(state, next)
+    }
+}
+
+// Collects violating states in BFS order, stopping at the `max`-th unless
+// `exhaustive`.
+pub(crate) struct Violations<S, F> {
+    pub found: Vec<(S, Option<String>)>,
+    max: usize,
+    stop: bool,
+    detect: F,
+}
+
+impl<S, F> Violations<S, F> {
+    pub fn new(max: usize, detect: F) -> Self {
+        Self {
+            found: Vec::new(),
+            max: max.max(1),
+            stop: true,
+            detect,
+        }
+    }
+
+    pub fn exhaustive(max: usize, detect: F) -> Self {
+        Self {
+            stop: false,
+            ..Self::new(max, detect)
+        }
+    }
+}
+
+impl<S, T, F> SearchVisitor<S, T> for Violations<S, F>
+where
+    S: Clone + PartialEq,
+    F: FnMut(&S, &[(T, S)]) -> Option<Option<String>>,
+{
+    fn visit(&mut self, state: &S, next: &[(T, S)]) -> ControlFlow<()> {
+        if self.found.len() >= self.max {
+            return ControlFlow::Continue(());
+        }
+        let Some(label) = (self.detect)(state, next) else {
+            return ControlFlow::Continue(());
+        };
+        self.found.push((state.clone(), label));
+        if self.stop && self.found.len() >= self.max {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn journal(&self, _codec: &dyn StateCodec<S>, state: &S, _next: &[(T, S)]) -> Vec<u8> {
+        let mut record = Vec::new();
+        if let Some((_, label)) = self.found.last().filter(|(found, _)| found == state) {
+            put_label(&mut record, label.as_deref());
+        }
+        record
+    }
+
+    fn replay(&mut self, _codec: &dyn StateCodec<S>, state: &S, record: &[u8]) -> io::Result<()> {
+        if record.is_empty() {
+            return Ok(());
+        }
+        let label = RecordReader::new(record).label()?;
+        self.found.push((state.clone(), label));
+        Ok(())
+    }
+}
+
+// Keeps the frontier of a disk-backed queue out of memory.
+const PARALLEL_BATCH: usize = 16 * 1024;
+
+// `visit` and store insertion run in queue order even with `workers > 1`, so
+// the verdict, counterexample and stats match the serial run. For divergence
+// an ample set reaching a stored state is widened to all transitions (the BFS
+// cycle proviso).
+pub(crate) fn bfs_search<P, V>(
+    provider: &P,
+    backend: &mut SearchBackend<'_, P::State>,
+    property: Option<PorProperty>,
+    visitor: &mut V,
+) -> io::Result<SearchOutcome>
+where
+    P: TransitionProvider + Sync,
+    P::Transition: TransitionLabel,
+    V: SearchVisitor<P::State, P::Transition>,
+{
+    let symmetric = property.is_some() && backend.symmetry.is_some();
+    let canonicalise = |moves: Vec<(P::Transition, P::State)>| {
+        if !symmetric {
+            return (moves, 0);
+        }
+        let mut replaced = 0u64;
+        let moves = moves
+            .into_iter()
+            .map(|(transition, next)| match provider.canonical_state(&next) {
+                Some(canonical) => {
+                    replaced += 1;
+                    (transition, canonical)
+                }
+                None => (transition, next),
+            })
+            .collect::<Vec<_>>();
+        (moves, replaced)
+    };
+
+    let limits = backend.limits;
+    let budget = limits.and_then(|limits| limits.memory.as_ref());
+    let mut tracking = Tracking {
+        memory: budget.map(|_| MemoryEstimate::default()),
+        journal: None,
+    };
+    let mut progress = SearchProgress::default();
+    let mut resumed = None;
+    if let Some(checkpoint) = backend.checkpoint {
+        let (journal, committed) =
+            resume_search(provider, backend, &mut tracking.memory, visitor, checkpoint)?;
+        tracking.journal = Some(SearchJournal {
+            journal,
+            codec: checkpoint.codec,
+        });
+        resumed = committed;
+    }
+    match resumed {
+        Some(committed) => progress = committed,
+        None => {
+            let mut initial = provider.initial_state();
+            if symmetric {
+                initial = provider.canonical_state(&initial).unwrap_or(initial);
+            }
+            if let Some(estimate) = tracking.memory.as_mut() {
+                let bytes = provider.state_bytes(&initial) as u64;
+                estimate.store += bytes + ENTRY_OVERHEAD_BYTES;
+                estimate.queue += bytes;
+            }
+            if backend.store.insert(initial.clone())? {
+                if let Some(journal) = tracking.journal.as_mut() {
+                    journal.discovered(NO_PARENT, None, &initial)?;
+                }
+                backend.queue.push(initial)?;
+                progress.states += 1;
+            }
+            // The queue is FIFO, so a level ends once every state inserted
+            // before it started has been taken off the queue.
+            progress.level_end = progress.states;
+        }
+    }
+
+    let pool = if backend.workers > 1 {
+        Some(
+            ThreadPoolBuilder::new()
+                .num_threads(backend.workers)
+                .build()
+                .map_err(|err| io::Error::other(err.to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    let property = property.filter(|_| backend.por.is_some());
+    loop {
+        let limit = if pool.is_some() { PARALLEL_BATCH } else { 1 };
+        let mut batch = Vec::new();
+        while batch.len() < limit {
+            let Some(state) = backend.queue.pop()? else {
+                break;
+            };
+            batch.push(state);
+        }
+        if batch.is_empty() {
+            break;
+        }
+
+        let expand = |state: &P::State| {
+            if limits.is_some_and(|limits| limits.check_deadline().is_err()) {
+                return ((Vec::new(), 0), None);
+            }
+            let next = canonicalise(provider.transitions(state));
+            let ample = property
+                .and_then(|property| {
+                    provider.ample_transitions(state, property == PorProperty::Divergence)
+                })
+                .filter(|ample| !ample.is_empty() && ample.len() < next.0.len())
+                .map(canonicalise);
+            (next, ample)
+        };
+        let expanded = match &pool {
+            Some(pool) => pool.install(|| batch.par_iter().map(expand).collect::<Vec<_>>()),
+            None => batch.iter().map(expand).collect::<Vec<_>>(),
+        };
+
+        for (state, ((full, full_replaced), ample)) in batch.into_iter().zip(expanded) {
+            let (depth, level_end) = if progress.processed == progress.level_end {
+                (progress.depth + 1, progress.states)
+            } else {
+                (progress.depth, progress.level_end)
+            };
+            if let Some(Err(limit)) = limits.map(RunLimits::check_deadline) {
+                // The checkpoint still has `state` waiting in the frontier.
+                tracking.commit(&progress, backend)?;
+                return Ok(SearchOutcome {
+                    stats: progress.stats(Some(depth)),
+                    stopped: Some(limit),
+                    symmetric,
+                });
+            }
+            progress.depth = depth;
+            progress.level_end = level_end;
+            progress.processed += 1;
+            let state_bytes = match tracking.memory.as_mut() {
+                Some(estimate) => {
+                    let bytes = provider.state_bytes(&state) as u64;
+                    estimate.queue = estimate.queue.saturating_sub(bytes);
+                    bytes
+                }
+                None => 0,
+            };
+            let parent = Parent {
+                state: &state,
+                bytes: state_bytes,
+                index: progress.processed - 1,
+            };
+            let (next, replaced) = match ample {
+                Some((ample, ample_replaced)) => {
+                    let mut revisits = false;
+                    for (transition, next_state) in &ample {
+                        if enqueue(
+                            provider,
+                            backend,
+                            &mut tracking,
+                            &parent,
+                            transition,
+                            next_state.clone(),
+                        )? {
+                            progress.states += 1;
+                        } else {
+                            revisits = true;
+                        }
+                    }
+                    let stats = backend.por.as_deref_mut().expect("por stats");
+                    if revisits && property == Some(PorProperty::Divergence) {
+                        stats.full_expansions += 1;
+                        (full, full_replaced)
+                    } else {
+                        stats.ample_expansions += 1;
+                        stats.skipped_transitions += (full.len() - ample.len()) as u64;
+                        (ample, ample_replaced)
+                    }
+                }
+                None => {
+                    if let Some(stats) = backend.por.as_deref_mut().filter(|_| property.is_some()) {
+                        stats.full_expansions += 1;
+                    }
+                    (full, full_replaced)
+                }
+            };
+            if let Some(stats) = backend.symmetry.as_deref_mut() {
+                stats.canonicalised_successors += replaced;
+            }
+            progress.transitions = progress.transitions.saturating_add(next.len() as u64);
+            if visitor.visit(&state, &next).is_break() {
+                return Ok(SearchOutcome {
+                    stats: progress.stats(None),
+                    stopped: None,
+                    symmetric,
+                });
+            }
+            if let Some(journal) = tracking.journal.as_mut() {
+                let record = visitor.journal(journal.codec, &state, &next);
+                journal.expanded(record)?;
+            }
+            for (transition, next_state) in next {
+                if enqueue(
+                    provider,
+                    backend,
+                    &mut tracking,
+                    &parent,
+                    &transition,
+                    next_state,
+                )? {
+                    progress.states += 1;
+                }
+            }
+            if let (Some(budget), Some(estimate)) = (budget, tracking.memory.as_mut()) {
+                if let Err(limit) = estimate.enforce(budget, backend, visitor.memory_bytes())? {
+                    tracking.commit(&progress, backend)?;
+                    return Ok(SearchOutcome {
+                        stats: progress.stats(Some(progress.depth)),
+                        stopped: Some(limit),
+                        symmetric,
+                    });
+                }
+            }
+            if tracking
+                .journal
+                .as_mut()
+                .is_some_and(|journal| journal.journal.tick())
+            {
+                tracking.commit(&progress, backend)?;
+            }
+        }
+    }
+
+    Ok(SearchOutcome {
+        stats: progress.stats(None),
+        stopped: None,
+        symmetric,
+    })
+}
+
+// The path to `state` replayed from the concrete initial state, since under
+// symmetry reduction the stored states are only representatives.
+pub(crate) fn counterexample_path<P>(
+    provider: &P,
+    backend: &SearchBackend<'_, P::State>,
+    state: P::State,
+    symmetric: bool,
+) -> io::Result<(Vec<CounterexampleEvent>, P::State)>
+where
+    P: TransitionProvider,
+    P::State: PartialEq,
+    P::Transition: TransitionLabel,
+{
+    let steps = backend.predecessors.path(&state)?;
+    replay_steps(provider, backend, steps, symmetric)
+}
+
+// As `counterexample_path`, for the state with discovery index `index`.
+pub(crate) fn counterexample_path_to<P>(
+    provider: &P,
+    backend: &SearchBackend<'_, P::State>,
+    index: u64,
+    symmetric: bool,
+) -> io::Result<(Vec<CounterexampleEvent>, P::State)>
+where
+    P: TransitionProvider,
+    P::Transition: TransitionLabel,
+{
+    let steps = backend.predecessors.path_to(index)?;
+    replay_steps(provider, backend, steps, symmetric)
+}
+
+fn replay_steps<P>(
+    provider: &P,
+    backend: &SearchBackend<'_, P::State>,
+    steps: Vec<PathStep<P::State>>,
+    symmetric: bool,
+) -> io::Result<(Vec<CounterexampleEvent>, P::State)>
+where
+    P: TransitionProvider,
+    P::Transition: TransitionLabel,
+{
+    let mut concrete = provider.initial_state();
+    let mut events = Vec::new();
+    for step in steps {
+        let reaches = |next: &P::State| backend.predecessors.reaches(&step, next);
+        let (transition, next) = concrete_step(provider, &concrete, symmetric, reaches, |t| {
+            if symmetric {
+                t.visible_label().is_some() == step.label.is_some()
+            } else {
+                t.visible_label() == step.label.as_deref()
+            }
+        })?;
+        events.push(step_event(provider, &concrete, &transition, &next));
+        concrete = next;
+    }
+    Ok((events, concrete))
+}
+
+// A transition from `from` accepted by `matches` whose target satisfies
+// `is_target`, or under symmetry reduction has a representative that does.
+pub(crate) fn concrete_step<P>(
+    provider: &P,
+    from: &P::State,
+    symmetric: bool,
+    is_target: impl Fn(&P::State) -> bool,
+    matches: impl Fn(&P::Transition) -> bool,
+) -> io::Result<(P::Transition, P::State)>
+where
+    P: TransitionProvider,
+{
+    provider
+        .transitions(from)
+        .into_iter()
+        .find(|(transition, next)| {
+            let canonical = symmetric.then(|| provider.canonical_state(next)).flatten();
+            matches(transition) && is_target(canonical.as_ref().unwrap_or(next))
+        })
+        .ok_or_else(|| io::Error::other("counterexample step has no concrete transition"))
+}
+
+pub(crate) fn step_event<P>(
+    provider: &P,
+    from: &P::State,
+    transition: &P::Transition,
+    to: &P::State,
+) -> CounterexampleEvent
+where
+    P: TransitionProvider,
+    P::Transition: TransitionLabel,
+{
+    CounterexampleEvent {
+        label: transition.label().to_string(),
+        hidden_label: transition
+            .is_tau()
+            .then(|| provider.hidden_label(from, to))
+            .flatten(),
+        spans: provider.event_spans(from, transition, to),
+    }
+}
+
+pub(crate) fn search_error_result(request: &CheckRequest, err: io::Error) -> CheckResult {
+    CheckResult {
+        name: "check".to_string(),
+        model: None,
+        target: request.target.clone(),
+        status: Status::Error,
+        reason: Some(Reason {
+            kind: ReasonKind::InternalError,
+            message: Some(format!("state store error: {err}")),
+        }),
+        counterexample: None,
+        counterexamples: Vec::new(),
+        stats: Some(Stats {
+            states: None,
+            transitions: None,
+            depth: None,
+        }),
+        replay: None,
+        simulation: None,
+    }
+}
+
+pub(crate) fn stopped_result(
+    request: &CheckRequest,
+    limit: LimitExceeded,
+    stats: Stats,
+) -> CheckResult {
+    CheckResult {
+        name: "check".to_string(),
+        model: None,
+        target: request.target.clone(),
+        status: limit.status(),
+        reason: Some(limit.reason()),
+        counterexample: None,
+        counterexamples: Vec::new(),
+        stats: Some(stats),
+        replay: None,
+        simulation: None,
+    }
+}
+
+pub(crate) fn failed_result(
+    request: &CheckRequest,
+    mut counterexamples: Vec<Counterexample>,
+    max_counterexamples: usize,
+    stats: Stats,
+) -> CheckResult {
+    let counterexample = counterexamples.first().cloned();
+    if max_counterexamples <= 1 {
+        counterexamples.clear();
+    }
+    CheckResult {
+        name: "check".to_string(),
+        model: None,
+        target: request.target.clone(),
+        status: Status::Fail,
+        reason: None,
+        counterexample,
+        counterexamples,
+        stats: Some(stats),
+        replay: None,
+        simulation: None,
+    }
+}
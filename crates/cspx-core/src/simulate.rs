@@ -0,0 +1,437 @@
+use crate::assertion_select::module_for_property_check;
+use crate::check::{CheckRequest, CheckResult, RefinementModel};
+use crate::check_determinism::{first_nondeterministic_label, label_spans};
+use crate::check_refine::{check_refinement_trace, RefinementInput, TraceCheck};
+use crate::counterexample_span::{module_counterexample_spans, refinement_counterexample_spans};
+use crate::explain::Explainer;
+use crate::explain_simple::BasicExplainer;
+use crate::ir::{Module, PropertyKind};
+use crate::limits::{LimitExceeded, RunLimits};
+use crate::lts::{TransitionLabel, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::CspmTransitionProvider;
+use crate::search::step_event;
+use crate::store_bitstate::splitmix64;
+use crate::types::{
+    Counterexample, CounterexampleEvent, CounterexampleType, Lasso, Minimality, Reason, ReasonKind,
+    Simulation, SourceSpan, Stats, Status,
+};
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulateOptions {
+    pub runs: usize,
+    // Transitions per run, tau steps included.
+    pub depth: usize,
+    pub seed: u64,
+}
+
+impl Default for SimulateOptions {
+    fn default() -> Self {
+        Self {
+            runs: 100,
+            depth: 100,
+            seed: 0,
+        }
+    }
+}
+
+// Seeded random walks from the initial state. Each run picks a transition
+// uniformly at random until it reaches `depth` steps or a state without
+// transitions, and the check fails on the first violation a run meets. The
+// walks only depend on the seed and the order of `transitions`, so the same
+// seed gives the same result. A pass is not a proof.
+#[derive(Debug, Default)]
+pub struct Simulator {
+    pub options: SimulateOptions,
+}
+
+impl Simulator {
+    pub fn check_property(
+        &self,
+        kind: PropertyKind,
+        request: &CheckRequest,
+        input: &Module,
+        limits: &RunLimits,
+    ) -> CheckResult {
+        let module = module_for_property_check(input, kind);
+        match CspmTransitionProvider::from_module(&module) {
+            Ok(provider) => simulate_property(
+                kind,
+                &provider,
+                request,
+                module_counterexample_spans(&module),
+                &self.options,
+                limits,
+            ),
+            Err(err) => invalid_input_result(request, err.to_string()),
+        }
+    }
+
+    pub fn check_property_provider<P>(
+        &self,
+        kind: PropertyKind,
+        request: &CheckRequest,
+        provider: &P,
+        limits: &RunLimits,
+    ) -> CheckResult
+    where
+        P: TransitionProvider + Sync,
+        P::State: Ord + Hash,
+        P::Transition: TransitionLabel,
+    {
+        simulate_property(kind, provider, request, Vec::new(), &self.options, limits)
+    }
+
+    // Walks the impl; the spec is followed along each visible trace and
+    // checked like a node of the refinement search.
+    pub fn check_refinement(
+        &self,
+        request: &CheckRequest,
+        input: &RefinementInput,
+        limits: &RunLimits,
+    ) -> CheckResult {
+        let providers = CspmTransitionProvider::from_module(&input.spec).and_then(|spec| {
+            CspmTransitionProvider::from_module(&input.impl_).map(|impl_| (spec, impl_))
+        });
+        match providers {
+            Ok((spec, impl_)) => simulate_refinement(
+                &spec,
+                &impl_,
+                request,
+                refinement_counterexample_spans(&input.spec, &input.impl_),
+                &self.options,
+                limits,
+            ),
+            Err(err) => invalid_input_result(request, err.to_string()),
+        }
+    }
+}
+
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        (splitmix64(self.0) % n as u64) as usize
+    }
+}
+
+struct Coverage<S> {
+    states: HashSet<S>,
+    events: BTreeSet<String>,
+    runs: u64,
+    steps: u64,
+    depth: u64,
+}
+
+impl<S: Eq + Hash> Coverage<S> {
+    fn new() -> Self {
+        Self {
+            states: HashSet::new(),
+            events: BTreeSet::new(),
+            runs: 0,
+            steps: 0,
+            depth: 0,
+        }
+    }
+
+    fn step(&mut self, event: &CounterexampleEvent, next: S, depth: usize) {
+        if event.label != TAU_LABEL {
+            self.events.insert(event.label.clone());
+        }
+        self.states.insert(next);
+        self.steps += 1;
+        self.depth = self.depth.max(depth as u64);
+    }
+}
+
+fn simulate_property<P>(
+    kind: PropertyKind,
+    provider: &P,
+    request: &CheckRequest,
+    source_spans: Vec<SourceSpan>,
+    options: &SimulateOptions,
+    limits: &RunLimits,
+) -> CheckResult
+where
+    P: TransitionProvider + Sync,
+    P::State: Ord + Hash,
+    P::Transition: TransitionLabel,
+{
+    let mut rng = Rng(options.seed);
+    let mut coverage = Coverage::new();
+    for _ in 0..options.runs {
+        coverage.runs += 1;
+        let mut state = provider.initial_state();
+        coverage.states.insert(state.clone());
+        let mut path = Vec::<CounterexampleEvent>::new();
+        // The states since the last visible event with the path length at
+        // each; a tau step back to one of them closes a divergence.
+        let mut tau_run = vec![(state.clone(), 0)];
+        loop {
+            if let Err(limit) = limits.check_deadline() {
+                return stopped_result(request, None, limit, coverage);
+            }
+            let transitions = provider.transitions(&state);
+            let violation = match kind {
+                PropertyKind::DeadlockFree => transitions.is_empty().then(|| Counterexample {
+                    deadlock_explanation: provider.deadlock_explanation(&state),
+                    ..counterexample(visible(&path), vec!["deadlock".to_string()], &source_spans)
+                }),
+                PropertyKind::Deterministic => {
+                    first_nondeterministic_label(provider, &state).map(|label| {
+                        let mut events = visible(&path);
+                        events.push(CounterexampleEvent {
+                            label: label.clone(),
+                            hidden_label: None,
+                            spans: label_spans(provider, &state, &label),
+                        });
+                        let tags = vec!["nondeterminism".to_string(), format!("label:{label}")];
+                        counterexample(events, tags, &source_spans)
+                    })
+                }
+                PropertyKind::DivergenceFree => None,
+            };
+            if let Some(counterexample) = violation {
+                return failed_result(request, None, counterexample, coverage);
+            }
+            if path.len() >= options.depth || transitions.is_empty() {
+                break;
+            }
+
+            let (transition, next) = transitions[rng.below(transitions.len())].clone();
+            let event = step_event(provider, &state, &transition, &next);
+            coverage.step(&event, next.clone(), path.len() + 1);
+            path.push(event);
+            if !transition.is_tau() {
+                tau_run = vec![(next.clone(), path.len())];
+            } else if let Some(&(_, start)) = tau_run.iter().find(|(seen, _)| *seen == next) {
+                if kind == PropertyKind::DivergenceFree {
+                    let counterexample = divergence_counterexample(&path, start, &source_spans);
+                    return failed_result(request, None, counterexample, coverage);
+                }
+            } else {
+                tau_run.push((next.clone(), path.len()));
+            }
+            state = next;
+        }
+    }
+    passed_result(request, None, options, coverage)
+}
+
+fn simulate_refinement<S, I>(
+    spec: &S,
+    impl_: &I,
+    request: &CheckRequest,
+    source_spans: Vec<SourceSpan>,
+    options: &SimulateOptions,
+    limits: &RunLimits,
+) -> CheckResult
+where
+    S: TransitionProvider + Sync,
+    S::State: Ord + Hash,
+    S::Transition: TransitionLabel,
+    I: TransitionProvider + Sync,
+    I::State: Ord + Hash,
+    I::Transition: TransitionLabel,
+{
+    let model = request.model.clone().unwrap_or(RefinementModel::T);
+    let mut rng = Rng(options.seed);
+    let mut coverage = Coverage::new();
+    for _ in 0..options.runs {
+        coverage.runs += 1;
+        let mut state = impl_.initial_state();
+        coverage.states.insert(state.clone());
+        let mut trace = Vec::<String>::new();
+        let mut steps = 0;
+        let mut check_trace = true;
+        loop {
+            if let Err(limit) = limits.check_deadline() {
+                return stopped_result(request, Some(&model), limit, coverage);
+            }
+            if check_trace {
+                match check_refinement_trace(&model, spec, impl_, &trace, source_spans.clone()) {
+                    TraceCheck::Continue => {}
+                    TraceCheck::Prune => break,
+                    TraceCheck::Fail(counterexample) => {
+                        return failed_result(request, Some(&model), *counterexample, coverage)
+                    }
+                }
+            }
+            let transitions = impl_.transitions(&state);
+            if steps >= options.depth || transitions.is_empty() {
+                break;
+            }
+
+            let (transition, next) = transitions[rng.below(transitions.len())].clone();
+            let event = step_event(impl_, &state, &transition, &next);
+            steps += 1;
+            coverage.step(&event, next.clone(), steps);
+            // The failures after a trace cover every state it leads to, so
+            // tau steps need no new check.
+            check_trace = !transition.is_tau();
+            if check_trace {
+                trace.push(event.label);
+            }
+            state = next;
+        }
+    }
+    passed_result(request, Some(&model), options, coverage)
+}
+
+fn visible(path: &[CounterexampleEvent]) -> Vec<CounterexampleEvent> {
+    path.iter()
+        .filter(|event| event.label != TAU_LABEL)
+        .cloned()
+        .collect()
+}
+
+// A walk takes no shortest route to a violation.
+fn counterexample(
+    events: Vec<CounterexampleEvent>,
+    tags: Vec<String>,
+    source_spans: &[SourceSpan],
+) -> Counterexample {
+    Counterexample {
+        kind: CounterexampleType::Trace,
+        events,
+        minimality: Minimality::None,
+        tags,
+        source_spans: source_spans.to_vec(),
+        lasso: None,
+        failure: None,
+        deadlock_explanation: None,
+        conformance: None,
+        explanation: None,
+    }
+}
+
+// The walk went round a tau cycle starting after `start` steps of `path`.
+fn divergence_counterexample(
+    path: &[CounterexampleEvent],
+    start: usize,
+    source_spans: &[SourceSpan],
+) -> Counterexample {
+    let (stem, cycle) = path.split_at(start);
+    let mut events = visible(stem);
+    events.push(CounterexampleEvent {
+        label: TAU_LABEL.to_string(),
+        hidden_label: cycle[0].hidden_label.clone(),
+        spans: cycle[0].spans.clone(),
+    });
+    Counterexample {
+        kind: CounterexampleType::Lasso,
+        lasso: Some(Lasso {
+            stem: stem.to_vec(),
+            cycle: cycle.to_vec(),
+        }),
+        ..counterexample(events, vec!["divergence".to_string()], source_spans)
+    }
+}
+
+fn simulation_result<S>(
+    request: &CheckRequest,
+    model: Option<&RefinementModel>,
+    status: Status,
+    reason: Option<Reason>,
+    counterexample: Option<Counterexample>,
+    coverage: Coverage<S>,
+) -> CheckResult {
+    CheckResult {
+        name: "simulate".to_string(),
+        model: model.map(|model| model.as_str().to_string()),
+        target: request.target.clone(),
+        status,
+        reason,
+        counterexample,
+        counterexamples: Vec::new(),
+        stats: Some(Stats {
+            states: Some(coverage.states.len() as u64),
+            transitions: Some(coverage.steps),
+            depth: Some(coverage.depth),
+        }),
+        replay: None,
+        simulation: Some(Simulation {
+            runs: coverage.runs,
+            events_seen: coverage.events.into_iter().collect(),
+        }),
+    }
+}
+
+fn failed_result<S>(
+    request: &CheckRequest,
+    model: Option<&RefinementModel>,
+    counterexample: Counterexample,
+    coverage: Coverage<S>,
+) -> CheckResult {
+    let explainer = BasicExplainer;
+    simulation_result(
+        request,
+        model,
+        Status::Fail,
+        None,
+        Some(explainer.explain(counterexample)),
+        coverage,
+    )
+}
+
+fn stopped_result<S>(
+    request: &CheckRequest,
+    model: Option<&RefinementModel>,
+    limit: LimitExceeded,
+    coverage: Coverage<S>,
+) -> CheckResult {
+    simulation_result(
+        request,
+        model,
+        limit.status(),
+        Some(limit.reason()),
+        None,
+        coverage,
+    )
+}
+
+fn passed_result<S>(
+    request: &CheckRequest,
+    model: Option<&RefinementModel>,
+    options: &SimulateOptions,
+    coverage: Coverage<S>,
+) -> CheckResult {
+    let reason = Reason {
+        kind: ReasonKind::ApproximateSearch,
+        message: Some(format!(
+            "random simulation found no violation in {} runs of depth {} ({} distinct states)",
+            coverage.runs,
+            options.depth,
+            coverage.states.len()
+        )),
+    };
+    simulation_result(request, model, Status::Pass, Some(reason), None, coverage)
+}
+
+fn invalid_input_result(request: &CheckRequest, message: String) -> CheckResult {
+    CheckResult {
+        name: "simulate".to_string(),
+        model: request
+            .model
+            .as_ref()
+            .map(RefinementModel::as_str)
+            .map(|s| s.to_string()),
+        target: request.target.clone(),
+        status: Status::Error,
+        reason: Some(Reason {
+            kind: ReasonKind::InvalidInput,
+            message: Some(message),
+        }),
+        counterexample: None,
+        counterexamples: Vec::new(),
+        stats: Some(Stats {
+            states: None,
+            transitions: None,
+            depth: None,
+        }),
+        replay: None,
+        simulation: None,
+    }
+}
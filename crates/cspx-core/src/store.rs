@@ -1,7 +1,84 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 pub trait StateStore<S> {
     fn insert(&mut self, state: S) -> std::io::Result<bool>;
     fn len(&self) -> usize;
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Estimated probability that a new state was reported as visited; `None`
+    /// when the store is exact.
+    fn omission_probability(&self) -> Option<f64> {
+        None
+    }
+
+    /// Estimated bytes held in memory; `None` lets the search estimate it.
+    fn memory_bytes(&self) -> Option<u64> {
+        None
+    }
+
+    /// Moves states to disk when the store can, near the memory budget.
+    fn spill(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub trait ConcurrentStateStore<S>: Sync {
+    fn insert(&self, state: S) -> std::io::Result<bool>;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts within a shard are serialised, so inserting each shard's states
+    /// in a fixed order gives the same result under any schedule.
+    fn shard_count(&self) -> usize {
+        1
+    }
+
+    fn shard_of(&self, _state: &S) -> usize {
+        0
+    }
+}
+
+pub(crate) struct LockedStateStore<'a, T> {
+    inner: std::sync::Mutex<&'a mut T>,
+}
+
+impl<'a, T> LockedStateStore<'a, T> {
+    pub(crate) fn new(inner: &'a mut T) -> Self {
+        Self {
+            inner: std::sync::Mutex::new(inner),
+        }
+    }
+}
+
+impl<S, T> ConcurrentStateStore<S> for LockedStateStore<'_, T>
+where
+    T: StateStore<S> + Send,
+{
+    fn insert(&self, state: S) -> std::io::Result<bool> {
+        lock_shard(&self.inner)?.insert(state)
+    }
+
+    fn len(&self) -> usize {
+        lock_shard(&self.inner).map_or(0, |inner| inner.len())
+    }
+}
+
+pub(crate) fn lock_shard<T>(
+    shard: &std::sync::Mutex<T>,
+) -> std::io::Result<std::sync::MutexGuard<'_, T>> {
+    shard
+        .lock()
+        .map_err(|_| std::io::Error::other("state store lock poisoned"))
+}
+
+// `DefaultHasher::new` uses fixed keys, so fingerprints are stable for a
+// given build and runs stay reproducible.
+pub(crate) fn fingerprint<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
 }
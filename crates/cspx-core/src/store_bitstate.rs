@@ -0,0 +1,142 @@
+use crate::limits::RunLimits;
+use crate::store::{fingerprint, StateStore};
+use std::hash::Hash;
+use std::io;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitstateStoreOptions {
+    pub log2_bits: u32,
+    pub hashes: u32,
+}
+
+impl Default for BitstateStoreOptions {
+    fn default() -> Self {
+        Self {
+            log2_bits: 27,
+            hashes: 3,
+        }
+    }
+}
+
+// A Bloom filter over state fingerprints: a state is new unless all of its
+// `hashes` bits are already set, which may also happen for a new state.
+#[derive(Debug)]
+pub struct BitstateStore<S>
+where
+    S: Hash,
+{
+    bits: Vec<u64>,
+    mask: u64,
+    hashes: u32,
+    ones: u64,
+    inserted: usize,
+    log_no_omission: f64,
+    _marker: PhantomData<S>,
+}
+
+impl<S> BitstateStore<S>
+where
+    S: Hash,
+{
+    pub fn new(options: BitstateStoreOptions) -> io::Result<Self> {
+        Self::new_with_limits(options, &RunLimits::unlimited())
+    }
+
+    // The bit array is allocated up front, so it must fit the memory budget
+    // before the search ever gets to enforce it.
+    pub fn new_with_limits(options: BitstateStoreOptions, limits: &RunLimits) -> io::Result<Self> {
+        if !(6..=40).contains(&options.log2_bits) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "log2_bits must be in 6..=40",
+            ));
+        }
+        if options.hashes == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "hashes must be >= 1",
+            ));
+        }
+        let bytes = 1u64 << (options.log2_bits - 3);
+        if let Some(budget) = limits.memory.as_ref() {
+            if bytes > budget.limit_bytes() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "bit array of {bytes} bytes (log2_bits {}) exceeds the memory budget of {} bytes",
+                        options.log2_bits,
+                        budget.limit_bytes()
+                    ),
+                ));
+            }
+        }
+        Ok(Self {
+            bits: vec![0; 1 << (options.log2_bits - 6)],
+            mask: (1 << options.log2_bits) - 1,
+            hashes: options.hashes,
+            ones: 0,
+            inserted: 0,
+            log_no_omission: 0.0,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn fill_ratio(&self) -> f64 {
+        self.ones as f64 / (self.mask + 1) as f64
+    }
+
+    // Double hashing: the i-th bit is h1 + i * h2 with h2 odd.
+    fn positions(&self, state: &S) -> impl Iterator<Item = u64> + '_ {
+        let h1 = fingerprint(state);
+        let h2 = splitmix64(h1) | 1;
+        (0..self.hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) & self.mask)
+    }
+}
+
+impl<S> StateStore<S> for BitstateStore<S>
+where
+    S: Hash,
+{
+    fn insert(&mut self, state: S) -> io::Result<bool> {
+        let positions = self.positions(&state).collect::<Vec<_>>();
+        let seen = positions
+            .iter()
+            .all(|&bit| self.bits[(bit >> 6) as usize] & (1 << (bit & 63)) != 0);
+        if seen {
+            return Ok(false);
+        }
+        // A new state is lost exactly when all of its bits are already set,
+        // which the fill ratio before the insert estimates.
+        let collision = self.fill_ratio().powi(self.hashes as i32);
+        self.log_no_omission += (-collision).ln_1p();
+        for bit in positions {
+            let word = &mut self.bits[(bit >> 6) as usize];
+            if *word & (1 << (bit & 63)) == 0 {
+                *word |= 1 << (bit & 63);
+                self.ones += 1;
+            }
+        }
+        self.inserted += 1;
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.inserted
+    }
+
+    fn omission_probability(&self) -> Option<f64> {
+        Some(-self.log_no_omission.exp_m1())
+    }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        Some(std::mem::size_of_val(self.bits.as_slice()) as u64)
+    }
+}
+
+pub(crate) fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
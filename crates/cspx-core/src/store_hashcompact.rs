@@ -0,0 +1,52 @@
+use crate::store::{fingerprint, StateStore};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+// Keeps a 64-bit fingerprint per state, so two states with the same
+// fingerprint are treated as one and the second is never explored.
+#[derive(Debug, Default)]
+pub struct HashCompactStateStore<S>
+where
+    S: Hash,
+{
+    fingerprints: HashSet<u64>,
+    _marker: PhantomData<S>,
+}
+
+impl<S> HashCompactStateStore<S>
+where
+    S: Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            fingerprints: HashSet::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> StateStore<S> for HashCompactStateStore<S>
+where
+    S: Hash,
+{
+    fn insert(&mut self, state: S) -> std::io::Result<bool> {
+        Ok(self.fingerprints.insert(fingerprint(&state)))
+    }
+
+    fn len(&self) -> usize {
+        self.fingerprints.len()
+    }
+
+    // Birthday bound for `n` uniformly distributed 64-bit fingerprints.
+    fn omission_probability(&self) -> Option<f64> {
+        let n = self.fingerprints.len() as f64;
+        let pairs = n * (n - 1.0).max(0.0) / 2.0;
+        Some(-(-pairs / 2f64.powi(64)).exp_m1())
+    }
+
+    // A fingerprint slot plus the table's load-factor slack.
+    fn memory_bytes(&self) -> Option<u64> {
+        Some(self.fingerprints.capacity() as u64 * 2 * std::mem::size_of::<u64>() as u64)
+    }
+}
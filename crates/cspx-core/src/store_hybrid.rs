@@ -21,6 +21,9 @@ impl Default for HybridStateStoreOptions {
     }
 }
 
+// Keeps states in memory until `spill_threshold` is exceeded or `spill` is
+// called, then moves them into a `DiskStateStore` and inserts there from then
+// on, leaving only the disk index in memory.
 #[derive(Debug)]
 pub struct HybridStateStore<S, C>
 where
@@ -81,21 +84,24 @@ where
         &self.spill_path
     }
 
-    fn activate_spill_if_needed(&mut self) -> io::Result<()> {
-        if self.spill_store.is_some() || self.in_memory.len() <= self.options.spill_threshold {
-            return Ok(());
-        }
-
-        let mut spill = DiskStateStore::open_with_options(
-            &self.spill_path,
-            self.codec.clone(),
-            self.options.disk_options,
-        )?;
-        for state in &self.in_memory {
-            let _ = spill.insert(state.clone())?;
+    // The in-memory states are only released once all of them are on disk,
+    // so a failed spill leaves the store as it was.
+    fn activate_spill(&mut self) -> io::Result<&mut DiskStateStore<S, C>> {
+        if self.spill_store.is_none() {
+            let mut spill = DiskStateStore::open_with_options(
+                &self.spill_path,
+                self.codec.clone(),
+                self.options.disk_options,
+            )?;
+            for state in &self.in_memory {
+                let _ = spill.insert(state.clone())?;
+            }
+            self.in_memory = HashSet::new();
+            self.spill_store = Some(spill);
         }
-        self.spill_store = Some(spill);
-        Ok(())
+        self.spill_store
+            .as_mut()
+            .ok_or_else(|| io::Error::other("spill store is not open"))
     }
 }
 
@@ -105,28 +111,36 @@ where
     C: StateCodec<S> + Clone,
 {
     fn insert(&mut self, state: S) -> io::Result<bool> {
-        if !self.in_memory.insert(state.clone()) {
-            return Ok(false);
+        if let Some(spill_store) = self.spill_store.as_mut() {
+            return spill_store.insert(state);
         }
-
-        let should_spill = self.in_memory.len() > self.options.spill_threshold;
-        let had_spill = self.spill_store.is_some();
-        if let Err(err) = self.activate_spill_if_needed() {
-            self.in_memory.remove(&state);
-            return Err(err);
+        if self.in_memory.contains(&state) {
+            return Ok(false);
         }
-        if should_spill && had_spill {
-            if let Some(spill_store) = self.spill_store.as_mut() {
-                if let Err(err) = spill_store.insert(state.clone()) {
-                    self.in_memory.remove(&state);
-                    return Err(err);
-                }
-            }
+        if self.in_memory.len() >= self.options.spill_threshold {
+            return self.activate_spill()?.insert(state);
         }
+        self.in_memory.insert(state);
         Ok(true)
     }
 
     fn len(&self) -> usize {
-        self.in_memory.len()
+        match &self.spill_store {
+            Some(spill_store) => spill_store.len(),
+            None => self.in_memory.len(),
+        }
+    }
+
+    // In-memory states are left to the search, which sizes them with
+    // `TransitionProvider::state_bytes`; once spilled only the disk index is
+    // held.
+    fn memory_bytes(&self) -> Option<u64> {
+        self.spill_store
+            .as_ref()
+            .map(|spill_store| spill_store.memory_bytes().unwrap_or(0))
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.activate_spill().map(|_| ())
     }
 }
@@ -0,0 +1,173 @@
+use crate::disk_store::{DiskStateStore, DiskStateStoreMetrics, DiskStateStoreOpenOptions};
+use crate::state_codec::StateCodec;
+use crate::store::{fingerprint, lock_shard, ConcurrentStateStore, StateStore};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+pub const DEFAULT_STORE_SHARDS: usize = 64;
+
+// States are split over `shards` independently locked sets by fingerprint, so
+// workers inserting different states rarely wait for each other.
+#[derive(Debug)]
+pub struct ShardedStateStore<S>
+where
+    S: Eq + Hash,
+{
+    shards: Vec<Mutex<HashSet<S>>>,
+    len: AtomicUsize,
+}
+
+impl<S> ShardedStateStore<S>
+where
+    S: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_STORE_SHARDS)
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| Mutex::new(HashSet::new()))
+                .collect(),
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<S> Default for ShardedStateStore<S>
+where
+    S: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> ConcurrentStateStore<S> for ShardedStateStore<S>
+where
+    S: Eq + Hash + Send,
+{
+    fn insert(&self, state: S) -> io::Result<bool> {
+        let shard = &self.shards[self.shard_of(&state)];
+        let inserted = lock_shard(shard)?.insert(state);
+        if inserted {
+            self.len.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(inserted)
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard_of(&self, state: &S) -> usize {
+        (fingerprint(state) % self.shards.len() as u64) as usize
+    }
+}
+
+impl<S> StateStore<S> for ShardedStateStore<S>
+where
+    S: Eq + Hash + Send,
+{
+    fn insert(&mut self, state: S) -> io::Result<bool> {
+        ConcurrentStateStore::insert(self, state)
+    }
+
+    fn len(&self) -> usize {
+        ConcurrentStateStore::len(self)
+    }
+}
+
+// One `DiskStateStore` per shard, each in its own `shard-<n>` directory under
+// the store directory.
+#[derive(Debug)]
+pub struct ShardedDiskStateStore<S, C>
+where
+    C: StateCodec<S>,
+{
+    shards: Vec<Mutex<DiskStateStore<S, C>>>,
+    len: AtomicUsize,
+}
+
+impl<S, C> ShardedDiskStateStore<S, C>
+where
+    C: StateCodec<S> + Clone,
+{
+    pub fn open(dir: impl AsRef<Path>, shards: usize, codec: C) -> io::Result<Self> {
+        Self::open_with_options(dir, shards, codec, DiskStateStoreOpenOptions::default())
+    }
+
+    pub fn open_with_options(
+        dir: impl AsRef<Path>,
+        shards: usize,
+        codec: C,
+        options: DiskStateStoreOpenOptions,
+    ) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut opened = Vec::with_capacity(shards.max(1));
+        let mut len = 0;
+        for shard in 0..shards.max(1) {
+            let shard_dir = dir.join(format!("shard-{shard:03}"));
+            std::fs::create_dir_all(&shard_dir)?;
+            let store = DiskStateStore::open_with_options(
+                shard_dir.join("state.log"),
+                codec.clone(),
+                options,
+            )?;
+            len += store.len();
+            opened.push(Mutex::new(store));
+        }
+        Ok(Self {
+            shards: opened,
+            len: AtomicUsize::new(len),
+        })
+    }
+}
+
+impl<S, C> ShardedDiskStateStore<S, C>
+where
+    C: StateCodec<S>,
+{
+    pub fn shard_metrics(&self) -> io::Result<Vec<DiskStateStoreMetrics>> {
+        self.shards
+            .iter()
+            .map(|shard| Ok(lock_shard(shard)?.metrics().clone()))
+            .collect()
+    }
+}
+
+impl<S, C> ConcurrentStateStore<S> for ShardedDiskStateStore<S, C>
+where
+    S: Hash + Send,
+    C: StateCodec<S> + Send,
+{
+    fn insert(&self, state: S) -> io::Result<bool> {
+        let shard = &self.shards[self.shard_of(&state)];
+        let inserted = lock_shard(shard)?.insert(state)?;
+        if inserted {
+            self.len.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(inserted)
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard_of(&self, state: &S) -> usize {
+        (fingerprint(state) % self.shards.len() as u64) as usize
+    }
+}
@@ -0,0 +1,234 @@
+use crate::lts::{Transition, TransitionProvider, TAU_LABEL};
+use crate::lts_cspm::{label_channel, CspmState};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// Operands with more reachable states than this are never treated as
+// symmetric, so detection stays cheap next to the search itself.
+const COMPONENT_STATE_LIMIT: usize = 10_000;
+
+// What a move of one operand of the root parallel looks like from outside:
+// synchronised events keep their name, hidden events are tau and every other
+// visible event is interchangeable for deadlock and divergence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum LocalLabel {
+    Tau,
+    Visible,
+    Sync(String),
+}
+
+type Signature = Vec<Vec<(LocalLabel, usize)>>;
+
+#[derive(Debug)]
+enum Shape {
+    Operand,
+    Node(Box<Shape>, Box<Shape>),
+}
+
+#[derive(Debug)]
+struct Component {
+    index: HashMap<CspmState, usize>,
+    states: Vec<CspmState>,
+}
+
+// Operands of the root parallel (under an optional root hiding) whose local
+// LTSs are isomorphic form a group; a state is canonicalised by sorting the
+// local states of each group, which is the same for every state obtained by
+// permuting those operands.
+#[derive(Debug)]
+pub(crate) struct CspmSymmetry {
+    hide: BTreeSet<String>,
+    shape: Shape,
+    components: Vec<Option<Component>>,
+    groups: Vec<Vec<usize>>,
+}
+
+impl CspmSymmetry {
+    pub(crate) fn detect<P>(provider: &P, initial: &CspmState) -> Option<Self>
+    where
+        P: TransitionProvider<State = CspmState, Transition = Transition>,
+    {
+        let (hide, chain) = match initial {
+            CspmState::Hide { hide, inner } => (hide.clone(), inner.as_ref()),
+            other => (BTreeSet::new(), other),
+        };
+        let CspmState::Parallel { sync, .. } = chain else {
+            return None;
+        };
+        let mut operands = Vec::new();
+        let shape = flatten(chain, sync, &mut operands);
+
+        let mut classes = BTreeMap::<Signature, Vec<usize>>::new();
+        let mut components = Vec::with_capacity(operands.len());
+        for (position, operand) in operands.into_iter().enumerate() {
+            match explore_component(provider, operand, &hide, sync) {
+                Some((component, signature)) => {
+                    classes.entry(signature).or_default().push(position);
+                    components.push(Some(component));
+                }
+                None => components.push(None),
+            }
+        }
+        let groups = classes
+            .into_values()
+            .filter(|positions| positions.len() > 1)
+            .collect::<Vec<_>>();
+        if groups.is_empty() {
+            return None;
+        }
+        for (position, component) in components.iter_mut().enumerate() {
+            if !groups.iter().any(|group| group.contains(&position)) {
+                *component = None;
+            }
+        }
+        Some(Self {
+            hide,
+            shape,
+            components,
+            groups,
+        })
+    }
+
+    pub(crate) fn canonical(&self, state: &CspmState) -> Option<CspmState> {
+        let chain = match state {
+            CspmState::Hide { hide, inner } if *hide == self.hide => inner.as_ref(),
+            _ if self.hide.is_empty() => state,
+            _ => return None,
+        };
+        let mut operands = Vec::new();
+        collect(&self.shape, chain, &mut operands)?;
+
+        let mut replaced = operands.clone();
+        let mut changed = false;
+        for group in &self.groups {
+            let locals = group
+                .iter()
+                .map(|&position| {
+                    let component = self.components[position].as_ref()?;
+                    component.index.get(operands[position]).copied()
+                })
+                .collect::<Option<Vec<_>>>()?;
+            let mut sorted = locals.clone();
+            sorted.sort_unstable();
+            if sorted == locals {
+                continue;
+            }
+            changed = true;
+            for (&position, &local) in group.iter().zip(&sorted) {
+                let component = self.components[position].as_ref()?;
+                replaced[position] = &component.states[local];
+            }
+        }
+        if !changed {
+            return None;
+        }
+
+        let chain = rebuild(&self.shape, chain, &mut replaced.into_iter());
+        if self.hide.is_empty() {
+            Some(chain)
+        } else {
+            Some(CspmState::Hide {
+                hide: self.hide.clone(),
+                inner: Box::new(chain),
+            })
+        }
+    }
+}
+
+fn flatten<'a>(
+    state: &'a CspmState,
+    sync: &BTreeSet<String>,
+    out: &mut Vec<&'a CspmState>,
+) -> Shape {
+    match state {
+        CspmState::Parallel {
+            sync: inner,
+            left,
+            right,
+        } if inner == sync => Shape::Node(
+            Box::new(flatten(left, sync, out)),
+            Box::new(flatten(right, sync, out)),
+        ),
+        _ => {
+            out.push(state);
+            Shape::Operand
+        }
+    }
+}
+
+fn collect<'a>(shape: &Shape, state: &'a CspmState, out: &mut Vec<&'a CspmState>) -> Option<()> {
+    match (shape, state) {
+        (Shape::Operand, _) => out.push(state),
+        (Shape::Node(l, r), CspmState::Parallel { left, right, .. }) => {
+            collect(l, left, out)?;
+            collect(r, right, out)?;
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn rebuild<'a>(
+    shape: &Shape,
+    state: &CspmState,
+    operands: &mut impl Iterator<Item = &'a CspmState>,
+) -> CspmState {
+    match (shape, state) {
+        (Shape::Node(l, r), CspmState::Parallel { sync, left, right }) => CspmState::Parallel {
+            sync: sync.clone(),
+            left: Box::new(rebuild(l, left, operands)),
+            right: Box::new(rebuild(r, right, operands)),
+        },
+        _ => operands.next().expect("one operand per shape leaf").clone(),
+    }
+}
+
+// States are numbered in BFS order with moves taken by local label, so equal
+// signatures mean the numbering itself is an isomorphism.
+fn explore_component<P>(
+    provider: &P,
+    initial: &CspmState,
+    hide: &BTreeSet<String>,
+    sync: &BTreeSet<String>,
+) -> Option<(Component, Signature)>
+where
+    P: TransitionProvider<State = CspmState, Transition = Transition>,
+{
+    let local_label = |label: &str| {
+        if label == TAU_LABEL {
+            LocalLabel::Tau
+        } else if sync.contains(label_channel(label)) {
+            LocalLabel::Sync(label.to_string())
+        } else if hide.contains(label_channel(label)) {
+            LocalLabel::Tau
+        } else {
+            LocalLabel::Visible
+        }
+    };
+
+    let mut index = HashMap::from([(initial.clone(), 0)]);
+    let mut states = vec![initial.clone()];
+    let mut signature = Signature::new();
+    while signature.len() < states.len() {
+        let mut moves = provider
+            .transitions(&states[signature.len()])
+            .into_iter()
+            .map(|(transition, next)| (local_label(&transition.label), next))
+            .collect::<Vec<_>>();
+        moves.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut edges = Vec::with_capacity(moves.len());
+        for (label, next) in moves {
+            let id = states.len();
+            let to = *index.entry(next.clone()).or_insert_with(|| {
+                states.push(next);
+                id
+            });
+            edges.push((label, to));
+        }
+        if states.len() > COMPONENT_STATE_LIMIT {
+            return None;
+        }
+        edges.sort();
+        signature.push(edges);
+    }
+    Some((Component { index, states }, signature))
+}
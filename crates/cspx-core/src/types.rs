@@ -20,6 +20,7 @@ pub enum ReasonKind {
     InternalError,
     Timeout,
     OutOfMemory,
+    ApproximateSearch,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -42,11 +43,66 @@ pub struct SourceSpan {
 #[serde(rename_all = "snake_case")]
 pub enum CounterexampleType {
     Trace,
+    Lasso,
+    Failure,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CounterexampleEvent {
     pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden_label: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<SourceSpan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lasso {
+    pub stem: Vec<CounterexampleEvent>,
+    pub cycle: Vec<CounterexampleEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Failure {
+    pub refusal: Vec<String>,
+    pub acceptances: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub divergence: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeadlockExplanation {
+    pub components: Vec<DeadlockComponent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeadlockComponent {
+    pub process: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+    pub offers: Vec<String>,
+    pub blocked: Vec<BlockedEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockedEvent {
+    pub event: String,
+    pub sync: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Conformance {
+    pub span: SourceSpan,
+    pub allowed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Minimality {
+    Shortest,
+    LocallyMinimal,
+    #[default]
+    None,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -54,15 +110,52 @@ pub struct Counterexample {
     #[serde(rename = "type")]
     pub kind: CounterexampleType,
     pub events: Vec<CounterexampleEvent>,
-    pub is_minimized: bool,
+    #[serde(default)]
+    pub minimality: Minimality,
     pub tags: Vec<String>,
     pub source_spans: Vec<SourceSpan>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lasso: Option<Lasso>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<Failure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadlock_explanation: Option<DeadlockExplanation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conformance: Option<Conformance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayVerdict {
+    StillFails,
+    NoLongerValid,
+    NowPasses,
+}
+
+// `step` equals the number of events when the violation is the final state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Replay {
+    pub verdict: ReplayVerdict,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offered: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Simulation {
+    pub runs: u64,
+    pub events_seen: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Stats {
     pub states: Option<u64>,
     pub transitions: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -0,0 +1,79 @@
+use cspx_core::{
+    BitstateStore, BitstateStoreOptions, HashCompactStateStore, RunLimits, StateStore,
+};
+
+#[test]
+fn bitstate_store_deduplicates_states() {
+    let mut store = BitstateStore::new(BitstateStoreOptions::default()).expect("open");
+    assert!(store.insert(1u32).expect("insert"));
+    assert!(store.insert(2u32).expect("insert"));
+    assert!(!store.insert(1u32).expect("dedup"));
+    assert_eq!(store.len(), 2);
+
+    let probability = store.omission_probability().expect("approximate");
+    assert!(probability > 0.0 && probability < 1e-9, "{probability}");
+}
+
+#[test]
+fn bitstate_store_reports_saturation() {
+    let mut store = BitstateStore::new(BitstateStoreOptions {
+        log2_bits: 6,
+        hashes: 2,
+    })
+    .expect("open");
+    let inserted = (0u32..1_000)
+        .filter(|state| store.insert(*state).expect("insert"))
+        .count();
+    assert_eq!(store.len(), inserted);
+    assert!(inserted < 1_000);
+    assert!(store.fill_ratio() > 0.9);
+    assert!(store.omission_probability().expect("approximate") > 0.99);
+}
+
+#[test]
+fn bitstate_store_rejects_invalid_options() {
+    for options in [
+        BitstateStoreOptions {
+            log2_bits: 5,
+            hashes: 3,
+        },
+        BitstateStoreOptions {
+            log2_bits: 27,
+            hashes: 0,
+        },
+    ] {
+        let err = BitstateStore::<u32>::new(options).expect_err("invalid options");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn bitstate_store_rejects_arrays_over_the_memory_budget() {
+    let limits = RunLimits::unlimited().with_memory_mib(1);
+    let options = |log2_bits| BitstateStoreOptions {
+        log2_bits,
+        hashes: 3,
+    };
+    // 2^23 bits fill the 1 MiB budget exactly; one more bit doubles it.
+    BitstateStore::<u32>::new_with_limits(options(23), &limits).expect("fits");
+    let err = BitstateStore::<u32>::new_with_limits(options(40), &limits).expect_err("too large");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("137438953472 bytes"), "{err}");
+    let err = BitstateStore::<u32>::new_with_limits(options(24), &limits).expect_err("too large");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn hash_compact_store_keeps_fingerprints() {
+    let mut store = HashCompactStateStore::new();
+    assert_eq!(store.omission_probability(), Some(0.0));
+    for state in 0u64..10_000 {
+        assert!(store.insert(state).expect("insert"));
+    }
+    assert!(!store.insert(42).expect("dedup"));
+    assert_eq!(store.len(), 10_000);
+
+    // 10_000 * 9_999 / 2 pairs against 2^64 fingerprints.
+    let probability = store.omission_probability().expect("approximate");
+    assert!((probability - 2.71e-12).abs() < 1e-13, "{probability}");
+}
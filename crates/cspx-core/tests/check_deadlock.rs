@@ -1,4 +1,9 @@
-use cspx_core::{CheckRequest, Checker, DeadlockChecker, Frontend, SimpleFrontend, Status};
+use cspx_core::{
+    CheckRequest, Checker, CspmState, CspmStateCodec, DeadlockChecker, DiskPredecessorStore,
+    DiskStateStore, Frontend, HashCompactPredecessorStore, HashCompactStateStore,
+    InMemoryPredecessorStore, InMemorySearch, PredecessorStore, SearchBackend, SimpleFrontend,
+    StateStore, Status, VecWorkQueue,
+};
 
 #[test]
 fn deadlock_selects_last_deadlock_free_assert_target() {
@@ -34,4 +39,333 @@ assert System :[deadlock free [F]]
     assert!(counterexample.tags.contains(&"explained".to_string()));
     assert_eq!(counterexample.source_spans.len(), 1);
     assert_eq!(counterexample.source_spans[0].start_line, 6);
+
+    let explanation = counterexample
+        .deadlock_explanation
+        .expect("deadlock explanation");
+    let components = explanation
+        .components
+        .iter()
+        .map(|component| {
+            (
+                component.process.as_deref(),
+                component.span.as_ref().map(|span| span.start_line),
+                component.offers.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        components,
+        vec![
+            (Some("P"), Some(4), vec!["a".to_string()]),
+            (Some("Q"), Some(5), vec!["b".to_string()]),
+        ]
+    );
+    for (component, event) in explanation.components.iter().zip(["a", "b"]) {
+        assert_eq!(component.blocked.len(), 1);
+        assert_eq!(component.blocked[0].event, event);
+        assert_eq!(component.blocked[0].sync, vec!["a", "b"]);
+    }
+}
+
+#[test]
+fn deadlock_explanation_names_nested_components_under_hiding() {
+    let input = r#"channel a
+channel b
+channel c
+A = a -> b -> STOP
+B = c -> a -> STOP
+C = c -> STOP
+System = (A [|{|a|}|] (B [|{|c|}|] C)) \\ {|b|}
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let result = DeadlockChecker.check(&request, &module);
+    assert_eq!(result.status, Status::Fail);
+
+    let counterexample = result.counterexample.expect("counterexample");
+    let labels = counterexample
+        .events
+        .iter()
+        .map(|event| event.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["c", "a"]);
+    let explanation = counterexample
+        .deadlock_explanation
+        .expect("deadlock explanation");
+    let processes = explanation
+        .components
+        .iter()
+        .map(|component| component.process.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(processes, vec![Some("A"), Some("B"), Some("C")]);
+    assert!(explanation
+        .components
+        .iter()
+        .all(|component| component.offers.is_empty() && component.blocked.is_empty()));
+}
+
+#[test]
+fn deadlock_with_disk_store_and_workers_matches_in_memory_run() {
+    let input = r#"channel ch : {0..1}
+Sender = ch!1 -> STOP
+Receiver = ch?x -> Receiver
+System = Sender [|{|ch|}|] Receiver
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let expected = DeadlockChecker.check(&request, &module);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut store =
+        DiskStateStore::open(dir.path().join("state.log"), CspmStateCodec).expect("open store");
+    let mut queue = VecWorkQueue::new();
+    let mut predecessors = InMemoryPredecessorStore::new();
+    let mut backend = SearchBackend {
+        store: &mut store,
+        queue: &mut queue,
+        predecessors: &mut predecessors,
+        workers: 4,
+        por: None,
+        symmetry: None,
+        limits: None,
+        checkpoint: None,
+        compression: None,
+        max_counterexamples: 1,
+    };
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+
+    assert_eq!(result, expected);
+    assert!(store.len() >= 2);
+}
+
+#[test]
+fn deadlock_counterexample_replays_from_disk_and_fingerprint_predecessors() {
+    let input = r#"channel a, b : {0..2}
+Count = a?x -> b!x -> Count
+Stop = a.0 -> a.1 -> a.2 -> STOP
+System = Count [|{|a|}|] Stop
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let expected = DeadlockChecker.check(&request, &module);
+    assert_eq!(expected.status, Status::Fail);
+
+    let run = |store: &mut dyn StateStore<CspmState>,
+               predecessors: &mut dyn PredecessorStore<CspmState>| {
+        let mut queue = VecWorkQueue::new();
+        let mut backend = SearchBackend {
+            store,
+            queue: &mut queue,
+            predecessors,
+            workers: 1,
+            por: None,
+            symmetry: None,
+            limits: None,
+            checkpoint: None,
+            compression: None,
+            max_counterexamples: 1,
+        };
+        DeadlockChecker.check_with(&request, &module, &mut backend)
+    };
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("predecessors.log");
+    let mut predecessors =
+        DiskPredecessorStore::open(&path, CspmStateCodec).expect("open predecessors");
+    let mut store =
+        DiskStateStore::open(dir.path().join("state.log"), CspmStateCodec).expect("open store");
+    assert_eq!(run(&mut store, &mut predecessors), expected);
+    assert!(predecessors.memory_bytes().expect("memory bytes") < 64 * 1024);
+    drop(predecessors);
+    assert!(!path.exists());
+
+    let mut store = HashCompactStateStore::new();
+    let mut predecessors = HashCompactPredecessorStore::new();
+    assert_eq!(run(&mut store, &mut predecessors), expected);
+}
+
+#[test]
+fn deadlock_events_carry_the_spans_of_the_prefixes_that_fired_them() {
+    let input = r#"channel a
+channel b
+channel c
+P = a -> b -> STOP
+Q = a -> c -> STOP
+System = (P [|{|a,b,c|}|] Q) ||| (c -> STOP)
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let result = DeadlockChecker.check(&request, &module);
+    assert_eq!(result.status, Status::Fail);
+
+    let counterexample = result.counterexample.expect("counterexample");
+    let events = counterexample
+        .events
+        .iter()
+        .map(|event| {
+            let lines = event
+                .spans
+                .iter()
+                .map(|span| (span.start_line, span.start_col))
+                .collect::<Vec<_>>();
+            (event.label.as_str(), lines)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        vec![("a", vec![(4, 5), (5, 5)]), ("c", vec![(6, 34)])]
+    );
+}
+
+#[test]
+fn deadlock_components_with_identical_bodies_keep_their_own_spans() {
+    let input = r#"channel a, b, d
+P = a -> b -> P [] d -> STOP
+R = d -> STOP
+System = P [|{|d|}|] R
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let result = DeadlockChecker.check(&request, &module);
+    assert_eq!(result.status, Status::Fail);
+
+    let counterexample = result.counterexample.expect("counterexample");
+    let explanation = counterexample
+        .deadlock_explanation
+        .expect("deadlock explanation");
+    let components = explanation
+        .components
+        .iter()
+        .map(|component| {
+            let span = component.span.as_ref().expect("component span");
+            (
+                component.process.as_deref(),
+                span.start_line,
+                span.start_col,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(components, vec![(Some("P"), 2, 25), (Some("R"), 3, 10)]);
+}
+
+#[test]
+fn synchronised_events_with_identical_bodies_carry_both_prefix_spans() {
+    let input = r#"channel a, b, d
+P = a -> b -> P [] d -> STOP
+R = d -> STOP
+System = P [|{|d|}|] R
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let result = DeadlockChecker.check(&request, &module);
+    assert_eq!(result.status, Status::Fail);
+
+    let counterexample = result.counterexample.expect("counterexample");
+    let events = counterexample
+        .events
+        .iter()
+        .map(|event| {
+            let spans = event
+                .spans
+                .iter()
+                .map(|span| (span.start_line, span.start_col))
+                .collect::<Vec<_>>();
+            (event.label.as_str(), spans)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(events, vec![("d", vec![(2, 20), (3, 5)])]);
+}
+
+#[test]
+fn deadlock_check_collects_distinct_deadlocks_in_bfs_order() {
+    let input = r#"channel a, b, c, d
+L = a -> c -> STOP [] b -> d -> STOP
+R = c -> STOP
+System = L [|{|c, d|}|] R
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let single = DeadlockChecker.check(&request, &module);
+    assert!(single.counterexamples.is_empty());
+
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.max_counterexamples = 5;
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+
+    assert_eq!(result.status, Status::Fail);
+    assert_eq!(result.counterexample, single.counterexample);
+    let traces = result
+        .counterexamples
+        .iter()
+        .map(|counterexample| {
+            counterexample
+                .events
+                .iter()
+                .map(|event| event.label.as_str())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(traces, vec![vec!["b"], vec!["a", "c"]]);
+    assert_eq!(
+        result.counterexamples.first(),
+        result.counterexample.as_ref()
+    );
 }
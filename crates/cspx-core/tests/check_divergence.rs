@@ -1,4 +1,9 @@
-use cspx_core::{CheckRequest, Checker, DivergenceChecker, Frontend, SimpleFrontend, Status};
+use cspx_core::{
+    CheckRequest, Checker, CounterexampleEvent, CounterexampleType, CspmState, CspmStateCodec,
+    DiskPredecessorStore, DiskStateStore, DivergenceChecker, Frontend, HashCompactPredecessorStore,
+    HashCompactStateStore, PredecessorStore, SearchBackend, SimpleFrontend, StateStore, Status,
+    VecWorkQueue,
+};
 
 #[test]
 fn divergence_free_passes_without_tau_cycle() {
@@ -84,3 +89,103 @@ assert P :[divergence free [FD]]
     assert_eq!(counterexample.events[0].label, "b");
     assert_eq!(counterexample.events[1].label, "tau");
 }
+
+#[test]
+fn divergence_lasso_reveals_hidden_events_of_stem_and_cycle() {
+    let input = r#"channel a, c, d, s
+Loop = a -> c -> Loop
+C = c -> C
+Sys = ((Loop \\ {|a|}) [|{|c|}|] C) \\ {|c|}
+P = (s -> d -> Sys) \\ {|d|}
+assert P :[divergence free [FD]]
+"#;
+
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("divergence free".to_string()),
+    };
+    let result = DivergenceChecker.check(&request, &module);
+    assert_eq!(result.status, Status::Fail);
+
+    let counterexample = result.counterexample.expect("counterexample");
+    assert_eq!(counterexample.kind, CounterexampleType::Lasso);
+    let steps = |events: &[CounterexampleEvent]| {
+        events
+            .iter()
+            .map(|event| (event.label.clone(), event.hidden_label.clone()))
+            .collect::<Vec<_>>()
+    };
+    let step = |label: &str, hidden: Option<&str>| (label.to_string(), hidden.map(String::from));
+    let lasso = counterexample.lasso.expect("lasso");
+    assert_eq!(
+        steps(&lasso.stem),
+        vec![step("s", None), step("tau", Some("d"))]
+    );
+    assert_eq!(
+        steps(&lasso.cycle),
+        vec![step("tau", Some("a")), step("tau", Some("c"))]
+    );
+    assert_eq!(
+        counterexample
+            .events
+            .iter()
+            .map(|event| event.label.as_str())
+            .collect::<Vec<_>>(),
+        vec!["s", "tau"]
+    );
+}
+
+#[test]
+fn divergence_lasso_replays_from_disk_and_fingerprint_predecessors() {
+    let input = r#"channel a, b, s
+Loop = a -> b -> Loop
+P = s -> (Loop \\ {|a, b|})
+assert P :[divergence free [FD]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("divergence free".to_string()),
+    };
+    let expected = DivergenceChecker.check(&request, &module);
+    assert_eq!(expected.status, Status::Fail);
+
+    let run = |store: &mut dyn StateStore<CspmState>,
+               predecessors: &mut dyn PredecessorStore<CspmState>| {
+        let mut queue = VecWorkQueue::new();
+        let mut backend = SearchBackend {
+            store,
+            queue: &mut queue,
+            predecessors,
+            workers: 1,
+            por: None,
+            symmetry: None,
+            limits: None,
+            checkpoint: None,
+            compression: None,
+            max_counterexamples: 1,
+        };
+        DivergenceChecker.check_with(&request, &module, &mut backend)
+    };
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut predecessors =
+        DiskPredecessorStore::open(dir.path().join("predecessors.log"), CspmStateCodec)
+            .expect("open predecessors");
+    let mut store =
+        DiskStateStore::open(dir.path().join("state.log"), CspmStateCodec).expect("open store");
+    assert_eq!(run(&mut store, &mut predecessors), expected);
+
+    let mut store = HashCompactStateStore::new();
+    let mut predecessors = HashCompactPredecessorStore::new();
+    assert_eq!(run(&mut store, &mut predecessors), expected);
+}
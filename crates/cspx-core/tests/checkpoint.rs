@@ -0,0 +1,407 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::ir::Module;
+use cspx_core::{
+    CheckRequest, CheckResult, Checker, CheckpointOptions, CspmState, CspmStateCodec,
+    DeadlockChecker, DivergenceChecker, Frontend, InMemorySearch, MemoryBudget, ReasonKind,
+    RefinementChecker, RefinementInput, RefinementOptions, RunLimits, SearchBackend,
+    SearchCheckpoint, SimpleFrontend, Status,
+};
+use std::path::Path;
+
+// 5^6 interleaved states; only the state where every counter stopped
+// deadlocks.
+const STOPPING_COUNTERS: &str = r#"channel a, b, c, d : {0..5}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> STOP
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> STOP
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> STOP
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> STOP
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> STOP
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> STOP
+System = ((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5
+assert System :[deadlock free [F]]
+"#;
+
+// The hidden loop only starts once the first counter finished.
+const DIVERGING_COUNTERS: &str = r#"channel a, b, c, d : {0..4}
+channel e
+Spin = e -> Spin
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> Spin
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> C4
+System = ((((C0 ||| C1) ||| C2) ||| C3) ||| C4) \\ {|e|}
+assert System :[divergence free [FD]]
+"#;
+
+// 4^4 interleaved states with the composition as the entry process.
+const ENTRY_COUNTERS: &str = r#"channel a, b, c, d : {0..3}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+((C0 ||| C1) ||| C2) ||| C3
+"#;
+
+// Like ENTRY_COUNTERS, but the first counter stops after two rounds.
+const STOPPING_ENTRY_COUNTERS: &str = r#"channel a, b, c, d : {0..3}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> a.0 -> b.0 -> c.0 -> d.0 -> STOP
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+((C0 ||| C1) ||| C2) ||| C3
+"#;
+
+fn module(input: &str) -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn request(target: &str) -> CheckRequest {
+    CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some(target.to_string()),
+    }
+}
+
+fn budget(bytes: u64) -> RunLimits {
+    let mut limits = RunLimits::unlimited();
+    limits.memory = Some(MemoryBudget::new(bytes));
+    limits
+}
+
+fn options(dir: &Path, resume: bool) -> CheckpointOptions {
+    CheckpointOptions {
+        every: 37,
+        resume,
+        ..CheckpointOptions::new(dir, "test-run")
+    }
+}
+
+fn states(result: &CheckResult) -> u64 {
+    result
+        .stats
+        .as_ref()
+        .and_then(|stats| stats.states)
+        .expect("states")
+}
+
+// Runs a property check against a fresh in-memory backend, with `workers`
+// threads and a checkpoint in `dir`.
+fn check_from(
+    checker: &dyn Fn(&mut SearchBackend<'_, CspmState>) -> CheckResult,
+    dir: &Path,
+    resume: bool,
+    workers: usize,
+    limits: &RunLimits,
+) -> CheckResult {
+    let checkpoint = SearchCheckpoint {
+        options: options(dir, resume),
+        codec: &CspmStateCodec,
+    };
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(workers);
+    backend.limits = Some(limits);
+    backend.checkpoint = Some(&checkpoint);
+    checker(&mut backend)
+}
+
+#[test]
+fn deadlock_check_resumes_to_the_same_counterexample() {
+    let module = module(STOPPING_COUNTERS);
+    let request = request("deadlock free");
+    let expected = DeadlockChecker.check(&request, &module);
+    assert_eq!(expected.status, Status::Fail);
+    let check = |backend: &mut SearchBackend<'_, CspmState>| {
+        DeadlockChecker.check_with(&request, &module, backend)
+    };
+
+    for workers in [1, 4] {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let stopped = check_from(&check, dir.path(), false, workers, &budget(64 * 1024));
+        assert_eq!(stopped.status, Status::OutOfMemory);
+        let partial = states(&stopped);
+        assert!(partial > 1 && partial < states(&expected), "{partial}");
+
+        // An expired deadline stops before expanding anything, so the stats
+        // are exactly what the checkpoint restored.
+        let expired = RunLimits::unlimited().with_timeout_ms(0);
+        let restored = check_from(&check, dir.path(), true, workers, &expired);
+        assert_eq!(restored.status, Status::Timeout);
+        assert_eq!(states(&restored), partial);
+
+        let resumed = check_from(&check, dir.path(), true, workers, &RunLimits::unlimited());
+        assert_eq!(resumed, expected, "workers={workers}");
+    }
+}
+
+// Every counter ends in one of two ways, either stopped or blocked on `e`,
+// so there are 2^5 deadlocks.
+const CHOOSING_COUNTERS: &str = r#"channel a, b, c, d, e : {0..4}
+C0 = a.0 -> b.0 -> (c.0 -> STOP [] d.0 -> e.0 -> STOP)
+C1 = a.1 -> b.1 -> (c.1 -> STOP [] d.1 -> e.1 -> STOP)
+C2 = a.2 -> b.2 -> (c.2 -> STOP [] d.2 -> e.2 -> STOP)
+C3 = a.3 -> b.3 -> (c.3 -> STOP [] d.3 -> e.3 -> STOP)
+C4 = a.4 -> b.4 -> (c.4 -> STOP [] d.4 -> e.4 -> STOP)
+System = ((((C0 ||| C1) ||| C2) ||| C3) ||| C4) [|{|e|}|] STOP
+assert System :[deadlock free [F]]
+"#;
+
+#[test]
+fn collecting_deadlock_check_resumes_to_the_same_counterexamples() {
+    let module = module(CHOOSING_COUNTERS);
+    let request = request("deadlock free");
+    let check = |backend: &mut SearchBackend<'_, CspmState>| {
+        backend.max_counterexamples = 30;
+        DeadlockChecker.check_with(&request, &module, backend)
+    };
+    let mut search = InMemorySearch::new();
+    let expected = check(&mut search.backend(1));
+    assert_eq!(expected.status, Status::Fail);
+    assert_eq!(expected.counterexamples.len(), 30);
+
+    // A finished search leaves its last periodic commit behind, which lies
+    // between the deadlocks; resuming replays the ones before it.
+    let dir = tempfile::tempdir().expect("tempdir");
+    let finished = check_from(&check, dir.path(), false, 1, &RunLimits::unlimited());
+    assert_eq!(finished, expected);
+    for workers in [1, 4] {
+        let resumed = check_from(&check, dir.path(), true, workers, &RunLimits::unlimited());
+        assert_eq!(resumed, expected, "workers={workers}");
+    }
+}
+
+#[test]
+fn divergence_check_resumes_with_its_tau_graph() {
+    let module = module(DIVERGING_COUNTERS);
+    let request = request("divergence free");
+    let expected = DivergenceChecker.check(&request, &module);
+    assert_eq!(expected.status, Status::Fail);
+    let check = |backend: &mut SearchBackend<'_, CspmState>| {
+        DivergenceChecker.check_with(&request, &module, backend)
+    };
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let stopped = check_from(&check, dir.path(), false, 1, &budget(64 * 1024));
+    assert_eq!(stopped.status, Status::OutOfMemory);
+    assert!(states(&stopped) < states(&expected));
+
+    let resumed = check_from(&check, dir.path(), true, 1, &RunLimits::unlimited());
+    assert_eq!(resumed, expected);
+}
+
+#[test]
+fn resume_without_a_checkpoint_starts_over() {
+    let module = module(STOPPING_COUNTERS);
+    let request = request("deadlock free");
+    let expected = DeadlockChecker.check(&request, &module);
+    let check = |backend: &mut SearchBackend<'_, CspmState>| {
+        DeadlockChecker.check_with(&request, &module, backend)
+    };
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let result = check_from(&check, dir.path(), true, 1, &RunLimits::unlimited());
+    assert_eq!(result, expected);
+    assert!(dir.path().join("progress.json").exists());
+}
+
+#[test]
+fn checkpoint_of_another_run_is_rejected() {
+    let module = module(STOPPING_COUNTERS);
+    let request = request("deadlock free");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let check = |backend: &mut SearchBackend<'_, CspmState>| {
+        DeadlockChecker.check_with(&request, &module, backend)
+    };
+    check_from(&check, dir.path(), false, 1, &budget(64 * 1024));
+
+    let checkpoint = SearchCheckpoint {
+        options: CheckpointOptions {
+            resume: true,
+            ..CheckpointOptions::new(dir.path(), "another-run")
+        },
+        codec: &CspmStateCodec,
+    };
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.checkpoint = Some(&checkpoint);
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+    assert_eq!(result.status, Status::Error);
+    let message = result
+        .reason
+        .and_then(|reason| reason.message)
+        .expect("message");
+    assert!(message.contains("different run"), "{message}");
+}
+
+fn refine(
+    model: RefinementModel,
+    input: &RefinementInput,
+    checkpoint: Option<&CheckpointOptions>,
+    limits: &RunLimits,
+) -> CheckResult {
+    refine_collecting(model, input, checkpoint, limits, 1)
+}
+
+fn refine_collecting(
+    model: RefinementModel,
+    input: &RefinementInput,
+    checkpoint: Option<&CheckpointOptions>,
+    limits: &RunLimits,
+    max_counterexamples: usize,
+) -> CheckResult {
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(model),
+        target: Some("spec impl".to_string()),
+    };
+    RefinementChecker.check_with_options(
+        &request,
+        input,
+        RefinementOptions {
+            checkpoint,
+            max_counterexamples,
+            ..RefinementOptions::new(2, limits)
+        },
+    )
+}
+
+#[test]
+fn refinement_resumes_with_identical_fd_diagnostics() {
+    let module = module(ENTRY_COUNTERS);
+    let input = RefinementInput {
+        spec: module.clone(),
+        impl_: module,
+    };
+    let expected = refine(RefinementModel::FD, &input, None, &RunLimits::unlimited());
+    assert_eq!(expected.status, Status::Pass);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let stopped = refine(
+        RefinementModel::FD,
+        &input,
+        Some(&options(dir.path(), false)),
+        &budget(64 * 1024),
+    );
+    assert_eq!(stopped.status, Status::OutOfMemory);
+    let partial = states(&stopped);
+    assert!(partial > 1 && partial < states(&expected), "{partial}");
+
+    let expired = RunLimits::unlimited().with_timeout_ms(0);
+    let restored = refine(
+        RefinementModel::FD,
+        &input,
+        Some(&options(dir.path(), true)),
+        &expired,
+    );
+    assert_eq!(restored.status, Status::Timeout);
+    assert_eq!(states(&restored), partial);
+
+    let resumed = refine(
+        RefinementModel::FD,
+        &input,
+        Some(&options(dir.path(), true)),
+        &RunLimits::unlimited(),
+    );
+    assert_eq!(resumed, expected);
+}
+
+#[test]
+fn failing_refinement_resumes_to_the_same_counterexample() {
+    let input = RefinementInput {
+        spec: module(ENTRY_COUNTERS),
+        impl_: module(STOPPING_ENTRY_COUNTERS),
+    };
+    let expected = refine(RefinementModel::F, &input, None, &RunLimits::unlimited());
+    assert_eq!(expected.status, Status::Fail);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let stopped = refine(
+        RefinementModel::F,
+        &input,
+        Some(&options(dir.path(), false)),
+        &budget(32 * 1024),
+    );
+    assert_eq!(stopped.status, Status::OutOfMemory);
+
+    let resumed = refine(
+        RefinementModel::F,
+        &input,
+        Some(&options(dir.path(), true)),
+        &RunLimits::unlimited(),
+    );
+    assert_eq!(resumed, expected);
+    assert!(resumed.counterexample.is_some());
+
+    let other = CheckpointOptions {
+        resume: true,
+        ..CheckpointOptions::new(dir.path(), "another-run")
+    };
+    let rejected = refine(
+        RefinementModel::F,
+        &input,
+        Some(&other),
+        &RunLimits::unlimited(),
+    );
+    assert_eq!(rejected.status, Status::Error);
+    assert_eq!(
+        rejected.reason.map(|reason| reason.kind),
+        Some(ReasonKind::InternalError)
+    );
+}
+
+// Every finished round of the first counter is an event the spec refuses.
+const SIGNALLING_ENTRY_COUNTERS: &str = r#"channel a, b, c, d : {0..3}
+channel x
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> x -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+((C0 ||| C1) ||| C2) ||| C3
+"#;
+
+#[test]
+fn collecting_refinement_resumes_to_the_same_counterexamples() {
+    let input = RefinementInput {
+        spec: module(ENTRY_COUNTERS),
+        impl_: module(SIGNALLING_ENTRY_COUNTERS),
+    };
+    let expected = refine_collecting(
+        RefinementModel::T,
+        &input,
+        None,
+        &RunLimits::unlimited(),
+        20,
+    );
+    assert_eq!(expected.status, Status::Fail);
+    assert_eq!(expected.counterexamples.len(), 20);
+
+    // The smaller budget stops before the first mismatch, the larger one
+    // after a few, which are then replayed from the journal.
+    for (bytes, status) in [
+        (128 * 1024, Status::OutOfMemory),
+        (512 * 1024, Status::Fail),
+    ] {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let stopped = refine_collecting(
+            RefinementModel::T,
+            &input,
+            Some(&options(dir.path(), false)),
+            &budget(bytes),
+            20,
+        );
+        assert_eq!(stopped.status, status, "budget={bytes}");
+        assert!(stopped.counterexamples.len() < 20);
+        let resumed = refine_collecting(
+            RefinementModel::T,
+            &input,
+            Some(&options(dir.path(), true)),
+            &RunLimits::unlimited(),
+            20,
+        );
+        assert_eq!(resumed, expected, "budget={bytes}");
+    }
+}
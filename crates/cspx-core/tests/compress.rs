@@ -0,0 +1,185 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::compress::{compress, explicate};
+use cspx_core::ir::CompressionKind;
+use cspx_core::{
+    CheckRequest, Checker, CspmTransitionProvider, DeadlockChecker, DivergenceChecker, ExplicitLts,
+    ExplicitLtsProvider, Frontend, InMemorySearch, RefinementChecker, SimpleFrontend, Status,
+};
+
+// a -> (b -> STOP |~| c -> STOP) with duplicated branches, a tau-cycle on the
+// `b` side and a redundant tau step before `c`.
+const AUT: &str = r#"des (0, 10, 9)
+(0, "a", 1)
+(0, "a", 2)
+(1, i, 3)
+(2, i, 3)
+(1, i, 4)
+(3, i, 5)
+(5, i, 3)
+(3, "b", 6)
+(4, i, 7)
+(7, "c", 8)
+"#;
+
+fn lts() -> ExplicitLts {
+    ExplicitLts::parse_aut(AUT, "model.aut").expect("parse")
+}
+
+fn fd_equivalent(left: &ExplicitLts, right: &ExplicitLts) -> bool {
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::FD),
+        target: None,
+    };
+    let left = ExplicitLtsProvider::new(left.clone());
+    let right = ExplicitLtsProvider::new(right.clone());
+    RefinementChecker
+        .check_providers(&request, &left, &right)
+        .status
+        == Status::Pass
+        && RefinementChecker
+            .check_providers(&request, &right, &left)
+            .status
+            == Status::Pass
+}
+
+fn parse(input: &str) -> cspx_core::ir::Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+#[test]
+fn every_compression_preserves_failures_divergences() {
+    let original = lts();
+    for kind in [
+        CompressionKind::Explicate,
+        CompressionKind::Sbisim,
+        CompressionKind::Normal,
+        CompressionKind::Diamond,
+        CompressionKind::TauLoopFactor,
+    ] {
+        let compressed = compress(kind, &original);
+        assert!(
+            compressed.state_count() <= original.state_count(),
+            "{}",
+            kind.as_str()
+        );
+        assert!(fd_equivalent(&original, &compressed), "{}", kind.as_str());
+    }
+}
+
+#[test]
+fn sbisim_merges_bisimilar_states() {
+    let compressed = compress(CompressionKind::Sbisim, &lts());
+    // The two deadlocked states collapse, as do 2 and 5 (both only tau to 3).
+    assert_eq!(compressed.state_count(), 7);
+}
+
+#[test]
+fn tau_loop_factor_keeps_divergence_as_self_loop() {
+    let compressed = compress(CompressionKind::TauLoopFactor, &lts());
+    assert_eq!(compressed.state_count(), 8);
+
+    let request = CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some("divergence free".to_string()),
+    };
+    let result = DivergenceChecker.check_provider(&request, &ExplicitLtsProvider::new(compressed));
+    assert_eq!(result.status, Status::Fail);
+}
+
+#[test]
+fn diamond_only_keeps_initial_and_visible_targets() {
+    let compressed = compress(CompressionKind::Diamond, &lts());
+    // The initial state, the two `a` targets, the `b`/`c` targets and the
+    // stable state offering `c`, reached by tau from the first `a` target.
+    assert_eq!(compressed.state_count(), 6);
+}
+
+#[test]
+fn cspm_compression_is_explored_as_explicit_component() {
+    let input = r#"channel a
+channel b
+P = a -> P [] a -> b -> P
+Q = sbisim(P) [] b -> STOP
+Q
+"#;
+    let module = parse(input);
+    let provider = CspmTransitionProvider::from_module(&module).expect("provider");
+    let stats = provider.compression_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].function, CompressionKind::Sbisim);
+    assert!(stats[0].span.is_some());
+    assert_eq!(stats[0].states_before, 2);
+
+    let uncompressed = parse(&input.replace("sbisim(P)", "P"));
+    let request = CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let compressed_result = DeadlockChecker.check(&request, &module);
+    let uncompressed_result = DeadlockChecker.check(&request, &uncompressed);
+    assert_eq!(compressed_result.status, uncompressed_result.status);
+    // Event spans point at different source text, so compare labels only.
+    let labels = |result: cspx_core::CheckResult| {
+        result.counterexample.map(|cx| {
+            cx.events
+                .into_iter()
+                .map(|event| event.label)
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(labels(compressed_result), labels(uncompressed_result));
+}
+
+#[test]
+fn checks_record_the_compressions_they_ran() {
+    let module = parse(
+        r#"channel a
+P = a -> P
+Q = normal(P)
+R = diamond(Q)
+assert R :[deadlock free [F]]
+"#,
+    );
+    let request = CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let mut stats = Vec::new();
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.compression = Some(&mut stats);
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+    assert_eq!(result.status, Status::Pass);
+    let functions = stats.iter().map(|stats| stats.function).collect::<Vec<_>>();
+    assert_eq!(
+        functions,
+        vec![CompressionKind::Normal, CompressionKind::Diamond]
+    );
+}
+
+#[test]
+fn compression_rejects_bound_variables() {
+    let module = parse(
+        r#"channel c : {0..1}
+P = c?x -> sbisim(c!x -> STOP)
+P
+"#,
+    );
+    let err = CspmTransitionProvider::from_module(&module).expect_err("free variable");
+    assert!(err.message.contains("sbisim"), "{}", err.message);
+}
+
+#[test]
+fn explicate_numbers_states_in_bfs_order() {
+    let lts = explicate(&ExplicitLtsProvider::new(lts()));
+    assert_eq!(lts.initial(), 0);
+    assert_eq!(lts.state_count(), 9);
+    assert_eq!(lts.transition_count(), 10);
+}
@@ -0,0 +1,95 @@
+use cspx_core::check::CheckCommand;
+use cspx_core::{
+    parse_trace_log, CheckRequest, Checker, ConformanceChecker, ConformanceInput, Frontend,
+    SimpleFrontend, Status,
+};
+
+const SPEC: &str = r#"channel req, ack, err, log
+Inner = req -> log -> ack -> Inner [] err -> Inner
+Server = Inner \\ {|log|}
+"#;
+
+fn conform(log: &str) -> cspx_core::CheckResult {
+    let mut spec = SimpleFrontend
+        .parse_and_typecheck(SPEC, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    spec.entry = spec
+        .declarations
+        .iter()
+        .find(|decl| decl.name.value == "Server")
+        .map(|decl| decl.expr.clone());
+    let request = CheckRequest {
+        command: CheckCommand::Conform,
+        model: None,
+        target: Some("Server".to_string()),
+    };
+    let input = ConformanceInput {
+        spec,
+        log: parse_trace_log(log, "trace.log").expect("parse log"),
+    };
+    ConformanceChecker.check(&request, &input)
+}
+
+#[test]
+fn trace_log_mixes_plain_labels_and_jsonl() {
+    let log = parse_trace_log(
+        "req\n\n{\"label\": \"ack\", \"ts\": 3}\n  err  \n",
+        "trace.log",
+    )
+    .expect("parse log");
+    let labels = log.iter().map(|e| e.label.as_str()).collect::<Vec<_>>();
+    assert_eq!(labels, ["req", "ack", "err"]);
+    assert_eq!(log[1].span.start_line, 3);
+    assert_eq!(log[2].span.start_line, 4);
+}
+
+#[test]
+fn trace_log_rejects_malformed_json_and_tau() {
+    let err = parse_trace_log("req\n{\"event\": \"ack\"}\n", "trace.log").unwrap_err();
+    assert_eq!(err.span.start_line, 2);
+    assert!(err
+        .to_string()
+        .starts_with("trace.log:2:1: invalid log event JSON"));
+
+    let err = parse_trace_log("tau\n", "trace.log").unwrap_err();
+    assert!(err.message.contains("not an observable event"), "{err}");
+}
+
+#[test]
+fn conforming_log_passes_across_hidden_steps() {
+    let result = conform("req\nack\nreq\nack\nerr\n");
+    assert_eq!(result.name, "conform");
+    assert_eq!(result.status, Status::Pass);
+    assert!(result.counterexample.is_none());
+}
+
+#[test]
+fn first_nonconforming_event_reports_allowed_events_and_log_line() {
+    let result = conform("req\nack\nack\nreq\n");
+    assert_eq!(result.status, Status::Fail);
+    let cex = result.counterexample.expect("counterexample");
+    let labels = cex
+        .events
+        .iter()
+        .map(|e| e.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["req", "ack", "ack"]);
+    assert!(cex.tags.iter().any(|tag| tag == "conformance"));
+    assert!(cex.events[0]
+        .spans
+        .iter()
+        .all(|span| span.path == "model.cspm"));
+    assert!(!cex.events[0].spans.is_empty());
+    assert!(cex.events[2].spans.is_empty());
+
+    let conformance = cex.conformance.expect("conformance");
+    assert_eq!(conformance.allowed, ["err", "req"]);
+    assert_eq!(conformance.span.path, "trace.log");
+    assert_eq!(conformance.span.start_line, 3);
+    let explanation = cex.explanation.expect("explanation");
+    assert!(
+        explanation.contains("log event ack (trace.log:3)"),
+        "{explanation}"
+    );
+}
@@ -0,0 +1,63 @@
+use cspx_core::ir::Module;
+use cspx_core::{model_coverage, Frontend, ModelCoverage, RunLimits, SimpleFrontend};
+
+fn coverage(input: &str) -> ModelCoverage {
+    let module: Module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    model_coverage(&module, &RunLimits::unlimited()).expect("coverage")
+}
+
+fn names(entries: &[cspx_core::CoverageEntry]) -> Vec<&str> {
+    entries.iter().map(|entry| entry.name.as_str()).collect()
+}
+
+#[test]
+fn coverage_reports_dead_processes_prefixes_and_events() {
+    let input = r#"channel req, ack, err
+channel out : {0..2}
+Server = req -> (ack -> Server [] out!1 -> Server)
+Unused = err -> STOP
+assert Server :[deadlock free [F]]
+"#;
+    let result = coverage(input);
+    assert!(result.complete);
+    assert_eq!(names(&result.unreached_processes), vec!["Unused"]);
+    assert_eq!(result.unreached_processes[0].span.start_line, 4);
+    assert_eq!(names(&result.unreached_prefixes), vec!["err"]);
+    assert_eq!(result.unreached_prefixes[0].span.start_line, 4);
+    assert_eq!(
+        names(&result.never_enabled_events),
+        vec!["err", "out.0", "out.2"]
+    );
+    assert_eq!(result.never_enabled_events[1].span.start_line, 2);
+}
+
+#[test]
+fn coverage_follows_synchronisation_and_hiding() {
+    let input = r#"channel a, b, c, d
+P = a -> b -> P
+Q = a -> (c -> d -> Q [] b -> Q)
+SYS = (P [|{|a, b, c|}|] Q) \\ {|b|}
+assert SYS :[deadlock free [F]]
+"#;
+    let result = coverage(input);
+    assert!(result.unreached_processes.is_empty());
+    // `c` is offered but blocked by P, so only what follows it is unreached.
+    assert_eq!(names(&result.unreached_prefixes), vec!["d"]);
+    assert_eq!(result.unreached_prefixes[0].span.start_line, 3);
+    assert_eq!(names(&result.never_enabled_events), vec!["c", "d"]);
+}
+
+#[test]
+fn fully_exercised_model_has_empty_coverage_report() {
+    let result = coverage(
+        r#"channel a, b
+P = a -> P [] b -> P
+"#,
+    );
+    assert!(result.never_enabled_events.is_empty());
+    assert!(result.unreached_processes.is_empty());
+    assert!(result.unreached_prefixes.is_empty());
+}
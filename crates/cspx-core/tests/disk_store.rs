@@ -2,6 +2,7 @@ use cspx_core::state_codec::StateCodecError;
 use cspx_core::{DiskStateStore, DiskStateStoreOpenOptions, StateCodec, StateStore};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
@@ -83,8 +84,39 @@ fn disk_state_store_rebuilds_index_when_corrupted() {
     assert_eq!(store.len(), 2);
 }
 
+fn segment(path: &Path, index: u32) -> PathBuf {
+    path.with_extension(format!("0.{index:06}.seg"))
+}
+
+fn small_segments() -> DiskStateStoreOpenOptions {
+    DiskStateStoreOpenOptions {
+        segment_bytes: 40,
+        ..DiskStateStoreOpenOptions::default()
+    }
+}
+
 #[test]
-fn disk_state_store_ignores_partial_log_tail() {
+fn disk_state_store_writes_checksummed_binary_records() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+
+    {
+        let mut store = DiskStateStore::open(&path, ByteCodec).expect("open");
+        assert!(store.insert(7).expect("insert"));
+    }
+
+    let manifest = fs::read_to_string(&path).expect("read manifest");
+    assert_eq!(manifest, "cspx-disk-log v2\ngeneration=0\n");
+    let data = fs::read(segment(&path, 0)).expect("read segment");
+    assert_eq!(&data[..8], b"CSPXSEG\0");
+    assert_eq!(&data[8..12], &2u32.to_le_bytes());
+    assert_eq!(&data[12..16], &1u32.to_le_bytes());
+    assert_eq!(&data[16..20], &crc32fast::hash(&[7]).to_le_bytes());
+    assert_eq!(&data[20..], &[7]);
+}
+
+#[test]
+fn disk_state_store_truncates_torn_tail_record() {
     let dir = tempfile::tempdir().expect("tempdir");
     let path = dir.path().join("states.log");
 
@@ -94,32 +126,159 @@ fn disk_state_store_ignores_partial_log_tail() {
         assert!(store.insert(2).expect("insert"));
     }
 
+    let seg = segment(&path, 0);
+    let complete_len = fs::metadata(&seg).expect("segment metadata").len();
     let mut file = OpenOptions::new()
         .append(true)
-        .open(&path)
+        .open(&seg)
         .expect("open for append");
-    file.write_all(b"ff").expect("write partial tail");
+    file.write_all(&[1, 0, 0, 0, 0xde, 0xad])
+        .expect("write torn tail");
     file.flush().expect("flush");
 
     let mut store = DiskStateStore::open(&path, ByteCodec).expect("reopen");
     assert_eq!(store.len(), 2);
+    assert_eq!(store.metrics().log_truncated_bytes, 6);
     assert!(!store.insert(1).expect("dedup"));
     assert!(!store.insert(2).expect("dedup"));
+    assert_eq!(
+        fs::metadata(&seg).expect("segment metadata").len(),
+        complete_len
+    );
+}
+
+#[test]
+fn disk_state_store_truncates_final_record_with_bad_checksum() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+
+    {
+        let mut store = DiskStateStore::open(&path, ByteCodec).expect("open");
+        for state in 1u8..=3 {
+            assert!(store.insert(state).expect("insert"));
+        }
+    }
 
-    let log = fs::read(&path).expect("read normalized log");
-    assert!(!log.ends_with(b"ff"));
+    let seg = segment(&path, 0);
+    let mut data = fs::read(&seg).expect("read segment");
+    let last = data.len() - 1;
+    data[last] ^= 0xff;
+    fs::write(&seg, &data).expect("corrupt last record");
+    fs::remove_file(path.with_extension("idx")).expect("remove idx");
+
+    let store = DiskStateStore::open(&path, ByteCodec).expect("reopen");
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.metrics().log_truncated_bytes, 9);
 }
 
 #[test]
-fn disk_state_store_errors_on_invalid_complete_log_line() {
+fn disk_state_store_errors_on_corrupt_record_before_tail() {
     let dir = tempfile::tempdir().expect("tempdir");
     let path = dir.path().join("states.log");
 
-    fs::write(&path, b"01\nzz\n").expect("write invalid log");
+    {
+        let mut store = DiskStateStore::open(&path, ByteCodec).expect("open");
+        for state in 1u8..=3 {
+            assert!(store.insert(state).expect("insert"));
+        }
+    }
+
+    let seg = segment(&path, 0);
+    let mut data = fs::read(&seg).expect("read segment");
+    // Payload of the first record, which is followed by two more.
+    data[20] ^= 0xff;
+    fs::write(&seg, &data).expect("corrupt first record");
+    fs::remove_file(path.with_extension("idx")).expect("remove idx");
+
     let err = DiskStateStore::open(&path, ByteCodec).expect_err("open should fail");
     assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
 }
 
+#[test]
+fn disk_state_store_rejects_unknown_log_format() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+
+    fs::write(&path, b"01\nzz\n").expect("write legacy log");
+    let err = DiskStateStore::open(&path, ByteCodec).expect_err("open should fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    fs::write(&path, b"cspx-disk-log v3\ngeneration=0\n").expect("write future manifest");
+    let err = DiskStateStore::open(&path, ByteCodec).expect_err("open should fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("version 3"), "{err}");
+}
+
+#[test]
+fn disk_state_store_rolls_segments_and_compacts() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+
+    let mut store =
+        DiskStateStore::open_with_options(&path, ByteCodec, small_segments()).expect("open");
+    for state in 0u8..10 {
+        assert!(store.insert(state).expect("insert"));
+    }
+    // Three 9-byte records fit after the 12-byte header of a 40-byte segment.
+    assert_eq!(store.segment_count(), 4);
+    store.compact().expect("compact");
+    assert_eq!(store.segment_count(), 4);
+    assert_eq!(store.metrics().compactions, 1);
+    assert!(!segment(&path, 0).exists());
+    assert!(path.with_extension("1.000003.seg").exists());
+    assert!(store.insert(10).expect("insert after compaction"));
+    assert!(!store.insert(3).expect("dedup after compaction"));
+    drop(store);
+
+    let manifest = fs::read_to_string(&path).expect("read manifest");
+    assert!(manifest.contains("generation=1"), "{manifest}");
+    let store =
+        DiskStateStore::open_with_options(&path, ByteCodec, small_segments()).expect("reopen");
+    assert_eq!(store.len(), 11);
+    assert_eq!(store.metrics().index_entries_loaded, 11);
+}
+
+#[test]
+fn disk_state_store_compacts_on_open_after_many_segments() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+    let options = DiskStateStoreOpenOptions {
+        compact_after_segments: 2,
+        ..DiskStateStoreOpenOptions::default()
+    };
+
+    for state in 0u8..3 {
+        let mut store = DiskStateStore::open_with_options(&path, ByteCodec, options).expect("open");
+        assert!(store.insert(state).expect("insert"));
+        assert_eq!(store.metrics().compactions, 0);
+    }
+    assert!(segment(&path, 2).exists());
+
+    let store = DiskStateStore::open_with_options(&path, ByteCodec, options).expect("reopen");
+    assert_eq!(store.metrics().compactions, 1);
+    assert_eq!(store.segment_count(), 1);
+    assert_eq!(store.len(), 3);
+    assert!(!segment(&path, 0).exists());
+}
+
+#[test]
+fn disk_state_store_removes_segments_of_other_generations() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+
+    {
+        let mut store = DiskStateStore::open(&path, ByteCodec).expect("open");
+        assert!(store.insert(1).expect("insert"));
+    }
+    // Left behind by a compaction that crashed before switching the manifest.
+    let stray = path.with_extension("1.000000.seg");
+    fs::write(&stray, b"partial").expect("write stray segment");
+
+    let store = DiskStateStore::open(&path, ByteCodec).expect("reopen");
+    assert_eq!(store.len(), 1);
+    assert!(!stray.exists());
+}
+
 #[test]
 fn disk_state_store_metrics_track_io_and_collisions() {
     let dir = tempfile::tempdir().expect("tempdir");
@@ -156,6 +315,7 @@ fn disk_state_store_metrics_capture_lock_retry_wait() {
                 lock_retry_count: 50,
                 lock_retry_backoff: Duration::from_millis(1),
                 index_flush_every: 1,
+                ..DiskStateStoreOpenOptions::default()
             },
         )
         .expect("retry open");
@@ -238,6 +398,36 @@ fn disk_state_store_batches_index_writes() {
     assert_eq!(metrics.pending_index_updates, 2);
 }
 
+#[test]
+fn disk_state_store_appends_index_updates() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("states.log");
+
+    {
+        let mut store = DiskStateStore::open_with_options(
+            &path,
+            ByteCodec,
+            DiskStateStoreOpenOptions {
+                index_flush_every: 1,
+                ..DiskStateStoreOpenOptions::default()
+            },
+        )
+        .expect("open");
+        for state in 0u8..200 {
+            assert!(store.insert(state).expect("insert"));
+        }
+        let metrics = store.metrics();
+        assert_eq!(metrics.index_write_ops, 201);
+        // Each flush appends one entry and one commit record instead of
+        // rewriting the whole index.
+        assert!(metrics.index_write_bytes < 200 * 64);
+    }
+
+    let reopened = DiskStateStore::open(&path, ByteCodec).expect("reopen");
+    assert_eq!(reopened.len(), 200);
+    assert_eq!(reopened.metrics().index_entries_loaded, 200);
+}
+
 #[test]
 fn disk_state_store_drop_flushes_pending_index_updates() {
     let dir = tempfile::tempdir().expect("tempdir");
@@ -1,6 +1,7 @@
 use cspx_core::state_codec::StateCodecError;
 use cspx_core::{
-    DiskStateStore, HybridStateStore, HybridStateStoreOptions, StateCodec, StateStore,
+    DiskStateStore, DiskStateStoreOpenOptions, HybridStateStore, HybridStateStoreOptions,
+    StateCodec, StateStore,
 };
 use std::fs;
 
@@ -71,6 +72,29 @@ fn hybrid_store_spills_and_persists_states() {
     assert_eq!(persisted.len(), 3);
 }
 
+#[test]
+fn hybrid_store_spill_on_request_moves_states_to_disk() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("spill.log");
+    let mut store =
+        HybridStateStore::open(&path, ByteCodec, HybridStateStoreOptions::default()).expect("open");
+
+    assert!(store.insert(1).expect("insert"));
+    assert!(store.insert(2).expect("insert"));
+    assert_eq!(store.memory_bytes(), None);
+    store.spill().expect("spill");
+    assert!(store.is_spilling());
+    assert!(store.memory_bytes().expect("memory bytes") > 0);
+    assert_eq!(store.len(), 2);
+    assert!(!store.insert(1).expect("dedup on disk"));
+    assert!(store.insert(3).expect("insert on disk"));
+    assert_eq!(store.len(), 3);
+    assert_eq!(
+        store.spill_metrics().expect("spill metrics").log_write_ops,
+        3
+    );
+}
+
 #[test]
 fn hybrid_store_rejects_zero_threshold() {
     let dir = tempfile::tempdir().expect("tempdir");
@@ -106,15 +130,21 @@ fn hybrid_store_rejects_existing_spill_path() {
 }
 
 #[test]
+#[allow(clippy::permissions_set_readonly_false)]
 fn hybrid_store_insert_rolls_back_when_spill_write_fails() {
     let dir = tempfile::tempdir().expect("tempdir");
-    let path = dir.path().join("spill.log");
+    let spill_dir = dir.path().join("spill");
+    let path = spill_dir.join("spill.log");
     let mut store = HybridStateStore::open(
         &path,
         ByteCodec,
         HybridStateStoreOptions {
             spill_threshold: 1,
-            ..HybridStateStoreOptions::default()
+            // Every record rolls to a new segment file.
+            disk_options: DiskStateStoreOpenOptions {
+                segment_bytes: 13,
+                ..DiskStateStoreOpenOptions::default()
+            },
         },
     )
     .expect("open");
@@ -122,23 +152,20 @@ fn hybrid_store_insert_rolls_back_when_spill_write_fails() {
     assert!(store.insert(2).expect("insert and activate spill"));
     assert_eq!(store.len(), 2);
 
-    let idx_path = path.with_extension("idx");
-    let mut log_permissions = fs::metadata(&path).expect("log metadata").permissions();
-    log_permissions.set_readonly(true);
-    fs::set_permissions(&path, log_permissions).expect("set log readonly");
-    let mut idx_permissions = fs::metadata(&idx_path).expect("idx metadata").permissions();
-    idx_permissions.set_readonly(true);
-    fs::set_permissions(&idx_path, idx_permissions).expect("set idx readonly");
+    let mut permissions = fs::metadata(&spill_dir)
+        .expect("dir metadata")
+        .permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&spill_dir, permissions).expect("set dir readonly");
 
     let _ = store.insert(3).expect_err("spill write must fail");
     assert_eq!(store.len(), 2);
 
-    let mut log_permissions = fs::metadata(&path).expect("log metadata").permissions();
-    log_permissions.set_readonly(false);
-    fs::set_permissions(&path, log_permissions).expect("restore log writable");
-    let mut idx_permissions = fs::metadata(&idx_path).expect("idx metadata").permissions();
-    idx_permissions.set_readonly(false);
-    fs::set_permissions(&idx_path, idx_permissions).expect("restore idx writable");
+    let mut permissions = fs::metadata(&spill_dir)
+        .expect("dir metadata")
+        .permissions();
+    permissions.set_readonly(false);
+    fs::set_permissions(&spill_dir, permissions).expect("restore dir writable");
 
     assert!(store.insert(3).expect("retry insert"));
     assert_eq!(store.len(), 3);
@@ -0,0 +1,128 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::lts_explicit::module_from_lts_file;
+use cspx_core::{
+    explore, CheckRequest, Checker, DeadlockChecker, ExplicitLts, ExplicitLtsProvider, Frontend,
+    InMemoryStateStore, RefinementChecker, RefinementInput, SimpleFrontend, Status,
+    TransitionProvider, VecWorkQueue,
+};
+
+const COMP_AUT: &str = r#"des (0, 3, 3)
+(0, "a", 1)
+(1, i, 2)
+(2, "b", 0)
+"#;
+
+#[test]
+fn parse_aut_maps_internal_action_to_tau() {
+    let lts = ExplicitLts::parse_aut(COMP_AUT, "comp.aut").expect("parse");
+    assert_eq!(lts.initial(), 0);
+    assert_eq!(lts.state_count(), 3);
+    assert_eq!(lts.transition_count(), 3);
+    assert_eq!(lts.transitions_from(1), &[("tau".to_string(), 2)]);
+
+    let provider = ExplicitLtsProvider::new(lts);
+    let mut store = InMemoryStateStore::new();
+    let mut queue = VecWorkQueue::new();
+    let stats = explore(&provider, &mut store, &mut queue).expect("explore");
+    assert_eq!(stats.states, Some(3));
+    assert_eq!(stats.transitions, Some(3));
+}
+
+#[test]
+fn parse_aut_reports_line_of_malformed_transition() {
+    let input = "des (0, 2, 2)\n(0, \"a\", 1)\n(1 \"b\" 0)\n";
+    let err = ExplicitLts::parse_aut(input, "bad.aut").expect_err("must fail");
+    let span = err.span.expect("span");
+    assert_eq!(span.path, "bad.aut");
+    assert_eq!(span.start_line, 3);
+}
+
+#[test]
+fn parse_aut_rejects_transition_count_mismatch() {
+    let input = "des (0, 2, 2)\n(0, \"a\", 1)\n";
+    let err = ExplicitLts::parse_aut(input, "short.aut").expect_err("must fail");
+    assert!(err.message.contains("transition count mismatch"));
+}
+
+#[test]
+fn parse_json_sorts_outgoing_transitions() {
+    let input = r#"{
+  "initial": 0,
+  "states": 2,
+  "transitions": [
+    {"from": 0, "label": "b", "to": 1},
+    {"from": 0, "label": "a", "to": 1}
+  ]
+}"#;
+    let lts = ExplicitLts::parse_json(input, "comp.json").expect("parse");
+    let provider = ExplicitLtsProvider::new(lts);
+    let labels: Vec<String> = provider
+        .transitions(&provider.initial_state())
+        .into_iter()
+        .map(|(t, _)| t.label)
+        .collect();
+    assert_eq!(labels, vec!["a".to_string(), "b".to_string()]);
+}
+
+#[test]
+fn load_lts_composes_with_cspm_operators() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dir.path().join("comp.aut"), COMP_AUT).expect("write aut");
+    let model_path = dir.path().join("model.cspm");
+    let input = r#"channel a
+channel b
+P = load_lts("comp.aut") \\ {|b|}
+P
+"#;
+
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, &model_path.to_string_lossy())
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let result = DeadlockChecker.check(&request, &module);
+    assert_eq!(result.status, Status::Pass);
+}
+
+#[test]
+fn load_lts_reports_missing_file_with_span() {
+    let input = "P = load_lts(\"missing.aut\")\nP\n";
+    let Err(err) = SimpleFrontend.parse_and_typecheck(input, "model.cspm") else {
+        panic!("missing LTS file must fail");
+    };
+    assert!(err.message.contains("load_lts failed"));
+    let span = err.span.expect("span");
+    assert_eq!(span.start_line, 1);
+}
+
+#[test]
+fn refinement_accepts_explicit_lts_modules() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let spec_path = dir.path().join("spec.aut");
+    let impl_path = dir.path().join("impl.aut");
+    std::fs::write(&spec_path, "des (0, 2, 2)\n(0, \"a\", 1)\n(0, \"b\", 1)\n").expect("spec");
+    std::fs::write(&impl_path, "des (0, 1, 2)\n(0, \"c\", 1)\n").expect("impl");
+
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::T),
+        target: None,
+    };
+    let input = RefinementInput {
+        spec: module_from_lts_file(&spec_path),
+        impl_: module_from_lts_file(&impl_path),
+    };
+    let result = RefinementChecker.check(&request, &input);
+    assert_eq!(result.status, Status::Fail);
+    let counterexample = result.counterexample.expect("counterexample");
+    let labels: Vec<&str> = counterexample
+        .events
+        .iter()
+        .map(|event| event.label.as_str())
+        .collect();
+    assert_eq!(labels, vec!["c"]);
+}
@@ -0,0 +1,260 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::ir::Module;
+use cspx_core::{
+    CheckRequest, Checker, CspmState, CspmStateCodec, DeadlockChecker, DiskPredecessorStore,
+    DivergenceChecker, Frontend, HybridStateStore, HybridStateStoreOptions, HybridWorkQueue,
+    HybridWorkQueueOptions, InMemoryPredecessorStore, InMemorySearch, MemoryBudget,
+    PredecessorStore, ReasonKind, RefinementChecker, RefinementInput, RunLimits, SearchBackend,
+    SimpleFrontend, Status,
+};
+
+// 4^6 interleaved states, all deadlock free.
+const COUNTERS: &str = r#"channel a, b, c, d : {0..5}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+C4 = a.4 -> b.4 -> c.4 -> d.4 -> C4
+C5 = a.5 -> b.5 -> c.5 -> d.5 -> C5
+System = ((((C0 ||| C1) ||| C2) ||| C3) ||| C4) ||| C5
+assert System :[deadlock free [F]]
+"#;
+
+// 4^4 interleaved states with the composition as the entry process.
+const ENTRY_COUNTERS: &str = r#"channel a, b, c, d : {0..3}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+((C0 ||| C1) ||| C2) ||| C3
+"#;
+
+fn module(input: &str) -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn deadlock_request() -> CheckRequest {
+    CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    }
+}
+
+fn budget(bytes: u64) -> RunLimits {
+    let mut limits = RunLimits::unlimited();
+    limits.memory = Some(MemoryBudget::new(bytes));
+    limits
+}
+
+#[test]
+fn exhausted_budget_stops_with_out_of_memory_and_partial_stats() {
+    let module = module(COUNTERS);
+    let request = deadlock_request();
+    let full = DeadlockChecker.check(&request, &module);
+    assert_eq!(full.status, Status::Pass);
+    assert_eq!(
+        full.stats.as_ref().and_then(|stats| stats.states),
+        Some(4096)
+    );
+
+    let limits = budget(64 * 1024);
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.limits = Some(&limits);
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+
+    assert_eq!(result.status, Status::OutOfMemory);
+    let reason = result.reason.expect("reason");
+    assert_eq!(reason.kind, ReasonKind::OutOfMemory);
+    assert!(reason.message.expect("message").contains("65536"));
+    let states = result.stats.and_then(|stats| stats.states).expect("states");
+    assert!(states > 1 && states < 4096, "partial states: {states}");
+    let budget = limits.memory.as_ref().expect("budget");
+    assert!(budget.peak_bytes() > budget.limit_bytes());
+}
+
+#[test]
+fn generous_budget_keeps_the_verdict() {
+    let module = module(COUNTERS);
+    let request = deadlock_request();
+    let expected = DeadlockChecker.check(&request, &module);
+
+    let limits = budget(1 << 30);
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(4);
+    backend.limits = Some(&limits);
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+    assert_eq!(result, expected);
+    assert!(limits.memory.as_ref().expect("budget").peak_bytes() > 0);
+}
+
+#[test]
+fn spilling_hybrid_backend_completes_within_budget() {
+    let module = module(COUNTERS);
+    let request = deadlock_request();
+    let expected = DeadlockChecker.check(&request, &module);
+
+    let run = |limits: &RunLimits| {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut store = HybridStateStore::open(
+            dir.path().join("spill.log"),
+            CspmStateCodec,
+            HybridStateStoreOptions::default(),
+        )
+        .expect("open store");
+        let mut queue = HybridWorkQueue::open(
+            dir.path().join("queue.log"),
+            CspmStateCodec,
+            HybridWorkQueueOptions::default(),
+        )
+        .expect("open queue");
+        let mut predecessors = InMemoryPredecessorStore::new();
+        let mut backend = SearchBackend {
+            store: &mut store,
+            queue: &mut queue,
+            predecessors: &mut predecessors,
+            workers: 1,
+            por: None,
+            symmetry: None,
+            limits: Some(limits),
+            checkpoint: None,
+            compression: None,
+            max_counterexamples: 1,
+        };
+        let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+        (result, store.is_spilling(), queue.is_spilling())
+    };
+
+    let unbounded = budget(u64::MAX);
+    let (result, store_spilled, _) = run(&unbounded);
+    assert_eq!(result, expected);
+    assert!(!store_spilled);
+    let peak = unbounded.memory.as_ref().expect("budget").peak_bytes();
+
+    // The spill starts at three quarters of the limit, well below the peak
+    // of the run that never spilled.
+    let tight = budget(peak + 64 * 1024);
+    let (result, store_spilled, queue_spilled) = run(&tight);
+    assert_eq!(result, expected);
+    assert!(store_spilled);
+    assert!(queue_spilled);
+}
+
+#[test]
+fn disk_predecessors_stay_out_of_the_estimate() {
+    let module = module(COUNTERS);
+    let request = deadlock_request();
+    let expected = DeadlockChecker.check(&request, &module);
+
+    let peak = |predecessors: &mut dyn PredecessorStore<CspmState>| {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut store = HybridStateStore::open(
+            dir.path().join("spill.log"),
+            CspmStateCodec,
+            HybridStateStoreOptions::default(),
+        )
+        .expect("open store");
+        let mut queue = HybridWorkQueue::open(
+            dir.path().join("queue.log"),
+            CspmStateCodec,
+            HybridWorkQueueOptions::default(),
+        )
+        .expect("open queue");
+        let limits = budget(u64::MAX);
+        let mut backend = SearchBackend {
+            store: &mut store,
+            queue: &mut queue,
+            predecessors,
+            workers: 1,
+            por: None,
+            symmetry: None,
+            limits: Some(&limits),
+            checkpoint: None,
+            compression: None,
+            max_counterexamples: 1,
+        };
+        let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+        assert_eq!(result, expected);
+        limits.memory.expect("budget").peak_bytes()
+    };
+
+    let in_memory = peak(&mut InMemoryPredecessorStore::new());
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut disk = DiskPredecessorStore::open(dir.path().join("predecessors.log"), CspmStateCodec)
+        .expect("open predecessors");
+    let on_disk = peak(&mut disk);
+    assert!(on_disk < in_memory, "{on_disk} >= {in_memory}");
+}
+
+#[test]
+fn divergence_tau_graph_counts_against_the_budget() {
+    let input = COUNTERS.replace(
+        "assert System :[deadlock free [F]]",
+        "Hidden = System \\\\ {|a, b, c, d|}
+assert Hidden :[deadlock free [F]]
+assert Hidden :[divergence free [FD]]",
+    );
+    let module = module(&input);
+    let peak = |target: &str| {
+        let request = CheckRequest {
+            command: CheckCommand::Check,
+            model: None,
+            target: Some(target.to_string()),
+        };
+        let limits = budget(u64::MAX);
+        let mut search = InMemorySearch::new();
+        let mut backend = search.backend(1);
+        backend.limits = Some(&limits);
+        let result = if target == "deadlock free" {
+            DeadlockChecker.check_with(&request, &module, &mut backend)
+        } else {
+            DivergenceChecker.check_with(&request, &module, &mut backend)
+        };
+        assert_eq!(result.stats.and_then(|stats| stats.states), Some(4096));
+        limits.memory.expect("budget").peak_bytes()
+    };
+
+    // Every one of the 6 moves per state is a tau edge of the graph.
+    let deadlock = peak("deadlock free");
+    let divergence = peak("divergence free");
+    assert!(
+        divergence >= deadlock + 4096 * 6 * 8,
+        "{divergence} < {deadlock} + tau edges"
+    );
+}
+
+#[test]
+fn refinement_stops_with_out_of_memory_and_partial_stats() {
+    let module = module(ENTRY_COUNTERS);
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::T),
+        target: Some("spec impl".to_string()),
+    };
+    let input = RefinementInput {
+        spec: module.clone(),
+        impl_: module,
+    };
+    let expected = RefinementChecker.check(&request, &input);
+    assert_eq!(expected.status, Status::Pass);
+
+    let limits = budget(64 * 1024);
+    let result = RefinementChecker.check_with_limits(&request, &input, 2, &limits);
+    assert_eq!(result.status, Status::OutOfMemory);
+    assert_eq!(result.model.as_deref(), Some("T"));
+    assert_eq!(
+        result.reason.map(|reason| reason.kind),
+        Some(ReasonKind::OutOfMemory)
+    );
+    assert!(result.counterexample.is_none());
+    let states = result.stats.and_then(|stats| stats.states).expect("states");
+    assert!(states < 256, "partial states: {states}");
+
+    let generous = budget(1 << 30);
+    let result = RefinementChecker.check_with_limits(&request, &input, 2, &generous);
+    assert_eq!(result, expected);
+}
@@ -0,0 +1,130 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::{
+    CheckRequest, ExplicitLts, ExplicitLtsProvider, LimitExceeded, NormalisedSpec,
+    RefinementChecker, RunLimits, Status,
+};
+use std::collections::BTreeSet;
+
+// a -> (b -> STOP |~| c -> STOP), with the internal choice on both branches of `a`.
+const SPEC_AUT: &str = r#"des (0, 6, 6)
+(0, "a", 1)
+(0, "a", 2)
+(1, i, 3)
+(2, i, 4)
+(3, "b", 5)
+(4, "c", 5)
+"#;
+
+fn spec_provider() -> ExplicitLtsProvider {
+    ExplicitLtsProvider::new(ExplicitLts::parse_aut(SPEC_AUT, "spec.aut").expect("parse"))
+}
+
+fn labels(items: &[&str]) -> BTreeSet<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+// The spec closure of a normalised node also holds its acceptance states.
+fn comparable(cx: cspx_core::Counterexample) -> (Vec<String>, Vec<String>) {
+    let events = cx.events.into_iter().map(|event| event.label).collect();
+    let tags = cx
+        .tags
+        .into_iter()
+        .filter(|tag| !tag.starts_with("fd_spec_closure_max:"))
+        .collect();
+    (events, tags)
+}
+
+#[test]
+fn normalise_determinises_and_records_acceptances() {
+    let spec = NormalisedSpec::from_provider(&spec_provider());
+
+    assert_eq!(spec.node_count(), 3);
+    assert_eq!(spec.transition_count(), 3);
+    let initial = &spec.nodes()[spec.initial()];
+    assert_eq!(initial.acceptances, vec![labels(&["a"])]);
+    let after_a = &spec.nodes()[initial.transitions["a"]];
+    assert_eq!(after_a.acceptances, vec![labels(&["b"]), labels(&["c"])]);
+    assert!(!after_a.divergent);
+}
+
+#[test]
+fn normalise_marks_tau_cycles_as_divergent() {
+    let lts =
+        ExplicitLts::parse_aut("des (0, 2, 2)\n(0, i, 1)\n(1, i, 0)\n", "div.aut").expect("parse");
+    let spec = NormalisedSpec::from_provider(&ExplicitLtsProvider::new(lts));
+
+    assert_eq!(spec.node_count(), 1);
+    assert!(spec.nodes()[0].divergent);
+    assert!(spec.nodes()[0].acceptances.is_empty());
+}
+
+#[test]
+fn normalised_spec_round_trips_through_json() {
+    let spec = NormalisedSpec::from_provider(&spec_provider());
+    let parsed = NormalisedSpec::parse(&spec.to_json(), "spec.norm").expect("parse");
+    assert_eq!(parsed, spec);
+}
+
+#[test]
+fn parse_rejects_out_of_range_target() {
+    let input = r#"{
+  "format": "cspx-norm",
+  "version": 1,
+  "initial": 0,
+  "nodes": [{"acceptances": [["a"]], "divergent": false, "transitions": {"a": 3}}]
+}"#;
+    let err = NormalisedSpec::parse(input, "bad.norm").expect_err("must fail");
+    assert!(err.message.contains("target 3 out of range"));
+    assert_eq!(err.span.expect("span").path, "bad.norm");
+}
+
+#[test]
+fn refinement_against_normalised_spec_matches_source_spec() {
+    let spec = spec_provider();
+    let normalised = NormalisedSpec::from_provider(&spec);
+    let impls = [
+        "des (0, 2, 3)\n(0, \"a\", 1)\n(1, \"b\", 2)\n",
+        "des (0, 1, 2)\n(0, \"a\", 1)\n",
+        "des (0, 2, 2)\n(0, \"a\", 1)\n(1, \"a\", 0)\n",
+        "des (0, 3, 3)\n(0, \"a\", 1)\n(1, i, 1)\n(1, \"c\", 2)\n",
+    ];
+
+    for (idx, source) in impls.iter().enumerate() {
+        let impl_ = ExplicitLtsProvider::new(
+            ExplicitLts::parse_aut(source, "impl.aut").expect("parse impl"),
+        );
+        for model in [RefinementModel::T, RefinementModel::F, RefinementModel::FD] {
+            let request = CheckRequest {
+                command: CheckCommand::Refine,
+                model: Some(model.clone()),
+                target: None,
+            };
+            let direct = RefinementChecker.check_providers(&request, &spec, &impl_);
+            let via_norm = RefinementChecker.check_providers(&request, &normalised, &impl_);
+            assert_ne!(direct.status, Status::Error);
+            assert_eq!(via_norm.status, direct.status, "impl {idx} {model:?}");
+            assert_eq!(via_norm.stats, direct.stats, "impl {idx} {model:?}");
+            assert_eq!(
+                via_norm.counterexample.map(comparable),
+                direct.counterexample.map(comparable),
+                "impl {idx} {model:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn normalise_stops_when_the_run_is_cancelled() {
+    let limits = RunLimits::unlimited();
+    let spec = NormalisedSpec::from_provider_with_limits(&spec_provider(), &limits)
+        .expect("within limits");
+    assert_eq!(
+        spec.to_json(),
+        NormalisedSpec::from_provider(&spec_provider()).to_json()
+    );
+
+    limits.cancel();
+    let err = NormalisedSpec::from_provider_with_limits(&spec_provider(), &limits)
+        .expect_err("cancelled");
+    assert_eq!(err, LimitExceeded::Cancelled);
+}
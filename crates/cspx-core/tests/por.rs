@@ -0,0 +1,164 @@
+use cspx_core::check::CheckCommand;
+use cspx_core::ir::Module;
+use cspx_core::types::Minimality;
+use cspx_core::{
+    CheckRequest, CheckResult, Checker, CspmTransitionProvider, DeadlockChecker, DivergenceChecker,
+    Frontend, InMemorySearch, PorStats, SimpleFrontend, Status, TransitionProvider,
+};
+
+fn parse(input: &str) -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn request(target: &str) -> CheckRequest {
+    CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some(target.to_string()),
+    }
+}
+
+fn check_reduced(target: &str, module: &Module) -> (CheckResult, PorStats) {
+    let mut por = PorStats::default();
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.por = Some(&mut por);
+    let request = request(target);
+    let result = match target {
+        "deadlock free" => DeadlockChecker.check_with(&request, module, &mut backend),
+        _ => DivergenceChecker.check_with(&request, module, &mut backend),
+    };
+    (result, por)
+}
+
+fn check_full(target: &str, module: &Module) -> CheckResult {
+    match target {
+        "deadlock free" => DeadlockChecker.check(&request(target), module),
+        _ => DivergenceChecker.check(&request(target), module),
+    }
+}
+
+fn states(result: &CheckResult) -> u64 {
+    result
+        .stats
+        .as_ref()
+        .and_then(|stats| stats.states)
+        .expect("states")
+}
+
+const PHILOSOPHERS: &str = r#"channel think, eat : {0..3}
+PHIL0 = think.0 -> eat.0 -> PHIL0
+PHIL1 = think.1 -> eat.1 -> PHIL1
+PHIL2 = think.2 -> eat.2 -> PHIL2
+PHIL3 = think.3 -> eat.3 -> PHIL3
+System = ((PHIL0 ||| PHIL1) ||| PHIL2) ||| PHIL3
+assert System :[deadlock free [F]]
+"#;
+
+#[test]
+fn por_reduces_interleaving_without_changing_deadlock_verdict() {
+    let module = parse(PHILOSOPHERS);
+    let full = check_full("deadlock free", &module);
+    let (reduced, por) = check_reduced("deadlock free", &module);
+
+    assert_eq!(full.status, Status::Pass);
+    assert_eq!(reduced.status, Status::Pass);
+    assert_eq!(states(&full), 16);
+    assert!(states(&reduced) < states(&full));
+    assert!(por.ample_expansions > 0);
+    assert!(por.skipped_transitions > 0);
+}
+
+#[test]
+fn por_finds_deadlock_behind_independent_components() {
+    let module = parse(
+        r#"channel a, b, c, d
+Left = a -> Left
+Right = b -> c -> STOP
+Pair = b -> d -> Pair
+System = Left ||| (Right [|{|b, c, d|}|] Pair)
+assert System :[deadlock free [F]]
+"#,
+    );
+    let full = check_full("deadlock free", &module);
+    let (reduced, _) = check_reduced("deadlock free", &module);
+
+    assert_eq!(full.status, Status::Pass);
+    assert_eq!(reduced.status, Status::Pass);
+
+    let module = parse(
+        r#"channel a, b, c
+Left = a -> STOP
+Right = b -> STOP
+Sync = c -> STOP
+System = (Left ||| Right) [|{|c|}|] Sync
+assert System :[deadlock free [F]]
+"#,
+    );
+    let full = check_full("deadlock free", &module);
+    let (reduced, por) = check_reduced("deadlock free", &module);
+
+    assert_eq!(full.status, Status::Fail);
+    assert_eq!(reduced.status, Status::Fail);
+    assert!(por.ample_expansions > 0);
+    assert_eq!(
+        full.counterexample.expect("counterexample").minimality,
+        Minimality::Shortest
+    );
+    let counterexample = reduced.counterexample.expect("counterexample");
+    assert_eq!(counterexample.minimality, Minimality::None);
+    let events = counterexample
+        .events
+        .into_iter()
+        .map(|event| event.label)
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 2);
+}
+
+#[test]
+fn por_keeps_divergence_with_cycle_proviso() {
+    let module = parse(
+        r#"channel a, b, c
+Busy = a -> Busy
+Loop = b -> c -> Loop
+Div = Loop \\ {|b, c|}
+System = Busy ||| (b -> STOP [] Div)
+assert System :[divergence free [FD]]
+"#,
+    );
+    let full = check_full("divergence free", &module);
+    let (reduced, por) = check_reduced("divergence free", &module);
+
+    assert_eq!(full.status, Status::Fail);
+    assert_eq!(reduced.status, Status::Fail);
+    assert!(por.full_expansions > 0);
+}
+
+#[test]
+fn ample_sets_only_hold_tau_moves_for_divergence() {
+    let module = parse(
+        r#"channel a, b
+Loop = b -> Loop
+System = (a -> STOP) ||| (Loop \\ {|b|})
+System
+"#,
+    );
+    let provider = CspmTransitionProvider::from_module(&module).expect("provider");
+    let state = provider.initial_state();
+    assert_eq!(provider.transitions(&state).len(), 2);
+
+    let ample = provider
+        .ample_transitions(&state, true)
+        .expect("tau ample set");
+    assert!(ample
+        .iter()
+        .all(|(transition, _)| transition.label == "tau"));
+    let ample = provider
+        .ample_transitions(&state, false)
+        .expect("ample set");
+    assert_eq!(ample.len(), 1);
+    assert_eq!(ample[0].0.label, "a");
+}
@@ -0,0 +1,144 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::{
+    CheckRequest, DeadlockChecker, DeterminismChecker, DivergenceChecker, RefinementChecker,
+    Status, TransitionLabel, TransitionProvider,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Inc,
+    Reset,
+    Internal,
+}
+
+impl TransitionLabel for Action {
+    fn label(&self) -> &str {
+        match self {
+            Action::Inc => "inc",
+            Action::Reset => "reset",
+            Action::Internal => "internal",
+        }
+    }
+
+    fn is_tau(&self) -> bool {
+        matches!(self, Action::Internal)
+    }
+}
+
+struct Counter {
+    limit: u8,
+    reset: bool,
+    spin_at_limit: bool,
+}
+
+impl TransitionProvider for Counter {
+    type State = u8;
+    type Transition = Action;
+
+    fn initial_state(&self) -> Self::State {
+        0
+    }
+
+    fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)> {
+        let mut out = Vec::new();
+        if *state < self.limit {
+            out.push((Action::Inc, state + 1));
+        } else if self.spin_at_limit {
+            out.push((Action::Internal, *state));
+        }
+        if self.reset && *state > 0 {
+            out.push((Action::Reset, 0));
+        }
+        out
+    }
+}
+
+fn check_request() -> CheckRequest {
+    CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: None,
+    }
+}
+
+#[test]
+fn deadlock_checker_accepts_custom_provider() {
+    let provider = Counter {
+        limit: 2,
+        reset: false,
+        spin_at_limit: false,
+    };
+    let result = DeadlockChecker.check_provider(&check_request(), &provider);
+    assert_eq!(result.status, Status::Fail);
+    assert_eq!(result.stats.and_then(|stats| stats.states), Some(3));
+    let labels = result
+        .counterexample
+        .expect("counterexample")
+        .events
+        .into_iter()
+        .map(|event| event.label)
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["inc", "inc"]);
+
+    let provider = Counter {
+        limit: 2,
+        reset: true,
+        spin_at_limit: false,
+    };
+    let result = DeadlockChecker.check_provider(&check_request(), &provider);
+    assert_eq!(result.status, Status::Pass);
+}
+
+#[test]
+fn divergence_checker_uses_label_tau_identification() {
+    let provider = Counter {
+        limit: 1,
+        reset: false,
+        spin_at_limit: true,
+    };
+    let result = DivergenceChecker.check_provider(&check_request(), &provider);
+    assert_eq!(result.status, Status::Fail);
+    let labels = result
+        .counterexample
+        .expect("counterexample")
+        .events
+        .into_iter()
+        .map(|event| event.label)
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["inc", "tau"]);
+
+    let result = DeterminismChecker.check_provider(&check_request(), &provider);
+    assert_eq!(result.status, Status::Pass);
+}
+
+#[test]
+fn refinement_checker_accepts_distinct_provider_types() {
+    let spec = Counter {
+        limit: 3,
+        reset: true,
+        spin_at_limit: false,
+    };
+    let impl_ = Counter {
+        limit: 2,
+        reset: true,
+        spin_at_limit: false,
+    };
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::T),
+        target: None,
+    };
+    let result = RefinementChecker.check_providers(&request, &spec, &impl_);
+    assert_eq!(result.status, Status::Pass);
+
+    let result = RefinementChecker.check_providers(&request, &impl_, &spec);
+    assert_eq!(result.status, Status::Fail);
+    let labels = result
+        .counterexample
+        .expect("counterexample")
+        .events
+        .into_iter()
+        .map(|event| event.label)
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["inc", "inc", "inc"]);
+}
@@ -1,6 +1,7 @@
+use cspx_core::types::{CounterexampleType, Minimality};
 use cspx_core::{
-    check::CheckCommand, check::RefinementModel, CheckRequest, Checker, RefinementChecker,
-    RefinementInput,
+    check::CheckCommand, check::RefinementModel, CheckRequest, Checker, Frontend,
+    RefinementChecker, RefinementInput, RefinementOptions, RunLimits, SimpleFrontend,
 };
 
 fn spanned<T>(value: T, path: &str) -> cspx_core::ir::Spanned<T> {
@@ -287,7 +288,7 @@ fn traces_refinement_fails_on_extra_visible_event() {
     let counterexample = result.counterexample.expect("counterexample");
     assert_eq!(counterexample.events.len(), 1);
     assert_eq!(counterexample.events[0].label, "b");
-    assert!(counterexample.is_minimized);
+    assert_eq!(counterexample.minimality, Minimality::Shortest);
 }
 
 #[test]
@@ -331,7 +332,7 @@ fn traces_refinement_handles_tau_closure() {
     let counterexample = result.counterexample.expect("counterexample");
     assert_eq!(counterexample.events.len(), 1);
     assert_eq!(counterexample.events[0].label, "b");
-    assert!(counterexample.is_minimized);
+    assert_eq!(counterexample.minimality, Minimality::Shortest);
 }
 
 #[test]
@@ -416,9 +417,64 @@ fn failures_refinement_fails_on_refusal_mismatch() {
     assert_eq!(result.status, cspx_core::types::Status::Fail);
     let counterexample = result.counterexample.expect("counterexample");
     assert_eq!(counterexample.events.len(), 0);
-    assert!(counterexample.is_minimized);
+    assert_eq!(counterexample.minimality, Minimality::Shortest);
     assert!(counterexample.tags.iter().any(|t| t == "refusal_mismatch"));
     assert!(counterexample.tags.iter().any(|t| t == "refuse:b"));
+    assert_eq!(counterexample.kind, CounterexampleType::Failure);
+    let failure = counterexample.failure.expect("failure");
+    assert_eq!(failure.refusal, vec!["b".to_string()]);
+    assert_eq!(
+        failure.acceptances,
+        vec![vec!["a".to_string(), "b".to_string()]]
+    );
+    assert!(!failure.divergence);
+    assert_eq!(
+        counterexample.explanation.as_deref(),
+        Some("after ⟨⟩ impl can refuse {b} but spec must accept one of {a,b}")
+    );
+}
+
+#[test]
+fn failures_refinement_minimises_trace_mismatch_with_oracle() {
+    let spec_path = "spec.cspm";
+    let impl_path = "impl.cspm";
+
+    let spec_expr = prefix("a", stop(spec_path), spec_path);
+    let impl_expr = choice(
+        cspx_core::ir::ChoiceKind::External,
+        prefix("a", stop(impl_path), impl_path),
+        prefix("c", stop(impl_path), impl_path),
+        impl_path,
+    );
+
+    let spec = single_process_module(
+        "SPEC",
+        spec_expr,
+        vec![unit_channel("a", spec_path), unit_channel("c", spec_path)],
+        spec_path,
+    );
+    let impl_ = single_process_module(
+        "IMPL",
+        impl_expr,
+        vec![unit_channel("a", impl_path), unit_channel("c", impl_path)],
+        impl_path,
+    );
+
+    let checker = RefinementChecker;
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::F),
+        target: Some("spec impl".to_string()),
+    };
+    let input = RefinementInput { spec, impl_ };
+    let result = checker.check(&request, &input);
+
+    assert_eq!(result.status, cspx_core::types::Status::Fail);
+    let counterexample = result.counterexample.expect("counterexample");
+    assert!(counterexample.tags.iter().any(|t| t == "trace_mismatch"));
+    assert_eq!(counterexample.events.len(), 1);
+    assert_eq!(counterexample.events[0].label, "c");
+    assert_eq!(counterexample.minimality, Minimality::LocallyMinimal);
 }
 
 #[test]
@@ -487,12 +543,20 @@ fn failures_divergences_refinement_fails_on_impl_divergence() {
     assert_eq!(result.status, cspx_core::types::Status::Fail);
     let counterexample = result.counterexample.expect("counterexample");
     assert!(counterexample.events.iter().any(|e| e.label == "tau"));
-    assert!(counterexample.is_minimized);
+    assert_eq!(counterexample.minimality, Minimality::Shortest);
     assert!(counterexample
         .tags
         .iter()
         .any(|t| t == "divergence_mismatch"));
     assert!(counterexample.tags.iter().any(|t| t == "divergence"));
+    assert_eq!(counterexample.kind, CounterexampleType::Failure);
+    let failure = counterexample.failure.as_ref().expect("failure");
+    assert!(failure.divergence);
+    assert!(failure.refusal.is_empty());
+    assert_eq!(
+        counterexample.explanation.as_deref(),
+        Some("after ⟨⟩ impl can diverge but spec cannot")
+    );
     assert!(counterexample
         .tags
         .iter()
@@ -581,3 +645,117 @@ fn failures_divergences_refinement_reuses_closure_cache() {
     assert!(closure_cache_hits > 0);
     assert!(divergence_cache_hits > 0);
 }
+
+#[test]
+fn parallel_refinement_matches_serial_verdict_counterexample_and_tags() {
+    let spec_path = "spec.cspm";
+    let impl_path = "impl.cspm";
+
+    let spec_expr = choice(
+        cspx_core::ir::ChoiceKind::External,
+        prefix("a", ref_proc("SPEC", spec_path), spec_path),
+        prefix("b", ref_proc("SPEC", spec_path), spec_path),
+        spec_path,
+    );
+    let spec = single_process_module(
+        "SPEC",
+        spec_expr,
+        vec![
+            unit_channel("a", spec_path),
+            unit_channel("b", spec_path),
+            unit_channel("c", spec_path),
+        ],
+        spec_path,
+    );
+    let impl_ = cspx_core::ir::Module {
+        channels: vec![
+            unit_channel("a", impl_path),
+            unit_channel("b", impl_path),
+            unit_channel("c", impl_path),
+        ],
+        declarations: vec![
+            cspx_core::ir::ProcessDecl {
+                name: spanned("P0".to_string(), impl_path),
+                expr: choice(
+                    cspx_core::ir::ChoiceKind::External,
+                    prefix("a", ref_proc("P1", impl_path), impl_path),
+                    prefix("b", ref_proc("P2", impl_path), impl_path),
+                    impl_path,
+                ),
+            },
+            cspx_core::ir::ProcessDecl {
+                name: spanned("P1".to_string(), impl_path),
+                expr: choice(
+                    cspx_core::ir::ChoiceKind::External,
+                    prefix("b", ref_proc("P2", impl_path), impl_path),
+                    prefix("a", ref_proc("P0", impl_path), impl_path),
+                    impl_path,
+                ),
+            },
+            cspx_core::ir::ProcessDecl {
+                name: spanned("P2".to_string(), impl_path),
+                expr: prefix("a", prefix("c", stop(impl_path), impl_path), impl_path),
+            },
+        ],
+        assertions: Vec::new(),
+        entry: Some(ref_proc("P0", impl_path)),
+    };
+    let input = RefinementInput { spec, impl_ };
+
+    for model in [RefinementModel::T, RefinementModel::F, RefinementModel::FD] {
+        let request = CheckRequest {
+            command: CheckCommand::Refine,
+            model: Some(model.clone()),
+            target: Some("spec impl".to_string()),
+        };
+        let serial = RefinementChecker.check(&request, &input);
+        assert_eq!(serial.status, cspx_core::types::Status::Fail);
+        for workers in [2, 4] {
+            let parallel = RefinementChecker.check_with(&request, &input, workers);
+            assert_eq!(parallel.status, serial.status);
+            assert_eq!(parallel.stats, serial.stats);
+            let serial_cx = serial.counterexample.as_ref().expect("counterexample");
+            let parallel_cx = parallel.counterexample.as_ref().expect("counterexample");
+            assert_eq!(parallel_cx.events, serial_cx.events);
+            assert_eq!(parallel_cx.tags, serial_cx.tags);
+        }
+    }
+}
+
+#[test]
+fn failures_with_the_same_refusal_after_different_traces_are_both_reported() {
+    let parse = |input: &str| {
+        SimpleFrontend
+            .parse_and_typecheck(input, "model.cspm")
+            .expect("parse_and_typecheck")
+            .ir
+    };
+    let input = RefinementInput {
+        spec: parse("channel a, b, c\nSPEC = a -> SPEC [] b -> SPEC\n"),
+        impl_: parse("channel a, b, c\nIMPL = a -> STOP [] b -> ((c -> STOP) \\\\ {|c|})\n"),
+    };
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::F),
+        target: None,
+    };
+    let limits = RunLimits::unlimited();
+    let result = RefinementChecker.check_with_options(
+        &request,
+        &input,
+        RefinementOptions {
+            max_counterexamples: 5,
+            ..RefinementOptions::new(1, &limits)
+        },
+    );
+    let traces = result
+        .counterexamples
+        .iter()
+        .map(|counterexample| counterexample.events[0].label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(traces, ["a", "b"]);
+    assert_eq!(
+        result.counterexamples[0].tags,
+        result.counterexamples[1].tags
+    );
+}
@@ -0,0 +1,172 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::ir::{Module, PropertyKind};
+use cspx_core::{
+    replay_property, replay_refinement_input, CheckRequest, Checker, Counterexample,
+    DeadlockChecker, DeterminismChecker, DivergenceChecker, Frontend, RefinementChecker,
+    RefinementInput, Replay, ReplayVerdict, SimpleFrontend,
+};
+
+fn parse(input: &str) -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn counterexample(checker: &dyn Checker<Module>, kind: &str, module: &Module) -> Counterexample {
+    let request = CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some(kind.to_string()),
+    };
+    checker
+        .check(&request, module)
+        .counterexample
+        .expect("counterexample")
+}
+
+fn replay(kind: PropertyKind, input: &str, counterexample: &Counterexample) -> Replay {
+    replay_property(kind, &parse(input), counterexample).expect("replay")
+}
+
+#[test]
+fn deadlock_replay_tells_apart_still_fails_no_longer_valid_and_now_passes() {
+    let original = r#"channel a, b
+P = a -> b -> STOP
+assert P :[deadlock free [F]]
+"#;
+    let cex = counterexample(&DeadlockChecker, "deadlock free", &parse(original));
+    assert_eq!(cex.events.len(), 2);
+
+    let same = replay(PropertyKind::DeadlockFree, original, &cex);
+    assert_eq!(same.verdict, ReplayVerdict::StillFails);
+    assert_eq!(same.step, None);
+
+    // Extra internal steps between the events do not invalidate the trace.
+    let with_tau = r#"channel a, b, c
+P = (a -> (c -> b -> STOP) \\ {|c|})
+assert P :[deadlock free [F]]
+"#;
+    let replayed = replay(PropertyKind::DeadlockFree, with_tau, &cex);
+    assert_eq!(replayed.verdict, ReplayVerdict::StillFails);
+
+    let reordered = r#"channel a, b
+P = b -> a -> STOP
+assert P :[deadlock free [F]]
+"#;
+    let replayed = replay(PropertyKind::DeadlockFree, reordered, &cex);
+    assert_eq!(replayed.verdict, ReplayVerdict::NoLongerValid);
+    assert_eq!(replayed.step, Some(0));
+    assert_eq!(replayed.offered, vec!["b".to_string()]);
+
+    let fixed = r#"channel a, b
+P = a -> b -> P
+assert P :[deadlock free [F]]
+"#;
+    let replayed = replay(PropertyKind::DeadlockFree, fixed, &cex);
+    assert_eq!(replayed.verdict, ReplayVerdict::NowPasses);
+    assert_eq!(replayed.step, Some(2));
+}
+
+#[test]
+fn divergence_replay_points_at_the_divergence() {
+    let original = r#"channel a, b
+Loop = a -> Loop
+P = b -> (Loop \\ {|a|})
+assert P :[divergence free [FD]]
+"#;
+    let cex = counterexample(&DivergenceChecker, "divergence free", &parse(original));
+    let still = replay(PropertyKind::DivergenceFree, original, &cex);
+    assert_eq!(still.verdict, ReplayVerdict::StillFails);
+
+    let fixed = r#"channel a, b
+Loop = a -> Loop
+P = b -> Loop
+assert P :[divergence free [FD]]
+"#;
+    let replayed = replay(PropertyKind::DivergenceFree, fixed, &cex);
+    assert_eq!(replayed.verdict, ReplayVerdict::NowPasses);
+    assert_eq!(replayed.step, Some(cex.events.len() - 1));
+}
+
+#[test]
+fn determinism_replay_checks_the_choice_after_the_trace() {
+    let original = r#"channel a, b
+P = b -> ((a -> b -> STOP) |~| (a -> STOP))
+assert P :[deterministic [FD]]
+"#;
+    let cex = counterexample(&DeterminismChecker, "deterministic", &parse(original));
+    assert_eq!(cex.events.len(), 2);
+    let still = replay(PropertyKind::Deterministic, original, &cex);
+    assert_eq!(still.verdict, ReplayVerdict::StillFails);
+
+    let fixed = r#"channel a, b
+P = b -> a -> b -> STOP
+assert P :[deterministic [FD]]
+"#;
+    let replayed = replay(PropertyKind::Deterministic, fixed, &cex);
+    assert_eq!(replayed.verdict, ReplayVerdict::NowPasses);
+    assert_eq!(replayed.step, Some(1));
+
+    let without_choice = r#"channel a, b
+P = b -> b -> STOP
+assert P :[deterministic [FD]]
+"#;
+    let replayed = replay(PropertyKind::Deterministic, without_choice, &cex);
+    assert_eq!(replayed.verdict, ReplayVerdict::NoLongerValid);
+    assert_eq!(replayed.step, Some(1));
+}
+
+#[test]
+fn refinement_replay_follows_both_sides() {
+    let spec = parse(
+        r#"channel a, b
+SPEC = a -> STOP
+"#,
+    );
+    let impl_ = parse(
+        r#"channel a, b
+IMPL = a -> b -> STOP
+"#,
+    );
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::T),
+        target: None,
+    };
+    let input = RefinementInput {
+        spec: spec.clone(),
+        impl_,
+    };
+    let cex = RefinementChecker
+        .check(&request, &input)
+        .counterexample
+        .expect("counterexample");
+    let still = replay_refinement_input(&RefinementModel::T, &input, &cex).expect("replay");
+    assert_eq!(still.verdict, ReplayVerdict::StillFails);
+
+    let relaxed = RefinementInput {
+        spec: parse(
+            r#"channel a, b
+SPEC = a -> b -> STOP
+"#,
+        ),
+        impl_: input.impl_.clone(),
+    };
+    let replayed = replay_refinement_input(&RefinementModel::T, &relaxed, &cex).expect("replay");
+    assert_eq!(replayed.verdict, ReplayVerdict::NowPasses);
+    assert_eq!(replayed.step, Some(1));
+
+    let shorter = RefinementInput {
+        spec,
+        impl_: parse(
+            r#"channel a, b
+IMPL = a -> STOP
+"#,
+        ),
+    };
+    let replayed = replay_refinement_input(&RefinementModel::T, &shorter, &cex).expect("replay");
+    assert_eq!(replayed.verdict, ReplayVerdict::NoLongerValid);
+    assert_eq!(replayed.step, Some(1));
+    assert!(replayed.offered.is_empty());
+}
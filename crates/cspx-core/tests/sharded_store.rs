@@ -0,0 +1,210 @@
+use cspx_core::state_codec::StateCodecError;
+use cspx_core::{
+    explore, explore_parallel_concurrent, explore_parallel_concurrent_with_limits,
+    ConcurrentStateStore, ExploreProfileMode, InMemoryStateStore, ParallelExploreOptions,
+    RunLimits, ShardedDiskStateStore, ShardedStateStore, StateCodec, Transition,
+    TransitionProvider, VecWorkQueue,
+};
+use std::collections::HashSet;
+use std::io;
+use std::sync::Mutex;
+
+// Monotone paths on a (SIZE+1)^2 grid: most states are reached twice.
+const SIZE: u8 = 40;
+const GRID_STATES: u64 = 41 * 41;
+const GRID_TRANSITIONS: u64 = 2 * 40 * 41;
+
+struct GridProvider;
+
+impl TransitionProvider for GridProvider {
+    type State = (u8, u8);
+    type Transition = Transition;
+
+    fn initial_state(&self) -> Self::State {
+        (0, 0)
+    }
+
+    fn transitions(&self, state: &Self::State) -> Vec<(Self::Transition, Self::State)> {
+        let (x, y) = *state;
+        let mut out = Vec::new();
+        if x < SIZE {
+            out.push((step("right"), (x + 1, y)));
+        }
+        if y < SIZE {
+            out.push((step("down"), (x, y + 1)));
+        }
+        out
+    }
+}
+
+fn step(label: &str) -> Transition {
+    Transition {
+        label: label.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PairCodec;
+
+impl StateCodec<(u8, u8)> for PairCodec {
+    fn encode(&self, state: &(u8, u8)) -> Vec<u8> {
+        vec![state.0, state.1]
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<(u8, u8), StateCodecError> {
+        match bytes {
+            [x, y] => Ok((*x, *y)),
+            _ => Err(StateCodecError::new("invalid pair state")),
+        }
+    }
+}
+
+// Records the insertion order of every shard.
+struct RecordingStore {
+    shards: Vec<Mutex<RecordedShard>>,
+}
+
+type RecordedShard = (HashSet<(u8, u8)>, Vec<(u8, u8)>);
+
+impl RecordingStore {
+    fn new(shards: usize) -> Self {
+        Self {
+            shards: (0..shards).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    fn orders(self) -> Vec<Vec<(u8, u8)>> {
+        self.shards
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap().1)
+            .collect()
+    }
+}
+
+impl ConcurrentStateStore<(u8, u8)> for RecordingStore {
+    fn insert(&self, state: (u8, u8)) -> io::Result<bool> {
+        let mut shard = self.shards[self.shard_of(&state)].lock().unwrap();
+        if !shard.0.insert(state) {
+            return Ok(false);
+        }
+        shard.1.push(state);
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().0.len())
+            .sum()
+    }
+
+    fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard_of(&self, state: &(u8, u8)) -> usize {
+        (state.0 as usize * 7 + state.1 as usize) % self.shards.len()
+    }
+}
+
+#[test]
+fn sharded_store_matches_serial_exploration() {
+    let serial = explore(
+        &GridProvider,
+        &mut InMemoryStateStore::new(),
+        &mut VecWorkQueue::new(),
+    )
+    .expect("serial");
+    assert_eq!(serial.states, Some(GRID_STATES));
+    assert_eq!(serial.transitions, Some(GRID_TRANSITIONS));
+
+    for deterministic in [false, true] {
+        for workers in [1, 4] {
+            let store = ShardedStateStore::with_shards(8);
+            let outcome = explore_parallel_concurrent_with_limits(
+                &GridProvider,
+                &store,
+                ParallelExploreOptions {
+                    workers,
+                    deterministic,
+                    seed: 0,
+                },
+                &RunLimits::unlimited(),
+                true,
+            )
+            .expect("concurrent");
+            assert_eq!(outcome.stats.states, serial.states);
+            assert_eq!(outcome.stats.transitions, serial.transitions);
+            assert_eq!(store.len() as u64, GRID_STATES);
+
+            let profile = outcome.profile.expect("profile");
+            let mode = if deterministic {
+                ExploreProfileMode::ParallelDeterministic
+            } else {
+                ExploreProfileMode::Parallel
+            };
+            assert_eq!(profile.mode, mode);
+            assert_eq!(profile.discovered_states, GRID_STATES);
+            assert!(profile.visited_insert_ns > 0);
+        }
+    }
+}
+
+#[test]
+fn deterministic_mode_inserts_each_shard_in_the_same_order() {
+    let run = |workers| {
+        let store = RecordingStore::new(4);
+        let stats = explore_parallel_concurrent(
+            &GridProvider,
+            &store,
+            ParallelExploreOptions {
+                workers,
+                deterministic: true,
+                seed: 0,
+            },
+        )
+        .expect("deterministic");
+        assert_eq!(stats.states, Some(GRID_STATES));
+        store.orders()
+    };
+
+    let expected = run(1);
+    // Within a level each shard takes its states in `Ord` order.
+    for order in &expected {
+        let mut sorted = order.clone();
+        sorted.sort_by_key(|&(x, y)| (x as u16 + y as u16, (x, y)));
+        assert_eq!(order, &sorted);
+    }
+    for workers in [2, 4, 8] {
+        assert_eq!(run(workers), expected, "workers={workers}");
+    }
+}
+
+#[test]
+fn sharded_disk_store_persists_across_reopen() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let options = ParallelExploreOptions {
+        workers: 4,
+        deterministic: false,
+        seed: 0,
+    };
+
+    let store = ShardedDiskStateStore::open(dir.path(), 4, PairCodec).expect("open");
+    let stats = explore_parallel_concurrent(&GridProvider, &store, options).expect("explore");
+    assert_eq!(stats.states, Some(GRID_STATES));
+    assert_eq!(store.len() as u64, GRID_STATES);
+    let metrics = store.shard_metrics().expect("metrics");
+    assert_eq!(metrics.len(), 4);
+    assert_eq!(
+        metrics.iter().map(|shard| shard.log_write_ops).sum::<u64>(),
+        GRID_STATES
+    );
+    drop(store);
+    assert!(dir.path().join("shard-003").exists());
+
+    let store = ShardedDiskStateStore::open(dir.path(), 4, PairCodec).expect("reopen");
+    assert_eq!(store.len() as u64, GRID_STATES);
+    assert!(!store.insert((SIZE, SIZE)).expect("insert"));
+    let stats = explore_parallel_concurrent(&GridProvider, &store, options).expect("explore");
+    assert_eq!(stats.states, Some(0));
+}
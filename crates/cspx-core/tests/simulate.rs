@@ -0,0 +1,122 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::ir::{Module, PropertyKind};
+use cspx_core::{
+    CheckRequest, CheckResult, CounterexampleType, Frontend, ReasonKind, RefinementInput,
+    RunLimits, SimpleFrontend, SimulateOptions, Simulator, Status,
+};
+
+fn parse(input: &str) -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn simulate(kind: PropertyKind, input: &str, seed: u64) -> CheckResult {
+    let request = CheckRequest {
+        command: CheckCommand::Simulate,
+        model: None,
+        target: None,
+    };
+    let simulator = Simulator {
+        options: SimulateOptions {
+            runs: 20,
+            depth: 30,
+            seed,
+        },
+    };
+    simulator.check_property(kind, &request, &parse(input), &RunLimits::unlimited())
+}
+
+#[test]
+fn simulation_finds_a_deadlock_and_repeats_it_with_the_same_seed() {
+    let input = r#"channel req, ack, err
+Server = req -> (ack -> Server |~| err -> STOP)
+assert Server :[deadlock free [F]]
+"#;
+    let result = simulate(PropertyKind::DeadlockFree, input, 7);
+    assert_eq!(result.status, Status::Fail);
+    let counterexample = result.counterexample.as_ref().expect("counterexample");
+    assert_eq!(counterexample.tags[0], "deadlock");
+    assert_eq!(counterexample.events.last().unwrap().label, "err");
+    assert_eq!(simulate(PropertyKind::DeadlockFree, input, 7), result);
+
+    let traces = (0..8)
+        .map(|seed| simulate(PropertyKind::DeadlockFree, input, seed))
+        .map(|result| result.counterexample.unwrap().events.len())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert!(traces.len() > 1);
+}
+
+#[test]
+fn simulation_without_violation_reports_coverage() {
+    let input = r#"channel a, b, c
+P = a -> P [] b -> P
+assert P :[deadlock free [F]]
+"#;
+    let result = simulate(PropertyKind::DeadlockFree, input, 1);
+    assert_eq!(result.status, Status::Pass);
+    assert_eq!(
+        result.reason.as_ref().map(|reason| &reason.kind),
+        Some(&ReasonKind::ApproximateSearch)
+    );
+    let stats = result.stats.as_ref().expect("stats");
+    assert_eq!(stats.transitions, Some(20 * 30));
+    assert_eq!(stats.depth, Some(30));
+    let simulation = result.simulation.as_ref().expect("simulation");
+    assert_eq!(simulation.runs, 20);
+    assert_eq!(
+        simulation.events_seen,
+        vec!["a".to_string(), "b".to_string()]
+    );
+}
+
+#[test]
+fn simulation_reports_a_tau_cycle_as_a_lasso() {
+    let input = r#"channel a, b
+Loop = a -> Loop
+P = b -> (Loop \\ {|a|})
+assert P :[divergence free [FD]]
+"#;
+    let result = simulate(PropertyKind::DivergenceFree, input, 3);
+    assert_eq!(result.status, Status::Fail);
+    let counterexample = result.counterexample.expect("counterexample");
+    assert_eq!(counterexample.kind, CounterexampleType::Lasso);
+    let lasso = counterexample.lasso.expect("lasso");
+    assert_eq!(lasso.stem[0].label, "b");
+    assert!(lasso.cycle.iter().all(|event| event.label == "tau"));
+}
+
+#[test]
+fn simulation_checks_refinement_along_the_impl_walk() {
+    let module = parse(
+        r#"channel a, b, c
+SPEC = a -> b -> SPEC
+IMPL = a -> (b -> IMPL |~| c -> IMPL)
+"#,
+    );
+    let with_entry = |name: &str| {
+        let mut module = module.clone();
+        module.entry = module
+            .declarations
+            .iter()
+            .find(|decl| decl.name.value == name)
+            .map(|decl| decl.expr.clone());
+        module
+    };
+    let request = CheckRequest {
+        command: CheckCommand::Simulate,
+        model: Some(RefinementModel::T),
+        target: None,
+    };
+    let input = RefinementInput {
+        spec: with_entry("SPEC"),
+        impl_: with_entry("IMPL"),
+    };
+    let result = Simulator::default().check_refinement(&request, &input, &RunLimits::unlimited());
+    assert_eq!(result.status, Status::Fail);
+    assert_eq!(result.model.as_deref(), Some("T"));
+    let counterexample = result.counterexample.expect("counterexample");
+    assert!(counterexample.tags.contains(&"trace_mismatch".to_string()));
+    assert_eq!(counterexample.events.last().unwrap().label, "c");
+}
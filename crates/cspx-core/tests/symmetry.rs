@@ -0,0 +1,166 @@
+use cspx_core::check::CheckCommand;
+use cspx_core::ir::Module;
+use cspx_core::{
+    CheckRequest, CheckResult, Checker, CspmTransitionProvider, DeadlockChecker, DivergenceChecker,
+    Frontend, InMemorySearch, SimpleFrontend, Status, SymmetryStats, TransitionProvider,
+};
+
+fn parse(input: &str) -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn request(target: &str) -> CheckRequest {
+    CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some(target.to_string()),
+    }
+}
+
+fn check_symmetric(target: &str, module: &Module) -> (CheckResult, SymmetryStats) {
+    let mut symmetry = SymmetryStats::default();
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.symmetry = Some(&mut symmetry);
+    let request = request(target);
+    let result = match target {
+        "deadlock free" => DeadlockChecker.check_with(&request, module, &mut backend),
+        _ => DivergenceChecker.check_with(&request, module, &mut backend),
+    };
+    (result, symmetry)
+}
+
+fn states(result: &CheckResult) -> u64 {
+    result
+        .stats
+        .as_ref()
+        .and_then(|stats| stats.states)
+        .expect("states")
+}
+
+fn labels(result: CheckResult) -> Vec<String> {
+    result
+        .counterexample
+        .expect("counterexample")
+        .events
+        .into_iter()
+        .map(|event| event.label)
+        .collect()
+}
+
+// Follows `labels` through the concrete LTS, resolving tau moves breadth-first.
+fn is_concrete_trace(provider: &CspmTransitionProvider, labels: &[String]) -> bool {
+    let closure = |states: Vec<_>| {
+        let mut seen = states;
+        let mut idx = 0;
+        while idx < seen.len() {
+            for (transition, next) in provider.transitions(&seen[idx]) {
+                if transition.label == "tau" && !seen.contains(&next) {
+                    seen.push(next);
+                }
+            }
+            idx += 1;
+        }
+        seen
+    };
+    let mut current = closure(vec![provider.initial_state()]);
+    for label in labels {
+        let next = current
+            .iter()
+            .flat_map(|state| provider.transitions(state))
+            .filter(|(transition, _)| &transition.label == label)
+            .map(|(_, next)| next)
+            .collect::<Vec<_>>();
+        if next.is_empty() {
+            return false;
+        }
+        current = closure(next);
+    }
+    true
+}
+
+const PHILOSOPHERS: &str = r#"channel think, eat : {0..3}
+PHIL0 = think.0 -> eat.0 -> PHIL0
+PHIL1 = think.1 -> eat.1 -> PHIL1
+PHIL2 = think.2 -> eat.2 -> PHIL2
+PHIL3 = think.3 -> eat.3 -> PHIL3
+System = ((PHIL0 ||| PHIL1) ||| PHIL2) ||| PHIL3
+assert System :[deadlock free [F]]
+"#;
+
+#[test]
+fn symmetry_collapses_permuted_philosophers() {
+    let module = parse(PHILOSOPHERS);
+    let full = DeadlockChecker.check(&request("deadlock free"), &module);
+    let (reduced, symmetry) = check_symmetric("deadlock free", &module);
+
+    assert_eq!(full.status, Status::Pass);
+    assert_eq!(reduced.status, Status::Pass);
+    assert_eq!(states(&full), 16);
+    // One state per number of philosophers that are eating.
+    assert_eq!(states(&reduced), 5);
+    assert!(symmetry.canonicalised_successors > 0);
+}
+
+#[test]
+fn symmetric_counterexample_is_a_concrete_trace() {
+    let module = parse(
+        r#"channel pick, put : {0..2}
+channel lock
+P0 = pick.0 -> (put.0 -> P0 [] lock -> STOP)
+P1 = pick.1 -> (put.1 -> P1 [] lock -> STOP)
+P2 = pick.2 -> (put.2 -> P2 [] lock -> STOP)
+System = (P0 [|{|lock|}|] P1) [|{|lock|}|] P2
+System
+"#,
+    );
+    let full = DeadlockChecker.check(&request("deadlock free"), &module);
+    let (reduced, _) = check_symmetric("deadlock free", &module);
+
+    assert_eq!(full.status, Status::Fail);
+    assert_eq!(reduced.status, Status::Fail);
+    assert!(states(&reduced) < states(&full));
+    let provider = CspmTransitionProvider::from_module(&module).expect("provider");
+    let events = labels(reduced);
+    assert_eq!(events.last().map(String::as_str), Some("lock"));
+    assert!(is_concrete_trace(&provider, &events), "{events:?}");
+}
+
+#[test]
+fn symmetry_keeps_divergence_under_hiding() {
+    let module = parse(
+        r#"channel step : {0..1}
+channel go
+P0 = go -> Loop0
+Loop0 = step.0 -> Loop0
+P1 = go -> Loop1
+Loop1 = step.1 -> Loop1
+System = (P0 ||| P1) \\ {|step|}
+assert System :[divergence free [FD]]
+"#,
+    );
+    let (reduced, _) = check_symmetric("divergence free", &module);
+
+    assert_eq!(reduced.status, Status::Fail);
+    assert_eq!(labels(reduced), vec!["go".to_string(), "tau".to_string()]);
+}
+
+#[test]
+fn components_with_different_behaviour_are_not_permuted() {
+    let module = parse(
+        r#"channel a, b
+Left = a -> b -> STOP
+Right = a -> STOP
+System = Left ||| Right
+System
+"#,
+    );
+    let provider = CspmTransitionProvider::from_module(&module).expect("provider");
+    let initial = provider.initial_state();
+    for (_, next) in provider.transitions(&initial) {
+        assert_eq!(provider.canonical_state(&next), None);
+    }
+}
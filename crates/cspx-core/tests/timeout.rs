@@ -0,0 +1,186 @@
+use cspx_core::check::{CheckCommand, RefinementModel};
+use cspx_core::ir::Module;
+use cspx_core::{
+    explore, explore_parallel_with_limits, explore_with_limits, CheckRequest, Checker,
+    CspmTransitionProvider, DeadlockChecker, Frontend, FrontendErrorKind, InMemorySearch,
+    InMemoryStateStore, LimitExceeded, ParallelExploreOptions, ReasonKind, RefinementChecker,
+    RefinementInput, RunLimits, SimpleFrontend, Status, VecWorkQueue,
+};
+
+// 4^4 interleaved states with the composition as the entry process.
+const COUNTERS: &str = r#"channel a, b, c, d : {0..3}
+C0 = a.0 -> b.0 -> c.0 -> d.0 -> C0
+C1 = a.1 -> b.1 -> c.1 -> d.1 -> C1
+C2 = a.2 -> b.2 -> c.2 -> d.2 -> C2
+C3 = a.3 -> b.3 -> c.3 -> d.3 -> C3
+System = ((C0 ||| C1) ||| C2) ||| C3
+assert System :[deadlock free [F]]
+((C0 ||| C1) ||| C2) ||| C3
+"#;
+
+fn module() -> Module {
+    SimpleFrontend
+        .parse_and_typecheck(COUNTERS, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir
+}
+
+fn deadlock_request() -> CheckRequest {
+    CheckRequest {
+        command: CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    }
+}
+
+fn expired() -> RunLimits {
+    RunLimits::unlimited().with_timeout_ms(0)
+}
+
+#[test]
+fn expired_deadline_stops_check_with_partial_stats() {
+    let module = module();
+    let request = deadlock_request();
+    let limits = expired();
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(1);
+    backend.limits = Some(&limits);
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+
+    assert_eq!(result.status, Status::Timeout);
+    let reason = result.reason.expect("reason");
+    assert_eq!(reason.kind, ReasonKind::Timeout);
+    assert_eq!(reason.message.as_deref(), Some("timed out after 0 ms"));
+    let stats = result.stats.expect("stats");
+    assert_eq!(stats.states, Some(1));
+    assert_eq!(stats.transitions, Some(0));
+    assert_eq!(stats.depth, Some(0));
+}
+
+#[test]
+fn cancelled_run_stops_with_timeout_status() {
+    let module = module();
+    let request = deadlock_request();
+    let limits = RunLimits::unlimited();
+    limits.cancel();
+    let mut search = InMemorySearch::new();
+    let mut backend = search.backend(4);
+    backend.limits = Some(&limits);
+    let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+
+    assert_eq!(result.status, Status::Timeout);
+    assert_eq!(
+        result.reason.and_then(|reason| reason.message).as_deref(),
+        Some("run cancelled")
+    );
+}
+
+#[test]
+fn distant_deadline_keeps_the_verdict() {
+    let module = module();
+    let request = deadlock_request();
+    let expected = DeadlockChecker.check(&request, &module);
+    assert_eq!(expected.status, Status::Pass);
+
+    let limits = RunLimits::unlimited().with_timeout_ms(60 * 60 * 1000);
+    for workers in [1, 4] {
+        let mut search = InMemorySearch::new();
+        let mut backend = search.backend(workers);
+        backend.limits = Some(&limits);
+        let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+        assert_eq!(result, expected);
+        assert_eq!(result.stats.and_then(|stats| stats.depth), None);
+    }
+}
+
+#[test]
+fn explore_reports_the_limit_that_stopped_it() {
+    let module = module();
+    let provider = CspmTransitionProvider::from_module(&module).expect("provider");
+    let full = explore(
+        &provider,
+        &mut InMemoryStateStore::new(),
+        &mut VecWorkQueue::new(),
+    )
+    .expect("explore");
+    assert_eq!(full.states, Some(256));
+
+    let outcome = explore_with_limits(
+        &provider,
+        &mut InMemoryStateStore::new(),
+        &mut VecWorkQueue::new(),
+        &RunLimits::unlimited(),
+        true,
+    )
+    .expect("explore");
+    assert_eq!(outcome.stats, full);
+    assert_eq!(outcome.stopped, None);
+    assert!(outcome.profile.is_some());
+
+    let outcome = explore_with_limits(
+        &provider,
+        &mut InMemoryStateStore::new(),
+        &mut VecWorkQueue::new(),
+        &expired(),
+        false,
+    )
+    .expect("explore");
+    assert_eq!(
+        outcome.stopped,
+        Some(LimitExceeded::Timeout { timeout_ms: 0 })
+    );
+    assert_eq!(outcome.stats.states, Some(1));
+    assert_eq!(outcome.stats.depth, Some(0));
+
+    for deterministic in [false, true] {
+        let outcome = explore_parallel_with_limits(
+            &provider,
+            &mut InMemoryStateStore::new(),
+            ParallelExploreOptions {
+                workers: 4,
+                deterministic,
+                seed: 0,
+            },
+            &expired(),
+            false,
+        )
+        .expect("explore");
+        assert!(outcome.stopped.is_some());
+        assert_eq!(outcome.stats.depth, Some(0));
+    }
+}
+
+#[test]
+fn expired_deadline_stops_refinement() {
+    let module = module();
+    let request = CheckRequest {
+        command: CheckCommand::Refine,
+        model: Some(RefinementModel::F),
+        target: Some("spec impl".to_string()),
+    };
+    let input = RefinementInput {
+        spec: module.clone(),
+        impl_: module,
+    };
+
+    let result = RefinementChecker.check_with_limits(&request, &input, 2, &expired());
+    assert_eq!(result.status, Status::Timeout);
+    assert_eq!(result.model.as_deref(), Some("F"));
+    assert_eq!(
+        result.reason.map(|reason| reason.kind),
+        Some(ReasonKind::Timeout)
+    );
+    assert!(result.counterexample.is_none());
+    assert_eq!(result.stats.and_then(|stats| stats.depth), Some(0));
+}
+
+#[test]
+fn expired_deadline_stops_the_frontend() {
+    let err =
+        match SimpleFrontend.parse_and_typecheck_with_limits(COUNTERS, "model.cspm", &expired()) {
+            Ok(_) => panic!("expired deadline must stop the frontend"),
+            Err(err) => err,
+        };
+    assert_eq!(err.kind, FrontendErrorKind::Timeout);
+    assert_eq!(err.to_string(), "timed out after 0 ms");
+}
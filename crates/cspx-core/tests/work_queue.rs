@@ -0,0 +1,183 @@
+use cspx_core::state_codec::StateCodecError;
+use cspx_core::{
+    CheckRequest, Checker, CspmStateCodec, DeadlockChecker, DiskWorkQueue, DiskWorkQueueOptions,
+    Frontend, HybridWorkQueue, HybridWorkQueueOptions, InMemoryPredecessorStore,
+    InMemoryStateStore, SearchBackend, SimpleFrontend, StateCodec, Status, WorkQueue,
+};
+use std::fs;
+
+#[derive(Debug, Clone, Copy)]
+struct U32Codec;
+
+impl StateCodec<u32> for U32Codec {
+    fn encode(&self, state: &u32) -> Vec<u8> {
+        state.to_le_bytes().to_vec()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<u32, StateCodecError> {
+        let bytes: [u8; 4] = bytes
+            .try_into()
+            .map_err(|_| StateCodecError::new("invalid u32 bytes"))?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+}
+
+fn drain(queue: &mut impl WorkQueue<u32>) -> Vec<u32> {
+    let mut states = Vec::new();
+    while let Some(state) = queue.pop().expect("pop") {
+        states.push(state);
+    }
+    states
+}
+
+#[test]
+fn disk_queue_is_fifo_across_segments() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("queue.log");
+    let mut queue = DiskWorkQueue::open_with_options(
+        &path,
+        U32Codec,
+        DiskWorkQueueOptions { segment_records: 3 },
+    )
+    .expect("open");
+    assert!(queue.is_empty());
+
+    for state in 0..5 {
+        queue.push(state).expect("push");
+    }
+    assert_eq!(queue.pop().expect("pop"), Some(0));
+    // The first segment is being read while pushes keep filling later ones.
+    for state in 5..8 {
+        queue.push(state).expect("push");
+    }
+    assert_eq!(drain(&mut queue), (1..8).collect::<Vec<_>>());
+    assert!(queue.is_empty());
+    assert_eq!(queue.metrics().pushed, 8);
+    assert_eq!(queue.metrics().popped, 8);
+    assert_eq!(queue.metrics().segments_written, 3);
+}
+
+#[test]
+fn disk_queue_removes_consumed_and_dropped_segments() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("queue.log");
+    let mut queue = DiskWorkQueue::open_with_options(
+        &path,
+        U32Codec,
+        DiskWorkQueueOptions { segment_records: 2 },
+    )
+    .expect("open");
+    for state in 0..6 {
+        queue.push(state).expect("push");
+    }
+    assert!(path.with_extension("000000.seg").exists());
+    assert_eq!(queue.pop().expect("pop"), Some(0));
+    assert_eq!(queue.pop().expect("pop"), Some(1));
+    assert!(!path.with_extension("000000.seg").exists());
+    assert!(path.with_extension("000002.seg").exists());
+
+    drop(queue);
+    assert_eq!(fs::read_dir(dir.path()).expect("read dir").count(), 0);
+}
+
+#[test]
+fn disk_queue_rejects_zero_segment_records() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let err = DiskWorkQueue::open_with_options(
+        dir.path().join("queue.log"),
+        U32Codec,
+        DiskWorkQueueOptions { segment_records: 0 },
+    )
+    .expect_err("segment_records=0 must fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn hybrid_queue_spills_above_threshold_and_keeps_order() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut queue = HybridWorkQueue::open(
+        dir.path().join("queue.log"),
+        U32Codec,
+        HybridWorkQueueOptions {
+            spill_threshold: 4,
+            disk_options: DiskWorkQueueOptions { segment_records: 2 },
+        },
+    )
+    .expect("open");
+
+    for state in 0..4 {
+        queue.push(state).expect("push");
+    }
+    assert!(!queue.is_spilling());
+    for state in 4..10 {
+        queue.push(state).expect("push");
+    }
+    assert!(queue.is_spilling());
+    assert_eq!(queue.in_memory_len(), 4);
+
+    let mut popped = Vec::new();
+    for _ in 0..5 {
+        popped.extend(queue.pop().expect("pop"));
+    }
+    // Pushes keep going to disk while older states wait there.
+    queue.push(10).expect("push");
+    assert_eq!(queue.in_memory_len(), 0);
+    popped.extend(drain(&mut queue));
+    assert_eq!(popped, (0..11).collect::<Vec<_>>());
+    assert_eq!(queue.spill_metrics().expect("spill metrics").pushed, 7);
+}
+
+#[test]
+fn deadlock_with_spilling_queue_matches_in_memory_run() {
+    let input = r#"channel think, eat : {0..3}
+channel lock
+PHIL0 = think.0 -> (eat.0 -> PHIL0 [] lock -> STOP)
+PHIL1 = think.1 -> eat.1 -> PHIL1
+PHIL2 = think.2 -> eat.2 -> PHIL2
+PHIL3 = think.3 -> eat.3 -> PHIL3
+System = ((PHIL0 ||| PHIL1) ||| PHIL2) ||| PHIL3
+assert System :[deadlock free [F]]
+"#;
+    let module = SimpleFrontend
+        .parse_and_typecheck(input, "model.cspm")
+        .expect("parse_and_typecheck")
+        .ir;
+    let request = CheckRequest {
+        command: cspx_core::check::CheckCommand::Check,
+        model: None,
+        target: Some("deadlock free".to_string()),
+    };
+    let expected = DeadlockChecker.check(&request, &module);
+    assert_eq!(expected.status, Status::Pass);
+
+    for workers in [1, 4] {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut store = InMemoryStateStore::new();
+        let mut queue = HybridWorkQueue::open(
+            dir.path().join("queue.log"),
+            CspmStateCodec,
+            HybridWorkQueueOptions {
+                spill_threshold: 2,
+                disk_options: DiskWorkQueueOptions { segment_records: 3 },
+            },
+        )
+        .expect("open queue");
+        let mut predecessors = InMemoryPredecessorStore::new();
+        let mut backend = SearchBackend {
+            store: &mut store,
+            queue: &mut queue,
+            predecessors: &mut predecessors,
+            workers,
+            por: None,
+            symmetry: None,
+            limits: None,
+            checkpoint: None,
+            compression: None,
+            max_counterexamples: 1,
+        };
+        let result = DeadlockChecker.check_with(&request, &module, &mut backend);
+
+        assert_eq!(result, expected);
+        assert!(queue.is_spilling());
+    }
+}
@@ -76,6 +76,7 @@ struct CheckExpect {
     status: Option<JsonValue>,
     reason: Option<ReasonExpect>,
     counterexample: Option<CounterexampleExpect>,
+    counterexamples: Option<Vec<CounterexampleExpect>>,
     stats: Option<StatsExpect>,
 }
 
@@ -90,8 +91,17 @@ struct CounterexampleExpect {
     present: Option<bool>,
     trace_len: Option<JsonValue>,
     tags: Option<TagConstraint>,
+    minimality: Option<JsonValue>,
+    // Older expectations; true means any minimality other than `none`.
     is_minimized: Option<JsonValue>,
     source_spans: Option<SpanMatch>,
+    events: Option<Vec<EventExpect>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventExpect {
+    label: Option<JsonValue>,
+    spans: Option<SpanMatch>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -103,6 +113,7 @@ struct TagConstraint {
 #[derive(Debug, Deserialize)]
 struct SpanMatch {
     any: Option<Vec<SpanConstraint>>,
+    all: Option<Vec<SpanConstraint>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -524,6 +535,21 @@ fn check_matches(expect: &CheckExpect, actual: &JsonValue) -> bool {
             return false;
         }
     }
+    if let Some(counterexamples) = &expect.counterexamples {
+        let actual = obj
+            .get("counterexamples")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if counterexamples.len() != actual.len()
+            || !counterexamples
+                .iter()
+                .zip(&actual)
+                .all(|(expect, actual)| counterexample_matches(expect, Some(actual)))
+        {
+            return false;
+        }
+    }
     if let Some(stats) = &expect.stats {
         if !stats_matches(stats, obj.get("stats")) {
             return false;
@@ -560,8 +586,10 @@ fn counterexample_matches(expect: &CounterexampleExpect, actual: Option<&JsonVal
     }
     if expect.trace_len.is_none()
         && expect.tags.is_none()
+        && expect.minimality.is_none()
         && expect.is_minimized.is_none()
         && expect.source_spans.is_none()
+        && expect.events.is_none()
     {
         return true;
     }
@@ -595,12 +623,17 @@ fn counterexample_matches(expect: &CounterexampleExpect, actual: Option<&JsonVal
             return false;
         }
     }
+    let actual_minimality = obj
+        .get("minimality")
+        .and_then(|v| v.as_str())
+        .unwrap_or("none");
+    if let Some(minimality) = &expect.minimality {
+        if !match_string(actual_minimality, minimality) {
+            return false;
+        }
+    }
     if let Some(is_minimized) = &expect.is_minimized {
-        let actual_minimized = obj
-            .get("is_minimized")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        if !match_bool(actual_minimized, is_minimized) {
+        if !match_bool(actual_minimality != "none", is_minimized) {
             return false;
         }
     }
@@ -610,6 +643,39 @@ fn counterexample_matches(expect: &CounterexampleExpect, actual: Option<&JsonVal
             return false;
         }
     }
+    if let Some(events) = &expect.events {
+        let actual_events = obj
+            .get("events")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if events.len() > actual_events.len() {
+            return false;
+        }
+        if !events
+            .iter()
+            .zip(&actual_events)
+            .all(|(expect, actual)| event_matches(expect, actual))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+fn event_matches(expect: &EventExpect, actual: &JsonValue) -> bool {
+    if let Some(label) = &expect.label {
+        let actual_label = actual.get("label").and_then(|v| v.as_str()).unwrap_or("");
+        if actual_label.is_empty() || !match_string(actual_label, label) {
+            return false;
+        }
+    }
+    if let Some(spans) = &expect.spans {
+        let actual_spans = actual.get("spans").and_then(|v| v.as_array());
+        if !spans_matches(spans, actual_spans) {
+            return false;
+        }
+    }
     true
 }
 
@@ -660,17 +726,22 @@ fn spans_matches(expect: &SpanMatch, actual: Option<&Vec<JsonValue>>) -> bool {
     let Some(list) = actual else {
         return false;
     };
-    let Some(any) = &expect.any else {
+    if expect.any.is_none() && expect.all.is_none() {
         return false;
-    };
-    for constraint in any {
-        for span in list {
-            if span_matches(constraint, span) {
-                return true;
-            }
+    }
+    let matched =
+        |constraint: &SpanConstraint| list.iter().any(|span| span_matches(constraint, span));
+    if let Some(any) = &expect.any {
+        if !any.iter().any(matched) {
+            return false;
         }
     }
-    false
+    if let Some(all) = &expect.all {
+        if !all.iter().all(matched) {
+            return false;
+        }
+    }
+    true
 }
 
 fn span_matches(expect: &SpanConstraint, actual: &JsonValue) -> bool {